@@ -0,0 +1,55 @@
+//! Byte arrays of the size of a permutation state.
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A byte array of fixed size, used for [`PermutationState::Block`].
+///
+/// Implemented for `[u8; N]` and (nested) arrays of those, like `[[u8; 4];
+/// N]`. Sealed, since the nested arrays are viewed as flat byte slices.
+///
+/// [`PermutationState::Block`]: crate::PermutationState::Block
+pub trait Block: Copy + sealed::Sealed {
+    /// The all zero block.
+    const ZERO: Self;
+
+    /// View the block as a slice of bytes.
+    fn as_bytes(&self) -> &[u8];
+    /// View the block as a mutable slice of bytes.
+    fn as_bytes_mut(&mut self) -> &mut [u8];
+}
+
+impl<const N: usize> sealed::Sealed for [u8; N] {}
+
+impl<const N: usize> Block for [u8; N] {
+    const ZERO: Self = [0; N];
+
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+impl<B: Block, const N: usize> sealed::Sealed for [B; N] {}
+
+impl<B: Block, const N: usize> Block for [B; N] {
+    const ZERO: Self = [B::ZERO; N];
+
+    fn as_bytes(&self) -> &[u8] {
+        let len = N * core::mem::size_of::<B>();
+        // SAFETY: `B` is a (nested) array of bytes, so `[B; N]` consists of
+        // `len` initialised bytes without padding.
+        unsafe { core::slice::from_raw_parts(self.as_ptr().cast(), len) }
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        let len = N * core::mem::size_of::<B>();
+        // SAFETY: `B` is a (nested) array of bytes, so `[B; N]` consists of
+        // `len` initialised bytes without padding, and any bytes are valid.
+        unsafe { core::slice::from_raw_parts_mut(self.as_mut_ptr().cast(), len) }
+    }
+}
@@ -32,6 +32,7 @@ impl<'a> From<&'a mut [u8]> for BufMut<'a> {
     }
 }
 
+#[allow(clippy::len_without_is_empty)]
 impl<'a> BufMut<'a> {
     /// Length of the buffer.
     pub fn len(&self) -> usize {
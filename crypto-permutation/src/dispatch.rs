@@ -0,0 +1,154 @@
+//! Runtime selection of the fastest permutation implementation.
+//!
+//! [`DispatchPermutation`] applies one of two implementations of the same
+//! permutation, depending on whether the CPU features described by a
+//! [`FeatureDetector`] are available. Nesting it selects among more than two
+//! implementations, e.g. AVX2, SSE2 and a scalar fallback:
+//!
+//! ```ignore
+//! type Keccak = DispatchPermutation<
+//!     Avx2Keccak,
+//!     DispatchPermutation<Sse2Keccak, Scalar, Sse2>,
+//!     Avx2,
+//! >;
+//! ```
+//!
+//! With the `cpufeatures` crate-feature, [`cpu_feature_detector!`] defines a
+//! [`FeatureDetector`] using the [`cpufeatures` crate], which caches the
+//! detection result after the first use.
+//!
+//! [`cpufeatures` crate]: https://crates.io/crates/cpufeatures
+//! [`cpu_feature_detector!`]: crate::cpu_feature_detector
+
+use crate::{ParallelPermutation, Permutation};
+use core::marker::PhantomData;
+
+/// Runtime detection of a set of CPU features.
+pub trait FeatureDetector: Copy + Default {
+    /// Whether the features are available on the CPU the program runs on.
+    ///
+    /// This is called on every application of a [`DispatchPermutation`], so it
+    /// should cache the result of the detection.
+    fn detected() -> bool;
+}
+
+/// Permutation applying `Fast` when the CPU features detected by `D` are
+/// available, and `Fallback` otherwise.
+///
+/// Both `Fast` and `Fallback` must compute the same permutation. They are
+/// ordinary, safe [`Permutation`]s: `Fast` must not rely on `D` for soundness.
+pub struct DispatchPermutation<Fast, Fallback, D> {
+    fast: Fast,
+    fallback: Fallback,
+    detector: PhantomData<D>,
+}
+
+impl<Fast, Fallback, D> DispatchPermutation<Fast, Fallback, D> {
+    /// Dispatch between the permutations `fast` and `fallback`.
+    pub fn new(fast: Fast, fallback: Fallback) -> Self {
+        Self {
+            fast,
+            fallback,
+            detector: PhantomData,
+        }
+    }
+}
+
+impl<Fast: Clone, Fallback: Clone, D> Clone for DispatchPermutation<Fast, Fallback, D> {
+    fn clone(&self) -> Self {
+        Self::new(self.fast.clone(), self.fallback.clone())
+    }
+}
+
+impl<Fast: Copy, Fallback: Copy, D> Copy for DispatchPermutation<Fast, Fallback, D> {}
+
+impl<Fast: Default, Fallback: Default, D> Default for DispatchPermutation<Fast, Fallback, D> {
+    fn default() -> Self {
+        Self::new(Fast::default(), Fallback::default())
+    }
+}
+
+impl<Fast: core::fmt::Debug, Fallback: core::fmt::Debug, D> core::fmt::Debug
+    for DispatchPermutation<Fast, Fallback, D>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DispatchPermutation")
+            .field("fast", &self.fast)
+            .field("fallback", &self.fallback)
+            .finish()
+    }
+}
+
+impl<Fast, Fallback, D> Permutation for DispatchPermutation<Fast, Fallback, D>
+where
+    Fast: Permutation,
+    Fallback: Permutation<State = Fast::State>,
+    D: FeatureDetector,
+{
+    type State = Fast::State;
+
+    fn apply(self, state: &mut Self::State) {
+        if D::detected() {
+            self.fast.apply(state);
+        } else {
+            self.fallback.apply(state);
+        }
+    }
+}
+
+impl<Fast, Fallback, D> ParallelPermutation for DispatchPermutation<Fast, Fallback, D>
+where
+    Fast: ParallelPermutation,
+    Fallback: ParallelPermutation<State = Fast::State>,
+    D: FeatureDetector,
+{
+    fn parallelism(self) -> usize {
+        if D::detected() {
+            self.fast.parallelism()
+        } else {
+            self.fallback.parallelism()
+        }
+    }
+
+    fn apply_parallel(self, states: &mut [Self::State]) {
+        if D::detected() {
+            self.fast.apply_parallel(states);
+        } else {
+            self.fallback.apply_parallel(states);
+        }
+    }
+}
+
+#[cfg(feature = "cpufeatures")]
+#[doc(hidden)]
+pub use cpufeatures as __cpufeatures;
+
+/// Define a [`FeatureDetector`] for the given target features, using the
+/// [`cpufeatures` crate](https://crates.io/crates/cpufeatures).
+///
+/// The features are given as string literals, like in `#[target_feature]`
+/// attributes. On architectures not supported by `cpufeatures` the features
+/// are never detected.
+///
+/// ```
+/// crypto_permutation::cpu_feature_detector!(
+///     /// Detects AVX2.
+///     pub Avx2, "avx2"
+/// );
+/// ```
+#[cfg(feature = "cpufeatures")]
+#[macro_export]
+macro_rules! cpu_feature_detector {
+    ($(#[$meta:meta])* $vis:vis $name:ident, $($feature:tt),+ $(,)?) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, Default)]
+        $vis struct $name;
+
+        impl $crate::dispatch::FeatureDetector for $name {
+            fn detected() -> bool {
+                $crate::dispatch::__cpufeatures::new!(detect, $($feature),+);
+                detect::get()
+            }
+        }
+    };
+}
@@ -5,11 +5,39 @@
 mod util;
 pub use util::check_write_size;
 
+pub mod bits;
+pub use bits::{BitReader, BitWriter, FinishWithSuffix};
+
+pub mod byte_slice;
+pub use byte_slice::{ByteSliceWriter, ByteSliceXorWriter, SliceReader};
+
+pub mod combinators;
+pub use combinators::{ChainWriter, TeeWriter};
+
+pub mod counting;
+pub use counting::{CountingWriter, WriteHistogram};
+
+#[cfg(feature = "alloc")]
+pub mod vec_writer;
+#[cfg(feature = "alloc")]
+pub use vec_writer::VecWriter;
+
+#[cfg(feature = "heapless")]
+pub mod heapless_vec_writer;
+#[cfg(feature = "heapless")]
+pub use heapless_vec_writer::HeaplessVecWriter;
+
 // `Reader` and `Writer` implementations:
+#[cfg(feature = "io_be_uint_slice")]
+pub mod be_uint_slice_reader;
+#[cfg(feature = "io_be_uint_slice")]
+pub mod be_uint_slice_writer;
 #[cfg(feature = "io_le_uint_slice")]
 pub mod le_uint_slice_reader;
 #[cfg(feature = "io_le_uint_slice")]
 pub mod le_uint_slice_writer;
+#[cfg(any(feature = "io_le_uint_slice", feature = "io_be_uint_slice"))]
+mod uint_slice;
 
 use crate::buffer::BufMut;
 
@@ -0,0 +1,14 @@
+//! Readers for arrays of big endian unsigned integers.
+
+use super::uint_slice::impl_uint_slice_reader;
+
+#[cfg(feature = "io_uint_u128")]
+impl_uint_slice_reader!(BeU128SliceReader, u128, "big", to_be_bytes);
+#[cfg(feature = "io_uint_u64")]
+impl_uint_slice_reader!(BeU64SliceReader, u64, "big", to_be_bytes);
+#[cfg(feature = "io_uint_u32")]
+impl_uint_slice_reader!(BeU32SliceReader, u32, "big", to_be_bytes);
+#[cfg(feature = "io_uint_u16")]
+impl_uint_slice_reader!(BeU16SliceReader, u16, "big", to_be_bytes);
+#[cfg(feature = "io_uint_u8")]
+impl_uint_slice_reader!(BeU8SliceReader, u8, "big", to_be_bytes);
@@ -0,0 +1,24 @@
+//! Writers for arrays of big endian unsigned integers.
+
+use super::uint_slice::{impl_uint_slice_writer, impl_uint_slice_xor_writer};
+
+#[cfg(feature = "io_uint_u128")]
+impl_uint_slice_writer!(BeU128SliceWriter, u128, "big", to_be_bytes, from_be_bytes);
+#[cfg(feature = "io_uint_u64")]
+impl_uint_slice_writer!(BeU64SliceWriter, u64, "big", to_be_bytes, from_be_bytes);
+#[cfg(feature = "io_uint_u32")]
+impl_uint_slice_writer!(BeU32SliceWriter, u32, "big", to_be_bytes, from_be_bytes);
+#[cfg(feature = "io_uint_u16")]
+impl_uint_slice_writer!(BeU16SliceWriter, u16, "big", to_be_bytes, from_be_bytes);
+#[cfg(feature = "io_uint_u8")]
+impl_uint_slice_writer!(BeU8SliceWriter, u8, "big", to_be_bytes, from_be_bytes);
+#[cfg(feature = "io_uint_u128")]
+impl_uint_slice_xor_writer!(BeU128SliceXorWriter, u128, "big", from_be_bytes);
+#[cfg(feature = "io_uint_u64")]
+impl_uint_slice_xor_writer!(BeU64SliceXorWriter, u64, "big", from_be_bytes);
+#[cfg(feature = "io_uint_u32")]
+impl_uint_slice_xor_writer!(BeU32SliceXorWriter, u32, "big", from_be_bytes);
+#[cfg(feature = "io_uint_u16")]
+impl_uint_slice_xor_writer!(BeU16SliceXorWriter, u16, "big", from_be_bytes);
+#[cfg(feature = "io_uint_u8")]
+impl_uint_slice_xor_writer!(BeU8SliceXorWriter, u8, "big", from_be_bytes);
@@ -0,0 +1,373 @@
+//! Bit granular reading and writing.
+//!
+//! [`BitWriter`] and [`BitReader`] wrap a byte oriented [`Writer`] or
+//! [`Reader`] and add a bit cursor, so bit strings that are not a whole number
+//! of bytes can be input or output, e.g. frame bits or padding that is not
+//! byte aligned. Bits are ordered least significant bit first within a byte,
+//! as in the Keccak team specifications: the first bit of a bit string is the
+//! least significant bit of its first byte.
+//!
+//! ```
+//! use crypto_permutation::io::BitWriter;
+//! use crypto_permutation::{BufMut, Writer};
+//!
+//! let mut buf = [0; 2];
+//! let mut writer = BitWriter::new(BufMut::from(&mut buf[..]));
+//! writer.write_bits(0b01, 2).unwrap();
+//! writer.write_bytes(&[0xff]).unwrap();
+//! writer.write_bit(true).unwrap();
+//! writer.finish();
+//! assert_eq!(buf, [0b1111_1101, 0b0000_0111]);
+//! ```
+//!
+//! Writers whose input is a bit string, like the input writers of deck
+//! functions, implement [`FinishWithSuffix`] to end the string after a number
+//! of bits that is not a multiple of eight. [`BitWriter::finish_bits`] passes
+//! the pending bits on to such a writer instead of padding them to a byte.
+
+use super::util::cold;
+use super::{check_write_size, Reader, WriteTooLargeError, Writer};
+
+/// Number of bytes processed at once when the cursor is not byte aligned.
+const CHUNK: usize = 64;
+
+/// The `n` least significant bits set.
+fn mask(n: u32) -> u8 {
+    ((1u16 << n) - 1) as u8
+}
+
+/// A [`Writer`] whose input is a bit string, which can end with a partial
+/// byte.
+///
+/// Bits are ordered least significant bit first, as for [`BitWriter`].
+pub trait FinishWithSuffix: Writer {
+    /// Append the `n` least significant bits of `bits`, least significant bit
+    /// first, and finish the writer. With `n == 0` this is equivalent to
+    /// [`Writer::finish`].
+    ///
+    /// The suffix never takes up capacity: implementations keep room for a
+    /// partial final byte.
+    ///
+    /// # Panics
+    /// Panics when `n > 7`.
+    fn finish_with_suffix(self, bits: u8, n: u32) -> Self::Return;
+}
+
+/// [`Writer`] adapter that also accepts single bits.
+///
+/// Bits are collected in a partial byte until it is full, and then written to
+/// the wrapped writer. Writing bytes when the cursor is not byte aligned
+/// shifts them by the number of pending bits. [`Writer::finish`] writes the
+/// pending bits, if any, as a byte padded with zero bits.
+#[derive(Debug, Clone)]
+pub struct BitWriter<W> {
+    inner: W,
+    /// The pending bits, in the `filled` least significant bits.
+    partial: u8,
+    /// Number of pending bits, less than 8.
+    filled: u32,
+}
+
+impl<W: Writer> BitWriter<W> {
+    /// Write bits to `inner`, starting byte aligned.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            partial: 0,
+            filled: 0,
+        }
+    }
+
+    /// Number of bits written since the last byte boundary.
+    pub fn bit_position(&self) -> u32 {
+        self.filled
+    }
+
+    /// Whether the cursor is at a byte boundary.
+    pub fn is_byte_aligned(&self) -> bool {
+        self.filled == 0
+    }
+
+    /// Write the `n` least significant bits of `bits`, least significant bit
+    /// first.
+    ///
+    /// # Errors
+    /// Errors when the wrapped writer has no room for the bits.
+    ///
+    /// # Panics
+    /// Panics when `n > 8`.
+    pub fn write_bits(&mut self, bits: u8, n: u32) -> Result<(), WriteTooLargeError> {
+        assert!(n <= 8, "at most 8 bits can be written at once");
+        check_write_size((self.filled + n + 7) as usize / 8, self.inner.capacity())?;
+        let acc = u16::from(self.partial) | (u16::from(bits & mask(n)) << self.filled);
+        self.filled += n;
+        if self.filled >= 8 {
+            self.inner.write_bytes(&[acc as u8])?;
+            self.filled -= 8;
+            self.partial = (acc >> 8) as u8;
+        } else {
+            self.partial = acc as u8;
+        }
+        Ok(())
+    }
+
+    /// Write a single bit.
+    ///
+    /// # Errors
+    /// Errors when the wrapped writer has no room for the bit.
+    pub fn write_bit(&mut self, bit: bool) -> Result<(), WriteTooLargeError> {
+        self.write_bits(u8::from(bit), 1)
+    }
+
+    /// Write `data` when the cursor is not byte aligned, shifting every byte
+    /// by the number of pending bits.
+    fn write_shifted(&mut self, data: &[u8]) -> Result<(), WriteTooLargeError> {
+        let mut shifted = [0; CHUNK];
+        for chunk in data.chunks(CHUNK) {
+            for (out, byte) in shifted.iter_mut().zip(chunk) {
+                *out = self.partial | (byte << self.filled);
+                self.partial = byte >> (8 - self.filled);
+            }
+            self.inner.write_bytes(&shifted[..chunk.len()])?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: FinishWithSuffix> BitWriter<W> {
+    /// Finish the wrapped writer with the pending bits as its final partial
+    /// byte, instead of padding them to a byte like [`Writer::finish`].
+    ///
+    /// ```
+    /// use crypto_permutation::io::{BitWriter, FinishWithSuffix};
+    /// use crypto_permutation::Writer;
+    ///
+    /// /// Returns the suffix it is finished with.
+    /// #[derive(Clone)]
+    /// struct Suffix;
+    ///
+    /// impl Writer for Suffix {
+    ///     type Return = (u8, u32);
+    /// #   fn capacity(&self) -> usize { usize::MAX }
+    /// #   fn skip(&mut self, _: usize) -> Result<(), crypto_permutation::WriteTooLargeError> { Ok(()) }
+    /// #   fn write_bytes(&mut self, _: &[u8]) -> Result<(), crypto_permutation::WriteTooLargeError> { Ok(()) }
+    ///     fn finish(self) -> (u8, u32) {
+    ///         (0, 0)
+    ///     }
+    /// }
+    ///
+    /// impl FinishWithSuffix for Suffix {
+    ///     fn finish_with_suffix(self, bits: u8, n: u32) -> (u8, u32) {
+    ///         (bits, n)
+    ///     }
+    /// }
+    ///
+    /// let mut writer = BitWriter::new(Suffix);
+    /// writer.write_bytes(b"data").unwrap();
+    /// writer.write_bits(0b101, 3).unwrap();
+    /// assert_eq!(writer.clone().finish_bits(), (0b101, 3));
+    /// assert_eq!(writer.clone().finish_with_suffix(0b10, 2), (0b10101, 5));
+    /// // The suffix completes a byte, which is written as a whole.
+    /// assert_eq!(writer.finish_with_suffix(0b110_1100, 7), (0b11, 2));
+    /// ```
+    pub fn finish_bits(self) -> W::Return {
+        self.inner.finish_with_suffix(self.partial, self.filled)
+    }
+}
+
+impl<W: FinishWithSuffix> FinishWithSuffix for BitWriter<W> {
+    /// Append the suffix to the pending bits, and finish the wrapped writer
+    /// with the bits that don't make up a whole byte.
+    fn finish_with_suffix(mut self, bits: u8, n: u32) -> Self::Return {
+        assert!(n <= 7, "a suffix has at most 7 bits");
+        let acc = u16::from(self.partial) | (u16::from(bits & mask(n)) << self.filled);
+        self.filled += n;
+        if self.filled >= 8 {
+            self.inner
+                .write_bytes(&[acc as u8])
+                .expect("capacity is kept for the pending bits");
+            self.filled -= 8;
+            self.partial = (acc >> 8) as u8;
+        } else {
+            self.partial = acc as u8;
+        }
+        self.finish_bits()
+    }
+}
+
+impl<W: Writer> Writer for BitWriter<W> {
+    type Return = W::Return;
+
+    /// Number of whole bytes that can still be written, keeping room for the
+    /// pending bits.
+    fn capacity(&self) -> usize {
+        let capacity = self.inner.capacity();
+        if self.is_byte_aligned() || capacity == usize::MAX {
+            capacity
+        } else {
+            capacity.saturating_sub(1)
+        }
+    }
+
+    /// Skip over `len` bytes. When the cursor is not byte aligned, zero bits
+    /// are written instead.
+    fn skip(&mut self, mut len: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(len, self.capacity())?;
+        if self.is_byte_aligned() {
+            return self.inner.skip(len);
+        }
+        cold();
+        while len > 0 {
+            let n = len.min(CHUNK);
+            self.write_shifted(&[0; CHUNK][..n])?;
+            len -= n;
+        }
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), WriteTooLargeError> {
+        check_write_size(data.len(), self.capacity())?;
+        if self.is_byte_aligned() {
+            self.inner.write_bytes(data)
+        } else {
+            self.write_shifted(data)
+        }
+    }
+
+    /// Write the pending bits padded with zero bits, if any, and finish the
+    /// wrapped writer. Use [`Self::finish_bits`] to pass the pending bits on
+    /// to a wrapped [`FinishWithSuffix`] writer instead.
+    fn finish(mut self) -> Self::Return {
+        if !self.is_byte_aligned() {
+            self.inner
+                .write_bytes(&[self.partial])
+                .expect("capacity is kept for the pending bits");
+        }
+        self.inner.finish()
+    }
+}
+
+/// [`Reader`] adapter that also reads single bits.
+///
+/// Reading bits takes a byte from the wrapped reader whenever the bits left
+/// from the previous byte don't suffice. Reading bytes when the cursor is not
+/// byte aligned shifts them by the number of bits already read. Bits left in
+/// the last byte taken are discarded when the reader is dropped.
+#[derive(Debug, Clone)]
+pub struct BitReader<R> {
+    inner: R,
+    /// The bits left from the last byte taken, in the `left` least
+    /// significant bits.
+    partial: u8,
+    /// Number of bits left, less than 8.
+    left: u32,
+}
+
+impl<R: Reader> BitReader<R> {
+    /// Read bits from `inner`, starting byte aligned.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            partial: 0,
+            left: 0,
+        }
+    }
+
+    /// Number of bits read since the last byte boundary.
+    pub fn bit_position(&self) -> u32 {
+        (8 - self.left) % 8
+    }
+
+    /// Whether the cursor is at a byte boundary.
+    pub fn is_byte_aligned(&self) -> bool {
+        self.left == 0
+    }
+
+    /// Read `n` bits, least significant bit first, into the `n` least
+    /// significant bits of the result.
+    ///
+    /// # Errors
+    /// Errors when the wrapped reader can't output the bits.
+    ///
+    /// # Panics
+    /// Panics when `n > 8`.
+    pub fn read_bits(&mut self, n: u32) -> Result<u8, WriteTooLargeError> {
+        assert!(n <= 8, "at most 8 bits can be read at once");
+        if n <= self.left {
+            let bits = self.partial & mask(n);
+            self.partial >>= n;
+            self.left -= n;
+            return Ok(bits);
+        }
+        let mut byte = [0];
+        self.inner.write_to_slice(&mut byte)?;
+        let acc = u16::from(self.partial) | (u16::from(byte[0]) << self.left);
+        self.partial = (acc >> n) as u8;
+        self.left = self.left + 8 - n;
+        Ok(acc as u8 & mask(n))
+    }
+
+    /// Read a single bit.
+    ///
+    /// # Errors
+    /// Errors when the wrapped reader can't output the bit.
+    pub fn read_bit(&mut self) -> Result<bool, WriteTooLargeError> {
+        Ok(self.read_bits(1)? == 1)
+    }
+
+    /// Write `n` bytes to `writer` when the cursor is not byte aligned,
+    /// shifting every byte by the number of bits already read.
+    fn write_shifted<W: Writer>(
+        &mut self,
+        writer: &mut W,
+        mut n: usize,
+    ) -> Result<(), WriteTooLargeError> {
+        let mut buf = [0; CHUNK];
+        while n > 0 {
+            let chunk = &mut buf[..n.min(CHUNK)];
+            self.inner.write_to_slice(chunk)?;
+            for byte in chunk.iter_mut() {
+                let next = *byte;
+                *byte = self.partial | (next << self.left);
+                self.partial = next >> (8 - self.left);
+            }
+            writer.write_bytes(chunk)?;
+            n -= chunk.len();
+        }
+        Ok(())
+    }
+}
+
+impl<R: Reader> Reader for BitReader<R> {
+    /// Number of whole bytes that can still be read, not counting the bits
+    /// left from the last byte taken.
+    fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(len, self.capacity())?;
+        if self.is_byte_aligned() {
+            return self.inner.skip(len);
+        }
+        cold();
+        if len > 0 {
+            // The bits left and all but the last byte skipped make up whole
+            // bytes, and the last byte skipped leaves as many bits.
+            self.inner.skip(len - 1)?;
+            let mut byte = [0];
+            self.inner.write_to_slice(&mut byte)?;
+            self.partial = byte[0] >> (8 - self.left);
+        }
+        Ok(())
+    }
+
+    fn write_to<W: Writer>(&mut self, writer: &mut W, n: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(n, self.capacity())?;
+        if self.is_byte_aligned() {
+            return self.inner.write_to(writer, n);
+        }
+        check_write_size(n, writer.capacity())?;
+        self.write_shifted(writer, n)
+    }
+}
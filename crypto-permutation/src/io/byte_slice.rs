@@ -0,0 +1,180 @@
+//! Readers and writers for plain byte slices.
+
+use super::{check_write_size, Reader, WriteTooLargeError, Writer};
+
+/// Reader that outputs the bytes of a slice.
+///
+/// This lets buffers in memory, like a plaintext or associated data, be
+/// consumed by generic code in the same way as the output of a construction.
+///
+/// ```
+/// use crypto_permutation::io::SliceReader;
+/// use crypto_permutation::Reader;
+///
+/// let mut reader = SliceReader::new(b"hello world");
+/// reader.skip(6).unwrap();
+/// let mut out = [0; 5];
+/// reader.write_to_slice(&mut out).unwrap();
+/// assert_eq!(&out, b"world");
+/// assert_eq!(reader.capacity(), 0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SliceReader<'a>(&'a [u8]);
+
+impl<'a> SliceReader<'a> {
+    /// Create a reader that outputs the bytes of `slice`.
+    pub fn new(slice: &'a [u8]) -> Self {
+        Self(slice)
+    }
+
+    /// The bytes that have not been read yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.0
+    }
+}
+
+impl<'a> From<&'a [u8]> for SliceReader<'a> {
+    fn from(slice: &'a [u8]) -> Self {
+        Self::new(slice)
+    }
+}
+
+impl<'a> Reader for SliceReader<'a> {
+    fn capacity(&self) -> usize {
+        self.0.len()
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(len, self.capacity())?;
+        self.0 = &self.0[len..];
+        Ok(())
+    }
+
+    fn write_to<W: Writer>(&mut self, writer: &mut W, n: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(n, self.capacity())?;
+        let (data, rest) = self.0.split_at(n);
+        writer.write_bytes(data)?;
+        self.0 = rest;
+        Ok(())
+    }
+}
+
+/// Split off the first `len` bytes of `*slice`, leaving the rest in `*slice`.
+fn take_front<'a>(slice: &mut &'a mut [u8], len: usize) -> &'a mut [u8] {
+    let (front, rest) = core::mem::take(slice).split_at_mut(len);
+    *slice = rest;
+    front
+}
+
+/// Writer that copies the bytes written to it into a slice.
+///
+/// Unlike [`BufMut`](crate::BufMut), the slice must be initialised, and
+/// skipped bytes are left unchanged. [`ByteSliceXorWriter`] xors into the
+/// slice instead.
+///
+/// ```
+/// use crypto_permutation::io::ByteSliceWriter;
+/// use crypto_permutation::Writer;
+///
+/// let mut buf = *b"hello world";
+/// let mut writer = ByteSliceWriter::new(&mut buf);
+/// writer.skip(6).unwrap();
+/// writer.write_bytes(b"there").unwrap();
+/// writer.finish();
+/// assert_eq!(&buf, b"hello there");
+/// ```
+#[derive(Debug)]
+pub struct ByteSliceWriter<'a>(&'a mut [u8]);
+
+impl<'a> ByteSliceWriter<'a> {
+    /// Create a writer that copies into `slice`.
+    pub fn new(slice: &'a mut [u8]) -> Self {
+        Self(slice)
+    }
+}
+
+impl<'a> From<&'a mut [u8]> for ByteSliceWriter<'a> {
+    fn from(slice: &'a mut [u8]) -> Self {
+        Self::new(slice)
+    }
+}
+
+impl<'a> Writer for ByteSliceWriter<'a> {
+    type Return = ();
+
+    fn capacity(&self) -> usize {
+        self.0.len()
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(len, self.capacity())?;
+        take_front(&mut self.0, len);
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), WriteTooLargeError> {
+        check_write_size(data.len(), self.capacity())?;
+        take_front(&mut self.0, data.len()).copy_from_slice(data);
+        Ok(())
+    }
+
+    /// No-op.
+    fn finish(self) -> Self::Return {}
+}
+
+/// Writer that xors the bytes written to it into a slice.
+///
+/// Skipped bytes are left unchanged, as if zero bytes were xored into them.
+///
+/// ```
+/// use crypto_permutation::io::ByteSliceXorWriter;
+/// use crypto_permutation::Writer;
+///
+/// let mut buf = [0x0f; 4];
+/// let mut writer = ByteSliceXorWriter::new(&mut buf);
+/// writer.skip(1).unwrap();
+/// writer.write_bytes(&[0xff, 0x0f]).unwrap();
+/// writer.finish();
+/// assert_eq!(buf, [0x0f, 0xf0, 0x00, 0x0f]);
+/// ```
+#[derive(Debug)]
+pub struct ByteSliceXorWriter<'a>(&'a mut [u8]);
+
+impl<'a> ByteSliceXorWriter<'a> {
+    /// Create a writer that xors into `slice`.
+    pub fn new(slice: &'a mut [u8]) -> Self {
+        Self(slice)
+    }
+}
+
+impl<'a> From<&'a mut [u8]> for ByteSliceXorWriter<'a> {
+    fn from(slice: &'a mut [u8]) -> Self {
+        Self::new(slice)
+    }
+}
+
+impl<'a> Writer for ByteSliceXorWriter<'a> {
+    type Return = ();
+
+    fn capacity(&self) -> usize {
+        self.0.len()
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(len, self.capacity())?;
+        take_front(&mut self.0, len);
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), WriteTooLargeError> {
+        check_write_size(data.len(), self.capacity())?;
+        let front = take_front(&mut self.0, data.len());
+        for (byte, data) in front.iter_mut().zip(data) {
+            *byte ^= data;
+        }
+        Ok(())
+    }
+
+    /// No-op.
+    fn finish(self) -> Self::Return {}
+}
@@ -0,0 +1,131 @@
+//! Writers combining other writers.
+
+use super::{check_write_size, WriteTooLargeError, Writer};
+
+/// Writer that forwards every write to two writers.
+///
+/// This e.g. absorbs data into a construction while copying it to an output
+/// buffer. The capacity is the minimum of the capacities of both writers, so a
+/// write either goes to both or to neither. [`Writer::finish`] finishes both
+/// writers and returns both results.
+///
+/// ```
+/// use crypto_permutation::io::{ByteSliceWriter, ByteSliceXorWriter, TeeWriter};
+/// use crypto_permutation::Writer;
+///
+/// let mut copy = [0; 4];
+/// let mut xor = [0xff; 5];
+/// let mut writer = TeeWriter::new(ByteSliceWriter::new(&mut copy), ByteSliceXorWriter::new(&mut xor));
+/// assert_eq!(writer.capacity(), 4);
+/// writer.write_bytes(&[0x0f; 4]).unwrap();
+/// assert!(writer.write_bytes(&[0]).is_err());
+/// writer.finish();
+/// assert_eq!(copy, [0x0f; 4]);
+/// assert_eq!(xor, [0xf0, 0xf0, 0xf0, 0xf0, 0xff]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TeeWriter<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: Writer, B: Writer> TeeWriter<A, B> {
+    /// Create a writer that forwards to both `first` and `second`.
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+
+    /// Return the wrapped writers, without finishing them.
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A: Writer, B: Writer> Writer for TeeWriter<A, B> {
+    type Return = (A::Return, B::Return);
+
+    fn capacity(&self) -> usize {
+        self.first.capacity().min(self.second.capacity())
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(len, self.capacity())?;
+        self.first.skip(len)?;
+        self.second.skip(len)
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), WriteTooLargeError> {
+        check_write_size(data.len(), self.capacity())?;
+        self.first.write_bytes(data)?;
+        self.second.write_bytes(data)
+    }
+
+    /// Finish both writers.
+    fn finish(self) -> Self::Return {
+        (self.first.finish(), self.second.finish())
+    }
+}
+
+/// Writer that fills one writer and then continues into a second.
+///
+/// Writes are split at the point where the first writer is full. This e.g.
+/// scatters a squeezed key and nonce into two separate arrays in one
+/// [`Reader::write_to`](super::Reader::write_to) call. [`Writer::finish`]
+/// finishes both writers and returns both results.
+///
+/// ```
+/// use crypto_permutation::io::{ByteSliceWriter, ChainWriter, SliceReader};
+/// use crypto_permutation::{Reader, Writer};
+///
+/// let mut key = [0; 4];
+/// let mut nonce = [0; 2];
+/// let mut writer = ChainWriter::new(ByteSliceWriter::new(&mut key), ByteSliceWriter::new(&mut nonce));
+/// SliceReader::new(b"keynon").write_to(&mut writer, 6).unwrap();
+/// writer.finish();
+/// assert_eq!(&key, b"keyn");
+/// assert_eq!(&nonce, b"on");
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChainWriter<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: Writer, B: Writer> ChainWriter<A, B> {
+    /// Create a writer that fills `first` and then continues into `second`.
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+
+    /// Return the wrapped writers, without finishing them.
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A: Writer, B: Writer> Writer for ChainWriter<A, B> {
+    type Return = (A::Return, B::Return);
+
+    fn capacity(&self) -> usize {
+        self.first.capacity().saturating_add(self.second.capacity())
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(len, self.capacity())?;
+        let front = len.min(self.first.capacity());
+        self.first.skip(front)?;
+        self.second.skip(len - front)
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), WriteTooLargeError> {
+        check_write_size(data.len(), self.capacity())?;
+        let (front, back) = data.split_at(data.len().min(self.first.capacity()));
+        self.first.write_bytes(front)?;
+        self.second.write_bytes(back)
+    }
+
+    /// Finish both writers.
+    fn finish(self) -> Self::Return {
+        (self.first.finish(), self.second.finish())
+    }
+}
@@ -0,0 +1,140 @@
+//! Writer instrumentation.
+
+use super::{WriteTooLargeError, Writer};
+
+/// Number of buckets of a [`WriteHistogram`].
+const BUCKETS: usize = usize::BITS as usize + 1;
+
+/// Histogram of the sizes of the writes to a [`CountingWriter`].
+///
+/// Bucket `0` counts empty writes, and bucket `k > 0` counts writes of
+/// `2^(k-1)` up to and excluding `2^k` bytes.
+#[derive(Debug, Clone)]
+pub struct WriteHistogram {
+    buckets: [usize; BUCKETS],
+}
+
+impl Default for WriteHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: [0; BUCKETS],
+        }
+    }
+}
+
+impl WriteHistogram {
+    /// The number of writes in every bucket.
+    pub fn buckets(&self) -> &[usize] {
+        &self.buckets
+    }
+
+    /// The index of the bucket for writes of `len` bytes.
+    pub fn bucket_of(len: usize) -> usize {
+        (usize::BITS - len.leading_zeros()) as usize
+    }
+
+    /// Count a write of `len` bytes.
+    fn record(&mut self, len: usize) {
+        self.buckets[Self::bucket_of(len)] += 1;
+    }
+}
+
+/// Writer that counts the bytes written to it and delegates to an inner
+/// writer.
+///
+/// Skipped bytes count as written. Failed writes are not counted. This e.g.
+/// enforces length fields of a protocol, or analyses how data is absorbed into
+/// a construction. With [`Self::with_histogram`], the sizes of the
+/// [`Writer::write_bytes`] calls are collected in a [`WriteHistogram`] too.
+///
+/// ```
+/// use crypto_permutation::io::{ByteSliceWriter, CountingWriter, WriteHistogram};
+/// use crypto_permutation::Writer;
+///
+/// let mut buf = [0; 16];
+/// let mut writer = CountingWriter::with_histogram(ByteSliceWriter::new(&mut buf));
+/// writer.write_bytes(b"hello").unwrap();
+/// writer.skip(2).unwrap();
+/// writer.write_bytes(b"world").unwrap();
+/// assert_eq!(writer.bytes_written(), 12);
+/// assert_eq!(writer.write_calls(), 2);
+/// let histogram = writer.histogram().unwrap();
+/// assert_eq!(histogram.buckets()[WriteHistogram::bucket_of(5)], 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CountingWriter<W> {
+    inner: W,
+    bytes_written: usize,
+    write_calls: usize,
+    histogram: Option<WriteHistogram>,
+}
+
+impl<W: Writer> CountingWriter<W> {
+    /// Count the bytes written to `inner`.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            bytes_written: 0,
+            write_calls: 0,
+            histogram: None,
+        }
+    }
+
+    /// Count the bytes written to `inner`, and collect a histogram of the
+    /// write sizes.
+    pub fn with_histogram(inner: W) -> Self {
+        Self {
+            histogram: Some(WriteHistogram::default()),
+            ..Self::new(inner)
+        }
+    }
+
+    /// Number of bytes written or skipped.
+    pub fn bytes_written(&self) -> usize {
+        self.bytes_written
+    }
+
+    /// Number of successful [`Writer::write_bytes`] calls.
+    pub fn write_calls(&self) -> usize {
+        self.write_calls
+    }
+
+    /// The histogram of the write sizes, when created with
+    /// [`Self::with_histogram`].
+    pub fn histogram(&self) -> Option<&WriteHistogram> {
+        self.histogram.as_ref()
+    }
+
+    /// Return the wrapped writer, without finishing it.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Writer> Writer for CountingWriter<W> {
+    type Return = W::Return;
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), WriteTooLargeError> {
+        self.inner.skip(len)?;
+        self.bytes_written += len;
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), WriteTooLargeError> {
+        self.inner.write_bytes(data)?;
+        self.bytes_written += data.len();
+        self.write_calls += 1;
+        if let Some(histogram) = &mut self.histogram {
+            histogram.record(data.len());
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Self::Return {
+        self.inner.finish()
+    }
+}
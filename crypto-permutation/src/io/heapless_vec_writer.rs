@@ -0,0 +1,77 @@
+//! Writer into a fixed capacity vector, without allocations.
+
+use super::{check_write_size, WriteTooLargeError, Writer};
+
+/// Writer that appends the bytes written to it to a [`heapless::Vec`] with
+/// room for `N` bytes.
+///
+/// This collects output, like tags or ciphertexts, without allocations on
+/// targets without a heap. [`Writer::finish`] returns the vector.
+///
+/// ```
+/// use crypto_permutation::io::{HeaplessVecWriter, SliceReader};
+/// use crypto_permutation::{Reader, Writer};
+///
+/// let mut writer = HeaplessVecWriter::<16>::new();
+/// SliceReader::new(b"tag").write_to(&mut writer, 3).unwrap();
+/// assert_eq!(writer.capacity(), 13);
+/// assert_eq!(writer.finish(), b"tag");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HeaplessVecWriter<const N: usize> {
+    vec: heapless::Vec<u8, N>,
+}
+
+impl<const N: usize> HeaplessVecWriter<N> {
+    /// Create a writer into an empty vector.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The bytes written so far.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.vec
+    }
+
+    /// Return the vector, like [`Writer::finish`].
+    pub fn into_inner(self) -> heapless::Vec<u8, N> {
+        self.vec
+    }
+}
+
+impl<const N: usize> From<heapless::Vec<u8, N>> for HeaplessVecWriter<N> {
+    /// Create a writer appending to `vec`.
+    fn from(vec: heapless::Vec<u8, N>) -> Self {
+        Self { vec }
+    }
+}
+
+impl<const N: usize> Writer for HeaplessVecWriter<N> {
+    type Return = heapless::Vec<u8, N>;
+
+    fn capacity(&self) -> usize {
+        N - self.vec.len()
+    }
+
+    /// Append `len` zero bytes.
+    fn skip(&mut self, len: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(len, self.capacity())?;
+        self.vec
+            .resize(self.vec.len() + len, 0)
+            .expect("length checked against the capacity");
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), WriteTooLargeError> {
+        check_write_size(data.len(), self.capacity())?;
+        self.vec
+            .extend_from_slice(data)
+            .expect("length checked against the capacity");
+        Ok(())
+    }
+
+    /// Return the vector.
+    fn finish(self) -> Self::Return {
+        self.vec
+    }
+}
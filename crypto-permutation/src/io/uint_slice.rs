@@ -0,0 +1,547 @@
+//! Readers and writers for arrays of unsigned integers, generic over the byte
+//! order. Instantiated by the little and big endian reader and writer modules.
+//!
+//! When the byte order is the native one of the target, the in-memory
+//! representation of the integers is their encoding, so runs of whole integers
+//! are copied in bulk instead of converted one by one.
+
+/// An unsigned integer type.
+///
+/// # Safety
+/// The type must not contain padding bytes and any bytes must be a valid
+/// value.
+pub(crate) unsafe trait Uint: Copy {}
+
+// SAFETY: unsigned integers have no padding and are valid for any bytes.
+unsafe impl Uint for u8 {}
+// SAFETY: see above.
+unsafe impl Uint for u16 {}
+// SAFETY: see above.
+unsafe impl Uint for u32 {}
+// SAFETY: see above.
+unsafe impl Uint for u64 {}
+// SAFETY: see above.
+unsafe impl Uint for u128 {}
+
+/// View `words` as their bytes in memory.
+pub(crate) fn as_bytes<U: Uint>(words: &[U]) -> &[u8] {
+    let len = core::mem::size_of_val(words);
+    // SAFETY: `U` has no padding, so `words` consists of `len` initialised
+    // bytes.
+    unsafe { core::slice::from_raw_parts(words.as_ptr().cast(), len) }
+}
+
+/// Mutably view `words` as their bytes in memory.
+pub(crate) fn as_bytes_mut<U: Uint>(words: &mut [U]) -> &mut [u8] {
+    let len = core::mem::size_of_val(words);
+    // SAFETY: `U` has no padding, so `words` consists of `len` initialised
+    // bytes, and any bytes are a valid `U`.
+    unsafe { core::slice::from_raw_parts_mut(words.as_mut_ptr().cast(), len) }
+}
+
+macro_rules! impl_uint_slice_reader {
+    ($name:ident, $uint:ty, $endian:literal, $to_bytes:ident) => {
+        #[doc = concat!(
+            "Reader that reads from a buffer of `", stringify!($uint), "`s, and outputs their bytes in ",
+            $endian, " endian order.\n\nDoes nothing fancy except for native-endian to ", $endian,
+            " endian conversion, which is a bulk copy on ", $endian, " endian targets."
+        )]
+        pub struct $name<'a> {
+            /// A slice of the part of the buffer that can still be read.
+            buffer: &'a [$uint],
+            /// Number of bytes of the first element of `buffer` that have already been
+            /// read.
+            partial_read: u8,
+        }
+
+        impl<'a> $name<'a> {
+            /// Number of bytes that `$uint` is long.
+            const UINT_SIZE: usize = core::mem::size_of::<$uint>();
+            /// Constant for compile time assertion that `UINT_SIZE` fits a `u8`.
+            const _CHECK: () = {
+                let size = Self::UINT_SIZE;
+                assert!(size as u8 as usize == size)
+            };
+
+            /// `self.partial_read as usize`
+            fn partial_read_usize(&self) -> usize {
+                usize::from(self.partial_read)
+            }
+
+            /// Step `n` `$uint`s forward in the buffer view.
+            fn increment_view(&mut self, n: usize) {
+                // We temporarily take ownership of `self.buffer` by swapping in an empty slice
+                // instead. We can then mutate `buffer` without changing the lifetime and swap
+                // it back in `self`.
+                let mut buffer: &'a [$uint] = core::mem::take(&mut self.buffer);
+                buffer = &buffer[n..];
+                let _ = core::mem::replace(&mut self.buffer, buffer);
+            }
+
+            /// Create a new reader that reads bytes `buffer`, and outputs it's bytes
+            /// in the byte order of the reader.
+            pub fn new(buffer: &'a [$uint]) -> Self {
+                Self {
+                    buffer,
+                    partial_read: 0,
+                }
+            }
+        }
+
+        // `UINT_SIZE` is 1 for `u8`, making some modulo operations trivial.
+        #[allow(clippy::modulo_one)]
+        impl<'a> $crate::io::Reader for $name<'a> {
+            fn capacity(&self) -> usize {
+                self.buffer.len() * Self::UINT_SIZE - self.partial_read_usize()
+            }
+
+            fn skip(&mut self, mut n: usize) -> Result<(), $crate::io::WriteTooLargeError> {
+                $crate::io::check_write_size(n, self.capacity())?;
+
+                if self.partial_read != 0 {
+                    $crate::io::util::cold();
+                    let partial_read =
+                        core::cmp::min(n, Self::UINT_SIZE - self.partial_read_usize());
+                    self.partial_read += partial_read as u8;
+                    n -= partial_read;
+                    if self.partial_read == Self::UINT_SIZE as u8 {
+                        self.increment_view(1);
+                        self.partial_read = 0;
+                    }
+                }
+
+                let remainder = n % Self::UINT_SIZE;
+                n -= remainder;
+                n /= Self::UINT_SIZE;
+                self.increment_view(n);
+
+                if remainder != 0 {
+                    $crate::io::util::cold();
+                    self.partial_read = remainder as u8;
+                }
+
+                Ok(())
+            }
+
+            fn write_to<W: $crate::io::Writer>(
+                &mut self,
+                writer: &mut W,
+                mut n: usize,
+            ) -> Result<(), $crate::io::WriteTooLargeError> {
+                $crate::io::check_write_size(n, self.capacity())?;
+                $crate::io::check_write_size(n, writer.capacity())?;
+
+                if self.partial_read != 0 {
+                    $crate::io::util::cold();
+                    let partial_read =
+                        core::cmp::min(n, Self::UINT_SIZE - self.partial_read_usize());
+                    {
+                        let old_partial_read = self.partial_read_usize();
+                        self.partial_read += partial_read as u8;
+                        let bytes = self.buffer[0].$to_bytes();
+                        writer.write_bytes(&bytes[old_partial_read..self.partial_read_usize()])?;
+                    }
+                    n -= partial_read;
+                    if self.partial_read == Self::UINT_SIZE as u8 {
+                        self.increment_view(1);
+                        self.partial_read = 0;
+                    }
+                }
+
+                let remainder = n % Self::UINT_SIZE;
+                n -= remainder;
+                n /= Self::UINT_SIZE;
+                let words = &self.buffer[..n];
+                if cfg!(target_endian = $endian) {
+                    writer.write_bytes($crate::io::uint_slice::as_bytes(words))?;
+                } else {
+                    for word in words {
+                        writer.write_bytes(word.$to_bytes().as_ref())?;
+                    }
+                }
+                self.increment_view(n);
+
+                if remainder != 0 {
+                    $crate::io::util::cold();
+                    let bytes = self.buffer[0].$to_bytes();
+                    writer.write_bytes(&bytes[..remainder])?;
+                    self.partial_read = remainder as u8;
+                }
+
+                Ok(())
+            }
+        }
+    };
+}
+pub(crate) use impl_uint_slice_reader;
+
+// Requires separetely provided functions `write_word`, `write_native` and
+// `reset_partial_block` for `$name`.
+macro_rules! impl_uint_slice_writer_core {
+    ($name:ident, $uint:ty, $endian:literal, $from_bytes:ident) => {
+        #[doc = concat!(
+            "Writer that writes/xors into a buffer of `", stringify!($uint), "`s, interpreting bytes as ",
+            $endian, " endian encoded integers.\n\nDoes nothing fancy except for ", $endian,
+            " endian to native-endian conversion, which is a bulk copy on ", $endian,
+            " endian targets."
+        )]
+        pub struct $name<'a> {
+            /// A slice of the part of the buffer that can still be written to.
+            buffer: &'a mut [$uint],
+            /// Small buffer to aggregate bytes until we have enough for a `$uint`.
+            partial_block: [u8; core::mem::size_of::<$uint>()],
+            /// Number of bytes currently cached in `partial_block`.
+            partial_filled: u8,
+        }
+
+        impl<'a> $name<'a> {
+            /// Number of bytes that `$uint` is long.
+            const UINT_SIZE: usize = core::mem::size_of::<$uint>();
+            /// Constant for compile time assertion that `UINT_SIZE` fits a `u8`.
+            const _CHECK: () = {
+                let size = Self::UINT_SIZE;
+                assert!(size as u8 as usize == size)
+            };
+
+            /// `self.partial_filled as usize`
+            fn partial_filled_usize(&self) -> usize {
+                usize::from(self.partial_filled)
+            }
+
+            /// Step `n` `$uint`s forward in the buffer view.
+            fn increment_view(&mut self, n: usize) {
+                // We temporarily take ownership of `self.buffer` by swapping in an empty slice
+                // instead. We can then mutate `buffer` without changing the lifetime and swap
+                // it back in `self`.
+                let mut buffer: &'a mut [$uint] = core::mem::take(&mut self.buffer);
+                buffer = &mut buffer[n..];
+                let _ = core::mem::replace(&mut self.buffer, buffer);
+            }
+
+            /// Write the partial block to the next `$uint` of the buffer.
+            fn write_partial_block(&mut self) {
+                let x = <$uint>::$from_bytes(self.partial_block);
+                Self::write_word(&mut self.buffer[0], x);
+                self.increment_view(1);
+                self.partial_filled = 0;
+            }
+
+            /// Write `data`, whole encoded `$uint`s, to the first elements of the
+            /// buffer and step past them.
+            fn write_words(&mut self, data: &[u8]) {
+                let len = data.len() / Self::UINT_SIZE;
+                if cfg!(target_endian = $endian) {
+                    self.write_native(data);
+                } else {
+                    let words = &mut self.buffer[..len];
+                    for (word, chunk) in words.iter_mut().zip(data.chunks_exact(Self::UINT_SIZE)) {
+                        let chunk = chunk.try_into().unwrap();
+                        Self::write_word(word, <$uint>::$from_bytes(chunk));
+                    }
+                }
+                self.increment_view(len);
+            }
+
+            /// Create a new writer that writes/xors into `buffer`, interpreting bytes
+            /// as integers encoded in the byte order of the writer.
+            pub fn new(buffer: &'a mut [$uint]) -> Self {
+                Self {
+                    buffer,
+                    partial_block: [0; core::mem::size_of::<$uint>()],
+                    partial_filled: 0,
+                }
+            }
+        }
+
+        // `UINT_SIZE` is 1 for `u8`, making some modulo operations trivial.
+        #[allow(clippy::modulo_one)]
+        impl<'a> $crate::io::Writer for $name<'a> {
+            type Return = ();
+
+            fn capacity(&self) -> usize {
+                self.buffer.len() * Self::UINT_SIZE - self.partial_filled_usize()
+            }
+
+            fn skip(&mut self, mut n: usize) -> Result<(), $crate::io::WriteTooLargeError> {
+                $crate::io::check_write_size(n, self.capacity())?;
+
+                if self.partial_filled != 0 {
+                    $crate::io::util::cold();
+                    let add_partial =
+                        core::cmp::min(n, Self::UINT_SIZE - self.partial_filled_usize());
+                    self.partial_filled += add_partial as u8;
+                    n -= add_partial;
+                    if self.partial_filled == Self::UINT_SIZE as u8 {
+                        self.write_partial_block();
+                    }
+                }
+
+                let remainder = n % Self::UINT_SIZE;
+                n -= remainder;
+                n /= Self::UINT_SIZE;
+                self.increment_view(n);
+
+                if remainder != 0 {
+                    $crate::io::util::cold();
+                    self.partial_filled = remainder as u8;
+                    self.reset_partial_block();
+                }
+
+                Ok(())
+            }
+
+            fn write_bytes(&mut self, mut data: &[u8]) -> Result<(), $crate::io::WriteTooLargeError> {
+                $crate::io::check_write_size(data.len(), self.capacity())?;
+
+                if self.partial_filled != 0 {
+                    $crate::io::util::cold();
+                    let add_partial =
+                        core::cmp::min(data.len(), Self::UINT_SIZE - self.partial_filled_usize());
+                    let old_partial_filled = self.partial_filled_usize();
+                    self.partial_filled += add_partial as u8;
+                    let partial =
+                        &mut self.partial_block[old_partial_filled..self.partial_filled.into()];
+                    partial.copy_from_slice(&data[..add_partial]);
+                    data = &data[add_partial..];
+                    if self.partial_filled == Self::UINT_SIZE as u8 {
+                        self.write_partial_block();
+                    }
+                }
+
+                let words = data.len() / Self::UINT_SIZE;
+                let (full, remainder) = data.split_at(words * Self::UINT_SIZE);
+                self.write_words(full);
+
+                if !remainder.is_empty() {
+                    $crate::io::util::cold();
+                    self.partial_filled = remainder.len() as u8;
+                    self.reset_partial_block();
+                    let n = remainder.len();
+                    self.partial_block[..n].copy_from_slice(remainder);
+                }
+
+                Ok(())
+            }
+
+            fn finish(mut self) -> Self::Return {
+                if self.partial_filled != 0 {
+                    $crate::io::util::cold();
+                    self.write_partial_block();
+                }
+            }
+        }
+    };
+}
+pub(crate) use impl_uint_slice_writer_core;
+
+macro_rules! impl_uint_slice_writer {
+    ($name:ident, $uint:ty, $endian:literal, $to_bytes:ident, $from_bytes:ident) => {
+        $crate::io::uint_slice::impl_uint_slice_writer_core!($name, $uint, $endian, $from_bytes);
+
+        impl<'a> $name<'a> {
+            /// Write `val` to `word`.
+            fn write_word(word: &mut $uint, val: $uint) {
+                *word = val;
+            }
+
+            /// Write `bytes`, whole integers in memory order, to the first
+            /// elements of the buffer.
+            fn write_native(&mut self, bytes: &[u8]) {
+                let words = &mut self.buffer[..bytes.len() / Self::UINT_SIZE];
+                $crate::io::uint_slice::as_bytes_mut(words).copy_from_slice(bytes);
+            }
+
+            /// Reset the partial block to a new clean state before use.
+            fn reset_partial_block(&mut self) {
+                self.partial_block = self.buffer[0].$to_bytes();
+            }
+        }
+    };
+}
+pub(crate) use impl_uint_slice_writer;
+
+macro_rules! impl_uint_slice_xor_writer {
+    ($name:ident, $uint:ty, $endian:literal, $from_bytes:ident) => {
+        $crate::io::uint_slice::impl_uint_slice_writer_core!($name, $uint, $endian, $from_bytes);
+
+        impl<'a> $name<'a> {
+            /// Xor `val` into `word`.
+            fn write_word(word: &mut $uint, val: $uint) {
+                *word ^= val;
+            }
+
+            /// Xor `bytes`, whole integers in memory order, into the first
+            /// elements of the buffer.
+            fn write_native(&mut self, bytes: &[u8]) {
+                let words = &mut self.buffer[..bytes.len() / Self::UINT_SIZE];
+                let buffer = $crate::io::uint_slice::as_bytes_mut(words);
+                for (byte, data) in buffer.iter_mut().zip(bytes) {
+                    *byte ^= data;
+                }
+            }
+
+            /// Reset the partial block to a new clean state before use.
+            fn reset_partial_block(&mut self) {
+                self.partial_block = [0; core::mem::size_of::<$uint>()];
+            }
+        }
+    };
+}
+pub(crate) use impl_uint_slice_xor_writer;
+
+#[cfg(all(test, feature = "io_uint_u32"))]
+mod tests {
+    /// Words of the test buffers, with distinct bytes.
+    const WORDS: [u32; 5] = [0x03020100, 0x07060504, 0x0b0a0908, 0x0f0e0d0c, 0x13121110];
+    /// Length of the test buffers in bytes.
+    const LEN: usize = 4 * WORDS.len();
+
+    /// Every split of the buffer into a skipped part and two written or read
+    /// parts: `(skip, first, second)`. Covers unaligned starts, and parts that
+    /// end in the middle of a word or span several whole words.
+    fn splits() -> impl Iterator<Item = (usize, usize, usize)> {
+        (0..=LEN).flat_map(|skip| {
+            (0..=LEN - skip).flat_map(move |first| {
+                (0..=LEN - skip - first).map(move |second| (skip, first, second))
+            })
+        })
+    }
+
+    /// A message of `LEN` bytes to write into the buffers.
+    fn message() -> [u8; LEN] {
+        core::array::from_fn(|i| 0xa0 ^ i as u8)
+    }
+
+    macro_rules! uint_slice_tests {
+        (
+            $mod:ident,
+            $feature:literal,
+            $reader_mod:ident::$reader:ident,
+            $writer_mod:ident::$writer:ident,
+            $xor_writer:ident,
+            $to_bytes:ident
+        ) => {
+            #[cfg(feature = $feature)]
+            mod $mod {
+                use super::{message, splits, LEN, WORDS};
+                use crate::io::$reader_mod::$reader;
+                use crate::io::$writer_mod::{$writer, $xor_writer};
+                use crate::io::{Reader, Writer};
+
+                /// Encode `words` byte by byte.
+                fn encode(words: &[u32]) -> [u8; LEN] {
+                    let mut bytes = [0; LEN];
+                    for (chunk, word) in bytes.chunks_exact_mut(4).zip(words) {
+                        for (i, byte) in chunk.iter_mut().enumerate() {
+                            *byte = word.$to_bytes()[i];
+                        }
+                    }
+                    bytes
+                }
+
+                #[test]
+                fn reader() {
+                    let expected = encode(&WORDS);
+                    for (skip, first, second) in splits() {
+                        let mut reader = $reader::new(&WORDS);
+                        reader.skip(skip).unwrap();
+                        let mut out = [0; LEN];
+                        reader.write_to_slice(&mut out[..first]).unwrap();
+                        reader
+                            .write_to_slice(&mut out[first..first + second])
+                            .unwrap();
+                        assert_eq!(
+                            out[..first + second],
+                            expected[skip..skip + first + second],
+                            "split: {:?}",
+                            (skip, first, second)
+                        );
+                        assert_eq!(reader.capacity(), LEN - skip - first - second);
+                    }
+                    let mut reader = $reader::new(&WORDS);
+                    reader.skip(3).unwrap();
+                    assert!(reader.skip(LEN - 2).is_err());
+                    assert!(reader.write_to_slice(&mut [0; LEN - 2]).is_err());
+                }
+
+                #[test]
+                fn copy_writer() {
+                    let message = message();
+                    for (skip, first, second) in splits() {
+                        let mut words = WORDS;
+                        let mut writer = $writer::new(&mut words);
+                        writer.skip(skip).unwrap();
+                        writer.write_bytes(&message[..first]).unwrap();
+                        writer.write_bytes(&message[first..first + second]).unwrap();
+                        assert_eq!(writer.capacity(), LEN - skip - first - second);
+                        writer.finish();
+
+                        let mut expected = encode(&WORDS);
+                        expected[skip..skip + first + second]
+                            .copy_from_slice(&message[..first + second]);
+                        assert_eq!(
+                            encode(&words),
+                            expected,
+                            "split: {:?}",
+                            (skip, first, second)
+                        );
+                    }
+                    let mut words = WORDS;
+                    let mut writer = $writer::new(&mut words);
+                    writer.skip(3).unwrap();
+                    assert!(writer.skip(LEN - 2).is_err());
+                    assert!(writer.write_bytes(&[0; LEN - 2]).is_err());
+                }
+
+                #[test]
+                fn xor_writer() {
+                    let message = message();
+                    for (skip, first, second) in splits() {
+                        let mut words = WORDS;
+                        let mut writer = $xor_writer::new(&mut words);
+                        writer.skip(skip).unwrap();
+                        writer.write_bytes(&message[..first]).unwrap();
+                        writer.write_bytes(&message[first..first + second]).unwrap();
+                        assert_eq!(writer.capacity(), LEN - skip - first - second);
+                        writer.finish();
+
+                        let mut expected = encode(&WORDS);
+                        for (byte, data) in
+                            expected[skip..].iter_mut().zip(&message[..first + second])
+                        {
+                            *byte ^= data;
+                        }
+                        assert_eq!(
+                            encode(&words),
+                            expected,
+                            "split: {:?}",
+                            (skip, first, second)
+                        );
+                    }
+                    let mut words = WORDS;
+                    let mut writer = $xor_writer::new(&mut words);
+                    writer.skip(3).unwrap();
+                    assert!(writer.skip(LEN - 2).is_err());
+                    assert!(writer.write_bytes(&[0; LEN - 2]).is_err());
+                }
+            }
+        };
+    }
+
+    uint_slice_tests!(
+        le,
+        "io_le_uint_slice",
+        le_uint_slice_reader::LeU32SliceReader,
+        le_uint_slice_writer::LeU32SliceWriter,
+        LeU32SliceXorWriter,
+        to_le_bytes
+    );
+    uint_slice_tests!(
+        be,
+        "io_be_uint_slice",
+        be_uint_slice_reader::BeU32SliceReader,
+        be_uint_slice_writer::BeU32SliceWriter,
+        BeU32SliceXorWriter,
+        to_be_bytes
+    );
+}
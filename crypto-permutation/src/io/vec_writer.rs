@@ -0,0 +1,81 @@
+//! Writer into a growable vector.
+
+use super::{check_write_size, WriteTooLargeError, Writer};
+use alloc::vec::Vec;
+
+/// Writer that appends the bytes written to it to a [`Vec`].
+///
+/// The vector grows as needed, so output streams can be collected without
+/// knowing their length in advance. [`Writer::finish`] returns the vector.
+///
+/// ```
+/// use crypto_permutation::io::{SliceReader, VecWriter};
+/// use crypto_permutation::{Reader, Writer};
+///
+/// let mut writer = VecWriter::new();
+/// writer.write_bytes(b"hello").unwrap();
+/// SliceReader::new(b" world").write_to(&mut writer, 6).unwrap();
+/// assert_eq!(writer.finish(), b"hello world");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct VecWriter {
+    vec: Vec<u8>,
+}
+
+impl VecWriter {
+    /// Create a writer into an empty vector.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a writer into an empty vector with room for `capacity` bytes
+    /// before it reallocates.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            vec: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// The bytes written so far.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.vec
+    }
+
+    /// Return the vector, like [`Writer::finish`].
+    pub fn into_inner(self) -> Vec<u8> {
+        self.vec
+    }
+}
+
+impl From<Vec<u8>> for VecWriter {
+    /// Create a writer appending to `vec`.
+    fn from(vec: Vec<u8>) -> Self {
+        Self { vec }
+    }
+}
+
+impl Writer for VecWriter {
+    type Return = Vec<u8>;
+
+    /// Returns `usize::MAX`: the vector grows as needed.
+    fn capacity(&self) -> usize {
+        usize::MAX
+    }
+
+    /// Append `len` zero bytes.
+    fn skip(&mut self, len: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(len, self.capacity())?;
+        self.vec.resize(self.vec.len() + len, 0);
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), WriteTooLargeError> {
+        self.vec.extend_from_slice(data);
+        Ok(())
+    }
+
+    /// Return the vector.
+    fn finish(self) -> Self::Return {
+        self.vec
+    }
+}
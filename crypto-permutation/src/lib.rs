@@ -27,22 +27,68 @@
 //!   generate an output stream. This is used for example for generating an
 //!   output stream from a deck function.
 //!
+//! [`SliceReader`](io::SliceReader) reads from a byte slice, so buffers in
+//! memory can be consumed by generic code like any other [`Reader`].
+//! [`ByteSliceWriter`](io::ByteSliceWriter) and
+//! [`ByteSliceXorWriter`](io::ByteSliceXorWriter) copy or xor into a byte
+//! slice, e.g. for byte oriented permutation states. With the `alloc`
+//! crate-feature, `VecWriter` collects output in a growable vector, and with
+//! the `heapless` crate-feature, `HeaplessVecWriter` in a fixed capacity
+//! `heapless::Vec`, without allocations.
+//!
+//! Bit strings that are not a whole number of bytes, like frame bits, are
+//! written and read through the [`BitWriter`](io::BitWriter) and
+//! [`BitReader`](io::BitReader) adapters.
+//!
+//! [`TeeWriter`](io::TeeWriter) forwards every write to two writers, e.g. to
+//! absorb data into a construction while copying it to an output buffer.
+//! [`ChainWriter`](io::ChainWriter) fills one writer and then continues into a
+//! second, e.g. to squeeze a key and a nonce into separate arrays at once.
+//! [`CountingWriter`](io::CountingWriter) counts the bytes written through it,
+//! e.g. to enforce length fields or to analyse absorption patterns.
+//!
 //! # Permutations
 //! Cryptographic permutations are abstracted over using two traits:
 //! * [`PermutationState`]: A fixed size buffer cryptographic permutations can
 //!   act on. It can have specific data layout (e.g. byteorder) requirements, as
 //!   long as it is possible to clone states, xor states together and xor and
 //!   write bytes into (using the [`Writer`] trait) and read bytes from (using
-//!   the [`Reader`] trait).
+//!   the [`Reader`] trait). With the `io_le_uint_slice` crate-feature,
+//!   `UintArrayState` is a generic state consisting of little endian unsigned
+//!   integers, for permutations without state specific accessors, like Gimli.
+//!   The `io_be_uint_slice` crate-feature provides readers and writers for big
+//!   endian unsigned integers, for permutations specified in big endian byte
+//!   order.
 //! * [`Permutation`]: A cryptographic permutation. It acts on a specific
 //!   [`PermutationState`].
 //!
+//! Permutations that can process multiple states at once (e.g. using SIMD
+//! instructions) can additionally implement [`ParallelPermutation`].
+//! Permutations with an efficiently computable inverse can implement
+//! [`InvertiblePermutation`]. Families of permutations selected by a public
+//! tweak implement [`TweakablePermutation`]. Permutations that can fail, like
+//! ones backed by hardware accelerators, implement [`TryPermutation`]. With the
+//! `async` crate-feature, permutations backed by interrupt driven hardware can
+//! implement `AsyncPermutation`. [`DispatchPermutation`] selects the fastest
+//! of several implementations of a permutation at runtime, based on the
+//! available CPU features.
+//!
 //! # Deck functions
 //! A deck function is a Doubly Extendable Cryptographic Keyed function. It is
 //! abstracted over by the [`DeckFunction`] trait. It allows repeatedly
 //! inputting and outputting variable length streams of data. For inputting
 //! data, the [`Writer`] trait is used, and for outputting the [`Reader`] trait
-//! is used.
+//! is used. Deck functions whose state can be saved to bytes and restored
+//! later implement [`ExportableDeckFunction`].
+//!
+//! # Sponges and duplexes
+//! The sponge construction turns a permutation into a hash function or
+//! extendable output function, and the duplex construction into a duplex
+//! object. The [`SpongeConstruction`] and [`DuplexState`] traits abstract over
+//! their implementations, e.g. hardware or masked sponges, so modes on top of
+//! them can be generic over the implementation. Implementations access the
+//! outer part of the state, its first `rate` bytes, through a [`StateView`],
+//! which keeps them from touching the capacity.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::needless_lifetimes)]
@@ -50,12 +96,26 @@
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
+pub mod block;
+pub use block::Block;
+
 pub mod buffer;
 pub use buffer::BufMut;
 
 pub mod io;
 pub use io::{CryptoReader, Reader, WriteTooLargeError, Writer};
 
+#[cfg(feature = "io_le_uint_slice")]
+pub mod uint_array_state;
+#[cfg(feature = "io_le_uint_slice")]
+pub use uint_array_state::UintArrayState;
+
+pub mod dispatch;
+pub use dispatch::DispatchPermutation;
+
+pub mod state_view;
+pub use state_view::StateView;
+
 /// A state where a cryptographic permutation acts upon.
 ///
 /// The API of this trait consists of two parts: the generic and the specific
@@ -68,12 +128,15 @@ pub use io::{CryptoReader, Reader, WriteTooLargeError, Writer};
 /// The generic API gives abstract ways to read, write and xor bytes from/to the
 /// state. It consists of
 /// * [`Self::SIZE`] (constant)
+/// * [`Self::Block`] (type)
 /// * [`Self::StateReader`] (type)
 /// * [`Self::CopyWriter`] (type)
 /// * [`Self::XorWriter`] (type)
 /// * [`Self::reader`] (method)
 /// * [`Self::copy_writer`] (method)
 /// * [`Self::xor_writer`] (method)
+/// * [`Self::xor_bytes_at`] (provided method)
+/// * [`Self::copy_bytes_at`] (provided method)
 ///
 /// Besides these trait items, there are also the [`Default`], [`Clone`] and
 /// [`BitXorAssign`] trait bounds.
@@ -92,6 +155,11 @@ pub trait PermutationState: Default + Clone + for<'a> core::ops::BitXorAssign<&'
 
     /// Number of bytes of the state.
     const SIZE: usize;
+    /// Byte array of exactly [`Self::SIZE`] bytes, e.g. `[u8; 48]`.
+    ///
+    /// Generic code can use it for stack buffers holding one block, without
+    /// allocations. Start from [`Block::ZERO`].
+    type Block: Block;
     /// [`Reader`] to read bytes from the state.
     type StateReader<'a>: Reader
     where
@@ -112,6 +180,36 @@ pub trait PermutationState: Default + Clone + for<'a> core::ops::BitXorAssign<&'
     /// Create a [`Writer`] to xor into the state.
     fn xor_writer<'a>(&'a mut self) -> Self::XorWriter<'a>;
 
+    /// Xor `data` into the state, starting at byte `offset`.
+    ///
+    /// This is equivalent to skipping `offset` bytes of a
+    /// [`Self::xor_writer`] and writing `data` to it, but implementations can
+    /// override it with a faster version without the [`Writer`] state machine.
+    ///
+    /// # Panics
+    /// Panics when `offset + data.len() > Self::SIZE`.
+    fn xor_bytes_at(&mut self, offset: usize, data: &[u8]) {
+        let mut writer = self.xor_writer();
+        writer.skip(offset).unwrap();
+        writer.write_bytes(data).unwrap();
+        writer.finish();
+    }
+
+    /// Write `data` into the state, starting at byte `offset`.
+    ///
+    /// This is equivalent to skipping `offset` bytes of a
+    /// [`Self::copy_writer`] and writing `data` to it, but implementations can
+    /// override it with a faster version without the [`Writer`] state machine.
+    ///
+    /// # Panics
+    /// Panics when `offset + data.len() > Self::SIZE`.
+    fn copy_bytes_at(&mut self, offset: usize, data: &[u8]) {
+        let mut writer = self.copy_writer();
+        writer.skip(offset).unwrap();
+        writer.write_bytes(data).unwrap();
+        writer.finish();
+    }
+
     // # Specific API
 
     /// Representation of the state the permutation works on.
@@ -141,6 +239,202 @@ pub trait Permutation: Copy + Default {
     fn apply(self, state: &mut Self::State);
 }
 
+/// A cryptographic permutation that can be applied to several states at once.
+///
+/// Implementations typically use SIMD instructions to process multiple states
+/// in parallel, and may select the kernel to use at runtime. The default
+/// implementation processes the states one by one, so any [`Permutation`] can
+/// implement this trait without further work.
+pub trait ParallelPermutation: Permutation {
+    /// Number of states processed at once by the fastest kernel available.
+    /// Passing a multiple of this number of states to
+    /// [`Self::apply_parallel`] gives the best performance.
+    fn parallelism(self) -> usize {
+        1
+    }
+
+    /// Apply the permutation to every state in `states`. This is equivalent to
+    /// calling [`Permutation::apply`] on every state.
+    fn apply_parallel(self, states: &mut [Self::State]) {
+        for state in states {
+            self.apply(state);
+        }
+    }
+}
+
+/// A cryptographic permutation with an efficiently computable inverse.
+///
+/// The inverse is itself an [`InvertiblePermutation`] acting on the same state,
+/// whose inverse is the original permutation again.
+pub trait InvertiblePermutation: Permutation {
+    /// The inverse permutation.
+    type Inverse: InvertiblePermutation<State = Self::State, Inverse = Self>;
+
+    /// The inverse of this permutation.
+    fn inverse(self) -> Self::Inverse;
+
+    /// Apply the inverse permutation to the state, undoing
+    /// [`Permutation::apply`].
+    fn apply_inverse(self, state: &mut Self::State) {
+        self.inverse().apply(state);
+    }
+}
+
+/// A cryptographic permutation whose application can fail.
+///
+/// This is meant for permutations backed by hardware peripherals, like
+/// cryptographic accelerators, that can report errors. Every [`Permutation`]
+/// is a [`TryPermutation`] that never fails. Conversely [`UnwrapPermutation`]
+/// turns a [`TryPermutation`] into a [`Permutation`] that panics on errors, so
+/// a hardware backed permutation can be used by constructions generic over
+/// [`Permutation`].
+pub trait TryPermutation: Copy + Default {
+    /// The state this permutation acts upon.
+    type State: PermutationState;
+    /// Error reported when applying the permutation fails.
+    type Error;
+
+    /// Apply the permutation to the state.
+    ///
+    /// # Errors
+    /// Errors when the permutation could not be applied. The state is
+    /// unspecified after an error.
+    fn try_apply(self, state: &mut Self::State) -> Result<(), Self::Error>;
+}
+
+impl<P: Permutation> TryPermutation for P {
+    type Error = core::convert::Infallible;
+    type State = P::State;
+
+    fn try_apply(self, state: &mut Self::State) -> Result<(), Self::Error> {
+        self.apply(state);
+        Ok(())
+    }
+}
+
+/// A [`TryPermutation`] as a [`Permutation`], panicking when applying the
+/// permutation fails.
+///
+/// Hardware backed permutations that can recover from errors themselves, e.g.
+/// by falling back to a software implementation, should implement
+/// [`Permutation`] directly instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UnwrapPermutation<P>(pub P);
+
+impl<P> Permutation for UnwrapPermutation<P>
+where
+    P: TryPermutation,
+    P::Error: core::fmt::Debug,
+{
+    type State = P::State;
+
+    /// Apply the permutation to the state.
+    ///
+    /// # Panics
+    /// Panics when [`TryPermutation::try_apply`] errors.
+    fn apply(self, state: &mut Self::State) {
+        self.0
+            .try_apply(state)
+            .expect("applying the permutation failed");
+    }
+}
+
+impl<P> ParallelPermutation for UnwrapPermutation<P>
+where
+    P: TryPermutation,
+    P::Error: core::fmt::Debug,
+{
+}
+
+/// A cryptographic permutation that is applied asynchronously.
+///
+/// This is meant for permutations backed by hardware peripherals that signal
+/// completion through an interrupt, e.g. accelerators fed using DMA, so the
+/// executor can do other work while the permutation is computed. Every
+/// [`Permutation`] is an [`AsyncPermutation`] that completes immediately.
+#[cfg(feature = "async")]
+pub trait AsyncPermutation: Copy + Default {
+    /// The state this permutation acts upon.
+    type State: PermutationState;
+    /// Future that applies the permutation to a borrowed state.
+    type Future<'a>: core::future::Future<Output = ()>
+    where
+        Self::State: 'a;
+
+    /// Apply the permutation to the state. The state is only valid after the
+    /// returned future has completed.
+    fn apply_async<'a>(self, state: &'a mut Self::State) -> Self::Future<'a>;
+}
+
+#[cfg(feature = "async")]
+impl<P: Permutation> AsyncPermutation for P {
+    type Future<'a>
+        = core::future::Ready<()>
+    where
+        P::State: 'a;
+    type State = P::State;
+
+    fn apply_async<'a>(self, state: &'a mut Self::State) -> Self::Future<'a> {
+        self.apply(state);
+        core::future::ready(())
+    }
+}
+
+/// A family of cryptographic permutations parameterized by a public tweak.
+///
+/// For every tweak, [`Self::apply_tweaked`] applies a permutation of the
+/// state. Examples are tweakable Even-Mansour style constructions and
+/// permutations with a domain separation constant injected per call. This
+/// allows tweakable constructions, like sector encryption, to be generic over
+/// the tweakable permutation.
+///
+/// [`Tweaked`] fixes the tweak, turning a [`TweakablePermutation`] into a
+/// [`Permutation`].
+pub trait TweakablePermutation: Copy + Default {
+    /// The state this permutation acts upon.
+    type State: PermutationState;
+    /// The public tweak selecting the permutation.
+    type Tweak;
+
+    /// Apply the permutation selected by `tweak` to the state.
+    fn apply_tweaked(self, tweak: &Self::Tweak, state: &mut Self::State);
+}
+
+/// A [`TweakablePermutation`] with a fixed tweak, which is a [`Permutation`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tweaked<P, T> {
+    /// The tweakable permutation.
+    pub permutation: P,
+    /// The tweak passed to [`TweakablePermutation::apply_tweaked`].
+    pub tweak: T,
+}
+
+impl<P, T> Tweaked<P, T> {
+    /// Fix the tweak of `permutation` to `tweak`.
+    pub fn new(permutation: P, tweak: T) -> Self {
+        Self { permutation, tweak }
+    }
+}
+
+impl<P, T> Permutation for Tweaked<P, T>
+where
+    P: TweakablePermutation<Tweak = T>,
+    T: Copy + Default,
+{
+    type State = P::State;
+
+    fn apply(self, state: &mut Self::State) {
+        self.permutation.apply_tweaked(&self.tweak, state);
+    }
+}
+
+impl<P, T> ParallelPermutation for Tweaked<P, T>
+where
+    P: TweakablePermutation<Tweak = T>,
+    T: Copy + Default,
+{
+}
+
 /// A doubly-ended cryptographic keyed function.
 ///
 /// A deck function is a Doubly Extendable Cryptographic Keyed function. It
@@ -170,7 +464,10 @@ pub trait Permutation: Copy + Default {
 pub trait DeckFunction {
     type OutputGenerator: CryptoReader;
     /// [`Writer`] that inputs data that is written to it to the deck function.
-    type InputWriter<'a>: Writer
+    ///
+    /// Deck functions take bit strings as input, so the writer implements
+    /// [`io::FinishWithSuffix`] to input strings that end with a partial byte.
+    type InputWriter<'a>: io::FinishWithSuffix
     where
         Self: 'a;
 
@@ -221,3 +518,138 @@ pub trait DeckFunction {
     /// streams.
     fn output_reader(&self) -> Self::OutputGenerator;
 }
+
+/// The sponge construction in its absorbing phase.
+///
+/// Data written to it through the [`Writer`] trait is absorbed, and
+/// [`Writer::finish`] pads the input and returns a [`Self::Squeezer`] that
+/// generates the output stream. The input is padded with the multi-rate
+/// padding `pad10*1`, preceded by domain separation suffix bits. [`Default`]
+/// creates a sponge without suffix bits.
+///
+/// ```
+/// use crypto_permutation::{Reader, SpongeConstruction, Writer};
+///
+/// /// Hash `data` with any sponge.
+/// fn hash<S: SpongeConstruction>(data: &[u8]) -> [u8; 32] {
+///     let mut sponge = S::default();
+///     sponge.write_bytes(data).unwrap();
+///     let mut digest = [0; 32];
+///     sponge.finish().write_to_slice(&mut digest).unwrap();
+///     digest
+/// }
+/// ```
+pub trait SpongeConstruction:
+    Default + Writer<Return = <Self as SpongeConstruction>::Squeezer>
+{
+    /// Number of bytes absorbed and squeezed between applications of the
+    /// permutation.
+    const RATE: usize;
+    /// The sponge in its squeezing phase, generating the output stream.
+    type Squeezer: CryptoReader;
+
+    /// Create a sponge that appends the domain separation suffix bits of
+    /// `suffix` to the input before padding.
+    ///
+    /// The suffix bits are given least significant bit first, followed by a
+    /// delimiting one bit, like the `delimitedSuffix` of the XKCP. E.g. `0x01`
+    /// means no suffix, and `0x06` is the suffix `01` of SHA-3.
+    ///
+    /// # Panics
+    /// Panics when `suffix` is zero.
+    fn with_suffix(suffix: u8) -> Self;
+}
+
+/// A duplex object: the state of the duplex construction.
+///
+/// Every duplexing call absorbs an input block padded with domain separation
+/// suffix bits and the multi-rate padding, applies the permutation, and
+/// squeezes an output block. The output of a call depends on all inputs so
+/// far. This is the basis of authenticated encryption modes, reseedable random
+/// number generators and protocol frameworks.
+///
+/// ```
+/// use crypto_permutation::DuplexState;
+///
+/// /// Absorb `seed` and generate a key, with any duplex object.
+/// fn derive_key<D: DuplexState>(duplex: &mut D, seed: &[u8]) -> [u8; 16] {
+///     assert!(D::RATE >= 16);
+///     for block in seed.chunks(D::MAX_INPUT) {
+///         duplex.duplex(block, &mut []);
+///     }
+///     let mut key = [0; 16];
+///     duplex.duplex_with_suffix(&[], 0x02, &mut key);
+///     key
+/// }
+/// ```
+pub trait DuplexState {
+    /// Maximum number of bytes squeezed by a single duplexing call.
+    const RATE: usize;
+    /// Maximum number of bytes absorbed by a single duplexing call.
+    const MAX_INPUT: usize;
+
+    /// Absorb `input` followed by the domain separation suffix bits of
+    /// `suffix`, apply the permutation and squeeze `output.len()` bytes into
+    /// `output`.
+    ///
+    /// The suffix is a delimited suffix, as for
+    /// [`SpongeConstruction::with_suffix`]. At most six suffix bits are
+    /// supported.
+    ///
+    /// # Panics
+    /// Panics when `suffix` is zero or at least `0x80`, when
+    /// `input.len() > Self::MAX_INPUT` or when `output.len() > Self::RATE`.
+    fn duplex_with_suffix(&mut self, input: &[u8], suffix: u8, output: &mut [u8]);
+
+    /// Like [`Self::duplex_with_suffix`], without suffix bits.
+    ///
+    /// # Panics
+    /// Panics when `input.len() > Self::MAX_INPUT` or
+    /// `output.len() > Self::RATE`.
+    fn duplex(&mut self, input: &[u8], output: &mut [u8]) {
+        self.duplex_with_suffix(input, 0x01, output);
+    }
+}
+
+/// Error returned when importing a malformed exported deck function state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidStateError;
+
+impl core::fmt::Display for InvalidStateError {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        fmt.write_str("Invalid exported deck function state")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidStateError {}
+
+/// A [`DeckFunction`] whose state can be exported to bytes and imported
+/// again later.
+///
+/// This lets long-lived constructions on top of a deck function, like
+/// sessions, survive process restarts.
+///
+/// # Crypto
+/// The exported state contains the key, so it must be protected like the key.
+/// Importing the same state twice restores the deck function twice, which
+/// many modes forbid, as it repeats output streams.
+pub trait ExportableDeckFunction: DeckFunction {
+    /// Number of bytes of an exported state.
+    const EXPORT_LEN: usize;
+
+    /// Write the state into `out`.
+    ///
+    /// # Panics
+    /// Panics when `out.len() != Self::EXPORT_LEN`.
+    fn export_state(&self, out: &mut [u8]);
+
+    /// Replace the state by the state exported into `bytes` using
+    /// [`Self::export_state`]. Parameters that are not part of the state, like
+    /// the permutations used, are kept.
+    ///
+    /// # Errors
+    /// Errors when `bytes` is not a valid exported state, leaving `self`
+    /// unchanged.
+    fn import_state(&mut self, bytes: &[u8]) -> Result<(), InvalidStateError>;
+}
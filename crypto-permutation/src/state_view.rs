@@ -0,0 +1,172 @@
+//! View of the outer part of a permutation state.
+//!
+//! Sponge and duplex constructions only read and write the first `rate` bytes
+//! of the state, the outer part, and must never touch the rest, the capacity.
+//! A [`StateView`] restricts access to the state to the outer part, so the
+//! constructions don't need to check the offsets themselves.
+
+use crate::io::{check_write_size, Reader, WriteTooLargeError, Writer};
+use crate::PermutationState;
+
+/// [`Reader`] or [`Writer`] restricted to the first `limit` bytes of the
+/// wrapped reader or writer.
+#[derive(Debug, Clone)]
+pub struct RateLimited<T> {
+    inner: T,
+    /// Number of bytes that can still be read or written.
+    remaining: usize,
+}
+
+impl<T> RateLimited<T> {
+    /// Restrict `inner` to its first `limit` bytes.
+    pub fn new(inner: T, limit: usize) -> Self {
+        Self {
+            inner,
+            remaining: limit,
+        }
+    }
+
+    /// Return the wrapped reader or writer.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<R: Reader> Reader for RateLimited<R> {
+    fn capacity(&self) -> usize {
+        self.inner.capacity().min(self.remaining)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(len, self.capacity())?;
+        self.inner.skip(len)?;
+        self.remaining -= len;
+        Ok(())
+    }
+
+    fn write_to<W: Writer>(&mut self, writer: &mut W, n: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(n, self.capacity())?;
+        self.inner.write_to(writer, n)?;
+        self.remaining -= n;
+        Ok(())
+    }
+}
+
+impl<W: Writer> Writer for RateLimited<W> {
+    type Return = W::Return;
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity().min(self.remaining)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(len, self.capacity())?;
+        self.inner.skip(len)?;
+        self.remaining -= len;
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), WriteTooLargeError> {
+        check_write_size(data.len(), self.capacity())?;
+        self.inner.write_bytes(data)?;
+        self.remaining -= data.len();
+        Ok(())
+    }
+
+    fn finish(self) -> Self::Return {
+        self.inner.finish()
+    }
+}
+
+/// The first `rate` bytes of a [`PermutationState`], its outer part.
+///
+/// Offers the byte oriented API of [`PermutationState`], restricted to the
+/// outer part: readers and writers have a capacity of at most `rate` bytes,
+/// and [`Self::xor_bytes_at`] and [`Self::copy_bytes_at`] panic when writing
+/// past it. The capacity, the rest of the state, can't be accessed through the
+/// view.
+///
+/// ```
+/// use crypto_permutation::{PermutationState, Reader, StateView};
+///
+/// /// Absorb `block` into the outer part of `state` and squeeze the output.
+/// fn duplex_block<S: PermutationState>(state: &mut S, rate: usize, block: &mut [u8]) {
+///     let mut view = StateView::new(state, rate);
+///     view.xor_bytes_at(0, block);
+///     view.reader().write_to_slice(block).unwrap();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct StateView<'a, S> {
+    state: &'a mut S,
+    rate: usize,
+}
+
+impl<'a, S: PermutationState> StateView<'a, S> {
+    /// View the first `rate` bytes of `state`.
+    ///
+    /// # Panics
+    /// Panics when `rate > S::SIZE`.
+    pub fn new(state: &'a mut S, rate: usize) -> Self {
+        assert!(rate <= S::SIZE, "the rate must fit the state");
+        Self { state, rate }
+    }
+
+    /// Number of bytes of the outer part.
+    pub fn rate(&self) -> usize {
+        self.rate
+    }
+
+    /// Create a [`Reader`] to read bytes from the outer part.
+    pub fn reader<'b>(&'b self) -> RateLimited<S::StateReader<'b>> {
+        RateLimited::new(self.state.reader(), self.rate)
+    }
+
+    /// Create a [`Writer`] to write into the outer part.
+    pub fn copy_writer<'b>(&'b mut self) -> RateLimited<S::CopyWriter<'b>> {
+        RateLimited::new(self.state.copy_writer(), self.rate)
+    }
+
+    /// Create a [`Writer`] to xor into the outer part.
+    pub fn xor_writer<'b>(&'b mut self) -> RateLimited<S::XorWriter<'b>> {
+        RateLimited::new(self.state.xor_writer(), self.rate)
+    }
+
+    /// Xor `data` into the outer part, starting at byte `offset`.
+    ///
+    /// # Panics
+    /// Panics when `offset + data.len() > self.rate()`.
+    pub fn xor_bytes_at(&mut self, offset: usize, data: &[u8]) {
+        self.check_range(offset, data.len());
+        self.state.xor_bytes_at(offset, data);
+    }
+
+    /// Write `data` into the outer part, starting at byte `offset`.
+    ///
+    /// # Panics
+    /// Panics when `offset + data.len() > self.rate()`.
+    pub fn copy_bytes_at(&mut self, offset: usize, data: &[u8]) {
+        self.check_range(offset, data.len());
+        self.state.copy_bytes_at(offset, data);
+    }
+
+    /// Read `out.len()` bytes of the outer part into `out`, starting at byte
+    /// `offset`.
+    ///
+    /// # Panics
+    /// Panics when `offset + out.len() > self.rate()`.
+    pub fn read_bytes_at(&self, offset: usize, out: &mut [u8]) {
+        self.check_range(offset, out.len());
+        let mut reader = self.state.reader();
+        reader.skip(offset).expect("range fits the state");
+        reader.write_to_slice(out).expect("range fits the state");
+    }
+
+    /// Check that `len` bytes starting at `offset` lie in the outer part.
+    fn check_range(&self, offset: usize, len: usize) {
+        assert!(
+            offset <= self.rate && len <= self.rate - offset,
+            "access exceeds the rate"
+        );
+    }
+}
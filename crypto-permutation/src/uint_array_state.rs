@@ -0,0 +1,281 @@
+//! Generic permutation state consisting of little endian unsigned integers.
+
+use crate::io::{Reader, Writer};
+use crate::{Block, PermutationState};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Unsigned integer type a [`UintArrayState`] can consist of.
+///
+/// Implemented for the unsigned integer types selected using the `io_uint_*`
+/// crate-features.
+pub trait LeUint: 'static + Copy + Default + core::ops::BitXorAssign + sealed::Sealed {
+    /// Byte array of the size of the integer.
+    type Bytes: Block;
+
+    /// Reader that outputs the bytes of a slice of integers in little endian
+    /// order.
+    type SliceReader<'a>: Reader
+    where
+        Self: 'a;
+    /// Writer into a slice of integers, interpreting bytes as little endian
+    /// encoded integers.
+    type SliceWriter<'a>: Writer
+    where
+        Self: 'a;
+    /// Writer that xors into a slice of integers, interpreting bytes as little
+    /// endian encoded integers.
+    type SliceXorWriter<'a>: Writer
+    where
+        Self: 'a;
+
+    /// Decode a little endian integer from `bytes`.
+    ///
+    /// # Panics
+    /// Panics when `bytes` is not exactly as long as the integer.
+    fn from_le_slice(bytes: &[u8]) -> Self;
+
+    /// Create a reader that reads from `slice`.
+    fn slice_reader<'a>(slice: &'a [Self]) -> Self::SliceReader<'a>;
+    /// Create a writer that writes into `slice`.
+    fn slice_writer<'a>(slice: &'a mut [Self]) -> Self::SliceWriter<'a>;
+    /// Create a writer that xors into `slice`.
+    fn slice_xor_writer<'a>(slice: &'a mut [Self]) -> Self::SliceXorWriter<'a>;
+}
+
+macro_rules! impl_le_uint {
+    ($uint:ty, $reader:ident, $writer:ident, $xor_writer:ident) => {
+        impl sealed::Sealed for $uint {}
+
+        impl LeUint for $uint {
+            type Bytes = [u8; core::mem::size_of::<$uint>()];
+            type SliceReader<'a> = crate::io::le_uint_slice_reader::$reader<'a>;
+            type SliceWriter<'a> = crate::io::le_uint_slice_writer::$writer<'a>;
+            type SliceXorWriter<'a> = crate::io::le_uint_slice_writer::$xor_writer<'a>;
+
+            fn from_le_slice(bytes: &[u8]) -> Self {
+                Self::from_le_bytes(bytes.try_into().unwrap())
+            }
+
+            fn slice_reader<'a>(slice: &'a [Self]) -> Self::SliceReader<'a> {
+                Self::SliceReader::new(slice)
+            }
+
+            fn slice_writer<'a>(slice: &'a mut [Self]) -> Self::SliceWriter<'a> {
+                Self::SliceWriter::new(slice)
+            }
+
+            fn slice_xor_writer<'a>(slice: &'a mut [Self]) -> Self::SliceXorWriter<'a> {
+                Self::SliceXorWriter::new(slice)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "io_uint_u128")]
+impl_le_uint!(
+    u128,
+    LeU128SliceReader,
+    LeU128SliceWriter,
+    LeU128SliceXorWriter
+);
+#[cfg(feature = "io_uint_u64")]
+impl_le_uint!(u64, LeU64SliceReader, LeU64SliceWriter, LeU64SliceXorWriter);
+#[cfg(feature = "io_uint_u32")]
+impl_le_uint!(u32, LeU32SliceReader, LeU32SliceWriter, LeU32SliceXorWriter);
+#[cfg(feature = "io_uint_u16")]
+impl_le_uint!(u16, LeU16SliceReader, LeU16SliceWriter, LeU16SliceXorWriter);
+#[cfg(feature = "io_uint_u8")]
+impl_le_uint!(u8, LeU8SliceReader, LeU8SliceWriter, LeU8SliceXorWriter);
+
+/// Xor `data` into `buffer`, interpreted as little endian encoded integers,
+/// starting at byte `offset`.
+///
+/// Fast implementation of [`PermutationState::xor_bytes_at`] for states
+/// consisting of little endian integers: when `offset` and `data.len()` are
+/// multiples of the integer size, the integers are xored directly without the
+/// [`Writer`] state machine.
+///
+/// # Panics
+/// Panics when `offset + data.len()` exceeds the size of `buffer` in bytes.
+pub fn xor_le_bytes_at<U: LeUint>(buffer: &mut [U], offset: usize, data: &[u8]) {
+    let size = core::mem::size_of::<U>();
+    if offset % size == 0 && data.len() % size == 0 {
+        let lanes = &mut buffer[offset / size..][..data.len() / size];
+        for (lane, chunk) in lanes.iter_mut().zip(data.chunks_exact(size)) {
+            *lane ^= U::from_le_slice(chunk);
+        }
+    } else {
+        let mut writer = U::slice_xor_writer(buffer);
+        writer.skip(offset).unwrap();
+        writer.write_bytes(data).unwrap();
+        writer.finish();
+    }
+}
+
+/// Write `data` into `buffer`, interpreted as little endian encoded integers,
+/// starting at byte `offset`.
+///
+/// Fast implementation of [`PermutationState::copy_bytes_at`] for states
+/// consisting of little endian integers: when `offset` and `data.len()` are
+/// multiples of the integer size, the integers are written directly without
+/// the [`Writer`] state machine.
+///
+/// # Panics
+/// Panics when `offset + data.len()` exceeds the size of `buffer` in bytes.
+pub fn copy_le_bytes_at<U: LeUint>(buffer: &mut [U], offset: usize, data: &[u8]) {
+    let size = core::mem::size_of::<U>();
+    if offset % size == 0 && data.len() % size == 0 {
+        let lanes = &mut buffer[offset / size..][..data.len() / size];
+        for (lane, chunk) in lanes.iter_mut().zip(data.chunks_exact(size)) {
+            *lane = U::from_le_slice(chunk);
+        }
+    } else {
+        let mut writer = U::slice_writer(buffer);
+        writer.skip(offset).unwrap();
+        writer.write_bytes(data).unwrap();
+        writer.finish();
+    }
+}
+
+/// Permutation state of `N` unsigned integers of type `U`, in little endian
+/// encoding.
+///
+/// This implements [`PermutationState`] with [`Representation`] `[U; N]`, so
+/// a permutation crate whose state is a plain array of integers only has to
+/// define a type alias for it, e.g.
+/// `type GimliState = UintArrayState<u32, 12>;`.
+///
+/// In this workspace only the Gimli state is a `UintArrayState`. A type alias
+/// cannot have inherent methods, and this state has the alignment of `U` and
+/// little endian byte order. So the Keccak states (lane and plane accessors,
+/// 32 byte alignment) and the Xoodoo state (lane and plane accessors) define
+/// their own types, implementing [`PermutationState`] using
+/// [`xor_le_bytes_at`] and [`copy_le_bytes_at`], and the Ascon state, which
+/// is big endian, uses the big endian slice readers and writers.
+///
+/// With the `subtle` crate-feature, implements `subtle::ConstantTimeEq`. With
+/// the `bytemuck` crate-feature, implements `bytemuck::Pod` (and hence
+/// [`Copy`]) and `bytemuck::TransparentWrapper<[U; N]>`. With the `debug`
+/// crate-feature, implements [`Debug`] and [`PartialEq`].
+///
+/// [`Representation`]: PermutationState::Representation
+#[derive(Clone)]
+#[cfg_attr(feature = "bytemuck", derive(Copy))]
+#[cfg_attr(feature = "debug", derive(Debug, PartialEq))]
+#[repr(transparent)]
+pub struct UintArrayState<U, const N: usize> {
+    state: [U; N],
+}
+
+impl<U: LeUint, const N: usize> Default for UintArrayState<U, N> {
+    fn default() -> Self {
+        Self {
+            state: [U::default(); N],
+        }
+    }
+}
+
+#[cfg(all(feature = "bytemuck", target_endian = "little"))]
+impl<U: LeUint + bytemuck::Pod, const N: usize> UintArrayState<U, N> {
+    /// View the state as its byte encoding, without copying.
+    ///
+    /// Only available on little endian targets, where the in-memory
+    /// representation coincides with the encoding.
+    pub fn as_le_bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.state)
+    }
+
+    /// Mutably view the state as its byte encoding, without copying.
+    ///
+    /// Only available on little endian targets, where the in-memory
+    /// representation coincides with the encoding.
+    pub fn as_le_bytes_mut(&mut self) -> &mut [u8] {
+        bytemuck::cast_slice_mut(&mut self.state)
+    }
+}
+
+impl<U: LeUint, const N: usize> core::ops::BitXorAssign<&Self> for UintArrayState<U, N> {
+    fn bitxor_assign(&mut self, rhs: &Self) {
+        for (self_chunk, other_chunk) in self.state.iter_mut().zip(rhs.state.iter()) {
+            *self_chunk ^= *other_chunk;
+        }
+    }
+}
+
+// SAFETY: `UintArrayState` is a `repr(transparent)` wrapper around `[U; N]`.
+#[cfg(feature = "bytemuck")]
+unsafe impl<U: LeUint + bytemuck::Zeroable, const N: usize> bytemuck::Zeroable
+    for UintArrayState<U, N>
+{
+}
+#[cfg(feature = "bytemuck")]
+unsafe impl<U: LeUint + bytemuck::Pod, const N: usize> bytemuck::Pod for UintArrayState<U, N> {}
+#[cfg(feature = "bytemuck")]
+unsafe impl<U: LeUint, const N: usize> bytemuck::TransparentWrapper<[U; N]>
+    for UintArrayState<U, N>
+{
+}
+
+#[cfg(feature = "subtle")]
+impl<U: LeUint + subtle::ConstantTimeEq, const N: usize> subtle::ConstantTimeEq
+    for UintArrayState<U, N>
+{
+    fn ct_eq(&self, other: &Self) -> subtle::Choice {
+        self.state[..].ct_eq(&other.state[..])
+    }
+}
+
+impl<U: LeUint, const N: usize> PermutationState for UintArrayState<U, N> {
+    type Block = [U::Bytes; N];
+    /// Writer into the state.
+    ///
+    /// Does nothing fancy except for little-endian to native-endian
+    /// conversion.
+    type CopyWriter<'a> = U::SliceWriter<'a>;
+    type Representation = [U; N];
+    /// Reader that reads from the state and outputs it's bytes in little
+    /// endian order.
+    type StateReader<'a> = U::SliceReader<'a>;
+    /// Writer that xors into the state.
+    ///
+    /// Does nothing fancy except for little-endian to native-endian
+    /// conversion.
+    type XorWriter<'a> = U::SliceXorWriter<'a>;
+
+    const SIZE: usize = N * core::mem::size_of::<U>();
+
+    fn from_state(state: Self::Representation) -> Self {
+        Self { state }
+    }
+
+    fn get_state(&self) -> &Self::Representation {
+        &self.state
+    }
+
+    fn get_state_mut(&mut self) -> &mut Self::Representation {
+        &mut self.state
+    }
+
+    fn reader<'a>(&'a self) -> Self::StateReader<'a> {
+        U::slice_reader(&self.state)
+    }
+
+    fn copy_writer<'a>(&'a mut self) -> Self::CopyWriter<'a> {
+        U::slice_writer(&mut self.state)
+    }
+
+    fn xor_writer<'a>(&'a mut self) -> Self::XorWriter<'a> {
+        U::slice_xor_writer(&mut self.state)
+    }
+
+    fn xor_bytes_at(&mut self, offset: usize, data: &[u8]) {
+        xor_le_bytes_at(&mut self.state, offset, data);
+    }
+
+    fn copy_bytes_at(&mut self, offset: usize, data: &[u8]) {
+        copy_le_bytes_at(&mut self.state, offset, data);
+    }
+}
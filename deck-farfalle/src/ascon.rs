@@ -0,0 +1,122 @@
+//! Non-standard Ascon-p instantiation of Farfalle.
+//!
+//! __Warning__: This instantiation is experimental and not specified or
+//! analysed by the designers of Ascon or Farfalle. Use it for experiments
+//! only.
+//!
+//! The 320-bit state of Ascon has exactly the size of the plane on which the
+//! Kravatte rolling function C acts, so [`RollC`] applies the same linear
+//! feedback shift register to the five 64-bit words of the Ascon state.
+//! [`RollE`] uses the non-linear feedback function of the Kravatte rolling
+//! function E, applied to the five words of the state instead of ten lanes.
+//!
+//! Permutations B and D are applied only once per key, respectively output
+//! stream, and use the full 12 rounds (p^a). Permutations C and E use 8 rounds
+//! (p^b of Ascon-128a).
+
+use super::{Farfalle, FarfalleConfig, RollFunction};
+use crypto_permutation::PermutationState;
+use permutation_ascon::{AsconP, AsconState};
+
+#[derive(Copy, Clone, Default, Debug)]
+pub struct RollC;
+
+impl RollFunction for RollC {
+    type State = AsconState;
+
+    fn apply(self, state: &mut Self::State) {
+        let words = state.get_state_mut();
+        let x0 = words[0];
+        let x1 = words[1];
+        let x5 = x0.rotate_left(7) ^ x1 ^ (x1 >> 3);
+        words.copy_within(1.., 0);
+        words[4] = x5;
+    }
+}
+
+#[derive(Copy, Clone, Default, Debug)]
+pub struct RollE;
+
+impl RollFunction for RollE {
+    type State = AsconState;
+
+    fn apply(self, state: &mut Self::State) {
+        let words = state.get_state_mut();
+        let x0 = words[0];
+        let x1 = words[1];
+        let x2 = words[2];
+        let x5 = x0.rotate_left(7) ^ x1.rotate_left(18) ^ (x2 & (x1 >> 1));
+        words.copy_within(1.., 0);
+        words[4] = x5;
+    }
+}
+
+/// Non-standard Ascon-p configuration for Farfalle.
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct AsconFarfalleConfig;
+
+impl FarfalleConfig for AsconFarfalleConfig {
+    type PermutationB = AsconP<12>;
+    type PermutationC = AsconP<8>;
+    type PermutationD = AsconP<12>;
+    type PermutationE = AsconP<8>;
+    type RollC = RollC;
+    type RollE = RollE;
+    type State = AsconState;
+
+    fn perm_b(&self) -> Self::PermutationB {
+        Default::default()
+    }
+
+    fn perm_c(&self) -> Self::PermutationC {
+        Default::default()
+    }
+
+    fn perm_d(&self) -> Self::PermutationD {
+        Default::default()
+    }
+
+    fn perm_e(&self) -> Self::PermutationE {
+        Default::default()
+    }
+
+    fn roll_c(&self) -> Self::RollC {
+        Default::default()
+    }
+
+    fn roll_e(&self) -> Self::RollE {
+        Default::default()
+    }
+}
+
+/// Non-standard Ascon-p based deck function.
+pub type AsconFarfalle = Farfalle<AsconFarfalleConfig>;
+
+#[cfg(test)]
+mod tests {
+    use super::AsconFarfalle;
+    use crate::test_util::generate;
+
+    /// A fresh instance with the test key.
+    fn deck() -> AsconFarfalle {
+        AsconFarfalle::init_default(b"ascon test key".as_ref())
+    }
+
+    /// Splitting an input string over multiple writes gives identical output,
+    /// also for inputs spanning multiple 40 byte blocks.
+    #[test]
+    fn split_input() {
+        let msg = [0x42_u8; 100];
+        let full: [u8; 64] = generate(deck(), &[&[&msg]]);
+        let split: [u8; 64] = generate(deck(), &[&[&msg[..7], &msg[7..40], &msg[40..]]]);
+        assert_eq!(full, split);
+    }
+
+    /// Separate input strings are domain separated.
+    #[test]
+    fn multi_input() {
+        let single: [u8; 32] = generate(deck(), &[&[b"hello world"]]);
+        let multi: [u8; 32] = generate(deck(), &[&[b"hello"], &[b" world"]]);
+        assert_ne!(single, multi);
+    }
+}
@@ -0,0 +1,122 @@
+//! Gimfff: an experimental Gimli instantiation of Farfalle.
+//!
+//! __Warning__: This instantiation is experimental and not specified or
+//! analysed by the designers of Gimli or Farfalle. It is intended for
+//! benchmarking and research only.
+//!
+//! The Gimli state consists of 3 rows of 4 32-bit words, exactly like the
+//! Xoodoo state consists of 3 planes of 4 lanes. The rolling functions are
+//! therefore the Xoofff rolling functions, applied to the rows of the Gimli
+//! state instead of the planes of the Xoodoo state. All permutations are the
+//! full 24 round Gimli permutation.
+
+use super::{Farfalle, FarfalleConfig, RollFunction};
+use crypto_permutation::PermutationState;
+use permutation_gimli::{Gimli, GimliState};
+
+/// Rotate the rows of `a`: the first row is replaced by the second, the second
+/// by the third, and the third by the first row with its words rotated by one
+/// position.
+fn rotate_rows(a: &mut [u32; 12]) {
+    let first_row = [a[1], a[2], a[3], a[0]];
+    a.copy_within(4.., 0);
+    a[8..].copy_from_slice(&first_row);
+}
+
+#[derive(Copy, Clone, Default, Debug)]
+pub struct RollC;
+
+impl RollFunction for RollC {
+    type State = GimliState;
+
+    fn apply(self, state: &mut Self::State) {
+        // The row y is given by `4 * y + x` indexing into the state
+        let a = state.get_state_mut();
+        a[0] ^= (a[0] << 13) ^ a[4].rotate_left(3);
+        rotate_rows(a);
+    }
+}
+
+#[derive(Copy, Clone, Default, Debug)]
+pub struct RollE;
+
+impl RollFunction for RollE {
+    type State = GimliState;
+
+    fn apply(self, state: &mut Self::State) {
+        // The row y is given by `4 * y + x` indexing into the state
+        let a = state.get_state_mut();
+        a[0] = (a[4] & a[8]) ^ a[0].rotate_left(5) ^ a[4].rotate_left(13) ^ 0x00000007;
+        rotate_rows(a);
+    }
+}
+
+/// Gimfff configuration for Farfalle.
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct GimfffConfig;
+
+impl FarfalleConfig for GimfffConfig {
+    type PermutationB = Gimli;
+    type PermutationC = Gimli;
+    type PermutationD = Gimli;
+    type PermutationE = Gimli;
+    type RollC = RollC;
+    type RollE = RollE;
+    type State = GimliState;
+
+    fn perm_b(&self) -> Self::PermutationB {
+        Default::default()
+    }
+
+    fn perm_c(&self) -> Self::PermutationC {
+        Default::default()
+    }
+
+    fn perm_d(&self) -> Self::PermutationD {
+        Default::default()
+    }
+
+    fn perm_e(&self) -> Self::PermutationE {
+        Default::default()
+    }
+
+    fn roll_c(&self) -> Self::RollC {
+        Default::default()
+    }
+
+    fn roll_e(&self) -> Self::RollE {
+        Default::default()
+    }
+}
+
+/// The experimental Gimfff deck function.
+pub type Gimfff = Farfalle<GimfffConfig>;
+
+#[cfg(test)]
+mod tests {
+    use super::Gimfff;
+    use crate::test_util::generate;
+
+    /// A fresh instance with the test key.
+    fn deck() -> Gimfff {
+        Gimfff::init_default(b"gimfff test key".as_ref())
+    }
+
+    /// Splitting an input string over multiple writes gives identical output,
+    /// also for inputs spanning multiple 48 byte blocks.
+    #[test]
+    fn split_input() {
+        let msg = [0x42_u8; 100];
+        let full: [u8; 64] = generate(deck(), &[&[&msg]]);
+        let split: [u8; 64] = generate(deck(), &[&[&msg[..7], &msg[7..48], &msg[48..]]]);
+        assert_eq!(full, split);
+    }
+
+    /// Separate input strings are domain separated.
+    #[test]
+    fn multi_input() {
+        let single: [u8; 32] = generate(deck(), &[&[b"hello world"]]);
+        let multi: [u8; 32] = generate(deck(), &[&[b"hello"], &[b" world"]]);
+        assert_ne!(single, multi);
+    }
+}
@@ -1,7 +1,12 @@
 //! Compression layer of the Farfalle construction.
 
-use super::{FarfalleConfig, RollFunction};
-use crypto_permutation::{Permutation, PermutationState, WriteTooLargeError, Writer};
+use super::{FarfalleConfig, Redacted, RollFunction, MAX_BATCH};
+use crypto_permutation::io::{check_write_size, FinishWithSuffix};
+#[cfg(feature = "async")]
+use crypto_permutation::AsyncPermutation;
+use crypto_permutation::{
+    ParallelPermutation, Permutation, PermutationState, WriteTooLargeError, Writer,
+};
 
 /// Generic Farfalle construction.
 ///
@@ -9,12 +14,106 @@ use crypto_permutation::{Permutation, PermutationState, WriteTooLargeError, Writ
 /// [`crypto_permutation::DeckFunction`] trait. The [`Self::init_default`]
 /// method provides a way to create an instance using a custom length key (but
 /// it has to fit in a permutation block minus one byte).
+///
+/// # Usage limits
+/// The security claims of Farfalle instantiations only hold up to a certain
+/// amount of data processed under a single key. A limit on the number of
+/// permutation blocks can be set using [`Self::set_block_limit`]. It bounds
+/// the absorbed blocks plus the output blocks.
+///
+/// Output generators don't modify the [`Farfalle`] instance, so output is
+/// paid for in advance: whenever the accumulator changes, i.e. when an input
+/// string is finished or the limit is set, [`Self::set_output_blocks`] blocks
+/// are reserved for the output of the new accumulator. Every
+/// [`FarfalleOutputGenerator`] created from that accumulator can generate the
+/// reserved blocks. Since all of them generate the same output stream,
+/// together they never generate more than the reserved blocks.
+///
+/// Once the limit is reached, the [`Writer::capacity`] of input writers and the
+/// [`Reader::capacity`] of output generators drop to zero, so further writes
+/// and reads return a [`WriteTooLargeError`].
+///
+/// # Comparison and formatting
+/// The [`Debug`] implementation never prints the key or the accumulator
+/// state. With the `subtle` crate-feature enabled, [`Farfalle`] implements
+/// [`subtle::ConstantTimeEq`] and a [`PartialEq`] based on it.
+///
+/// [`FarfalleOutputGenerator`]: crate::FarfalleOutputGenerator
+/// [`Reader::capacity`]: crypto_permutation::Reader::capacity
+/// [`subtle::ConstantTimeEq`]: https://docs.rs/subtle/latest/subtle/trait.ConstantTimeEq.html
 #[derive(Clone)]
-#[cfg_attr(feature = "debug", derive(Debug, PartialEq))]
+#[cfg_attr(all(feature = "debug", not(feature = "subtle")), derive(PartialEq))]
 pub struct Farfalle<C: FarfalleConfig> {
     pub(super) key: C::State,
     pub(super) state: C::State,
     pub(super) config: C,
+    /// Number of blocks absorbed under the current key.
+    pub(super) blocks_absorbed: u64,
+    /// Number of output blocks reserved under the current key.
+    pub(super) blocks_reserved: u64,
+    /// Maximum number of blocks that may be processed under the current key,
+    /// or `None` when unlimited.
+    pub(super) block_limit: Option<u64>,
+    /// Number of output blocks to reserve for every accumulator.
+    pub(super) output_blocks: u64,
+    /// Number of output blocks reserved for the current accumulator.
+    pub(super) output_budget: u64,
+}
+
+impl<C: FarfalleConfig + core::fmt::Debug> core::fmt::Debug for Farfalle<C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Farfalle")
+            .field("key", &Redacted)
+            .field("state", &Redacted)
+            .field("config", &self.config)
+            .field("blocks_absorbed", &self.blocks_absorbed)
+            .field("blocks_reserved", &self.blocks_reserved)
+            .field("block_limit", &self.block_limit)
+            .field("output_blocks", &self.output_blocks)
+            .field("output_budget", &self.output_budget)
+            .finish()
+    }
+}
+
+#[cfg(feature = "subtle")]
+impl<C> subtle::ConstantTimeEq for Farfalle<C>
+where
+    C: FarfalleConfig + PartialEq,
+    C::State: subtle::ConstantTimeEq,
+{
+    /// Compare the key and accumulator state in constant time. The public
+    /// parameters (config, block counter and limit) are compared in variable
+    /// time.
+    fn ct_eq(&self, other: &Self) -> subtle::Choice {
+        let public_eq = self.config == other.config
+            && self.blocks_absorbed == other.blocks_absorbed
+            && self.blocks_reserved == other.blocks_reserved
+            && self.block_limit == other.block_limit
+            && self.output_blocks == other.output_blocks
+            && self.output_budget == other.output_budget;
+        subtle::Choice::from(u8::from(public_eq))
+            & self.key.ct_eq(&other.key)
+            & self.state.ct_eq(&other.state)
+    }
+}
+
+#[cfg(feature = "subtle")]
+impl<C> PartialEq for Farfalle<C>
+where
+    C: FarfalleConfig + PartialEq,
+    C::State: subtle::ConstantTimeEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        subtle::ConstantTimeEq::ct_eq(self, other).into()
+    }
+}
+
+#[cfg(feature = "subtle")]
+impl<C> Eq for Farfalle<C>
+where
+    C: FarfalleConfig + Eq,
+    C::State: subtle::ConstantTimeEq,
+{
 }
 
 const PAD_BYTE: u8 = 1;
@@ -23,10 +122,8 @@ impl<C: FarfalleConfig> Farfalle<C> {
     fn key_expand(key: &[u8], p_b: C::PermutationB) -> C::State {
         assert!(key.len() < C::State::SIZE);
         let mut key_state = C::State::default();
-        let mut state_writer = key_state.copy_writer();
-        state_writer.write_bytes(key).unwrap();
-        state_writer.write_bytes(&[PAD_BYTE]).unwrap();
-        state_writer.finish();
+        key_state.copy_bytes_at(0, key);
+        key_state.copy_bytes_at(key.len(), &[PAD_BYTE]);
         p_b.apply(&mut key_state);
         key_state
     }
@@ -43,6 +140,11 @@ impl<C: FarfalleConfig> Farfalle<C> {
             key: Self::key_expand(key, config.perm_b()),
             state: Default::default(),
             config,
+            blocks_absorbed: 0,
+            blocks_reserved: 0,
+            block_limit: None,
+            output_blocks: 1,
+            output_budget: 0,
         }
     }
 
@@ -59,6 +161,90 @@ impl<C: FarfalleConfig> Farfalle<C> {
         Self::init_custom(key, C::default())
     }
 
+    /// Limit the number of permutation blocks that can be processed (absorbed
+    /// plus squeezed) under the key of this instance to `limit`. `None` means
+    /// no limit, which is the default.
+    ///
+    /// Setting a limit reserves the output blocks of the current accumulator.
+    /// See the [usage limits](Self#usage-limits) section for the exact
+    /// semantics.
+    pub fn set_block_limit(&mut self, limit: Option<u64>) {
+        self.block_limit = limit;
+        self.reserve_output();
+    }
+
+    /// Reserve `blocks` output blocks for every following accumulator, when a
+    /// block limit is set. The default is one block.
+    ///
+    /// This is the maximum number of blocks an output generator can generate.
+    /// See the [usage limits](Self#usage-limits) section for the exact
+    /// semantics.
+    pub fn set_output_blocks(&mut self, blocks: u64) {
+        self.output_blocks = blocks;
+    }
+
+    /// The number of output blocks set using [`Self::set_output_blocks`].
+    pub fn output_blocks(&self) -> u64 {
+        self.output_blocks
+    }
+
+    /// The block limit set using [`Self::set_block_limit`].
+    pub fn block_limit(&self) -> Option<u64> {
+        self.block_limit
+    }
+
+    /// Number of blocks that have been absorbed under the key of this instance.
+    pub fn blocks_absorbed(&self) -> u64 {
+        self.blocks_absorbed
+    }
+
+    /// Number of output blocks that have been reserved under the key of this
+    /// instance.
+    pub fn blocks_reserved(&self) -> u64 {
+        self.blocks_reserved
+    }
+
+    /// Number of blocks that can still be processed under the key of this
+    /// instance before the block limit is reached, or `None` when there is no
+    /// limit.
+    pub fn remaining_blocks(&self) -> Option<u64> {
+        self.block_limit.map(|limit| {
+            limit
+                .saturating_sub(self.blocks_absorbed)
+                .saturating_sub(self.blocks_reserved)
+        })
+    }
+
+    /// Number of blocks output generators of the current accumulator can
+    /// generate, or `None` when there is no limit.
+    pub(super) fn output_budget(&self) -> Option<u64> {
+        self.block_limit.map(|_| self.output_budget)
+    }
+
+    /// Reserve the output blocks of the current accumulator, when a block
+    /// limit is set.
+    fn reserve_output(&mut self) {
+        if let Some(remaining) = self.remaining_blocks() {
+            self.output_budget = self.output_blocks.min(remaining);
+            self.blocks_reserved += self.output_budget;
+        }
+    }
+
+    /// Number of bytes that can still be input in a string of which `filled`
+    /// bytes of the current block are already filled, reserving room for the
+    /// padding. `usize::MAX` when there is no block limit.
+    fn input_capacity(&self, filled: usize) -> usize {
+        match self.remaining_blocks() {
+            None => usize::MAX,
+            Some(blocks) => {
+                let bytes = blocks
+                    .saturating_mul(C::State::SIZE as u64)
+                    .saturating_sub(filled as u64 + 1);
+                usize::try_from(bytes).unwrap_or(usize::MAX)
+            }
+        }
+    }
+
     /// Apply rolling function C to the key.
     fn roll_c_key(&mut self) {
         self.config.roll_c().apply(&mut self.key);
@@ -72,13 +258,98 @@ impl<C: FarfalleConfig> Farfalle<C> {
         self.roll_c_key();
         self.config.perm_c().apply(block);
         self.state ^= block;
+        self.blocks_absorbed += 1;
+    }
+
+    /// Process `n` full blocks of data, where `fill(i, block)` writes block `i`
+    /// to `block`. Permutation C is applied to batches of blocks in parallel.
+    fn process_blocks<F>(&mut self, n: usize, mut fill: F)
+    where
+        F: FnMut(usize, &mut C::State),
+    {
+        let perm_c = self.config.perm_c();
+        let batch = perm_c.parallelism().clamp(1, MAX_BATCH);
+        let mut blocks: [C::State; MAX_BATCH] = Default::default();
+        let mut done = 0;
+        while done < n {
+            let batch_blocks = &mut blocks[..core::cmp::min(batch, n - done)];
+            for block in batch_blocks.iter_mut() {
+                fill(done, block);
+                *block ^= &self.key;
+                self.roll_c_key();
+                done += 1;
+            }
+            perm_c.apply_parallel(batch_blocks);
+            for block in batch_blocks.iter() {
+                self.state ^= block;
+            }
+        }
+        self.blocks_absorbed += n as u64;
+    }
+
+    /// Like [`Self::process_block`], but applying permutation C using the
+    /// asynchronous `perm_c`.
+    #[cfg(feature = "async")]
+    async fn process_block_async<P>(&mut self, block: &mut C::State, perm_c: P)
+    where
+        P: AsyncPermutation<State = C::State>,
+    {
+        *block ^= &self.key;
+        self.roll_c_key();
+        perm_c.apply_async(block).await;
+        self.state ^= block;
+        self.blocks_absorbed += 1;
+    }
+
+    /// Input the string `data` using the asynchronous permutation `perm_c`
+    /// instead of [`FarfalleConfig::perm_c`]. This is equivalent to writing
+    /// `data` to an [`InputWriter`] and finishing it, but lets permutation C
+    /// run on a DMA driven accelerator without blocking.
+    ///
+    /// `perm_c` must compute the same permutation as
+    /// [`FarfalleConfig::perm_c`]. Every [`Permutation`] is an
+    /// [`AsyncPermutation`], so the synchronous permutation C of the config can
+    /// be used as well.
+    ///
+    /// # Errors
+    /// Errors when `data` exceeds the [`Writer::capacity`] of an input writer,
+    /// see the [usage limits](Self#usage-limits). Nothing is input in that
+    /// case.
+    #[cfg(feature = "async")]
+    pub async fn input_async<P>(&mut self, perm_c: P, data: &[u8]) -> Result<(), WriteTooLargeError>
+    where
+        P: AsyncPermutation<State = C::State>,
+    {
+        check_write_size(data.len(), self.input_capacity(0))?;
+
+        let mut chunks = data.chunks_exact(C::State::SIZE);
+        for chunk in &mut chunks {
+            let mut block = C::State::default();
+            block.copy_bytes_at(0, chunk);
+            self.process_block_async(&mut block, perm_c).await;
+        }
+
+        let remainder = chunks.remainder();
+        let mut block = C::State::default();
+        block.copy_bytes_at(0, remainder);
+        block.copy_bytes_at(remainder.len(), &[PAD_BYTE]);
+        self.process_block_async(&mut block, perm_c).await;
+        self.roll_c_key();
+        self.reserve_output();
+        Ok(())
     }
 }
 
 /// A [`Writer`] structure that inputs all data that is written to it into the
 /// Farfalle construction.
+///
+/// [`Writer::skip`] absorbs zero bytes, so skipping `n` bytes is equivalent to
+/// writing `n` zero bytes.
 pub struct InputWriter<'a, C: FarfalleConfig> {
     /// A permutation state to accumulate data into before processing.
+    ///
+    /// When `filled != 0`, all bytes from `filled` onwards are zero. When
+    /// `filled == 0` the contents are unspecified.
     block: C::State,
     /// Number of bytes of `block` that are initialised.
     filled: usize,
@@ -100,58 +371,112 @@ impl<'a, C: FarfalleConfig> InputWriter<'a, C> {
         self.farfalle.process_block(&mut self.block);
         self.filled = 0;
     }
+
+    /// Start a new partial block by clearing `self.block`.
+    fn clear_block(&mut self) {
+        debug_assert_eq!(self.filled, 0);
+        self.block = Default::default();
+    }
+
+    /// Number of bytes that can still be added to the current (partial) block.
+    fn block_remaining(&self) -> usize {
+        C::State::SIZE - self.filled
+    }
 }
 
 impl<'a, C: FarfalleConfig> Writer for InputWriter<'a, C> {
     type Return = ();
 
+    /// Returns `usize::MAX` when no block limit is set. Otherwise returns the
+    /// number of bytes that can still be written before the block limit is
+    /// reached, reserving room for the padding applied by [`Self::finish`].
     fn capacity(&self) -> usize {
-        usize::MAX
+        self.farfalle.input_capacity(self.filled)
     }
 
-    /// No-op.
-    fn skip(&mut self, _n: usize) -> Result<(), WriteTooLargeError> {
+    /// Absorb `n` zero bytes.
+    ///
+    /// Zero bytes that complete the current partial block or fill entire
+    /// blocks cause those blocks to be processed, exactly like
+    /// [`Self::write_bytes`] would.
+    fn skip(&mut self, mut n: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(n, self.capacity())?;
+
+        if self.filled != 0 {
+            // The block is already zero from `self.filled` onwards.
+            let add_partial = core::cmp::min(n, self.block_remaining());
+            self.filled += add_partial;
+            n -= add_partial;
+            if self.filled == C::State::SIZE {
+                self.process_block();
+            }
+        }
+
+        self.farfalle
+            .process_blocks(n / C::State::SIZE, |_, block| *block = Default::default());
+
+        let remainder = n % C::State::SIZE;
+        if remainder != 0 {
+            self.clear_block();
+            self.filled = remainder;
+        }
+
         Ok(())
     }
 
     fn write_bytes(&mut self, mut data: &[u8]) -> Result<(), WriteTooLargeError> {
+        check_write_size(data.len(), self.capacity())?;
+
         if self.filled != 0 {
-            let add_partial = core::cmp::min(data.len(), C::State::SIZE - self.filled);
+            let add_partial = core::cmp::min(data.len(), self.block_remaining());
             let old_filled = self.filled;
             self.filled += add_partial;
-            let mut block_writer = self.block.copy_writer();
-            block_writer.skip(old_filled).unwrap();
-            block_writer.write_bytes(&data[..add_partial]).unwrap();
-            block_writer.finish();
+            self.block.copy_bytes_at(old_filled, &data[..add_partial]);
             data = &data[add_partial..];
             if self.filled == C::State::SIZE {
                 self.process_block();
             }
         }
 
-        let mut chunks = data.chunks_exact(C::State::SIZE);
-        for chunk in &mut chunks {
-            let mut block_writer = self.block.copy_writer();
-            block_writer.write_bytes(chunk).unwrap();
-            block_writer.finish();
-            self.process_block();
-        }
+        let n_blocks = data.len() / C::State::SIZE;
+        let (full, remainder) = data.split_at(n_blocks * C::State::SIZE);
+        self.farfalle.process_blocks(n_blocks, |i, block| {
+            block.copy_bytes_at(0, &full[i * C::State::SIZE..(i + 1) * C::State::SIZE]);
+        });
 
-        let remainder = chunks.remainder();
         if !remainder.is_empty() {
+            self.clear_block();
             self.filled = remainder.len();
-            let mut block_writer = self.block.copy_writer();
-            block_writer.write_bytes(remainder).unwrap();
-            block_writer.finish();
+            self.block.copy_bytes_at(0, remainder);
         }
 
         Ok(())
     }
 
     /// Applies padding to the final block and processes it.
-    fn finish(mut self) {
-        self.write_bytes(&[PAD_BYTE]).unwrap();
+    ///
+    /// The final block is always processed, even when this exceeds the block
+    /// limit. In that case no output can be generated anymore. Otherwise the
+    /// output blocks of the new accumulator are reserved.
+    fn finish(self) {
+        self.finish_with_suffix(0, 0);
+    }
+}
+
+impl<'a, C: FarfalleConfig> FinishWithSuffix for InputWriter<'a, C> {
+    /// Appends the suffix bits and the padding to the final block and
+    /// processes it, like [`Writer::finish`]. The suffix and the first padding
+    /// bit share the final byte.
+    fn finish_with_suffix(mut self, bits: u8, n: u32) {
+        assert!(n <= 7, "a suffix has at most 7 bits");
+        if self.filled == 0 {
+            self.clear_block();
+        }
+        let suffix = bits & ((1 << n) - 1);
+        self.block
+            .copy_bytes_at(self.filled, &[suffix | (PAD_BYTE << n)]);
         self.process_block();
         self.farfalle.roll_c_key();
+        self.farfalle.reserve_output();
     }
 }
@@ -1,7 +1,6 @@
 //! Kravatte: a keccak-p instantiation of Farfalle.
 
 use super::{Farfalle, FarfalleConfig, RollFunction};
-use crypto_permutation::PermutationState;
 use permutation_keccak::{KeccakP1600, KeccakState1600};
 
 #[derive(Copy, Clone, Default, Debug)]
@@ -11,14 +10,11 @@ impl RollFunction for RollC {
     type State = KeccakState1600;
 
     fn apply(self, state: &mut Self::State) {
-        // The y = 4 plane is given by `5 * 4 + x` indexing into the state
-        let y4_plane = &mut state.get_state_mut()[20..];
+        let y4_plane = state.plane_mut(4);
         let x0 = y4_plane[0];
         let x1 = y4_plane[1];
         let x5 = x0.rotate_left(7) ^ x1 ^ (x1 >> 3);
-        for i in 0..4 {
-            y4_plane[i] = y4_plane[i + 1];
-        }
+        y4_plane.copy_within(1.., 0);
         y4_plane[4] = x5;
     }
 }
@@ -30,16 +26,16 @@ impl RollFunction for RollE {
     type State = KeccakState1600;
 
     fn apply(self, state: &mut Self::State) {
-        // The y plane is given by `5 * y + x` indexing into the state
-        let y34_plane = &mut state.get_state_mut()[15..];
-        let x0 = y34_plane[0];
-        let x1 = y34_plane[1];
-        let x2 = y34_plane[2];
+        // The lanes of the planes y = 3 and y = 4 are shifted as a single
+        // sequence of 10 lanes.
+        let x0 = state.lane(0, 3);
+        let x1 = state.lane(1, 3);
+        let x2 = state.lane(2, 3);
         let x10 = x0.rotate_left(7) ^ x1.rotate_left(18) ^ (x2 & (x1 >> 1));
         for i in 0..9 {
-            y34_plane[i] = y34_plane[i + 1];
+            *state.lane_mut(i % 5, 3 + i / 5) = state.lane((i + 1) % 5, 3 + (i + 1) / 5);
         }
-        y34_plane[9] = x10;
+        *state.lane_mut(4, 4) = x10;
     }
 }
 
@@ -253,4 +249,188 @@ mod tests {
 
         assert_eq!(kra_full, kra_split);
     }
+
+    /// Check that the block limit bounds both absorbed and squeezed blocks.
+    #[test]
+    fn block_limit() {
+        let key = b"kravatte test key";
+        let mut kravatte = Kravatte::init_default(key.as_ref());
+        kravatte.set_block_limit(Some(3));
+        assert_eq!(kravatte.blocks_reserved(), 1);
+        {
+            let mut writer = kravatte.input_writer();
+            assert_eq!(writer.capacity(), 2 * 200 - 1);
+            writer
+                .write_bytes(&[0; 2 * 200])
+                .expect_err("write exceeding the block limit succeeded");
+            writer
+                .write_bytes(b"hello world")
+                .expect("writing message failed");
+            assert_eq!(writer.capacity(), 2 * 200 - 12);
+            writer.finish();
+        }
+        assert_eq!(kravatte.blocks_absorbed(), 1);
+        assert_eq!(kravatte.blocks_reserved(), 2);
+        assert_eq!(kravatte.remaining_blocks(), Some(0));
+
+        let mut reader = kravatte.output_reader();
+        assert_eq!(reader.capacity(), 200);
+        let mut output = [0_u8; 150];
+        reader
+            .write_to_slice(&mut output)
+            .expect("writing output failed");
+        assert_eq!(reader.capacity(), 50);
+        reader
+            .write_to_slice(&mut output[..51])
+            .expect_err("read exceeding the block limit succeeded");
+        reader
+            .write_to_slice(&mut output[..50])
+            .expect("writing output failed");
+        assert_eq!(reader.remaining_blocks(), Some(0));
+        assert_eq!(reader.capacity(), 0);
+    }
+
+    /// Output generators of the same accumulator share its reserved output
+    /// blocks, so opening more of them doesn't exceed the block limit.
+    #[test]
+    fn block_limit_multiple_readers() {
+        let key = b"kravatte test key";
+        let mut kravatte = Kravatte::init_default(key.as_ref());
+        kravatte.set_output_blocks(2);
+        kravatte.set_block_limit(Some(6));
+        {
+            let mut writer = kravatte.input_writer();
+            writer
+                .write_bytes(b"hello world")
+                .expect("writing message failed");
+            writer.finish();
+        }
+        assert_eq!(kravatte.remaining_blocks(), Some(1));
+
+        let mut output1 = [0_u8; 400];
+        let mut output2 = [0_u8; 400];
+        let mut reader1 = kravatte.output_reader();
+        let mut reader2 = kravatte.output_reader();
+        assert_eq!(reader1.capacity(), 400);
+        assert_eq!(reader2.capacity(), 400);
+        reader1
+            .write_to_slice(&mut output1)
+            .expect("writing output failed");
+        reader2
+            .write_to_slice(&mut output2)
+            .expect("writing output failed");
+        assert_eq!(output1, output2);
+        assert_eq!(reader1.capacity(), 0);
+        assert_eq!(kravatte.output_reader().capacity(), 400);
+        assert_eq!(kravatte.remaining_blocks(), Some(1));
+
+        {
+            let mut writer = kravatte.input_writer();
+            assert_eq!(writer.capacity(), 199);
+            writer
+                .write_bytes(&[0; 200])
+                .expect_err("write exceeding the block limit succeeded");
+            writer.finish();
+        }
+        assert_eq!(kravatte.remaining_blocks(), Some(0));
+        assert_eq!(kravatte.output_reader().capacity(), 0);
+    }
+
+    /// Check that skipping bytes in an input writer is equivalent to writing
+    /// zero bytes, also when crossing block boundaries.
+    #[test]
+    fn skip_absorbs_zeros() {
+        let key = b"kravatte test key";
+        for (prefix, skip) in [(0, 0), (0, 5), (11, 189), (11, 190), (199, 402), (200, 200)] {
+            let msg = [0xa5_u8; 200];
+            let mut kra_skip = Kravatte::init_default(key.as_ref());
+            let mut kra_write = Kravatte::init_default(key.as_ref());
+            {
+                let mut writer = kra_skip.input_writer();
+                writer
+                    .write_bytes(&msg[..prefix])
+                    .expect("writing message failed");
+                writer.skip(skip).expect("skipping failed");
+                writer.write_bytes(b"end").expect("writing message failed");
+                writer.finish();
+            }
+            {
+                let mut writer = kra_write.input_writer();
+                writer
+                    .write_bytes(&msg[..prefix])
+                    .expect("writing message failed");
+                writer
+                    .write_bytes(&vec![0; skip])
+                    .expect("writing message failed");
+                writer.write_bytes(b"end").expect("writing message failed");
+                writer.finish();
+            }
+            let mut out_skip = [0_u8; 32];
+            let mut out_write = [0_u8; 32];
+            kra_skip
+                .output_reader()
+                .write_to_slice(&mut out_skip)
+                .expect("writing output failed");
+            kra_write
+                .output_reader()
+                .write_to_slice(&mut out_write)
+                .expect("writing output failed");
+            assert_eq!(out_skip, out_write);
+            assert_eq!(kra_skip.blocks_absorbed(), kra_write.blocks_absorbed());
+        }
+    }
+
+    /// A message that exactly fills a block together with its padding byte
+    /// results in a single processed block.
+    #[test]
+    fn padding_fills_block() {
+        let key = b"kravatte test key";
+        let mut kravatte = Kravatte::init_default(key.as_ref());
+        {
+            let mut writer = kravatte.input_writer();
+            writer
+                .write_bytes(&[0x5a; 199])
+                .expect("writing message failed");
+            writer.finish();
+        }
+        assert_eq!(kravatte.blocks_absorbed(), 1);
+    }
+
+    /// The `Debug` implementation must not print secret data.
+    #[test]
+    fn debug_redacts_secrets() {
+        let key = b"kravatte test key";
+        let kravatte = Kravatte::init_default(key.as_ref());
+        let formatted = format!("{:?}", kravatte);
+        assert_eq!(
+            formatted,
+            "Farfalle { key: <redacted>, state: <redacted>, config: KravatteConfig, \
+             blocks_absorbed: 0, blocks_reserved: 0, block_limit: None, output_blocks: 1, \
+             output_budget: 0 }"
+        );
+        let formatted = format!("{:?}", kravatte.output_reader());
+        assert!(formatted.contains("key: <redacted>"));
+        assert!(formatted.contains("output_buffer: <redacted>"));
+    }
+
+    /// Constant time comparison distinguishes different keys and inputs.
+    #[cfg(feature = "subtle")]
+    #[test]
+    fn constant_time_eq() {
+        use subtle::ConstantTimeEq;
+
+        let kra1 = Kravatte::init_default(b"kravatte test key".as_ref());
+        let mut kra2 = kra1.clone();
+        let kra3 = Kravatte::init_default(b"other test key".as_ref());
+        assert!(bool::from(kra1.ct_eq(&kra2)));
+        assert!(!bool::from(kra1.ct_eq(&kra3)));
+        {
+            let mut writer = kra2.input_writer();
+            writer
+                .write_bytes(b"hello world")
+                .expect("writing message failed");
+            writer.finish();
+        }
+        assert!(!bool::from(kra1.ct_eq(&kra2)));
+    }
 }
@@ -0,0 +1,142 @@
+//! Kravatte-800: a keccak-p\[800\] instantiation of Farfalle.
+//!
+//! __Warning__: This is not a standardised instantiation. The rolling
+//! functions are the Kravatte rolling functions applied to the 32-bit lanes of
+//! Keccak-p\[800\], with the same rotation and shift constants, and the round
+//! counts are those of Kravatte (Achouffe). Neither has been subjected to
+//! dedicated cryptanalysis.
+//!
+//! It halves the block size (and hence the size of the staging buffers) from
+//! 200 to 100 bytes compared to Kravatte, which is useful for memory
+//! constrained devices.
+
+use super::{Farfalle, FarfalleConfig, RollFunction};
+use permutation_keccak::{KeccakP800, KeccakState800};
+
+#[derive(Copy, Clone, Default, Debug)]
+pub struct RollC;
+
+impl RollFunction for RollC {
+    type State = KeccakState800;
+
+    fn apply(self, state: &mut Self::State) {
+        let y4_plane = state.plane_mut(4);
+        let x0 = y4_plane[0];
+        let x1 = y4_plane[1];
+        let x5 = x0.rotate_left(7) ^ x1 ^ (x1 >> 3);
+        y4_plane.copy_within(1.., 0);
+        y4_plane[4] = x5;
+    }
+}
+
+#[derive(Copy, Clone, Default, Debug)]
+pub struct RollE;
+
+impl RollFunction for RollE {
+    type State = KeccakState800;
+
+    fn apply(self, state: &mut Self::State) {
+        // The lanes of the planes y = 3 and y = 4 are shifted as a single
+        // sequence of 10 lanes.
+        let x0 = state.lane(0, 3);
+        let x1 = state.lane(1, 3);
+        let x2 = state.lane(2, 3);
+        let x10 = x0.rotate_left(7) ^ x1.rotate_left(18) ^ (x2 & (x1 >> 1));
+        for i in 0..9 {
+            *state.lane_mut(i % 5, 3 + i / 5) = state.lane((i + 1) % 5, 3 + (i + 1) / 5);
+        }
+        *state.lane_mut(4, 4) = x10;
+    }
+}
+
+/// Kravatte-800 configuration for Farfalle.
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct Kravatte800Config;
+
+impl FarfalleConfig for Kravatte800Config {
+    type PermutationB = KeccakP800<6>;
+    type PermutationC = KeccakP800<6>;
+    type PermutationD = KeccakP800<6>;
+    type PermutationE = KeccakP800<6>;
+    type RollC = RollC;
+    type RollE = RollE;
+    type State = KeccakState800;
+
+    fn perm_b(&self) -> Self::PermutationB {
+        Default::default()
+    }
+
+    fn perm_c(&self) -> Self::PermutationC {
+        Default::default()
+    }
+
+    fn perm_d(&self) -> Self::PermutationD {
+        Default::default()
+    }
+
+    fn perm_e(&self) -> Self::PermutationE {
+        Default::default()
+    }
+
+    fn roll_c(&self) -> Self::RollC {
+        Default::default()
+    }
+
+    fn roll_e(&self) -> Self::RollE {
+        Default::default()
+    }
+}
+
+/// The Kravatte-800 deck function.
+pub type Kravatte800 = Farfalle<Kravatte800Config>;
+
+#[cfg(test)]
+mod tests {
+    use super::Kravatte800;
+    use crate::test_util::generate;
+    use crypto_permutation::{DeckFunction, Reader, Writer};
+
+    /// A fresh instance with the test key.
+    fn deck() -> Kravatte800 {
+        Kravatte800::init_default(b"kravatte800 test key".as_ref())
+    }
+
+    /// Splitting an input string over multiple writes gives identical output,
+    /// also for inputs spanning multiple 100 byte blocks.
+    #[test]
+    fn split_input() {
+        let msg = [0x42_u8; 250];
+        let full: [u8; 32] = generate(deck(), &[&[&msg]]);
+        let split: [u8; 32] = generate(deck(), &[&[&msg[..7], &msg[7..100], &msg[100..]]]);
+        assert_eq!(full, split);
+    }
+
+    /// Separate input strings are domain separated.
+    #[test]
+    fn multi_input() {
+        let single: [u8; 32] = generate(deck(), &[&[b"hello world"]]);
+        let multi: [u8; 32] = generate(deck(), &[&[b"hello"], &[b" world"]]);
+        assert_ne!(single, multi);
+    }
+
+    /// Reading the output stream in chunks gives the same result as reading it
+    /// at once.
+    #[test]
+    fn multi_output() {
+        let expected: [u8; 320] = generate(deck(), &[&[b"hello world"]]);
+        let mut kravatte = deck();
+        {
+            let mut writer = kravatte.input_writer();
+            writer
+                .write_bytes(b"hello world")
+                .expect("writing message failed");
+            writer.finish();
+        }
+        let mut reader = kravatte.output_reader();
+        let mut output = [0_u8; 320];
+        for chunk in output.chunks_mut(32) {
+            reader.write_to_slice(chunk).expect("writing output failed");
+        }
+        assert_eq!(expected, output);
+    }
+}
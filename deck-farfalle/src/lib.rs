@@ -22,7 +22,18 @@
 //!
 //! # Features
 //! * `kravatte`: Enables the [`kravatte`] module.
+//! * `kravatte800`: Enables the [`kravatte800`] module, containing a
+//!   non-standard instantiation of Farfalle over Keccak-p\[800\].
 //! * `xoofff`: Enables the [`xoofff`] module.
+//! * `subtle`: Implements [`subtle::ConstantTimeEq`] and a constant time
+//!   [`PartialEq`] for [`Farfalle`].
+//! * `nonstandard-ascon`: Enables the [`ascon`] module, containing a
+//!   non-standard, experimental instantiation of Farfalle over Ascon-p.
+//! * `experimental`: Enables the [`gimfff`] module, containing an experimental
+//!   instantiation of Farfalle over Gimli.
+//! * `async`: Provides [`Farfalle::input_async`], which inputs a string using
+//!   an asynchronous implementation of permutation C, e.g. one backed by a DMA
+//!   driven accelerator.
 //! * `debug`: Used for tests. Don't use!
 //!
 //! # Testing
@@ -33,17 +44,40 @@
 //! [Farfalle construction]: https://keccak.team/farfalle.html
 //! [`kravatte` python package]: https://pypi.org/project/kravatte
 //! [`xoofff` crate]: https://crates.io/crates/xoofff
+//! [`subtle::ConstantTimeEq`]: https://docs.rs/subtle/latest/subtle/trait.ConstantTimeEq.html
 
 #![cfg_attr(not(test), no_std)]
 #![allow(clippy::needless_lifetimes)]
 
-use crypto_permutation::{DeckFunction, Permutation, PermutationState};
+use crypto_permutation::{
+    DeckFunction, ExportableDeckFunction, InvalidStateError, ParallelPermutation, Permutation,
+    PermutationState, Reader,
+};
 
 mod input;
 mod output;
+#[cfg(all(
+    test,
+    any(
+        feature = "kravatte800",
+        feature = "nonstandard-ascon",
+        feature = "experimental"
+    )
+))]
+mod test_util;
 pub use input::{Farfalle, InputWriter};
 pub use output::FarfalleOutputGenerator;
 
+/// Placeholder printed by [`Debug`](core::fmt::Debug) implementations instead
+/// of secret data.
+struct Redacted;
+
+impl core::fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
 /// A rolling function as used in the Farfalle construction.
 pub trait RollFunction: Copy + Default {
     /// The state this rolling function acts upon.
@@ -53,16 +87,24 @@ pub trait RollFunction: Copy + Default {
     fn apply(self, state: &mut Self::State);
 }
 
+/// Maximal number of blocks that are passed to
+/// [`ParallelPermutation::apply_parallel`] at once.
+const MAX_BATCH: usize = 8;
+
 /// Parameters for the Farfalle construction.
 ///
 /// The permutation state is expected to be at least 33 bytes long, i.e. 262
 /// bits.
+///
+/// Permutations C and E are applied to runs of full blocks in batches of
+/// [`ParallelPermutation::parallelism`] blocks (at most eight), so they
+/// benefit from a parallel kernel.
 pub trait FarfalleConfig: Default + Clone {
     type State: PermutationState;
     type PermutationB: Permutation<State = Self::State>;
-    type PermutationC: Permutation<State = Self::State>;
+    type PermutationC: ParallelPermutation<State = Self::State>;
     type PermutationD: Permutation<State = Self::State>;
-    type PermutationE: Permutation<State = Self::State>;
+    type PermutationE: ParallelPermutation<State = Self::State>;
     type RollC: RollFunction<State = Self::State>;
     type RollE: RollFunction<State = Self::State>;
 
@@ -90,11 +132,69 @@ impl<C: FarfalleConfig> DeckFunction for Farfalle<C> {
     fn output_reader(&self) -> Self::OutputGenerator {
         let mut state = self.state.clone();
         self.config.perm_d().apply(&mut state);
-        FarfalleOutputGenerator::new(self.key.clone(), state, self.config.clone())
+        FarfalleOutputGenerator::new(
+            self.key.clone(),
+            state,
+            self.config.clone(),
+            self.output_budget(),
+        )
+    }
+}
+
+/// The exported state is the rolled key and the accumulator, followed by the
+/// numbers of absorbed and reserved blocks as 8 little endian bytes each, the
+/// block limit as a byte `1` followed by 8 little endian bytes, or 9 zero bytes
+/// when there is no limit, and the number of output blocks to reserve and
+/// reserved for the accumulator, as 8 little endian bytes each. The
+/// [`FarfalleConfig`] is not exported.
+impl<C: FarfalleConfig> ExportableDeckFunction for Farfalle<C> {
+    const EXPORT_LEN: usize = 2 * C::State::SIZE + 41;
+
+    fn export_state(&self, out: &mut [u8]) {
+        assert_eq!(out.len(), Self::EXPORT_LEN);
+        let (key, rest) = out.split_at_mut(C::State::SIZE);
+        let (state, rest) = rest.split_at_mut(C::State::SIZE);
+        self.key.reader().write_to_slice(key).unwrap();
+        self.state.reader().write_to_slice(state).unwrap();
+        rest[..8].copy_from_slice(&self.blocks_absorbed.to_le_bytes());
+        rest[8..16].copy_from_slice(&self.blocks_reserved.to_le_bytes());
+        rest[16] = u8::from(self.block_limit.is_some());
+        rest[17..25].copy_from_slice(&self.block_limit.unwrap_or(0).to_le_bytes());
+        rest[25..33].copy_from_slice(&self.output_blocks.to_le_bytes());
+        rest[33..].copy_from_slice(&self.output_budget.to_le_bytes());
+    }
+
+    fn import_state(&mut self, bytes: &[u8]) -> Result<(), InvalidStateError> {
+        if bytes.len() != Self::EXPORT_LEN {
+            return Err(InvalidStateError);
+        }
+        let (key, rest) = bytes.split_at(C::State::SIZE);
+        let (state, rest) = rest.split_at(C::State::SIZE);
+        let read_u64 = |bytes: &[u8]| u64::from_le_bytes(bytes.try_into().unwrap());
+        let limit = read_u64(&rest[17..25]);
+        let block_limit = match rest[16] {
+            0 if limit == 0 => None,
+            1 => Some(limit),
+            _ => return Err(InvalidStateError),
+        };
+        self.key.copy_bytes_at(0, key);
+        self.state.copy_bytes_at(0, state);
+        self.blocks_absorbed = read_u64(&rest[..8]);
+        self.blocks_reserved = read_u64(&rest[8..16]);
+        self.block_limit = block_limit;
+        self.output_blocks = read_u64(&rest[25..33]);
+        self.output_budget = read_u64(&rest[33..]);
+        Ok(())
     }
 }
 
+#[cfg(feature = "nonstandard-ascon")]
+pub mod ascon;
+#[cfg(feature = "experimental")]
+pub mod gimfff;
 #[cfg(feature = "kravatte")]
 pub mod kravatte;
+#[cfg(feature = "kravatte800")]
+pub mod kravatte800;
 #[cfg(feature = "xoofff")]
 pub mod xoofff;
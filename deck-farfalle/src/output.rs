@@ -1,10 +1,10 @@
 //! Expansion layer of the Farfalle construction.
 
-use crate::RollFunction;
+use crate::{Redacted, RollFunction, MAX_BATCH};
 
 use super::FarfalleConfig;
 use crypto_permutation::io::{check_write_size, CryptoReader, Reader, WriteTooLargeError, Writer};
-use crypto_permutation::{Permutation, PermutationState};
+use crypto_permutation::{ParallelPermutation, Permutation, PermutationState};
 
 /// Expansion part in the Farfalle construction.
 pub struct FarfalleOutputGenerator<C: FarfalleConfig> {
@@ -19,22 +19,51 @@ pub struct FarfalleOutputGenerator<C: FarfalleConfig> {
     output_buffer: C::State,
     /// Number of output bytes still available in `output_buffer`.
     buffered: usize,
+    /// Number of blocks that can still be generated before the block limit is
+    /// reached, or `None` when unlimited.
+    remaining_blocks: Option<u64>,
+}
+
+impl<C: FarfalleConfig + core::fmt::Debug> core::fmt::Debug for FarfalleOutputGenerator<C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FarfalleOutputGenerator")
+            .field("config", &self.config)
+            .field("key", &Redacted)
+            .field("state", &Redacted)
+            .field("output_buffer", &Redacted)
+            .field("buffered", &self.buffered)
+            .field("remaining_blocks", &self.remaining_blocks)
+            .finish()
+    }
 }
 
 impl<C: FarfalleConfig> FarfalleOutputGenerator<C> {
     /// Create a new [`FarfalleOutputGenerator`] from an expansion key `key`,
-    /// state `state` (to which permutation D has already been applied) and
-    /// Farfalle parameters `config`.
-    pub(super) fn new(key: C::State, state: C::State, config: C) -> Self {
+    /// state `state` (to which permutation D has already been applied),
+    /// Farfalle parameters `config` and remaining block budget
+    /// `remaining_blocks`.
+    pub(super) fn new(
+        key: C::State,
+        state: C::State,
+        config: C,
+        remaining_blocks: Option<u64>,
+    ) -> Self {
         Self {
             config,
             key,
             state,
             output_buffer: Default::default(),
             buffered: 0,
+            remaining_blocks,
         }
     }
 
+    /// Number of output blocks that can still be generated before the block
+    /// limit is reached, or `None` when there is no limit.
+    pub fn remaining_blocks(&self) -> Option<u64> {
+        self.remaining_blocks
+    }
+
     /// Apply rolling function E to the state `self.state`.
     fn roll_e_state(&mut self) {
         self.config.roll_e().apply(&mut self.state);
@@ -42,20 +71,72 @@ impl<C: FarfalleConfig> FarfalleOutputGenerator<C> {
 
     /// Write the next output block to `self.output_buffer` and updates
     /// `self.state`. Does not modify `self.buffered`.
+    ///
+    /// The caller must check that the block limit allows generating another
+    /// block.
     fn next_out_block(&mut self) {
+        if let Some(remaining) = self.remaining_blocks.as_mut() {
+            *remaining -= 1;
+        }
         self.output_buffer = self.state.clone();
         self.roll_e_state();
         self.config.perm_e().apply(&mut self.output_buffer);
         self.output_buffer ^= &self.key;
     }
+
+    /// Write `n` full output blocks to `writer` and update `self.state`.
+    /// Permutation E is applied to batches of blocks in parallel. Does not
+    /// modify `self.output_buffer` or `self.buffered`.
+    ///
+    /// The caller must check that the block limit allows generating `n` blocks
+    /// and that `writer` has room for them.
+    fn write_blocks_to<W: Writer>(
+        &mut self,
+        writer: &mut W,
+        n: usize,
+    ) -> Result<(), WriteTooLargeError> {
+        if let Some(remaining) = self.remaining_blocks.as_mut() {
+            *remaining -= n as u64;
+        }
+        let perm_e = self.config.perm_e();
+        let batch = perm_e.parallelism().clamp(1, MAX_BATCH);
+        let mut blocks: [C::State; MAX_BATCH] = Default::default();
+        let mut done = 0;
+        while done < n {
+            let batch_blocks = &mut blocks[..core::cmp::min(batch, n - done)];
+            for block in batch_blocks.iter_mut() {
+                *block = self.state.clone();
+                self.roll_e_state();
+            }
+            perm_e.apply_parallel(batch_blocks);
+            for block in batch_blocks.iter_mut() {
+                *block ^= &self.key;
+                block.reader().write_to(writer, C::State::SIZE)?;
+            }
+            done += batch_blocks.len();
+        }
+        Ok(())
+    }
 }
 
 impl<C: FarfalleConfig> Reader for FarfalleOutputGenerator<C> {
+    /// Returns `usize::MAX` when no block limit is set. Otherwise returns the
+    /// number of bytes that can still be generated before the block limit is
+    /// reached.
     fn capacity(&self) -> usize {
-        usize::MAX
+        match self.remaining_blocks {
+            None => usize::MAX,
+            Some(blocks) => {
+                let bytes = blocks
+                    .saturating_mul(C::State::SIZE as u64)
+                    .saturating_add(self.buffered as u64);
+                usize::try_from(bytes).unwrap_or(usize::MAX)
+            }
+        }
     }
 
     fn skip(&mut self, mut n: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(n, self.capacity())?;
         if self.buffered != 0 {
             let out_size = core::cmp::min(self.buffered, n);
             n -= out_size;
@@ -78,6 +159,7 @@ impl<C: FarfalleConfig> Reader for FarfalleOutputGenerator<C> {
         writer: &mut W,
         mut n: usize,
     ) -> Result<(), WriteTooLargeError> {
+        check_write_size(n, self.capacity())?;
         check_write_size(n, writer.capacity())?;
         if self.buffered != 0 {
             let out_size = core::cmp::min(self.buffered, n);
@@ -89,11 +171,7 @@ impl<C: FarfalleConfig> Reader for FarfalleOutputGenerator<C> {
         }
         let remainder = n % C::State::SIZE;
         let n_blocks = (n - remainder) / C::State::SIZE;
-        for _ in 0..n_blocks {
-            self.next_out_block();
-            let mut reader = self.output_buffer.reader();
-            reader.write_to(writer, C::State::SIZE)?;
-        }
+        self.write_blocks_to(writer, n_blocks)?;
         if remainder != 0 {
             self.next_out_block();
             let mut reader = self.output_buffer.reader();
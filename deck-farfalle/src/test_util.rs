@@ -0,0 +1,24 @@
+//! Helpers shared by the tests of the Farfalle instantiations.
+
+use crate::{Farfalle, FarfalleConfig};
+use crypto_permutation::{DeckFunction, Reader, Writer};
+
+/// Generate `N` bytes of output of `deck` after inputting `msgs` as separate
+/// strings, each split in the given chunks.
+pub(crate) fn generate<C: FarfalleConfig, const N: usize>(
+    mut deck: Farfalle<C>,
+    msgs: &[&[&[u8]]],
+) -> [u8; N] {
+    for msg in msgs {
+        let mut writer = deck.input_writer();
+        for chunk in msg.iter() {
+            writer.write_bytes(chunk).expect("writing message failed");
+        }
+        writer.finish();
+    }
+    let mut output = [0_u8; N];
+    deck.output_reader()
+        .write_to_slice(&mut output)
+        .expect("writing output failed");
+    output
+}
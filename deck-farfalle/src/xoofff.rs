@@ -1,9 +1,18 @@
 //! Xoofff: a xoodoo instantiation of Farfalle.
 
 use super::{Farfalle, FarfalleConfig, RollFunction};
-use crypto_permutation::PermutationState;
 use permutation_xoodoo::{XoodooP, XoodooState};
 
+/// Move planes 1 and 2 to planes 0 and 1, and plane 0, shifted by one lane in
+/// the `x` direction, to plane 2.
+fn shift_planes(state: &mut XoodooState) {
+    let mut b = *state.plane(0);
+    b.rotate_left(1);
+    *state.plane_mut(0) = *state.plane(1);
+    *state.plane_mut(1) = *state.plane(2);
+    *state.plane_mut(2) = b;
+}
+
 #[derive(Copy, Clone, Default, Debug)]
 pub struct RollC;
 
@@ -11,21 +20,9 @@ impl RollFunction for RollC {
     type State = XoodooState;
 
     fn apply(self, state: &mut Self::State) {
-        // The y plane is given by `4 * y + x` indexing into the state
-        let a = &mut state.get_state_mut();
-        a[4 * 0 + 0] ^= (a[4 * 0 + 0] << 13) ^ a[4 * 1 + 0].rotate_left(3);
-        let b: [u32; 4] = {
-            let mut b = [0; 4];
-            b[3] = a[0];
-            b[0] = a[1];
-            b[1] = a[2];
-            b[2] = a[3];
-            b
-        };
-        for i in 0..8 {
-            a[i] = a[i + 4];
-        }
-        a[8..].copy_from_slice(&b[..]);
+        let a0 = state.lane(0, 0);
+        *state.lane_mut(0, 0) ^= (a0 << 13) ^ state.lane(0, 1).rotate_left(3);
+        shift_planes(state);
     }
 }
 
@@ -36,24 +33,11 @@ impl RollFunction for RollE {
     type State = XoodooState;
 
     fn apply(self, state: &mut Self::State) {
-        // The y plane is given by `4 * y + x` indexing into the state
-        let a = &mut state.get_state_mut();
-        a[4 * 0 + 0] = (a[4 * 1 + 0] & a[4 * 2 + 0])
-            ^ (a[4 * 0 + 0].rotate_left(5))
-            ^ (a[4 * 1 + 0].rotate_left(13))
+        *state.lane_mut(0, 0) = (state.lane(0, 1) & state.lane(0, 2))
+            ^ state.lane(0, 0).rotate_left(5)
+            ^ state.lane(0, 1).rotate_left(13)
             ^ 0x00000007;
-        let b: [u32; 4] = {
-            let mut b = [0; 4];
-            b[3] = a[0];
-            b[0] = a[1];
-            b[1] = a[2];
-            b[2] = a[3];
-            b
-        };
-        for i in 0..8 {
-            a[i] = a[i + 4];
-        }
-        a[8..].copy_from_slice(&b[..]);
+        shift_planes(state);
     }
 }
 
@@ -101,6 +85,7 @@ pub type Xoofff = Farfalle<XoofffConfig>;
 #[cfg(test)]
 mod tests {
     use super::Xoofff;
+    use crypto_permutation::io::FinishWithSuffix;
     use crypto_permutation::{DeckFunction, Reader, Writer};
 
     struct XoofffTester {
@@ -127,16 +112,22 @@ mod tests {
         }
 
         fn input_str(&mut self, slices: &[&[u8]]) {
+            self.input_str_with_suffix(slices, 0, 0);
+        }
+
+        /// Input the concatenation of `slices` followed by the `n` bits of
+        /// `suffix`.
+        fn input_str_with_suffix(&mut self, slices: &[&[u8]], suffix: u8, n: u32) {
             assert!(self.state == XoofffState::Absorb);
             let mut writer = self.farfalle.input_writer();
             for data in slices.iter() {
                 writer.write_bytes(data).expect("writing message failed");
                 self.xoofff_crate.absorb(data);
             }
-            writer.finish();
+            writer.finish_with_suffix(suffix, n);
             self.state = XoofffState::Squeeze;
             self.farfalle_output_reader = Some(self.farfalle.output_reader());
-            self.xoofff_crate.finalize(0, 0, 0);
+            self.xoofff_crate.finalize(suffix, n as usize, 0);
         }
 
         fn squeeze_compare(&mut self, n: usize) {
@@ -228,6 +219,275 @@ mod tests {
             writer.finish();
         }
 
-        assert_eq!(xoofff_full, xoofff_split);
+        let mut output_full = [0; 64];
+        let mut output_split = [0; 64];
+        xoofff_full
+            .output_reader()
+            .write_to_slice(&mut output_full)
+            .unwrap();
+        xoofff_split
+            .output_reader()
+            .write_to_slice(&mut output_split)
+            .unwrap();
+        assert_eq!(output_full, output_split);
+    }
+
+    /// Inputting strings asynchronously gives the same output as using an
+    /// input writer.
+    #[cfg(feature = "async")]
+    #[test]
+    fn input_async() {
+        use super::XoofffConfig;
+        use crate::FarfalleConfig;
+
+        /// Poll `future` to completion, without support for waking up.
+        fn block_on<F: core::future::Future>(future: F) -> F::Output {
+            use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+            fn raw_waker() -> RawWaker {
+                fn clone(_: *const ()) -> RawWaker {
+                    raw_waker()
+                }
+                fn noop(_: *const ()) {}
+                static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+                RawWaker::new(core::ptr::null(), &VTABLE)
+            }
+
+            // SAFETY: the vtable functions do nothing, so they uphold the `RawWaker`
+            // contract.
+            let waker = unsafe { Waker::from_raw(raw_waker()) };
+            let mut context = Context::from_waker(&waker);
+            let mut future = Box::pin(future);
+            loop {
+                if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                    return output;
+                }
+            }
+        }
+
+        let key = b"xoofff test key";
+        for len in [0, 11, 47, 48, 49, 96, 200] {
+            let msg: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let mut xoofff_sync = Xoofff::init_default(key);
+            let mut xoofff_async = Xoofff::init_default(key);
+
+            let mut writer = xoofff_sync.input_writer();
+            writer.write_bytes(&msg).expect("writing message failed");
+            writer.finish();
+            block_on(xoofff_async.input_async(XoofffConfig.perm_c(), &msg))
+                .expect("writing message failed");
+
+            let mut output_sync = [0; 64];
+            let mut output_async = [0; 64];
+            xoofff_sync
+                .output_reader()
+                .write_to_slice(&mut output_sync)
+                .unwrap();
+            xoofff_async
+                .output_reader()
+                .write_to_slice(&mut output_async)
+                .unwrap();
+            assert_eq!(output_sync, output_async);
+        }
+    }
+
+    /// Inputs spanning multiple blocks, with lengths around the block
+    /// boundaries.
+    #[test]
+    fn long_input() {
+        let key = b"xoofff test key";
+        for len in [47, 48, 49, 95, 96, 97, 200] {
+            let msg: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let mut tester = XoofffTester::new(key);
+            tester.input_str(&[&msg]);
+            tester.squeeze_compare(64);
+        }
+    }
+
+    /// Sequences of strings, written in pieces, whose final blocks are
+    /// partial, empty or exactly filled by the padding byte. The input writer
+    /// must clear data left behind by earlier blocks before padding.
+    #[test]
+    fn padding_after_processed_blocks() {
+        let key = b"xoofff test key";
+        let msg: Vec<u8> = (0..200).map(|i| (i as u8) ^ 0xa5).collect();
+        let mut tester = XoofffTester::new(key);
+        for len in [97, 48, 47, 95, 1, 96, 0, 143, 49] {
+            let (first, second) = msg[..len].split_at(len / 3);
+            tester.input_str(&[first, second]);
+            tester.squeeze_compare(32);
+            tester.finish_squeeze();
+        }
+        tester.input_str(&[&msg[..47], &msg[47..49], &msg[49..95]]);
+        tester.squeeze_compare(100);
+    }
+
+    /// Xoodoo\[6\] reporting a parallelism of three, counting the largest
+    /// batch passed to `apply_parallel`.
+    #[derive(Copy, Clone, Default, Debug, PartialEq)]
+    struct BatchedXoodoo;
+
+    static LARGEST_BATCH: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
+
+    impl crypto_permutation::Permutation for BatchedXoodoo {
+        type State = permutation_xoodoo::XoodooState;
+
+        fn apply(self, state: &mut Self::State) {
+            crypto_permutation::Permutation::apply(permutation_xoodoo::XoodooP::<6>, state);
+        }
+    }
+
+    impl crypto_permutation::ParallelPermutation for BatchedXoodoo {
+        fn parallelism(self) -> usize {
+            3
+        }
+
+        fn apply_parallel(self, states: &mut [Self::State]) {
+            assert!(states.len() <= 3);
+            LARGEST_BATCH.fetch_max(states.len(), core::sync::atomic::Ordering::Relaxed);
+            for state in states {
+                crypto_permutation::Permutation::apply(self, state);
+            }
+        }
+    }
+
+    /// [`super::XoofffConfig`], but applying permutations C and E in batches
+    /// of three blocks.
+    #[derive(Copy, Clone, Default, Debug, PartialEq)]
+    struct BatchedConfig;
+
+    impl crate::FarfalleConfig for BatchedConfig {
+        type PermutationB = permutation_xoodoo::XoodooP<6>;
+        type PermutationC = BatchedXoodoo;
+        type PermutationD = permutation_xoodoo::XoodooP<6>;
+        type PermutationE = BatchedXoodoo;
+        type RollC = super::RollC;
+        type RollE = super::RollE;
+        type State = permutation_xoodoo::XoodooState;
+
+        fn perm_b(&self) -> Self::PermutationB {
+            Default::default()
+        }
+
+        fn perm_c(&self) -> Self::PermutationC {
+            Default::default()
+        }
+
+        fn perm_d(&self) -> Self::PermutationD {
+            Default::default()
+        }
+
+        fn perm_e(&self) -> Self::PermutationE {
+            Default::default()
+        }
+
+        fn roll_c(&self) -> Self::RollC {
+            Default::default()
+        }
+
+        fn roll_e(&self) -> Self::RollE {
+            Default::default()
+        }
+    }
+
+    /// Processing runs of full blocks in batches gives the same result as
+    /// processing the blocks one at a time. Writing and reading byte by byte
+    /// only ever handles a single block at once.
+    #[test]
+    fn batched_equals_serial() {
+        let key = b"xoofff test key";
+        let msg: Vec<u8> = (0..500).map(|i| (i as u8) ^ 0x3c).collect();
+        for len in [0, 47, 48, 96, 143, 144, 145, 191, 192, 385, 500] {
+            let mut batched = crate::Farfalle::<BatchedConfig>::init_default(key);
+            let mut serial = Xoofff::init_default(key);
+
+            let mut writer = batched.input_writer();
+            writer.write_bytes(&msg[..len]).unwrap();
+            writer.skip(len).unwrap();
+            writer.finish();
+            let mut writer = serial.input_writer();
+            for byte in &msg[..len] {
+                writer.write_bytes(core::slice::from_ref(byte)).unwrap();
+            }
+            for _ in 0..len {
+                writer.skip(1).unwrap();
+            }
+            writer.finish();
+
+            let mut output_batched = vec![0; len + 5];
+            let mut output_serial = vec![0; len + 5];
+            let mut reader = batched.output_reader();
+            reader.write_to_slice(&mut output_batched[..5]).unwrap();
+            reader.write_to_slice(&mut output_batched[5..]).unwrap();
+            let mut reader = serial.output_reader();
+            for byte in output_serial.iter_mut() {
+                reader.write_to_slice(core::slice::from_mut(byte)).unwrap();
+            }
+            assert_eq!(output_batched, output_serial);
+        }
+        assert_eq!(LARGEST_BATCH.load(core::sync::atomic::Ordering::Relaxed), 3);
+    }
+
+    /// Strings ending with a partial byte, whose suffix bits share the final
+    /// byte with the padding.
+    #[test]
+    fn suffix_bits() {
+        let key = b"xoofff test key";
+        let msg: Vec<u8> = (0..100).map(|i| i as u8).collect();
+        let mut tester = XoofffTester::new(key);
+        for (len, suffix, n) in [
+            (0, 0b1, 1),
+            (11, 0b10, 2),
+            (47, 0b011, 3),
+            (48, 0x5a, 7),
+            (60, 0xff, 4),
+        ] {
+            tester.input_str_with_suffix(&[&msg[..len]], suffix, n);
+            tester.squeeze_compare(32);
+            tester.finish_squeeze();
+        }
+    }
+
+    /// An exported and imported state continues like the original one.
+    #[test]
+    fn export_import() {
+        use crypto_permutation::ExportableDeckFunction;
+
+        let key = b"xoofff test key";
+        let mut original = Xoofff::init_default(key);
+        original.set_output_blocks(2);
+        original.set_block_limit(Some(100));
+        let mut writer = original.input_writer();
+        writer.write_bytes(&[0x17; 100]).unwrap();
+        writer.finish();
+
+        let mut exported = [0; Xoofff::EXPORT_LEN];
+        original.export_state(&mut exported);
+        let mut imported = Xoofff::init_default(b"other key");
+        imported.import_state(&exported).unwrap();
+        assert_eq!(imported.blocks_absorbed(), original.blocks_absorbed());
+        assert_eq!(imported.blocks_reserved(), original.blocks_reserved());
+        assert_eq!(imported.block_limit(), Some(100));
+        assert_eq!(imported.output_blocks(), 2);
+        for deck in [&mut original, &mut imported] {
+            let mut writer = deck.input_writer();
+            writer.write_bytes(b"hello world").unwrap();
+            writer.finish();
+        }
+        let mut output_original = [0; 64];
+        let mut output_imported = [0; 64];
+        original
+            .output_reader()
+            .write_to_slice(&mut output_original)
+            .unwrap();
+        imported
+            .output_reader()
+            .write_to_slice(&mut output_imported)
+            .unwrap();
+        assert_eq!(output_original, output_imported);
+
+        assert!(imported.import_state(&exported[1..]).is_err());
+        exported[2 * 48 + 16] = 2;
+        assert!(imported.import_state(&exported).is_err());
     }
 }
@@ -0,0 +1,224 @@
+//! Implementations of the [`aead`] crate traits for single message sessions.
+
+use super::{DeckSane, DeckSanse};
+use aead::consts::{U0, U16, U32};
+use aead::generic_array::{ArrayLength, GenericArray};
+use aead::{AeadCore, AeadInPlace, Key, KeyInit, KeySizeUser, Nonce, Tag};
+use crypto_permutation::DeckFunction;
+
+/// Deck-SANE as a nonce-based AEAD for the [`aead`] crate traits.
+///
+/// Every message is wrapped in a session of its own, started with the 16 byte
+/// nonce; the tag is the 16 byte tag of the message. The tag of the session
+/// start is not needed, since the message tag authenticates the nonce as
+/// well.
+///
+/// # Crypto
+/// A nonce must never be used twice under the same key.
+///
+/// ```
+/// use aead::{AeadInPlace, KeyInit};
+/// use deck_farfalle::xoofff::Xoofff;
+/// use deck_modes::SaneAead;
+///
+/// let aead = SaneAead::<Xoofff>::new(&[0x42; 32].into());
+/// let nonce = [0x17; 16].into();
+/// let mut message = *b"hello world";
+/// let tag = aead
+///     .encrypt_in_place_detached(&nonce, b"metadata", &mut message)
+///     .unwrap();
+/// aead.decrypt_in_place_detached(&nonce, b"metadata", &mut message, &tag)
+///     .unwrap();
+/// assert_eq!(&message, b"hello world");
+/// ```
+#[derive(Clone)]
+pub struct SaneAead<F> {
+    deck: F,
+}
+
+/// Deck-SANSE as a nonce-misuse resistant AEAD for the [`aead`] crate
+/// traits.
+///
+/// Every message is wrapped in a session of its own, with the 16 byte nonce
+/// prepended to the metadata; the tag is the 32 byte tag of the message.
+/// Reusing a nonce only leaks whether messages were equal.
+#[derive(Clone)]
+pub struct SanseAead<F> {
+    deck: F,
+}
+
+impl<F> core::fmt::Debug for SaneAead<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SaneAead").finish_non_exhaustive()
+    }
+}
+
+impl<F> core::fmt::Debug for SanseAead<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SanseAead").finish_non_exhaustive()
+    }
+}
+
+/// The tag `tag` of the `aead` traits as a [`crate::Tag`].
+fn tag_from<N: ArrayLength<u8>, const LEN: usize>(
+    tag: &GenericArray<u8, N>,
+) -> aead::Result<crate::Tag<LEN>> {
+    tag.as_slice().try_into().map_err(|_| aead::Error)
+}
+
+impl<F: DeckFunction> KeySizeUser for SaneAead<F> {
+    type KeySize = U32;
+}
+
+impl<F: DeckFunction> KeyInit for SaneAead<F> {
+    fn new(key: &Key<Self>) -> Self {
+        Self {
+            deck: F::init(key.as_ref()),
+        }
+    }
+}
+
+impl<F: DeckFunction> AeadCore for SaneAead<F> {
+    type NonceSize = U16;
+    type TagSize = U16;
+    type CiphertextOverhead = U0;
+}
+
+impl<F: DeckFunction + Clone> AeadInPlace for SaneAead<F> {
+    fn encrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+    ) -> aead::Result<Tag<Self>> {
+        let (mut session, _) =
+            DeckSane::<F, 16>::start_session(self.deck.clone(), nonce).map_err(|_| aead::Error)?;
+        let tag = session
+            .wrap(associated_data, buffer)
+            .map_err(|_| aead::Error)?;
+        Ok(GenericArray::from(tag.into_bytes()))
+    }
+
+    fn decrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+        tag: &Tag<Self>,
+    ) -> aead::Result<()> {
+        let (mut session, _) =
+            DeckSane::<F, 16>::start_session(self.deck.clone(), nonce).map_err(|_| aead::Error)?;
+        session
+            .unwrap(associated_data, buffer, &tag_from(tag)?)
+            .map_err(|_| aead::Error)
+    }
+}
+
+impl<F: DeckFunction> KeySizeUser for SanseAead<F> {
+    type KeySize = U32;
+}
+
+impl<F: DeckFunction> KeyInit for SanseAead<F> {
+    fn new(key: &Key<Self>) -> Self {
+        Self {
+            deck: F::init(key.as_ref()),
+        }
+    }
+}
+
+impl<F: DeckFunction> AeadCore for SanseAead<F> {
+    type NonceSize = U16;
+    type TagSize = U32;
+    type CiphertextOverhead = U0;
+}
+
+impl<F: DeckFunction + Clone> AeadInPlace for SanseAead<F> {
+    fn encrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+    ) -> aead::Result<Tag<Self>> {
+        let tag = DeckSanse::<F, 32>::new(self.deck.clone())
+            .wrap_parts(&[nonce, associated_data], buffer)
+            .map_err(|_| aead::Error)?;
+        Ok(GenericArray::from(tag.into_bytes()))
+    }
+
+    fn decrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+        tag: &Tag<Self>,
+    ) -> aead::Result<()> {
+        DeckSanse::<F, 32>::new(self.deck.clone())
+            .unwrap_parts(&[nonce, associated_data], buffer, &tag_from(tag)?)
+            .map_err(|_| aead::Error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SaneAead, SanseAead};
+    use crate::{DeckSane, DeckSanse};
+    use aead::{AeadInPlace, KeyInit};
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::xoofff::Xoofff;
+
+    const KEY: [u8; 32] = [0xae; 32];
+
+    fn roundtrip<A: AeadInPlace>(aead: A) {
+        let nonce = Default::default();
+        let mut data = *b"attack at dawn";
+        let tag = aead
+            .encrypt_in_place_detached(&nonce, b"metadata", &mut data)
+            .unwrap();
+        let ciphertext = data;
+        assert!(aead
+            .decrypt_in_place_detached(&nonce, b"other", &mut data, &tag)
+            .is_err());
+        assert_eq!(data, ciphertext);
+        aead.decrypt_in_place_detached(&nonce, b"metadata", &mut data, &tag)
+            .unwrap();
+        assert_eq!(&data, b"attack at dawn");
+    }
+
+    #[test]
+    fn roundtrips() {
+        roundtrip(SaneAead::<Xoofff>::new(&KEY.into()));
+        roundtrip(SanseAead::<Xoofff>::new(&KEY.into()));
+    }
+
+    /// The traits wrap single message sessions.
+    #[test]
+    fn sessions() {
+        let nonce = [0x17; 16];
+        let mut data = *b"plaintext";
+        let tag = SaneAead::<Xoofff>::new(&KEY.into())
+            .encrypt_in_place_detached(&nonce.into(), b"metadata", &mut data)
+            .unwrap();
+        let mut expected = *b"plaintext";
+        let (mut session, _) =
+            DeckSane::<Xoofff>::start_session(Xoofff::init(&KEY), &nonce).unwrap();
+        assert_eq!(
+            session.wrap(b"metadata", &mut expected).unwrap().as_ref(),
+            tag.as_slice()
+        );
+        assert_eq!(data, expected);
+
+        let mut data = *b"plaintext";
+        let tag = SanseAead::<Xoofff>::new(&KEY.into())
+            .encrypt_in_place_detached(&nonce.into(), b"metadata", &mut data)
+            .unwrap();
+        let mut expected = *b"plaintext";
+        let mut metadata = nonce.to_vec();
+        metadata.extend_from_slice(b"metadata");
+        let mut session = DeckSanse::<Xoofff>::new(Xoofff::init(&KEY));
+        assert_eq!(
+            session.wrap(&metadata, &mut expected).unwrap().as_ref(),
+            tag.as_slice()
+        );
+        assert_eq!(data, expected);
+    }
+}
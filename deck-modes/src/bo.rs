@@ -0,0 +1,217 @@
+//! Deck-BO: nonce-based authenticated encryption.
+
+use super::{input_suffixed, output_at, xor_output, Error, Tag};
+use crypto_permutation::{DeckFunction, Writer};
+use subtle::ConstantTimeEq;
+
+/// Domain separation bit following metadata.
+const METADATA: u8 = 0;
+/// Domain separation bit following ciphertext.
+const CIPHERTEXT: u8 = 1;
+
+/// Deck-BO nonce-based authenticated encryption, with `TAG_LEN` byte tags.
+///
+/// The single-message mode from "Jammin' on the deck": the ciphertext is the
+/// plaintext xored with the keystream `F(A || 0 ∘ N)`, and the tag is
+/// `F(C || 1 ∘ A || 0 ∘ N)`, with `N` the nonce, `A` the metadata, `C` the
+/// ciphertext and `|| 0` and `|| 1` appending a single bit. The ciphertext expands by exactly `TAG_LEN` bytes. As the
+/// keystream and tag share the prefix `A || 0 ∘ N`, it is absorbed only once.
+///
+/// Unwrapping verifies the tag before decrypting. The mode is not tested
+/// against reference test vectors, as the paper has no reference
+/// implementation.
+///
+/// # Crypto
+/// A nonce must never be used twice under the same key.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use crypto_permutation::DeckFunction;
+/// use deck_modes::DeckBo;
+///
+/// let bo = DeckBo::<Xoofff>::new(Xoofff::init(&[0x42; 32]));
+/// let mut message = *b"hello world";
+/// let tag = bo.wrap(b"nonce", b"metadata", &mut message).unwrap();
+/// bo.unwrap(b"nonce", b"metadata", &mut message, &tag).unwrap();
+/// assert_eq!(&message, b"hello world");
+/// ```
+#[derive(Clone)]
+pub struct DeckBo<F, const TAG_LEN: usize = 16> {
+    deck: F,
+}
+
+impl<F, const TAG_LEN: usize> core::fmt::Debug for DeckBo<F, TAG_LEN> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DeckBo")
+            .field("tag_len", &TAG_LEN)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction + Clone, const TAG_LEN: usize> DeckBo<F, TAG_LEN> {
+    /// Create the scheme keyed by the deck function `deck`.
+    pub fn new(deck: F) -> Self {
+        Self { deck }
+    }
+
+    /// The deck function after absorbing `nonce` and `metadata`.
+    fn absorb(&self, nonce: &[u8], metadata: &[u8]) -> Result<F, Error> {
+        let mut deck = self.deck.clone();
+        let mut writer = deck.input_writer();
+        writer.write_bytes(nonce)?;
+        writer.finish();
+        input_suffixed(&mut deck, &[metadata], METADATA, 1)?;
+        Ok(deck)
+    }
+
+    /// The tag for `ciphertext`, given the absorbed nonce and metadata.
+    fn tag(prefix: &F, ciphertext: &[u8]) -> Result<Tag<TAG_LEN>, Error> {
+        let mut deck = prefix.clone();
+        input_suffixed(&mut deck, &[ciphertext], CIPHERTEXT, 1)?;
+        Ok(output_at(&deck, 0)?.into())
+    }
+
+    /// Encrypt `data` in place and authenticate it together with `nonce` and
+    /// `metadata`. Returns the tag.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the input, leaving the
+    /// contents of `data` unspecified.
+    pub fn wrap(
+        &self,
+        nonce: &[u8],
+        metadata: &[u8],
+        data: &mut [u8],
+    ) -> Result<Tag<TAG_LEN>, Error> {
+        let prefix = self.absorb(nonce, metadata)?;
+        xor_output(&mut prefix.output_reader(), data)?;
+        Self::tag(&prefix, data)
+    }
+
+    /// Verify `tag` for the ciphertext `data`, `nonce` and `metadata`, and
+    /// decrypt `data` in place.
+    ///
+    /// The tag is compared in constant time, and `data` is only decrypted
+    /// when it matches.
+    ///
+    /// # Errors
+    /// Errors with [`Error::TagMismatch`] when the tag doesn't match, leaving
+    /// `data` unchanged. Errors when the deck function does not accept the
+    /// input.
+    pub fn unwrap(
+        &self,
+        nonce: &[u8],
+        metadata: &[u8],
+        data: &mut [u8],
+        tag: &Tag<TAG_LEN>,
+    ) -> Result<(), Error> {
+        let prefix = self.absorb(nonce, metadata)?;
+        if !bool::from(Self::tag(&prefix, data)?.ct_eq(tag)) {
+            return Err(Error::TagMismatch);
+        }
+        xor_output(&mut prefix.output_reader(), data)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeckBo;
+    use crate::Error;
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::xoofff::Xoofff;
+
+    fn bo() -> DeckBo<Xoofff> {
+        DeckBo::new(Xoofff::init(&[0x69; 32]))
+    }
+
+    #[test]
+    fn roundtrip() {
+        let bo = bo();
+        for len in [0, 1, 48, 100] {
+            let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let mut data = plaintext.clone();
+            let tag = bo.wrap(b"nonce", b"metadata", &mut data).unwrap();
+            if len > 0 {
+                assert_ne!(data, plaintext);
+            }
+            bo.unwrap(b"nonce", b"metadata", &mut data, &tag).unwrap();
+            assert_eq!(data, plaintext);
+        }
+    }
+
+    /// The nonce and metadata are domain separated from each other.
+    #[test]
+    fn separation() {
+        let bo = bo();
+        let mut data = [0; 16];
+        let tag = bo.wrap(b"nonce", b"", &mut data).unwrap();
+        let mut other = [0; 16];
+        let other_tag = bo.wrap(b"", b"nonce", &mut other).unwrap();
+        assert_ne!(data, other);
+        assert_ne!(tag, other_tag);
+
+        let mut other = [0; 16];
+        let other_tag = bo.wrap(b"nonce2", b"", &mut other).unwrap();
+        assert_ne!(data, other);
+        assert_ne!(tag, other_tag);
+    }
+
+    /// The metadata and the ciphertext are followed by a single domain
+    /// separation bit, the nonce by none.
+    #[test]
+    fn suffix_bits() {
+        use crypto_permutation::io::FinishWithSuffix;
+        use crypto_permutation::{Reader, Writer};
+
+        fn input(deck: &mut Xoofff, data: &[u8], bit: u8, n: u32) {
+            let mut writer = deck.input_writer();
+            writer.write_bytes(data).unwrap();
+            writer.finish_with_suffix(bit, n);
+        }
+
+        let mut deck = Xoofff::init(&[0x69; 32]);
+        input(&mut deck, b"nonce", 0, 0);
+        input(&mut deck, b"metadata", 0, 1);
+        let plaintext = *b"plaintext";
+        let mut ciphertext = [0; 9];
+        deck.output_reader()
+            .write_to_slice(&mut ciphertext)
+            .unwrap();
+        for (byte, plain) in ciphertext.iter_mut().zip(plaintext) {
+            *byte ^= plain;
+        }
+        input(&mut deck, &ciphertext, 1, 1);
+        let mut expected_tag = [0; 16];
+        deck.output_reader()
+            .write_to_slice(&mut expected_tag)
+            .unwrap();
+
+        let mut data = plaintext;
+        let tag = bo().wrap(b"nonce", b"metadata", &mut data).unwrap();
+        assert_eq!(data, ciphertext);
+        assert_eq!(tag.into_bytes(), expected_tag);
+    }
+
+    #[test]
+    fn forgery_rejected() {
+        let bo = bo();
+        let mut data = *b"attack at dawn";
+        let tag = bo.wrap(b"nonce", b"metadata", &mut data).unwrap();
+        let ciphertext = data;
+
+        data[0] ^= 1;
+        let result = bo.unwrap(b"nonce", b"metadata", &mut data, &tag);
+        assert!(matches!(result, Err(Error::TagMismatch)));
+        data[0] ^= 1;
+        assert_eq!(data, ciphertext);
+        for (nonce, metadata) in [(&b"nonce"[..], &b"other"[..]), (b"other", b"metadata")] {
+            let result = bo.unwrap(nonce, metadata, &mut data, &tag);
+            assert!(matches!(result, Err(Error::TagMismatch)));
+            assert_eq!(data, ciphertext);
+        }
+
+        bo.unwrap(b"nonce", b"metadata", &mut data, &tag).unwrap();
+        assert_eq!(&data, b"attack at dawn");
+    }
+}
@@ -0,0 +1,198 @@
+//! Cascade of two deck functions.
+
+use crypto_permutation::io::{check_write_size, FinishWithSuffix};
+use crypto_permutation::{CryptoReader, DeckFunction, Reader, WriteTooLargeError, Writer};
+
+/// Deck function combining two deck functions, for redundancy against the
+/// failure of a single primitive.
+///
+/// Every input string is input into both deck functions, and the output is
+/// the xor of their outputs. The combination is a pseudorandom function as
+/// long as one of the two is, provided that their keys are independent.
+/// [`DeckFunction::init`] keys both with the same key, so that a break of one
+/// deck function that reveals its key also reveals the key of the other;
+/// [`Cascade::new`] combines independently keyed deck functions.
+///
+/// The cascade costs the sum of the costs of the two deck functions, and
+/// accepts as much input and output as the most limited of them.
+///
+/// ```
+/// # #[cfg(feature = "kravatte")]
+/// # {
+/// use deck_farfalle::kravatte::Kravatte;
+/// use deck_farfalle::xoofff::Xoofff;
+/// use crypto_permutation::DeckFunction;
+/// use deck_modes::{Cascade, DeckBo};
+///
+/// let deck = Cascade::new(Kravatte::init(&[0x42; 32]), Xoofff::init(&[0x17; 32]));
+/// let bo = DeckBo::<_>::new(deck);
+/// let mut message = *b"hello world";
+/// let tag = bo.wrap(b"nonce", b"", &mut message).unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Cascade<D1, D2> {
+    first: D1,
+    second: D2,
+}
+
+impl<D1, D2> core::fmt::Debug for Cascade<D1, D2> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Cascade").finish_non_exhaustive()
+    }
+}
+
+impl<D1, D2> Cascade<D1, D2> {
+    /// Combine the keyed deck functions `first` and `second`.
+    pub fn new(first: D1, second: D2) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<D1: DeckFunction, D2: DeckFunction> DeckFunction for Cascade<D1, D2> {
+    type InputWriter<'a>
+        = CascadeWriter<D1::InputWriter<'a>, D2::InputWriter<'a>>
+    where
+        Self: 'a;
+    type OutputGenerator = CascadeReader<D1::OutputGenerator, D2::OutputGenerator>;
+
+    fn init(key: &[u8; 32]) -> Self {
+        Self::new(D1::init(key), D2::init(key))
+    }
+
+    fn input_writer(&mut self) -> Self::InputWriter<'_> {
+        CascadeWriter {
+            first: self.first.input_writer(),
+            second: self.second.input_writer(),
+        }
+    }
+
+    fn output_reader(&self) -> Self::OutputGenerator {
+        CascadeReader {
+            first: self.first.output_reader(),
+            second: self.second.output_reader(),
+        }
+    }
+}
+
+/// Input writer of a [`Cascade`], writing into both deck functions.
+#[derive(Debug)]
+pub struct CascadeWriter<W1, W2> {
+    first: W1,
+    second: W2,
+}
+
+impl<W1: Writer, W2: Writer> Writer for CascadeWriter<W1, W2> {
+    type Return = (W1::Return, W2::Return);
+
+    fn capacity(&self) -> usize {
+        self.first.capacity().min(self.second.capacity())
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(len, self.capacity())?;
+        self.first.skip(len)?;
+        self.second.skip(len)
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), WriteTooLargeError> {
+        check_write_size(data.len(), self.capacity())?;
+        self.first.write_bytes(data)?;
+        self.second.write_bytes(data)
+    }
+
+    fn finish(self) -> Self::Return {
+        (self.first.finish(), self.second.finish())
+    }
+}
+
+impl<W1: FinishWithSuffix, W2: FinishWithSuffix> FinishWithSuffix for CascadeWriter<W1, W2> {
+    fn finish_with_suffix(self, bits: u8, n: u32) -> Self::Return {
+        (
+            self.first.finish_with_suffix(bits, n),
+            self.second.finish_with_suffix(bits, n),
+        )
+    }
+}
+
+/// Output generator of a [`Cascade`], generating the xor of the outputs of
+/// both deck functions.
+#[derive(Debug, Clone)]
+pub struct CascadeReader<R1, R2> {
+    first: R1,
+    second: R2,
+}
+
+impl<R1: Reader, R2: Reader> Reader for CascadeReader<R1, R2> {
+    fn capacity(&self) -> usize {
+        self.first.capacity().min(self.second.capacity())
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(len, self.capacity())?;
+        self.first.skip(len)?;
+        self.second.skip(len)
+    }
+
+    fn write_to<W: Writer>(&mut self, writer: &mut W, n: usize) -> Result<(), WriteTooLargeError> {
+        const CHUNK: usize = 64;
+        check_write_size(n, self.capacity())?;
+        check_write_size(n, writer.capacity())?;
+        let mut first = [0; CHUNK];
+        let mut second = [0; CHUNK];
+        let mut remaining = n;
+        while remaining > 0 {
+            let len = remaining.min(CHUNK);
+            let (first, second) = (&mut first[..len], &mut second[..len]);
+            self.first.write_to_slice(first)?;
+            self.second.write_to_slice(second)?;
+            for (byte, other) in first.iter_mut().zip(second.iter()) {
+                *byte ^= other;
+            }
+            writer.write_bytes(first)?;
+            remaining -= len;
+        }
+        Ok(())
+    }
+}
+
+impl<R1: CryptoReader, R2: CryptoReader> CryptoReader for CascadeReader<R1, R2> {}
+
+#[cfg(test)]
+mod tests {
+    use super::Cascade;
+    use crypto_permutation::{DeckFunction, Reader, Writer};
+    use deck_farfalle::xoofff::Xoofff;
+
+    fn output<D: DeckFunction>(mut deck: D) -> [u8; 300] {
+        for input in [&b"hello"[..], b"", b"world"] {
+            let mut writer = deck.input_writer();
+            writer.write_bytes(input).unwrap();
+            writer.finish();
+        }
+        let mut output = [0; 300];
+        let mut reader = deck.output_reader();
+        reader.write_to_slice(&mut output[..100]).unwrap();
+        reader.skip(1).unwrap();
+        output[100] = 0;
+        reader.write_to_slice(&mut output[101..]).unwrap();
+        output
+    }
+
+    /// The output is the xor of the outputs of both deck functions.
+    #[test]
+    fn xor() {
+        let cascade = Cascade::new(Xoofff::init(&[1; 32]), Xoofff::init(&[2; 32]));
+        let first = output(Xoofff::init(&[1; 32]));
+        let second = output(Xoofff::init(&[2; 32]));
+        let expected: Vec<u8> = first.iter().zip(second).map(|(a, b)| a ^ b).collect();
+        assert_eq!(output(cascade)[..], expected);
+    }
+
+    #[test]
+    fn init() {
+        // Identical deck functions with the same key cancel out.
+        let cascade = Cascade::<Xoofff, Xoofff>::init(&[3; 32]);
+        assert_eq!(output(cascade), [0; 300]);
+    }
+}
@@ -0,0 +1,292 @@
+//! Secure bidirectional channel with automatic rekeying.
+
+use super::kdf::DeckKdf;
+use super::{DeckBo, Error, Tag};
+use crypto_permutation::DeckFunction;
+
+/// Role of a party of a [`SecureChannel`]. The two parties must have
+/// different roles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// The party that started the connection, e.g. a client.
+    Initiator,
+    /// The party that accepted the connection, e.g. a server.
+    Responder,
+}
+
+/// Limits after which a direction of a [`SecureChannel`] rekeys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RekeyLimits {
+    /// Maximum number of messages under a key.
+    pub messages: u64,
+    /// Maximum number of plaintext bytes under a key. A single message may
+    /// exceed it.
+    pub bytes: u64,
+}
+
+impl Default for RekeyLimits {
+    /// Rekey after 2^20 messages or 2^30 bytes.
+    fn default() -> Self {
+        Self {
+            messages: 1 << 20,
+            bytes: 1 << 30,
+        }
+    }
+}
+
+/// One direction of a [`SecureChannel`].
+#[derive(Clone)]
+struct Direction<F, const TAG_LEN: usize> {
+    key: [u8; 32],
+    bo: DeckBo<F, TAG_LEN>,
+    /// Sequence number of the next message.
+    sequence: u64,
+    /// Number of messages under the current key.
+    messages: u64,
+    /// Number of plaintext bytes under the current key.
+    bytes: u64,
+}
+
+impl<F: DeckFunction + Clone, const TAG_LEN: usize> Direction<F, TAG_LEN> {
+    fn new(key: [u8; 32]) -> Self {
+        Self {
+            key,
+            bo: DeckBo::new(F::init(&key)),
+            sequence: 0,
+            messages: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Account for a message of `len` bytes, and rekey when over the limits.
+    fn advance(&mut self, len: usize, limits: &RekeyLimits) -> Result<(), Error> {
+        self.sequence = self
+            .sequence
+            .checked_add(1)
+            .expect("sequence number doesn't overflow");
+        self.messages += 1;
+        self.bytes = self.bytes.saturating_add(len as u64);
+        if self.messages >= limits.messages || self.bytes >= limits.bytes {
+            let mut key = [0; 32];
+            DeckKdf::new(F::init(&self.key)).derive(REKEY, &[], &mut key)?;
+            *self = Self {
+                sequence: self.sequence,
+                ..Self::new(key)
+            };
+        }
+        Ok(())
+    }
+}
+
+/// KDF label of the key of messages from the initiator to the responder.
+const INITIATOR_TO_RESPONDER: &str = "initiator to responder";
+/// KDF label of the key of messages from the responder to the initiator.
+const RESPONDER_TO_INITIATOR: &str = "responder to initiator";
+/// KDF label of the next key of a direction.
+const REKEY: &str = "rekey";
+
+/// Secure bidirectional channel over a reliable, ordered transport, with
+/// `TAG_LEN` byte tags.
+///
+/// Each direction is encrypted with Deck-BO under its own key, derived from
+/// the shared secret with [`DeckKdf`]. The nonce of a message is its 8 byte
+/// little endian sequence number, so messages that are replayed, reordered
+/// or dropped fail to open. After [`RekeyLimits`] is reached, the key of the
+/// direction is replaced by one derived from it, which the other party
+/// mirrors. Old keys are discarded, so the compromise of a channel doesn't
+/// reveal messages from before the last rekey.
+///
+/// Opening a message only advances the channel when it is authentic. As the
+/// transport is reliable, a failure means that the channel was tampered with,
+/// and it should be closed.
+///
+/// # Crypto
+/// The shared secret must be uniformly random, e.g. the output of a key
+/// exchange processed with [`kdf::extract`](super::kdf::extract), and must
+/// not be used for more than one channel.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use deck_modes::{Role, SecureChannel};
+///
+/// let secret = [0x42; 32];
+/// let mut client = SecureChannel::<Xoofff>::new(&secret, Role::Initiator, Default::default());
+/// let mut server = SecureChannel::<Xoofff>::new(&secret, Role::Responder, Default::default());
+///
+/// let mut request = *b"GET /";
+/// let tag = client.seal(b"", &mut request).unwrap();
+/// server.open(b"", &mut request, &tag).unwrap();
+/// assert_eq!(&request, b"GET /");
+/// ```
+#[derive(Clone)]
+pub struct SecureChannel<F, const TAG_LEN: usize = 16> {
+    send: Direction<F, TAG_LEN>,
+    receive: Direction<F, TAG_LEN>,
+    limits: RekeyLimits,
+}
+
+impl<F, const TAG_LEN: usize> core::fmt::Debug for SecureChannel<F, TAG_LEN> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SecureChannel")
+            .field("tag_len", &TAG_LEN)
+            .field("limits", &self.limits)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction + Clone, const TAG_LEN: usize> SecureChannel<F, TAG_LEN> {
+    /// Create the channel endpoint of the party with role `role`, from the
+    /// 32 byte secret `secret` shared with the other party.
+    pub fn new(secret: &[u8; 32], role: Role, limits: RekeyLimits) -> Self {
+        let kdf = DeckKdf::new(F::init(secret));
+        let derive = |label| {
+            let mut key = [0; 32];
+            kdf.derive(label, &[], &mut key)
+                .expect("deck function generates a key");
+            Direction::new(key)
+        };
+        let (send, receive) = match role {
+            Role::Initiator => (INITIATOR_TO_RESPONDER, RESPONDER_TO_INITIATOR),
+            Role::Responder => (RESPONDER_TO_INITIATOR, INITIATOR_TO_RESPONDER),
+        };
+        Self {
+            send: derive(send),
+            receive: derive(receive),
+            limits,
+        }
+    }
+
+    /// Encrypt the next message `data` in place and authenticate it together
+    /// with `metadata`. Returns the tag.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the input, leaving the
+    /// contents of `data` unspecified. The channel is not advanced.
+    pub fn seal(&mut self, metadata: &[u8], data: &mut [u8]) -> Result<Tag<TAG_LEN>, Error> {
+        let mut send = self.send.clone();
+        let tag = send.bo.wrap(&send.sequence.to_le_bytes(), metadata, data)?;
+        send.advance(data.len(), &self.limits)?;
+        self.send = send;
+        Ok(tag)
+    }
+
+    /// Verify and decrypt the next message `data` in place, with metadata
+    /// `metadata` and tag `tag`.
+    ///
+    /// # Errors
+    /// Errors with [`Error::TagMismatch`] when the message is not authentic or
+    /// not the next one, leaving `data` unchanged. Errors when the deck
+    /// function does not accept the input. The channel is not advanced.
+    pub fn open(
+        &mut self,
+        metadata: &[u8],
+        data: &mut [u8],
+        tag: &Tag<TAG_LEN>,
+    ) -> Result<(), Error> {
+        let mut receive = self.receive.clone();
+        receive
+            .bo
+            .unwrap(&receive.sequence.to_le_bytes(), metadata, data, tag)?;
+        receive.advance(data.len(), &self.limits)?;
+        self.receive = receive;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RekeyLimits, Role, SecureChannel};
+    use crate::Error;
+    use deck_farfalle::xoofff::Xoofff;
+
+    const SECRET: [u8; 32] = [0xc4; 32];
+
+    fn channels(limits: RekeyLimits) -> (SecureChannel<Xoofff>, SecureChannel<Xoofff>) {
+        (
+            SecureChannel::new(&SECRET, Role::Initiator, limits),
+            SecureChannel::new(&SECRET, Role::Responder, limits),
+        )
+    }
+
+    #[test]
+    fn roundtrip() {
+        let limits = RekeyLimits {
+            messages: 3,
+            bytes: 100,
+        };
+        let (mut client, mut server) = channels(limits);
+        for len in [0, 1, 50, 200, 7, 7, 7, 7, 64] {
+            let plaintext: Vec<u8> = (0..len as u8).collect();
+            let mut data = plaintext.clone();
+            let tag = client.seal(b"to server", &mut data).unwrap();
+            server.open(b"to server", &mut data, &tag).unwrap();
+            assert_eq!(data, plaintext);
+
+            let tag = server.seal(b"to client", &mut data).unwrap();
+            client.open(b"to client", &mut data, &tag).unwrap();
+            assert_eq!(data, plaintext);
+        }
+    }
+
+    /// The directions use different keys.
+    #[test]
+    fn directions() {
+        let (mut client, mut server) = channels(RekeyLimits::default());
+        let mut data = *b"message";
+        let tag = client.seal(b"", &mut data).unwrap();
+        let mut reflected = data;
+        assert!(matches!(
+            client.open(b"", &mut reflected, &tag),
+            Err(Error::TagMismatch)
+        ));
+        let mut other = *b"message";
+        assert_ne!(server.seal(b"", &mut other).unwrap(), tag);
+    }
+
+    /// Replayed, reordered and tampered messages fail to open, without
+    /// advancing the channel.
+    #[test]
+    fn ordering() {
+        let (mut client, mut server) = channels(RekeyLimits::default());
+        let mut first = *b"first";
+        let first_tag = client.seal(b"", &mut first).unwrap();
+        let mut second = *b"second";
+        let second_tag = client.seal(b"", &mut second).unwrap();
+
+        let mut data = second;
+        assert!(server.open(b"", &mut data, &second_tag).is_err());
+        let mut data = first;
+        assert!(server.open(b"x", &mut data, &first_tag).is_err());
+        server.open(b"", &mut data, &first_tag).unwrap();
+        let mut data = first;
+        assert!(server.open(b"", &mut data, &first_tag).is_err());
+        server.open(b"", &mut second, &second_tag).unwrap();
+        assert_eq!(&second, b"second");
+    }
+
+    /// Rekeying changes the key, and is mirrored by the other party.
+    #[test]
+    fn rekey() {
+        let limits = RekeyLimits {
+            messages: 2,
+            bytes: u64::MAX,
+        };
+        let (mut client, _) = channels(limits);
+        let key = client.send.key;
+        client.seal(b"", &mut []).unwrap();
+        assert_eq!(client.send.key, key);
+        client.seal(b"", &mut []).unwrap();
+        assert_ne!(client.send.key, key);
+        assert_eq!(client.send.sequence, 2);
+
+        let limits = RekeyLimits {
+            messages: u64::MAX,
+            bytes: 10,
+        };
+        let (mut client, _) = channels(limits);
+        client.seal(b"", &mut [0; 9]).unwrap();
+        assert_eq!(client.send.key, key);
+        client.seal(b"", &mut [0; 1]).unwrap();
+        assert_ne!(client.send.key, key);
+    }
+}
@@ -0,0 +1,556 @@
+//! STREAM-like chunked authenticated encryption.
+
+use super::{DeckBo, Error, Tag};
+use crypto_permutation::DeckFunction;
+
+/// Length in bytes of the tag of a chunk.
+pub const CHUNK_TAG_LEN: usize = 16;
+
+/// State shared by [`ChunkedEncryptor`] and [`ChunkedDecryptor`].
+#[derive(Clone)]
+struct ChunkState<F> {
+    bo: DeckBo<F, CHUNK_TAG_LEN>,
+    nonce: [u8; 16],
+    /// Index of the next chunk.
+    counter: u64,
+}
+
+impl<F: DeckFunction + Clone> ChunkState<F> {
+    fn new(deck: F, nonce: &[u8; 16]) -> Self {
+        Self {
+            bo: DeckBo::new(deck),
+            nonce: *nonce,
+            counter: 0,
+        }
+    }
+
+    /// The Deck-BO nonce of the next chunk: the stream nonce, the chunk index
+    /// as 8 little endian bytes and the last chunk flag.
+    fn chunk_nonce(&self, last: bool) -> [u8; 25] {
+        let mut nonce = [0; 25];
+        nonce[..16].copy_from_slice(&self.nonce);
+        nonce[16..24].copy_from_slice(&self.counter.to_le_bytes());
+        nonce[24] = u8::from(last);
+        nonce
+    }
+
+    fn advance(&mut self) {
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .expect("chunk counter doesn't overflow");
+    }
+}
+
+/// Encryptor of a stream of chunks, like STREAM or the payload encryption of
+/// age, with `CHUNK_LEN` byte chunks.
+///
+/// Every chunk is encrypted with Deck-BO and gets a [`CHUNK_TAG_LEN`] byte
+/// tag. Its nonce consists of the 16 byte stream nonce, the index of the
+/// chunk and a flag marking the last chunk, so chunks can't be reordered,
+/// dropped or appended, and the stream can't be truncated, without detection.
+/// All chunks are `CHUNK_LEN` bytes long, except for the last one, which may
+/// be shorter or empty.
+///
+/// With the `std` feature, [`EncryptWriter`] and [`DecryptReader`] encrypt
+/// and decrypt arbitrarily long files in bounded memory.
+///
+/// # Crypto
+/// A stream nonce must never be used twice under the same key.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use crypto_permutation::DeckFunction;
+/// use deck_modes::{ChunkedDecryptor, ChunkedEncryptor};
+///
+/// let deck = Xoofff::init(&[0x42; 32]);
+/// let mut encryptor = ChunkedEncryptor::<_, 4>::new(deck.clone(), &[0x17; 16]);
+/// let mut first = *b"abcd";
+/// let first_tag = encryptor.encrypt_next(&mut first).unwrap();
+/// let mut last = *b"ef";
+/// let last_tag = encryptor.encrypt_last(&mut last).unwrap();
+///
+/// let mut decryptor = ChunkedDecryptor::<_, 4>::new(deck, &[0x17; 16]);
+/// decryptor.decrypt_next(&mut first, &first_tag).unwrap();
+/// decryptor.decrypt_last(&mut last, &last_tag).unwrap();
+/// assert_eq!((&first, &last), (b"abcd", b"ef"));
+/// ```
+#[derive(Clone)]
+pub struct ChunkedEncryptor<F, const CHUNK_LEN: usize = 65536> {
+    state: ChunkState<F>,
+}
+
+impl<F, const CHUNK_LEN: usize> core::fmt::Debug for ChunkedEncryptor<F, CHUNK_LEN> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ChunkedEncryptor")
+            .field("chunk_len", &CHUNK_LEN)
+            .field("counter", &self.state.counter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction + Clone, const CHUNK_LEN: usize> ChunkedEncryptor<F, CHUNK_LEN> {
+    /// Start encrypting a stream with the keyed deck function `deck` and the
+    /// stream nonce `nonce`.
+    pub fn new(deck: F, nonce: &[u8; 16]) -> Self {
+        Self {
+            state: ChunkState::new(deck, nonce),
+        }
+    }
+
+    /// Encrypt the next chunk `chunk` in place, which is not the last one.
+    /// Returns its tag.
+    ///
+    /// # Panics
+    /// Panics when `chunk` is not `CHUNK_LEN` bytes long.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the input, leaving the
+    /// contents of `chunk` unspecified. The stream is not advanced.
+    pub fn encrypt_next(&mut self, chunk: &mut [u8]) -> Result<Tag<CHUNK_TAG_LEN>, Error> {
+        assert_eq!(chunk.len(), CHUNK_LEN, "chunk is not the chunk length");
+        let tag = self
+            .state
+            .bo
+            .wrap(&self.state.chunk_nonce(false), &[], chunk)?;
+        self.state.advance();
+        Ok(tag)
+    }
+
+    /// Encrypt the last chunk `chunk` in place, ending the stream. Returns its
+    /// tag.
+    ///
+    /// # Panics
+    /// Panics when `chunk` is longer than `CHUNK_LEN` bytes.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the input, leaving the
+    /// contents of `chunk` unspecified.
+    pub fn encrypt_last(self, chunk: &mut [u8]) -> Result<Tag<CHUNK_TAG_LEN>, Error> {
+        assert!(chunk.len() <= CHUNK_LEN, "chunk exceeds the chunk length");
+        self.state
+            .bo
+            .wrap(&self.state.chunk_nonce(true), &[], chunk)
+    }
+}
+
+/// Decryptor of a stream of chunks encrypted by [`ChunkedEncryptor`].
+#[derive(Clone)]
+pub struct ChunkedDecryptor<F, const CHUNK_LEN: usize = 65536> {
+    state: ChunkState<F>,
+}
+
+impl<F, const CHUNK_LEN: usize> core::fmt::Debug for ChunkedDecryptor<F, CHUNK_LEN> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ChunkedDecryptor")
+            .field("chunk_len", &CHUNK_LEN)
+            .field("counter", &self.state.counter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction + Clone, const CHUNK_LEN: usize> ChunkedDecryptor<F, CHUNK_LEN> {
+    /// Start decrypting a stream with the keyed deck function `deck` and the
+    /// stream nonce `nonce`.
+    pub fn new(deck: F, nonce: &[u8; 16]) -> Self {
+        Self {
+            state: ChunkState::new(deck, nonce),
+        }
+    }
+
+    /// Verify `tag` and decrypt the next chunk `chunk` in place, which is not
+    /// the last one.
+    ///
+    /// # Panics
+    /// Panics when `chunk` is not `CHUNK_LEN` bytes long.
+    ///
+    /// # Errors
+    /// Errors with [`Error::TagMismatch`] when the chunk is not authentic,
+    /// not the next one or the last one, leaving `chunk` unchanged. Errors
+    /// when the deck function does not accept the input. The stream is not
+    /// advanced.
+    pub fn decrypt_next(
+        &mut self,
+        chunk: &mut [u8],
+        tag: &Tag<CHUNK_TAG_LEN>,
+    ) -> Result<(), Error> {
+        assert_eq!(chunk.len(), CHUNK_LEN, "chunk is not the chunk length");
+        self.state
+            .bo
+            .unwrap(&self.state.chunk_nonce(false), &[], chunk, tag)?;
+        self.state.advance();
+        Ok(())
+    }
+
+    /// Verify `tag` and decrypt the last chunk `chunk` in place, ending the
+    /// stream.
+    ///
+    /// # Errors
+    /// Errors with [`Error::TagMismatch`] when the chunk is not authentic or
+    /// not the last one, leaving `chunk` unchanged. Errors when the deck
+    /// function does not accept the input.
+    pub fn decrypt_last(self, chunk: &mut [u8], tag: &Tag<CHUNK_TAG_LEN>) -> Result<(), Error> {
+        if chunk.len() > CHUNK_LEN {
+            return Err(Error::TagMismatch);
+        }
+        self.state
+            .bo
+            .unwrap(&self.state.chunk_nonce(true), &[], chunk, tag)
+    }
+}
+
+#[cfg(feature = "std")]
+mod io {
+    use super::{ChunkedDecryptor, ChunkedEncryptor, CHUNK_TAG_LEN};
+    use crate::{Error, Tag};
+    use crypto_permutation::DeckFunction;
+    use std::io::{self, Read, Write};
+    use std::vec::Vec;
+
+    /// The I/O error for `err`.
+    fn io_error(err: Error) -> io::Error {
+        let kind = match err {
+            Error::TagMismatch => io::ErrorKind::InvalidData,
+            _ => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, err)
+    }
+
+    /// Writer encrypting the data written to it with [`ChunkedEncryptor`],
+    /// and writing the ciphertext chunks, each followed by its tag, to an
+    /// inner writer.
+    ///
+    /// Call [`Self::finish`] to write the last chunk; without it the stream
+    /// is truncated and fails to decrypt. After an error the stream is
+    /// broken.
+    ///
+    /// ```
+    /// use deck_farfalle::xoofff::Xoofff;
+    /// use crypto_permutation::DeckFunction;
+    /// use deck_modes::{DecryptReader, EncryptWriter};
+    /// use std::io::{Read, Write};
+    ///
+    /// let deck = Xoofff::init(&[0x42; 32]);
+    /// let mut writer = EncryptWriter::<_, _>::new(deck.clone(), &[0x17; 16], Vec::new());
+    /// writer.write_all(b"hello world").unwrap();
+    /// let ciphertext = writer.finish().unwrap();
+    ///
+    /// let mut reader = DecryptReader::<_, _>::new(deck, &[0x17; 16], &ciphertext[..]);
+    /// let mut plaintext = Vec::new();
+    /// reader.read_to_end(&mut plaintext).unwrap();
+    /// assert_eq!(plaintext, b"hello world");
+    /// ```
+    pub struct EncryptWriter<F, W, const CHUNK_LEN: usize = 65536> {
+        encryptor: ChunkedEncryptor<F, CHUNK_LEN>,
+        inner: W,
+        /// Plaintext of the current chunk.
+        buffer: Vec<u8>,
+    }
+
+    impl<F, W, const CHUNK_LEN: usize> core::fmt::Debug for EncryptWriter<F, W, CHUNK_LEN> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_struct("EncryptWriter")
+                .field("encryptor", &self.encryptor)
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl<F: DeckFunction + Clone, W: Write, const CHUNK_LEN: usize> EncryptWriter<F, W, CHUNK_LEN> {
+        /// Encrypt with the keyed deck function `deck` and the stream nonce
+        /// `nonce` into `inner`.
+        pub fn new(deck: F, nonce: &[u8; 16], inner: W) -> Self {
+            Self {
+                encryptor: ChunkedEncryptor::new(deck, nonce),
+                inner,
+                buffer: Vec::with_capacity(CHUNK_LEN),
+            }
+        }
+
+        /// Write the encrypted chunk and its tag to the inner writer.
+        fn write_chunk(inner: &mut W, chunk: &[u8], tag: &Tag<CHUNK_TAG_LEN>) -> io::Result<()> {
+            inner.write_all(chunk)?;
+            inner.write_all(tag.as_ref())
+        }
+
+        /// Encrypt and write the last chunk, and return the inner writer.
+        ///
+        /// # Errors
+        /// Errors when the inner writer errors.
+        pub fn finish(self) -> io::Result<W> {
+            let Self {
+                encryptor,
+                mut inner,
+                mut buffer,
+            } = self;
+            let tag = encryptor.encrypt_last(&mut buffer).map_err(io_error)?;
+            Self::write_chunk(&mut inner, &buffer, &tag)?;
+            Ok(inner)
+        }
+    }
+
+    impl<F: DeckFunction + Clone, W: Write, const CHUNK_LEN: usize> Write
+        for EncryptWriter<F, W, CHUNK_LEN>
+    {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if buf.is_empty() {
+                return Ok(0);
+            }
+            // A full chunk is only encrypted once more data follows, as the
+            // last chunk may be full too.
+            if self.buffer.len() == CHUNK_LEN {
+                let tag = self
+                    .encryptor
+                    .encrypt_next(&mut self.buffer)
+                    .map_err(io_error)?;
+                Self::write_chunk(&mut self.inner, &self.buffer, &tag)?;
+                self.buffer.clear();
+            }
+            let len = buf.len().min(CHUNK_LEN - self.buffer.len());
+            self.buffer.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        /// Flush the inner writer. The data of the current chunk is written
+        /// only when the chunk is complete.
+        fn flush(&mut self) -> io::Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    /// Reader decrypting a stream written by [`EncryptWriter`] from an inner
+    /// reader.
+    ///
+    /// Only authenticated plaintext is returned, one chunk at a time. A
+    /// modified or truncated stream gives an error of kind
+    /// [`io::ErrorKind::InvalidData`], which is repeated by all further
+    /// reads.
+    pub struct DecryptReader<F, R, const CHUNK_LEN: usize = 65536> {
+        /// `None` after the last chunk was decrypted.
+        decryptor: Option<ChunkedDecryptor<F, CHUNK_LEN>>,
+        inner: R,
+        /// Ciphertext read ahead from the inner reader.
+        ciphertext: Vec<u8>,
+        /// Plaintext of the current chunk.
+        plaintext: Vec<u8>,
+        /// Number of bytes of `plaintext` already read.
+        pos: usize,
+        failed: bool,
+    }
+
+    impl<F, R, const CHUNK_LEN: usize> core::fmt::Debug for DecryptReader<F, R, CHUNK_LEN> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_struct("DecryptReader")
+                .field("decryptor", &self.decryptor)
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl<F: DeckFunction + Clone, R: Read, const CHUNK_LEN: usize> DecryptReader<F, R, CHUNK_LEN> {
+        /// Decrypt with the keyed deck function `deck` and the stream nonce
+        /// `nonce` from `inner`.
+        pub fn new(deck: F, nonce: &[u8; 16], inner: R) -> Self {
+            Self {
+                decryptor: Some(ChunkedDecryptor::new(deck, nonce)),
+                inner,
+                ciphertext: Vec::with_capacity(CHUNK_LEN + CHUNK_TAG_LEN + 1),
+                plaintext: Vec::with_capacity(CHUNK_LEN),
+                pos: 0,
+                failed: false,
+            }
+        }
+
+        /// Return the inner reader.
+        pub fn into_inner(self) -> R {
+            self.inner
+        }
+
+        /// Read and decrypt the next chunk into `self.plaintext`. A chunk is
+        /// the last one when the inner reader ends before the byte following
+        /// it.
+        fn next_chunk(&mut self, mut decryptor: ChunkedDecryptor<F, CHUNK_LEN>) -> io::Result<()> {
+            let full = CHUNK_LEN + CHUNK_TAG_LEN + 1;
+            let mut len = self.ciphertext.len();
+            self.ciphertext.resize(full, 0);
+            while len < full {
+                match self.inner.read(&mut self.ciphertext[len..]) {
+                    Ok(0) => break,
+                    Ok(read) => len += read,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(err) => {
+                        self.ciphertext.truncate(len);
+                        self.decryptor = Some(decryptor);
+                        return Err(err);
+                    }
+                }
+            }
+            self.ciphertext.truncate(len);
+
+            self.failed = true;
+            let last = len < full;
+            let data_len = if last {
+                len.checked_sub(CHUNK_TAG_LEN)
+                    .ok_or_else(|| io_error(Error::TagMismatch))?
+            } else {
+                CHUNK_LEN
+            };
+            let tag = Tag::try_from(&self.ciphertext[data_len..data_len + CHUNK_TAG_LEN])
+                .expect("tag has the tag length");
+            self.plaintext.clear();
+            self.plaintext
+                .extend_from_slice(&self.ciphertext[..data_len]);
+            if last {
+                decryptor.decrypt_last(&mut self.plaintext, &tag)
+            } else {
+                decryptor
+                    .decrypt_next(&mut self.plaintext, &tag)
+                    .map(|()| self.decryptor = Some(decryptor))
+            }
+            .map_err(|err| {
+                self.plaintext.clear();
+                io_error(err)
+            })?;
+            self.ciphertext.drain(..data_len + CHUNK_TAG_LEN);
+            self.pos = 0;
+            self.failed = false;
+            Ok(())
+        }
+    }
+
+    impl<F: DeckFunction + Clone, R: Read, const CHUNK_LEN: usize> Read
+        for DecryptReader<F, R, CHUNK_LEN>
+    {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.failed {
+                return Err(io_error(Error::TagMismatch));
+            }
+            if self.pos == self.plaintext.len() {
+                match self.decryptor.take() {
+                    Some(decryptor) => self.next_chunk(decryptor)?,
+                    None => return Ok(0),
+                }
+            }
+            let available = &self.plaintext[self.pos..];
+            let len = buf.len().min(available.len());
+            buf[..len].copy_from_slice(&available[..len]);
+            self.pos += len;
+            Ok(len)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{DecryptReader, EncryptWriter};
+        use crypto_permutation::DeckFunction;
+        use deck_farfalle::xoofff::Xoofff;
+        use std::io::{ErrorKind, Read, Write};
+
+        const NONCE: [u8; 16] = [0x0c; 16];
+
+        fn deck() -> Xoofff {
+            Xoofff::init(&[0xcc; 32])
+        }
+
+        fn encrypt(plaintext: &[u8]) -> Vec<u8> {
+            let mut writer = EncryptWriter::<_, _, 8>::new(deck(), &NONCE, Vec::new());
+            // Write in pieces not aligned with the chunks.
+            for piece in plaintext.chunks(3) {
+                writer.write_all(piece).unwrap();
+            }
+            writer.finish().unwrap()
+        }
+
+        fn decrypt(ciphertext: &[u8]) -> std::io::Result<Vec<u8>> {
+            let mut reader = DecryptReader::<_, _, 8>::new(deck(), &NONCE, ciphertext);
+            let mut plaintext = Vec::new();
+            reader.read_to_end(&mut plaintext)?;
+            Ok(plaintext)
+        }
+
+        #[test]
+        fn roundtrip() {
+            for len in [0, 1, 7, 8, 9, 16, 17, 100] {
+                let plaintext: Vec<u8> = (0..len as u8).collect();
+                let ciphertext = encrypt(&plaintext);
+                let chunks = (len.max(1) + 7) / 8;
+                assert_eq!(ciphertext.len(), len + 16 * chunks);
+                assert_eq!(decrypt(&ciphertext).unwrap(), plaintext);
+            }
+        }
+
+        #[test]
+        fn tampering() {
+            let ciphertext = encrypt(&[0x55; 20]);
+            let invalid = |ciphertext: &[u8]| matches!(decrypt(ciphertext), Err(err) if err.kind() == ErrorKind::InvalidData);
+            // Truncation at a chunk boundary and in a chunk.
+            assert!(invalid(&ciphertext[..24]));
+            assert!(invalid(&ciphertext[..30]));
+            assert!(invalid(&[]));
+            // Appended data.
+            let mut extended = ciphertext.clone();
+            extended.push(0);
+            assert!(invalid(&extended));
+            // Reordered chunks.
+            let mut reordered = ciphertext[24..48].to_vec();
+            reordered.extend_from_slice(&ciphertext[..24]);
+            reordered.extend_from_slice(&ciphertext[48..]);
+            assert!(invalid(&reordered));
+            // Modified ciphertext.
+            let mut modified = ciphertext;
+            modified[30] ^= 1;
+            assert!(invalid(&modified));
+        }
+
+        /// Authentic chunks before a modified one are returned.
+        #[test]
+        fn partial() {
+            let mut ciphertext = encrypt(&[0x55; 20]);
+            ciphertext[30] ^= 1;
+            let mut reader = DecryptReader::<_, _, 8>::new(deck(), &NONCE, &ciphertext[..]);
+            let mut buf = [0; 8];
+            reader.read_exact(&mut buf).unwrap();
+            assert_eq!(buf, [0x55; 8]);
+            assert!(reader.read(&mut buf).is_err());
+            assert!(reader.read(&mut buf).is_err());
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+pub use io::{DecryptReader, EncryptWriter};
+
+#[cfg(test)]
+mod tests {
+    use super::{ChunkedDecryptor, ChunkedEncryptor};
+    use crate::Error;
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::xoofff::Xoofff;
+
+    fn deck() -> Xoofff {
+        Xoofff::init(&[0xcc; 32])
+    }
+
+    /// A chunk only decrypts at its own position and with its own last
+    /// chunk flag.
+    #[test]
+    fn positions() {
+        let mut encryptor = ChunkedEncryptor::<_, 4>::new(deck(), &[1; 16]);
+        let mut chunks = [*b"abcd", *b"efgh"];
+        let first_tag = encryptor.encrypt_next(&mut chunks[0]).unwrap();
+        let second_tag = encryptor.encrypt_last(&mut chunks[1]).unwrap();
+
+        let mut decryptor = ChunkedDecryptor::<_, 4>::new(deck(), &[1; 16]);
+        let result = decryptor.clone().decrypt_last(&mut chunks[0], &first_tag);
+        assert!(matches!(result, Err(Error::TagMismatch)));
+        let result = decryptor.decrypt_next(&mut chunks[1], &second_tag);
+        assert!(matches!(result, Err(Error::TagMismatch)));
+        let other_nonce = ChunkedDecryptor::<_, 4>::new(deck(), &[2; 16])
+            .decrypt_next(&mut chunks[0].clone(), &first_tag);
+        assert!(matches!(other_nonce, Err(Error::TagMismatch)));
+
+        decryptor.decrypt_next(&mut chunks[0], &first_tag).unwrap();
+        let result = decryptor.clone().decrypt_next(&mut chunks[1], &second_tag);
+        assert!(matches!(result, Err(Error::TagMismatch)));
+        decryptor.decrypt_last(&mut chunks[1], &second_tag).unwrap();
+        assert_eq!(chunks, [*b"abcd", *b"efgh"]);
+    }
+}
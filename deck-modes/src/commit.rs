@@ -0,0 +1,137 @@
+//! Key commitment for the authenticated encryption modes.
+
+use super::{output_at, Error};
+use crypto_permutation::DeckFunction;
+use subtle::ConstantTimeEq;
+
+/// Keyed deck function together with a `COMMIT_LEN` byte commitment to its
+/// key, making the authenticated encryption modes in this crate key
+/// committing.
+///
+/// The tag of an authenticated encryption mode does not bind the key: an
+/// attacker can craft a ciphertext that successfully decrypts under several
+/// keys. This matters in multi-key settings like message franking or
+/// password based encryption. To prevent it, the sender includes
+/// [`Self::commitment`] with the ciphertext, and the receiver checks it with
+/// [`Self::verify`] before decrypting, with any mode constructed from
+/// [`Self::deck`].
+///
+/// The commitment is the output of the deck function on the empty sequence
+/// of strings, which none of the modes in this crate query. It is binding as
+/// long as the deck function is collision resistant as a function of its
+/// key.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use crypto_permutation::DeckFunction;
+/// use deck_modes::{DeckSiv, KeyCommitted};
+///
+/// let committed = KeyCommitted::<Xoofff>::new(Xoofff::init(&[0x42; 32])).unwrap();
+/// let mut message = *b"hello world";
+/// let siv = DeckSiv::<Xoofff>::new(committed.deck().clone());
+/// let tag = siv.wrap(b"metadata", &mut message).unwrap();
+/// let commitment = committed.commitment();
+///
+/// let receiver = KeyCommitted::<Xoofff>::new(Xoofff::init(&[0x42; 32])).unwrap();
+/// receiver.verify(&commitment).unwrap();
+/// let siv = DeckSiv::<Xoofff>::new(receiver.deck().clone());
+/// siv.unwrap(b"metadata", &mut message, &tag).unwrap();
+/// assert_eq!(&message, b"hello world");
+/// ```
+#[derive(Clone)]
+pub struct KeyCommitted<F, const COMMIT_LEN: usize = 32> {
+    deck: F,
+    commitment: [u8; COMMIT_LEN],
+}
+
+impl<F, const COMMIT_LEN: usize> core::fmt::Debug for KeyCommitted<F, COMMIT_LEN> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("KeyCommitted")
+            .field("commitment", &self.commitment)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction, const COMMIT_LEN: usize> KeyCommitted<F, COMMIT_LEN> {
+    /// Compute the commitment to the key of the deck function `deck`.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not produce `COMMIT_LEN` bytes of
+    /// output.
+    pub fn new(deck: F) -> Result<Self, Error> {
+        let commitment = output_at(&deck, 0)?;
+        Ok(Self { deck, commitment })
+    }
+
+    /// The keyed deck function, to construct modes from.
+    pub fn deck(&self) -> &F {
+        &self.deck
+    }
+
+    /// The commitment to the key, to send along with ciphertexts.
+    pub fn commitment(&self) -> [u8; COMMIT_LEN] {
+        self.commitment
+    }
+
+    /// Verify that `commitment` commits to the key of this deck function.
+    ///
+    /// The commitment is compared in constant time.
+    ///
+    /// # Errors
+    /// Errors with [`Error::TagMismatch`] when the commitment doesn't match,
+    /// i.e. the ciphertext was not created under this key.
+    pub fn verify(&self, commitment: &[u8; COMMIT_LEN]) -> Result<(), Error> {
+        if bool::from(self.commitment.ct_eq(commitment)) {
+            Ok(())
+        } else {
+            Err(Error::TagMismatch)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeyCommitted;
+    use crate::Error;
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::xoofff::Xoofff;
+
+    fn committed(key: u8) -> KeyCommitted<Xoofff> {
+        KeyCommitted::new(Xoofff::init(&[key; 32])).unwrap()
+    }
+
+    #[test]
+    fn verify() {
+        let commitment = committed(1).commitment();
+        assert_eq!(committed(1).commitment(), commitment);
+        committed(1).verify(&commitment).unwrap();
+
+        assert_ne!(committed(2).commitment(), commitment);
+        assert!(matches!(
+            committed(2).verify(&commitment),
+            Err(Error::TagMismatch)
+        ));
+        let mut forged = commitment;
+        forged[31] ^= 1;
+        assert!(matches!(
+            committed(1).verify(&forged),
+            Err(Error::TagMismatch)
+        ));
+    }
+
+    /// The commitment is independent of the output of the modes on empty
+    /// input.
+    #[test]
+    fn separation() {
+        let committed = committed(1);
+        let commitment = committed.commitment();
+        let mut data = [0; 32];
+        let bo = crate::DeckBo::<Xoofff>::new(committed.deck().clone());
+        bo.wrap(b"", b"", &mut data).unwrap();
+        assert_ne!(data, commitment);
+        let tag = crate::DeckSiv::<Xoofff>::new(committed.deck().clone())
+            .wrap(b"", &mut [])
+            .unwrap();
+        assert_ne!(tag.into_bytes(), commitment);
+    }
+}
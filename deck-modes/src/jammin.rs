@@ -0,0 +1,312 @@
+//! Jammin cipher: ideal-emulating session authenticated encryption.
+
+use super::{input_suffixed, xor_output, Error};
+use crypto_permutation::DeckFunction;
+use subtle::ConstantTimeEq;
+
+/// Domain separation bit following metadata in the history.
+const METADATA: u8 = 0;
+/// Domain separation bit following ciphertext in the history.
+const CIPHERTEXT: u8 = 1;
+/// Domain separation bits following `L` and `R` in the Feistel rounds.
+const LEFT: u8 = 0;
+const RIGHT: u8 = 1;
+
+/// Jammin cipher session authenticated encryption, with `TAG_LEN` bytes of
+/// redundancy.
+///
+/// A session wraps a sequence of messages, each consisting of metadata and
+/// plaintext. Every message is extended with `TAG_LEN` zero bytes and
+/// enciphered with a tweakable wide block cipher whose tweak is the whole
+/// session history, i.e. all earlier metadata and ciphertexts, followed by
+/// the metadata of the message. The wide block cipher is the four round
+/// Feistel network of [`DeckWbc`](crate::DeckWbc), with the tweaked rounds
+/// starting from the history. Unwrapping deciphers and checks that the
+/// redundancy is still zero.
+///
+/// The metadata and `L` are followed by a single `0` bit, the ciphertext and
+/// `R` by a single `1` bit. The tweaked and untweaked rounds and the history
+/// are separated by the number of strings in their input sequences. The
+/// scheme has not been checked against reference test vectors.
+///
+/// Enciphering instead of encrypting then authenticating makes the scheme
+/// ideal-emulating: it only leaks equality of whole sessions, even without a
+/// nonce, and every ciphertext authenticates the session up to that point,
+/// so it acts as an intermediate tag for online use. The ciphertext expands
+/// by only `TAG_LEN` bytes.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use crypto_permutation::DeckFunction;
+/// use deck_modes::DeckJammin;
+///
+/// let key = [0x42; 32];
+/// let mut sender = DeckJammin::<Xoofff>::new(Xoofff::init(&key));
+/// let mut message = [0; 11 + 16];
+/// message[..11].copy_from_slice(b"hello world");
+/// sender.wrap(b"metadata", &mut message).unwrap();
+///
+/// let mut receiver = DeckJammin::<Xoofff>::new(Xoofff::init(&key));
+/// let plaintext = receiver.unwrap(b"metadata", &mut message).unwrap();
+/// assert_eq!(plaintext, b"hello world");
+/// ```
+#[derive(Clone)]
+pub struct DeckJammin<F, const TAG_LEN: usize = 16> {
+    key: F,
+    history: F,
+}
+
+impl<F, const TAG_LEN: usize> core::fmt::Debug for DeckJammin<F, TAG_LEN> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DeckJammin")
+            .field("tag_len", &TAG_LEN)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction + Clone, const TAG_LEN: usize> DeckJammin<F, TAG_LEN> {
+    /// Start a session using the keyed deck function `deck`.
+    pub fn new(deck: F) -> Self {
+        Self {
+            history: deck.clone(),
+            key: deck,
+        }
+    }
+
+    /// Xor the output of `deck` on `input || bit` into `target`.
+    fn round(deck: &F, input: &[u8], bit: u8, target: &mut [u8]) -> Result<(), Error> {
+        let mut deck = deck.clone();
+        input_suffixed(&mut deck, &[input], bit, 1)?;
+        xor_output(&mut deck.output_reader(), target)?;
+        Ok(())
+    }
+
+    /// Split `data` into the halves `L` and `R`.
+    fn split(data: &mut [u8]) -> Result<(&mut [u8], &mut [u8]), Error> {
+        if data.len() < 2 {
+            return Err(Error::BlockTooShort);
+        }
+        let len = data.len();
+        Ok(data.split_at_mut(len / 2))
+    }
+
+    /// Encipher `data` under the tweak `tweak`.
+    fn encipher(&self, tweak: &F, data: &mut [u8]) -> Result<(), Error> {
+        let (left, right) = Self::split(data)?;
+        Self::round(&self.key, left, LEFT, right)?;
+        Self::round(tweak, right, RIGHT, left)?;
+        Self::round(tweak, left, LEFT, right)?;
+        Self::round(&self.key, right, RIGHT, left)
+    }
+
+    /// Decipher `data` under the tweak `tweak`.
+    fn decipher(&self, tweak: &F, data: &mut [u8]) -> Result<(), Error> {
+        let (left, right) = Self::split(data)?;
+        Self::round(&self.key, right, RIGHT, left)?;
+        Self::round(tweak, left, LEFT, right)?;
+        Self::round(tweak, right, RIGHT, left)?;
+        Self::round(&self.key, left, LEFT, right)
+    }
+
+    /// The session history after absorbing `metadata`.
+    fn absorb_metadata(&self, metadata: &[u8]) -> Result<F, Error> {
+        let mut tweak = self.history.clone();
+        input_suffixed(&mut tweak, &[metadata], METADATA, 1)?;
+        Ok(tweak)
+    }
+
+    /// Encrypt and authenticate the plaintext in `data` in place, together
+    /// with `metadata` and the session so far.
+    ///
+    /// The plaintext is `data` without its last `TAG_LEN` bytes, which are
+    /// overwritten. The plaintext may be empty.
+    ///
+    /// # Errors
+    /// Errors with [`Error::BlockTooShort`] when `data` is shorter than
+    /// `TAG_LEN` bytes or than 2 bytes. Errors when the deck function does not
+    /// accept the message. The session is left unchanged, but the contents of
+    /// `data` are unspecified.
+    pub fn wrap(&mut self, metadata: &[u8], data: &mut [u8]) -> Result<(), Error> {
+        let len = data
+            .len()
+            .checked_sub(TAG_LEN)
+            .ok_or(Error::BlockTooShort)?;
+        data[len..].fill(0);
+        let mut tweak = self.absorb_metadata(metadata)?;
+        self.encipher(&tweak, data)?;
+        input_suffixed(&mut tweak, &[data], CIPHERTEXT, 1)?;
+        self.history = tweak;
+        Ok(())
+    }
+
+    /// Decrypt `data` in place and verify it together with `metadata` and
+    /// the session so far. Returns the plaintext, which is `data` without its
+    /// last `TAG_LEN` bytes.
+    ///
+    /// The redundancy is checked in constant time. Only when it is correct is
+    /// the session advanced to the next message; otherwise `data` is
+    /// encrypted again, so the unverified plaintext is not released.
+    ///
+    /// # Errors
+    /// Errors with [`Error::TagMismatch`] when the ciphertext, metadata or
+    /// session is not authentic, leaving `data` and the session unchanged.
+    /// Other errors are as for [`Self::wrap`].
+    pub fn unwrap<'a>(
+        &mut self,
+        metadata: &[u8],
+        data: &'a mut [u8],
+    ) -> Result<&'a mut [u8], Error> {
+        let len = data
+            .len()
+            .checked_sub(TAG_LEN)
+            .ok_or(Error::BlockTooShort)?;
+        let tweak = self.absorb_metadata(metadata)?;
+        let mut history = tweak.clone();
+        input_suffixed(&mut history, &[data], CIPHERTEXT, 1)?;
+        self.decipher(&tweak, data)?;
+        if !bool::from(data[len..].ct_eq(&[0; TAG_LEN])) {
+            self.encipher(&tweak, data)?;
+            return Err(Error::TagMismatch);
+        }
+        self.history = history;
+        Ok(&mut data[..len])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeckJammin;
+    use crate::Error;
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::xoofff::Xoofff;
+
+    fn session() -> DeckJammin<Xoofff> {
+        DeckJammin::new(Xoofff::init(&[0x7a; 32]))
+    }
+
+    #[test]
+    fn roundtrip() {
+        let messages: [(&[u8], &[u8]); 4] = [
+            (b"header", b"first message"),
+            (b"", b"no metadata"),
+            (b"only metadata", b""),
+            (&[7; 100], &[3; 300]),
+        ];
+
+        let mut sender = session();
+        let mut receiver = session();
+        for (metadata, plaintext) in messages {
+            let mut data = plaintext.to_vec();
+            data.extend_from_slice(&[0xff; 16]);
+            sender.wrap(metadata, &mut data).unwrap();
+            if !plaintext.is_empty() {
+                assert_ne!(data[..plaintext.len()], *plaintext);
+            }
+            assert_eq!(receiver.unwrap(metadata, &mut data).unwrap(), plaintext);
+        }
+    }
+
+    /// Identical messages encrypt differently within a session, but equal
+    /// sessions give equal ciphertexts.
+    #[test]
+    fn determinism() {
+        let mut first = session();
+        let mut data = [0; 32];
+        first.wrap(b"", &mut data).unwrap();
+        let mut second = [0; 32];
+        session().wrap(b"", &mut second).unwrap();
+        assert_eq!(data, second);
+
+        let mut next = [0; 32];
+        first.wrap(b"", &mut next).unwrap();
+        assert_ne!(next, data);
+    }
+
+    /// The deck inputs are finished with single domain separation bits, for
+    /// two messages so that the history is covered.
+    #[test]
+    fn suffix_bits() {
+        use crypto_permutation::io::FinishWithSuffix;
+        use crypto_permutation::{Reader, Writer};
+
+        fn input(deck: &mut Xoofff, data: &[u8], bit: u8) {
+            let mut writer = deck.input_writer();
+            writer.write_bytes(data).unwrap();
+            writer.finish_with_suffix(bit, 1);
+        }
+
+        fn round(deck: &Xoofff, data: &[u8], bit: u8, target: &mut [u8]) {
+            let mut deck = deck.clone();
+            input(&mut deck, data, bit);
+            let mut output = vec![0; target.len()];
+            deck.output_reader().write_to_slice(&mut output).unwrap();
+            for (byte, key) in target.iter_mut().zip(output) {
+                *byte ^= key;
+            }
+        }
+
+        let key = Xoofff::init(&[0x7a; 32]);
+        let mut history = key.clone();
+        let mut sender = session();
+        for (metadata, plaintext) in [(&b"header"[..], &b"first"[..]), (b"", b"second")] {
+            let mut expected = plaintext.to_vec();
+            expected.extend_from_slice(&[0; 16]);
+            input(&mut history, metadata, 0);
+            let (left, right) = expected.split_at_mut((plaintext.len() + 16) / 2);
+            round(&key, left, 0, right);
+            round(&history, right, 1, left);
+            round(&history, left, 0, right);
+            round(&key, right, 1, left);
+            input(&mut history, &expected, 1);
+
+            let mut data = plaintext.to_vec();
+            data.extend_from_slice(&[0xff; 16]);
+            sender.wrap(metadata, &mut data).unwrap();
+            assert_eq!(data, expected);
+        }
+    }
+
+    #[test]
+    fn forgery_rejected() {
+        let mut sender = session();
+        let mut receiver = session();
+        let mut first = [0; 5 + 16];
+        first[..5].copy_from_slice(b"first");
+        sender.wrap(b"", &mut first).unwrap();
+        let mut second = [0; 6 + 16];
+        second[..6].copy_from_slice(b"second");
+        sender.wrap(b"", &mut second).unwrap();
+        let ciphertext = second;
+
+        // Out of order delivery is rejected.
+        let result = receiver.unwrap(b"", &mut second);
+        assert!(matches!(result, Err(Error::TagMismatch)));
+        assert_eq!(second, ciphertext);
+
+        receiver.unwrap(b"", &mut first).unwrap();
+        let result = receiver.unwrap(b"metadata", &mut second);
+        assert!(matches!(result, Err(Error::TagMismatch)));
+        second[0] ^= 1;
+        let result = receiver.unwrap(b"", &mut second);
+        assert!(matches!(result, Err(Error::TagMismatch)));
+        second[0] ^= 1;
+        assert_eq!(second, ciphertext);
+
+        // A rejected message leaves the receiving session unchanged.
+        assert_eq!(receiver.unwrap(b"", &mut second).unwrap(), b"second");
+    }
+
+    #[test]
+    fn too_short() {
+        let mut data = [0; 15];
+        assert!(matches!(
+            session().wrap(b"", &mut data),
+            Err(Error::BlockTooShort)
+        ));
+        let mut data = [0; 1];
+        assert!(matches!(
+            DeckJammin::<Xoofff, 0>::new(Xoofff::init(&[0; 32])).wrap(b"", &mut data),
+            Err(Error::BlockTooShort)
+        ));
+    }
+}
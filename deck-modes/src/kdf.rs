@@ -0,0 +1,426 @@
+//! Key derivation from a deck function.
+
+use super::{input_parts, output_at, Error};
+use crypto_permutation::{DeckFunction, Reader};
+
+/// Domain separation byte following the label.
+const LABEL: u8 = 0;
+/// Domain separation byte following the context.
+const CONTEXT: u8 = 1;
+/// Domain separation byte following the input key material.
+const EXTRACT: u8 = 2;
+/// Domain separation byte following the info string.
+const EXPAND: u8 = 3;
+/// Domain separation bytes of the inputs and phases of [`stretch`].
+const SALT: u8 = 4;
+const PASSWORD: u8 = 5;
+const FILL: u8 = 6;
+const MIX: u8 = 7;
+const FINAL: u8 = 8;
+/// Domain separation byte following a label of a [`KeyTree`] path.
+const PATH: u8 = 9;
+/// Domain separation byte of the output request of a [`KeyTree`] node.
+const NODE: u8 = 10;
+
+/// Size in bytes of a memory block of [`stretch`].
+const STRETCH_BLOCK: usize = 32;
+
+/// Key derivation function deriving independent subkeys from a master key.
+///
+/// The output for label `L` and context `C` is `F(C || 1 ∘ L || 0)`. Since
+/// label and context are separate input strings, their encoding is
+/// unambiguous: no two different pairs give the same input. The label names
+/// the purpose of the output, like `"encryption key"`, and the context binds
+/// it to e.g. a session or user.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use crypto_permutation::DeckFunction;
+/// use deck_modes::kdf::DeckKdf;
+///
+/// let kdf = DeckKdf::new(Xoofff::init(&[0x42; 32]));
+/// let mut encryption_key = [0; 32];
+/// kdf.derive("encryption key", b"session 1", &mut encryption_key).unwrap();
+/// let mut mac_key = [0; 32];
+/// kdf.derive("mac key", b"session 1", &mut mac_key).unwrap();
+/// assert_ne!(encryption_key, mac_key);
+/// ```
+#[derive(Clone)]
+pub struct DeckKdf<F> {
+    deck: F,
+}
+
+impl<F> core::fmt::Debug for DeckKdf<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DeckKdf").finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction + Clone> DeckKdf<F> {
+    /// Create the key derivation function keyed by the master key of the deck
+    /// function `deck`.
+    pub fn new(deck: F) -> Self {
+        Self { deck }
+    }
+
+    /// Fill `out` with key material for `label` and `context`.
+    ///
+    /// Outputs of different lengths for the same label and context are
+    /// prefixes of each other.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the input or can't
+    /// generate `out.len()` bytes.
+    pub fn derive(&self, label: &str, context: &[u8], out: &mut [u8]) -> Result<(), Error> {
+        let mut deck = self.deck.clone();
+        input_parts(&mut deck, &[label.as_bytes()], LABEL)?;
+        input_parts(&mut deck, &[context], CONTEXT)?;
+        deck.output_reader().write_to_slice(out)?;
+        Ok(())
+    }
+}
+
+/// Tree of keys, where every node is named by a path of string labels from
+/// the root.
+///
+/// The key of the node with path `L_1, ..., L_n` is
+/// `F(10 ∘ L_n || 9 ∘ ... ∘ L_1 || 9)`: every label is a separate input
+/// string, so no two paths give the same input. A `KeyTree` is a handle to a
+/// node, holding the deck function after absorbing its path; [`Self::child`]
+/// gives a handle to a child. Keeping the handle of a node, e.g. a tenant,
+/// makes deriving the keys below it cheap, as its path is not absorbed again.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use crypto_permutation::DeckFunction;
+/// use deck_modes::kdf::KeyTree;
+///
+/// let root = KeyTree::new(Xoofff::init(&[0x42; 32]));
+/// let mut key = [0; 32];
+/// root.derive(["tenant-42", "db", "column:email"], &mut key).unwrap();
+///
+/// let db = root.child("tenant-42").unwrap().child("db").unwrap();
+/// let mut same_key = [0; 32];
+/// db.derive(["column:email"], &mut same_key).unwrap();
+/// assert_eq!(key, same_key);
+/// ```
+#[derive(Clone)]
+pub struct KeyTree<F> {
+    deck: F,
+}
+
+impl<F> core::fmt::Debug for KeyTree<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("KeyTree").finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction + Clone> KeyTree<F> {
+    /// The root of the tree keyed by the master key of the deck function
+    /// `deck`.
+    pub fn new(deck: F) -> Self {
+        Self { deck }
+    }
+
+    /// The child of this node with label `label`.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the label.
+    pub fn child(&self, label: &str) -> Result<Self, Error> {
+        let mut deck = self.deck.clone();
+        input_parts(&mut deck, &[label.as_bytes()], PATH)?;
+        Ok(Self { deck })
+    }
+
+    /// Fill `out` with the key of the descendant at `path` relative to this
+    /// node; an empty path gives the key of this node.
+    ///
+    /// Keys of different lengths for the same node are prefixes of each
+    /// other.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the path or can't
+    /// generate `out.len()` bytes.
+    pub fn derive<'a>(
+        &self,
+        path: impl IntoIterator<Item = &'a str>,
+        out: &mut [u8],
+    ) -> Result<(), Error> {
+        let mut node = self.clone();
+        for label in path {
+            node = node.child(label)?;
+        }
+        input_parts(&mut node.deck, &[], NODE)?;
+        node.deck.output_reader().write_to_slice(out)?;
+        Ok(())
+    }
+}
+
+/// Pseudorandom key output by [`extract`] and input to [`expand`].
+#[derive(Clone)]
+pub struct Prk {
+    bytes: [u8; 32],
+}
+
+impl core::fmt::Debug for Prk {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Prk").finish_non_exhaustive()
+    }
+}
+
+impl Prk {
+    /// The pseudorandom key from its byte representation, e.g. one that was
+    /// stored by a protocol implementation.
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self { bytes }
+    }
+
+    /// The byte representation of the pseudorandom key.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.bytes
+    }
+}
+
+/// Extract a pseudorandom key from the input key material `ikm`, which need
+/// not be uniformly random, e.g. a Diffie-Hellman shared secret.
+///
+/// Like the extract phase of HKDF, with the deck function `F` keyed by `salt`
+/// in place of HMAC: the pseudorandom key is `F_salt(ikm || 2)`. Use an all
+/// zero salt when there is none.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use deck_modes::kdf::{expand, extract};
+///
+/// let prk = extract::<Xoofff>(&[0; 32], b"shared secret").unwrap();
+/// let mut key = [0; 32];
+/// expand::<Xoofff>(&prk, b"handshake key", &mut key).unwrap();
+/// ```
+///
+/// # Errors
+/// Errors when the deck function does not accept the input.
+pub fn extract<F: DeckFunction>(salt: &[u8; 32], ikm: &[u8]) -> Result<Prk, Error> {
+    let mut deck = F::init(salt);
+    input_parts(&mut deck, &[ikm], EXTRACT)?;
+    let mut bytes = [0; 32];
+    deck.output_reader().write_to_slice(&mut bytes)?;
+    Ok(Prk { bytes })
+}
+
+/// Fill `out` with key material for `info`, derived from the pseudorandom
+/// key `prk`.
+///
+/// Like the expand phase of HKDF: the output is `F_prk(info || 3)`. It is not
+/// limited to 255 blocks.
+///
+/// # Errors
+/// Errors when the deck function does not accept the input or can't generate
+/// `out.len()` bytes.
+pub fn expand<F: DeckFunction>(prk: &Prk, info: &[u8], out: &mut [u8]) -> Result<(), Error> {
+    let mut deck = F::init(&prk.bytes);
+    input_parts(&mut deck, &[info], EXPAND)?;
+    deck.output_reader().write_to_slice(out)?;
+    Ok(())
+}
+
+/// One step of [`stretch`]: the first block of output of the deck function
+/// keyed by `key` on the concatenation of `parts` and `trailer`.
+fn stretch_step<F: DeckFunction>(
+    key: &[u8; STRETCH_BLOCK],
+    parts: &[&[u8]],
+    trailer: u8,
+) -> Result<[u8; STRETCH_BLOCK], Error> {
+    let mut deck = F::init(key);
+    input_parts(&mut deck, parts, trailer)?;
+    Ok(output_at(&deck, 0)?)
+}
+
+/// Derive key material from a low-entropy `password` and `salt` into `out`,
+/// iterating the deck function to slow down guessing attacks.
+///
+/// For devices where Argon2 is not available. The password and salt are
+/// compressed into a 32 byte state `h`. If `memory` is non-empty, it is
+/// filled with a chain of 32 byte blocks, each computed from the previous
+/// one. Then each of the `iterations` rounds computes the next `h` with the
+/// deck function keyed by `h`, from the round number and, with memory, from
+/// a block selected by `h`, into which the new `h` is xored, like scrypt.
+/// Finally `out` is generated from `h`.
+///
+/// Every iteration costs a key expansion and a few permutation calls. The
+/// memory makes guessing on parallel hardware more expensive; its length is
+/// rounded down to a multiple of 32 bytes, and it is zeroed before
+/// returning. The salt should be unique per password, e.g. random.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use deck_modes::kdf::stretch;
+///
+/// let mut memory = [0; 1024];
+/// let mut key = [0; 32];
+/// stretch::<Xoofff>(b"hunter2", b"user@example.com", 1000, &mut memory, &mut key).unwrap();
+/// ```
+///
+/// # Errors
+/// Errors when the deck function does not accept the input or can't generate
+/// `out.len()` bytes.
+pub fn stretch<F: DeckFunction>(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    memory: &mut [u8],
+    out: &mut [u8],
+) -> Result<(), Error> {
+    let mut deck = F::init(&[0; 32]);
+    input_parts(&mut deck, &[salt], SALT)?;
+    input_parts(&mut deck, &[password], PASSWORD)?;
+    let mut h: [u8; STRETCH_BLOCK] = output_at(&deck, 0)?;
+
+    let len = memory.len() - memory.len() % STRETCH_BLOCK;
+    let used = &mut memory[..len];
+    let mut previous = h;
+    for (index, block) in used.chunks_exact_mut(STRETCH_BLOCK).enumerate() {
+        previous = stretch_step::<F>(&h, &[&previous, &(index as u64).to_le_bytes()], FILL)?;
+        block.copy_from_slice(&previous);
+    }
+
+    let block_count = (len / STRETCH_BLOCK) as u64;
+    for round in 0..iterations {
+        let round = round.to_le_bytes();
+        if block_count == 0 {
+            h = stretch_step::<F>(&h, &[&round], MIX)?;
+            continue;
+        }
+        let mut index = [0; 8];
+        index.copy_from_slice(&h[..8]);
+        let index = (u64::from_le_bytes(index) % block_count) as usize * STRETCH_BLOCK;
+        let block = &mut used[index..index + STRETCH_BLOCK];
+        h = stretch_step::<F>(&h, &[block, &round], MIX)?;
+        for (byte, h) in block.iter_mut().zip(h.iter()) {
+            *byte ^= h;
+        }
+    }
+    memory.fill(0);
+
+    let mut deck = F::init(&h);
+    input_parts(&mut deck, &[], FINAL)?;
+    deck.output_reader().write_to_slice(out)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand, extract, stretch, DeckKdf, KeyTree};
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::xoofff::Xoofff;
+
+    fn derive(label: &str, context: &[u8]) -> [u8; 32] {
+        let mut out = [0; 32];
+        DeckKdf::new(Xoofff::init(&[0xdf; 32]))
+            .derive(label, context, &mut out)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn independence() {
+        let key = derive("key", b"context");
+        assert_eq!(derive("key", b"context"), key);
+        assert_ne!(derive("key", b"contexu"), key);
+        assert_ne!(derive("kez", b"context"), key);
+        // Moving bytes between label and context changes the output.
+        assert_ne!(derive("keyc", b"ontext"), key);
+        assert_ne!(derive("", b"keycontext"), key);
+    }
+
+    #[test]
+    fn prefix() {
+        let mut short = [0; 16];
+        DeckKdf::new(Xoofff::init(&[0xdf; 32]))
+            .derive("key", b"context", &mut short)
+            .unwrap();
+        assert_eq!(short, derive("key", b"context")[..16]);
+    }
+
+    #[test]
+    fn extract_expand() {
+        let prk = extract::<Xoofff>(&[0; 32], b"ikm").unwrap();
+        assert_ne!(
+            prk.as_bytes(),
+            extract::<Xoofff>(&[1; 32], b"ikm").unwrap().as_bytes()
+        );
+        assert_ne!(
+            prk.as_bytes(),
+            extract::<Xoofff>(&[0; 32], b"ikn").unwrap().as_bytes()
+        );
+
+        let mut key = [0; 32];
+        expand::<Xoofff>(&prk, b"info", &mut key).unwrap();
+        let mut other = [0; 32];
+        expand::<Xoofff>(&prk, b"infp", &mut other).unwrap();
+        assert_ne!(key, other);
+
+        let stored = super::Prk::from_bytes(*prk.as_bytes());
+        expand::<Xoofff>(&stored, b"info", &mut other).unwrap();
+        assert_eq!(key, other);
+    }
+
+    fn stretched(password: &[u8], iterations: u32, memory: usize) -> [u8; 32] {
+        let mut memory = vec![0xff; memory];
+        let mut out = [0; 32];
+        stretch::<Xoofff>(password, b"salt", iterations, &mut memory, &mut out).unwrap();
+        assert!(memory.iter().all(|&byte| byte == 0));
+        out
+    }
+
+    #[test]
+    fn stretching() {
+        let key = stretched(b"password", 100, 0);
+        assert_eq!(stretched(b"password", 100, 0), key);
+        assert_ne!(stretched(b"passwore", 100, 0), key);
+        assert_ne!(stretched(b"password", 101, 0), key);
+        assert_ne!(stretched(b"password", 100, 32), key);
+
+        let key = stretched(b"password", 100, 1024);
+        assert_eq!(stretched(b"password", 100, 1024), key);
+        // Memory is used in whole blocks.
+        assert_eq!(stretched(b"password", 100, 1024 + 31), key);
+        assert_ne!(stretched(b"password", 100, 1024 + 32), key);
+        assert_ne!(stretched(b"password", 0, 1024), key);
+    }
+
+    fn tree_key(path: &[&str]) -> [u8; 32] {
+        let mut key = [0; 32];
+        KeyTree::new(Xoofff::init(&[0xdf; 32]))
+            .derive(path.iter().copied(), &mut key)
+            .unwrap();
+        key
+    }
+
+    #[test]
+    fn key_tree() {
+        let paths: [&[&str]; 6] = [
+            &[],
+            &["a"],
+            &["a", "b"],
+            &["ab"],
+            &["a", "b", ""],
+            &["b", "a"],
+        ];
+        let keys: Vec<_> = paths.iter().map(|path| tree_key(path)).collect();
+        for (i, key) in keys.iter().enumerate() {
+            assert!(!keys[i + 1..].contains(key));
+        }
+
+        let root = KeyTree::new(Xoofff::init(&[0xdf; 32]));
+        let mut key = [0; 32];
+        root.child("a").unwrap().derive(["b"], &mut key).unwrap();
+        assert_eq!(key, keys[2]);
+        root.child("a")
+            .unwrap()
+            .child("b")
+            .unwrap()
+            .derive([], &mut key)
+            .unwrap();
+        assert_eq!(key, keys[2]);
+    }
+}
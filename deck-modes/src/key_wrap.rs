@@ -0,0 +1,105 @@
+//! Key wrapping on top of Deck-SIV.
+
+use super::{DeckSiv, Error, Tag};
+use alloc::vec::Vec;
+use crypto_permutation::DeckFunction;
+
+/// Length in bytes of the tag of a wrapped key, and hence the overhead of key
+/// wrapping.
+pub const KEY_WRAP_TAG_LEN: usize = 16;
+
+/// Wrap (encrypt and authenticate) `key_material` under the key encryption
+/// key `kek`.
+///
+/// The wrapped key is the [`KEY_WRAP_TAG_LEN`] byte tag followed by the
+/// ciphertext of [`DeckSiv`] with empty metadata. Key wrapping is
+/// deterministic: wrapping the same key twice gives the same wrapped key,
+/// which is harmless for uniformly random key material.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use crypto_permutation::DeckFunction;
+/// use deck_modes::{unwrap_key, wrap_key};
+///
+/// let kek = Xoofff::init(&[0x42; 32]);
+/// let wrapped = wrap_key(&kek, &[0x17; 32]).unwrap();
+/// assert_eq!(wrapped.len(), 32 + 16);
+/// assert_eq!(unwrap_key(&kek, &wrapped).unwrap(), [0x17; 32]);
+/// ```
+///
+/// # Errors
+/// Errors when the deck function does not accept the input.
+pub fn wrap_key<F: DeckFunction + Clone>(kek: &F, key_material: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut wrapped = Vec::with_capacity(KEY_WRAP_TAG_LEN + key_material.len());
+    wrapped.extend_from_slice(&[0; KEY_WRAP_TAG_LEN]);
+    wrapped.extend_from_slice(key_material);
+    let (tag, data) = wrapped.split_at_mut(KEY_WRAP_TAG_LEN);
+    let siv = DeckSiv::<F, KEY_WRAP_TAG_LEN>::new(kek.clone());
+    tag.copy_from_slice(siv.wrap(b"", data)?.as_ref());
+    Ok(wrapped)
+}
+
+/// Unwrap (verify and decrypt) a key `wrapped` by [`wrap_key`] under the key
+/// encryption key `kek`.
+///
+/// The tag is verified in constant time, and the key material is only
+/// returned when it matches.
+///
+/// # Errors
+/// Errors with [`Error::TagMismatch`] when `wrapped` is not authentic,
+/// including when it is shorter than a tag. Errors when the deck function
+/// does not accept the input.
+pub fn unwrap_key<F: DeckFunction + Clone>(kek: &F, wrapped: &[u8]) -> Result<Vec<u8>, Error> {
+    if wrapped.len() < KEY_WRAP_TAG_LEN {
+        return Err(Error::TagMismatch);
+    }
+    let (tag, data) = wrapped.split_at(KEY_WRAP_TAG_LEN);
+    let tag: Tag<KEY_WRAP_TAG_LEN> = tag.try_into().unwrap();
+    let mut key_material = data.to_vec();
+    DeckSiv::<F, KEY_WRAP_TAG_LEN>::new(kek.clone()).unwrap(b"", &mut key_material, &tag)?;
+    Ok(key_material)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{unwrap_key, wrap_key, KEY_WRAP_TAG_LEN};
+    use crate::Error;
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::xoofff::Xoofff;
+
+    fn kek() -> Xoofff {
+        Xoofff::init(&[0xe7; 32])
+    }
+
+    #[test]
+    fn roundtrip() {
+        for len in [0, 16, 32, 64] {
+            let key_material: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let wrapped = wrap_key(&kek(), &key_material).unwrap();
+            assert_eq!(wrapped.len(), KEY_WRAP_TAG_LEN + len);
+            assert_eq!(unwrap_key(&kek(), &wrapped).unwrap(), key_material);
+        }
+    }
+
+    #[test]
+    fn forgery_rejected() {
+        let wrapped = wrap_key(&kek(), &[0x17; 32]).unwrap();
+        for changed in [0, 15, 16, 47] {
+            let mut forged = wrapped.clone();
+            forged[changed] ^= 0x80;
+            assert!(matches!(
+                unwrap_key(&kek(), &forged),
+                Err(Error::TagMismatch)
+            ));
+        }
+        let other_kek = Xoofff::init(&[0xe8; 32]);
+        assert!(matches!(
+            unwrap_key(&other_kek, &wrapped),
+            Err(Error::TagMismatch)
+        ));
+        assert!(matches!(
+            unwrap_key(&kek(), &wrapped[..15]),
+            Err(Error::TagMismatch)
+        ));
+    }
+}
@@ -0,0 +1,140 @@
+//! Kravatte instantiations of the modes.
+//!
+//! The parameters follow the Keccak team's definitions of Kravatte-SANE,
+//! Kravatte-SANSE, Kravatte-WBC and Kravatte-WBC-AE. The official test vectors
+//! of the XKCP were not available when these instantiations were written, so
+//! they have not been checked against them; the tests pin their output
+//! instead.
+
+use crate::{DeckSane, DeckSanse, DeckWbc, DeckWbcAe, FarfalleSplit};
+use deck_farfalle::kravatte::Kravatte;
+
+/// Kravatte-SANE: [`DeckSane`] over [`Kravatte`] with 128 bit tags.
+pub type KravatteSane = DeckSane<Kravatte, 16>;
+
+/// Kravatte-SANSE: [`DeckSanse`] over [`Kravatte`] with 256 bit tags.
+pub type KravatteSanse = DeckSanse<Kravatte, 32>;
+
+/// Kravatte-WBC: [`DeckWbc`] over [`Kravatte`], splitting blocks with
+/// [`FarfalleSplit`] for the 200 byte Keccak-p[1600] state.
+pub type KravatteWbc = DeckWbc<Kravatte, FarfalleSplit<200>>;
+
+/// Kravatte-WBC-AE: [`DeckWbcAe`] over [`Kravatte`] with 128 bits of
+/// redundancy.
+pub type KravatteWbcAe = DeckWbcAe<Kravatte, 16, FarfalleSplit<200>>;
+
+/// Kravatte-SANE as a nonce-based AEAD for the `aead` crate traits.
+#[cfg(feature = "aead")]
+pub type KravatteSaneAead = crate::SaneAead<Kravatte>;
+
+/// Kravatte-SANSE as a nonce-misuse resistant AEAD for the `aead` crate
+/// traits.
+#[cfg(feature = "aead")]
+pub type KravatteSanseAead = crate::SanseAead<Kravatte>;
+
+/// Stream cipher for the `cipher` crate traits, using the Kravatte keystream.
+#[cfg(feature = "cipher")]
+pub type KravatteStreamCipher = crate::DeckStreamCipher<Kravatte>;
+
+/// Kravatte-MAC for the `digest` crate traits, with 256 bit tags.
+#[cfg(feature = "digest")]
+pub type KravatteMac = crate::DeckMac<Kravatte>;
+
+#[cfg(test)]
+mod tests {
+    use super::{KravatteSane, KravatteSanse, KravatteWbc, KravatteWbcAe};
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::kravatte::Kravatte;
+
+    fn key() -> Kravatte {
+        Kravatte::init(&core::array::from_fn(|i| i as u8))
+    }
+
+    /// Regression vector.
+    #[test]
+    fn kravatte_sane() {
+        let (mut session, start_tag) = KravatteSane::start_session(key(), b"nonce").unwrap();
+        assert_eq!(
+            start_tag.into_bytes(),
+            [
+                0x63, 0x0c, 0xcd, 0x5e, 0x1c, 0xa1, 0xa6, 0x82, 0x2b, 0xe7, 0xbe, 0x07, 0x67, 0x45,
+                0xb4, 0x20
+            ]
+        );
+        let mut data = *b"plaintext";
+        let tag = session.wrap(b"metadata", &mut data).unwrap();
+        assert_eq!(data, [0xf4, 0x63, 0x2d, 0x53, 0xa5, 0xdc, 0xaa, 0x88, 0x7f]);
+        assert_eq!(
+            tag.into_bytes(),
+            [
+                0x9c, 0xb7, 0xb5, 0x87, 0x86, 0xcb, 0xea, 0x47, 0x90, 0x9e, 0x8d, 0x41, 0x28, 0xbd,
+                0xc8, 0xd8
+            ]
+        );
+    }
+
+    /// Regression vector.
+    #[test]
+    fn kravatte_sanse() {
+        let mut session = KravatteSanse::new(key());
+        let mut data = *b"plaintext";
+        let tag = session.wrap(b"metadata", &mut data).unwrap();
+        assert_eq!(data, [0x3f, 0x41, 0x90, 0xb2, 0xe9, 0x1a, 0xbb, 0x04, 0x40]);
+        assert_eq!(
+            tag.into_bytes(),
+            [
+                0x96, 0x79, 0xce, 0x7f, 0xa4, 0xdc, 0x7b, 0xae, 0x47, 0xe9, 0x17, 0xa8, 0xce, 0xbf,
+                0xe4, 0x1c, 0xa8, 0xc8, 0x5a, 0x7e, 0x11, 0xe7, 0x76, 0x74, 0x6f, 0xf3, 0x3f, 0x3b,
+                0xc0, 0xac, 0x13, 0x64
+            ]
+        );
+    }
+
+    /// Regression vectors, for blocks split in halves and for a block longer
+    /// than two Keccak-p[1600] states.
+    #[test]
+    fn kravatte_wbc() {
+        let wbc = KravatteWbc::new(key());
+        let mut short = *b"plaintext";
+        wbc.encrypt(b"tweak", &mut short).unwrap();
+        assert_eq!(
+            short,
+            [0xad, 0xae, 0x0c, 0x7b, 0xed, 0x5f, 0x57, 0x5d, 0x29]
+        );
+        let mut long: [u8; 500] = core::array::from_fn(|i| i as u8);
+        wbc.encrypt(b"tweak", &mut long).unwrap();
+        assert_eq!(
+            long[..16],
+            [
+                0xf1, 0x08, 0xd5, 0x2f, 0xe5, 0xeb, 0x20, 0x4b, 0xd6, 0xbf, 0x44, 0x64, 0x60, 0x3a,
+                0x8e, 0xb0
+            ]
+        );
+        assert_eq!(
+            long[484..],
+            [
+                0xde, 0x7c, 0x2b, 0x6a, 0x4c, 0x13, 0xa0, 0x1b, 0x94, 0x71, 0xd9, 0xd9, 0x2f, 0x68,
+                0xa5, 0xe4
+            ]
+        );
+        wbc.decrypt(b"tweak", &mut long).unwrap();
+        assert!(long.iter().enumerate().all(|(i, &b)| b == i as u8));
+    }
+
+    /// Regression vector.
+    #[test]
+    fn kravatte_wbc_ae() {
+        let wbc_ae = KravatteWbcAe::new(key());
+        let mut data = [0; 9 + 16];
+        data[..9].copy_from_slice(b"plaintext");
+        wbc_ae.wrap(b"metadata", &mut data).unwrap();
+        assert_eq!(
+            data,
+            [
+                0x37, 0x8c, 0x3d, 0x59, 0x1f, 0x4a, 0x5a, 0x6b, 0xc0, 0x28, 0x41, 0xa0, 0xa8, 0x6e,
+                0x0f, 0x91, 0x2b, 0xbc, 0xd1, 0xe0, 0x51, 0xb1, 0x57, 0x73, 0xac
+            ]
+        );
+        assert_eq!(wbc_ae.unwrap(b"metadata", &mut data).unwrap(), b"plaintext");
+    }
+}
@@ -0,0 +1,272 @@
+//! Modes of use of deck functions, generic over the [`DeckFunction`] trait.
+//!
+//! `Mode: DeckFunction -> Scheme`
+//!
+//! This crate implements the modes the Keccak team defined on top of deck
+//! functions, like the [Farfalle] instantiations Kravatte and Xoofff in the
+//! [`deck-farfalle` crate]. Every mode is generic over the deck function, so
+//! it works with any [`DeckFunction`] implementation.
+//!
+//! __Note__: No security audits of this crate have ever been performed. Use at
+//! your own risk!
+//!
+//! # Modes
+//! * [`DeckBo`]: Deck-BO, nonce-based authenticated encryption of single
+//!   messages.
+//! * [`DeckSane`]: Deck-SANE, session authenticated encryption with a nonce.
+//! * [`DeckSanse`]: Deck-SANSE, nonce-misuse resistant session authenticated
+//!   encryption.
+//! * [`DeckJammin`]: the Jammin cipher, ideal-emulating session authenticated
+//!   encryption with minimal expansion.
+//! * [`DeckSiv`]: Deck-SIV, deterministic authenticated encryption of single
+//!   messages.
+//! * [`DeckWbc`]: Deck-WBC, a tweakable wide block cipher.
+//! * [`DeckWbcAe`]: Deck-WBC-AE, authenticated encryption with minimal
+//!   expansion on top of Deck-WBC.
+//! * [`DeckD3`]: the docked double-decker, a tweakable wide block cipher with
+//!   better bounds than Deck-WBC for short tweaks.
+//! * [`SmallBlockCipher`]: a length doubler, a tweakable block cipher for
+//!   short blocks.
+//! * [`ChunkedEncryptor`] and [`ChunkedDecryptor`]: STREAM-like chunked
+//!   authenticated encryption of arbitrarily long streams, like files.
+//!
+//! The authenticated encryption modes return their tags as a [`Tag`], which
+//! compares in constant time.
+//!
+//! On top of Deck-BO, `encrypt` and `decrypt` encrypt byte strings in one
+//! call, for applications that just need to encrypt a blob. On top of
+//! Deck-SIV, [`wrap_key`] and [`unwrap_key`] wrap keys for storage,
+//! e.g. data encryption keys in a database. On top of Deck-WBC,
+//! [`SectorCipher`] encrypts disk sectors. [`KeyCommitted`] makes the
+//! authenticated encryption modes key committing. [`Transcript`] implements
+//! the Fiat-Shamir transform, and [`DeckProtocol`] is a Strobe-like framework
+//! for building whole symmetric protocols. [`SecureChannel`] encrypts both
+//! directions of a connection, rekeying automatically, and
+//! [`SymmetricRatchet`] derives a key per message for secure messaging.
+//! [`RekeyingKeystream`] is a keystream that ratchets its key after every
+//! epoch of a fixed length, for forward secrecy within long streams.
+//!
+//! [`DeckTreeMac`] authenticates long messages with a tree mode, so that it can
+//! use multiple cores.
+//!
+//! [`Cascade`] combines two deck functions into one, for redundancy against
+//! the failure of a single primitive.
+//!
+//! The [`kdf`] module derives keys using a deck function, including an
+//! analogue of HKDF, password stretching and key trees with labeled paths.
+//!
+//! # Domain separation bits
+//! The modes are specified on bit strings, and append a few domain separation
+//! bits to their inputs. [`DeckBo`], [`DeckSane`], [`DeckSanse`],
+//! [`DeckJammin`], [`DeckWbc`] and [`DeckWbcAe`] append these bits as bits,
+//! finishing the input with
+//! [`FinishWithSuffix::finish_with_suffix`](crypto_permutation::io::FinishWithSuffix::finish_with_suffix),
+//! like the reference implementations in the [XKCP]. The other modes append a
+//! whole domain separation byte instead. The modes have not been checked
+//! against the test vectors of the XKCP.
+//!
+//! # Features
+//! * `alloc`: Enables the key wrapping functions [`wrap_key`] and
+//!   [`unwrap_key`], and the one-shot encryption functions `encrypt` and
+//!   `decrypt`.
+//! * `std`: Implements [`std::error::Error`] for [`Error`], and provides
+//!   `EncryptWriter` and `DecryptReader` for chunked encryption of files.
+//!   Implies `alloc`.
+//! * `aead`: Implements the [`aead`] crate traits for single message Deck-SANE
+//!   and Deck-SANSE sessions, in `SaneAead` and `SanseAead`.
+//! * `cipher`: Implements the [`cipher`] crate stream cipher traits for the
+//!   keystream of a deck function, in `DeckStreamCipher`, and for
+//!   [`RekeyingKeystream`].
+//! * `digest`: Implements the [`digest`] crate MAC traits for deck functions,
+//!   in `DeckMac`. Implies `alloc`.
+//! * `rand_core`: Provides `DeckRng`, a random number generator implementing
+//!   the [`rand_core`] traits.
+//! * `rayon`: Provides `DeckTreeMac::par_tag`, processing the chunks of long
+//!   messages in parallel on the [`rayon`] thread pool. Implies `std`.
+//! * `kravatte`: Enables the [`kravatte`] module, containing the
+//!   Kravatte-SANE, Kravatte-SANSE, Kravatte-WBC and Kravatte-WBC-AE
+//!   instantiations, and Kravatte instantiations of the `aead`, `cipher` and
+//!   `digest` crate traits.
+//! * `xoofff`: Enables the [`xoofff`] module, containing the Xoofff-SANE and
+//!   Xoofff-SANSE instantiations, and Xoofff instantiations of the `aead`,
+//!   `cipher` and `digest` crate traits.
+//!
+//! [Farfalle]: https://keccak.team/farfalle.html
+//! [`deck-farfalle` crate]: https://crates.io/crates/deck-farfalle
+//! [XKCP]: https://github.com/XKCP/XKCP
+//! [`aead`]: https://crates.io/crates/aead
+//! [`cipher`]: https://crates.io/crates/cipher
+//! [`digest`]: https://crates.io/crates/digest
+//! [`rand_core`]: https://crates.io/crates/rand_core
+//! [`rayon`]: https://crates.io/crates/rayon
+
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use crypto_permutation::io::FinishWithSuffix;
+use crypto_permutation::{DeckFunction, Reader, WriteTooLargeError, Writer};
+
+#[cfg(feature = "aead")]
+mod aead_traits;
+mod bo;
+mod cascade;
+mod channel;
+mod chunked;
+mod commit;
+mod d3;
+mod jammin;
+#[cfg(feature = "alloc")]
+mod key_wrap;
+#[cfg(feature = "digest")]
+mod mac;
+#[cfg(feature = "alloc")]
+mod oneshot;
+mod protocol;
+mod ratchet;
+mod rekeying;
+#[cfg(feature = "rand_core")]
+mod rng;
+mod sane;
+mod sanse;
+mod sector;
+mod siv;
+mod small_block;
+#[cfg(feature = "cipher")]
+mod stream;
+mod tag;
+mod transcript;
+mod tree_mac;
+mod wbc;
+mod wbc_ae;
+#[cfg(feature = "aead")]
+pub use aead_traits::{SaneAead, SanseAead};
+pub use bo::DeckBo;
+pub use cascade::{Cascade, CascadeReader, CascadeWriter};
+pub use channel::{RekeyLimits, Role, SecureChannel};
+pub use chunked::{ChunkedDecryptor, ChunkedEncryptor, CHUNK_TAG_LEN};
+#[cfg(feature = "std")]
+pub use chunked::{DecryptReader, EncryptWriter};
+pub use commit::KeyCommitted;
+pub use d3::DeckD3;
+pub use jammin::DeckJammin;
+#[cfg(feature = "alloc")]
+pub use key_wrap::{unwrap_key, wrap_key, KEY_WRAP_TAG_LEN};
+#[cfg(feature = "digest")]
+pub use mac::DeckMac;
+#[cfg(feature = "alloc")]
+pub use oneshot::{decrypt, encrypt, ENCRYPT_TAG_LEN};
+pub use protocol::DeckProtocol;
+pub use ratchet::SymmetricRatchet;
+pub use rekeying::RekeyingKeystream;
+#[cfg(feature = "rand_core")]
+pub use rng::DeckRng;
+pub use sane::DeckSane;
+pub use sanse::DeckSanse;
+pub use sector::SectorCipher;
+pub use siv::DeckSiv;
+pub use small_block::SmallBlockCipher;
+#[cfg(feature = "cipher")]
+pub use stream::DeckStreamCipher;
+pub use tag::Tag;
+pub use transcript::Transcript;
+pub use tree_mac::{DeckTreeMac, TREE_CHUNK_LEN};
+pub use wbc::{DeckWbc, FarfalleSplit, HalfSplit, WbcSplit};
+pub use wbc_ae::DeckWbcAe;
+
+pub mod kdf;
+#[cfg(feature = "kravatte")]
+pub mod kravatte;
+#[cfg(feature = "xoofff")]
+pub mod xoofff;
+
+/// Error returned by the modes in this crate.
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// The tag does not match: the ciphertext or the metadata is not
+    /// authentic.
+    TagMismatch,
+    /// The block is shorter than the minimum length of the wide block cipher.
+    BlockTooShort,
+    /// The deck function did not accept more input or output, e.g. because
+    /// its usage limit was reached.
+    WriteTooLarge(WriteTooLargeError),
+}
+
+impl From<WriteTooLargeError> for Error {
+    fn from(err: WriteTooLargeError) -> Self {
+        Self::WriteTooLarge(err)
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::TagMismatch => fmt.write_str("Tag mismatch"),
+            Self::BlockTooShort => fmt.write_str("Block too short"),
+            Self::WriteTooLarge(err) => err.fmt(fmt),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// Input the concatenation of `parts` followed by the domain separation byte
+/// `trailer` into `deck`, as a single string.
+fn input_parts<F: DeckFunction>(
+    deck: &mut F,
+    parts: &[&[u8]],
+    trailer: u8,
+) -> Result<(), WriteTooLargeError> {
+    let mut writer = deck.input_writer();
+    for part in parts {
+        writer.write_bytes(part)?;
+    }
+    writer.write_bytes(&[trailer])?;
+    writer.finish();
+    Ok(())
+}
+
+/// Input the concatenation of `parts` followed by the `n` domain separation
+/// bits of `suffix` into `deck`, as a single bit string.
+fn input_suffixed<F: DeckFunction>(
+    deck: &mut F,
+    parts: &[&[u8]],
+    suffix: u8,
+    n: u32,
+) -> Result<(), WriteTooLargeError> {
+    let mut writer = deck.input_writer();
+    for part in parts {
+        writer.write_bytes(part)?;
+    }
+    writer.finish_with_suffix(suffix, n);
+    Ok(())
+}
+
+/// Xor the next `data.len()` bytes of `reader` into `data`.
+fn xor_output<R: Reader>(reader: &mut R, data: &mut [u8]) -> Result<(), WriteTooLargeError> {
+    const CHUNK: usize = 64;
+    let mut keystream = [0; CHUNK];
+    for chunk in data.chunks_mut(CHUNK) {
+        let keystream = &mut keystream[..chunk.len()];
+        reader.write_to_slice(keystream)?;
+        for (byte, key) in chunk.iter_mut().zip(keystream.iter()) {
+            *byte ^= key;
+        }
+    }
+    Ok(())
+}
+
+/// The first `N` output bytes of `deck` after skipping `offset` bytes.
+fn output_at<F: DeckFunction, const N: usize>(
+    deck: &F,
+    offset: usize,
+) -> Result<[u8; N], WriteTooLargeError> {
+    let mut reader = deck.output_reader();
+    reader.skip(offset)?;
+    let mut output = [0; N];
+    reader.write_to_slice(&mut output)?;
+    Ok(output)
+}
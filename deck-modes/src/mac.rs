@@ -0,0 +1,144 @@
+//! Implementations of the [`digest`] crate MAC traits.
+
+use alloc::vec::Vec;
+use crypto_permutation::{DeckFunction, Reader, Writer};
+use digest::consts::U32;
+use digest::crypto_common::KeySizeUser;
+use digest::generic_array::ArrayLength;
+use digest::{
+    FixedOutput, FixedOutputReset, Key, KeyInit, MacMarker, Output, OutputSizeUser, Reset, Update,
+};
+
+/// Message authentication code for the [`digest`] crate traits, with `N`
+/// byte tags.
+///
+/// The tag is the first `N` bytes of output of the deck function on the
+/// message, so it can replace e.g. HMAC or KMAC in code written against
+/// [`digest::Mac`]. The tag size is a [`typenum`](digest::typenum) number,
+/// as the [`digest`] traits require.
+///
+/// A deck function takes its input strings at once, so the message is
+/// buffered until the tag is computed.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use deck_modes::DeckMac;
+/// use digest::Mac;
+///
+/// let mut mac = DeckMac::<Xoofff>::new(&[0x42; 32].into());
+/// mac.update(b"hello ");
+/// mac.update(b"world");
+/// let tag = mac.finalize().into_bytes();
+///
+/// let mut mac = DeckMac::<Xoofff>::new(&[0x42; 32].into());
+/// mac.update(b"hello world");
+/// mac.verify_slice(&tag).unwrap();
+/// ```
+#[derive(Clone)]
+pub struct DeckMac<F, N = U32> {
+    deck: F,
+    message: Vec<u8>,
+    tag_len: core::marker::PhantomData<N>,
+}
+
+impl<F, N> core::fmt::Debug for DeckMac<F, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DeckMac").finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction, N> KeySizeUser for DeckMac<F, N> {
+    type KeySize = U32;
+}
+
+impl<F: DeckFunction, N> KeyInit for DeckMac<F, N> {
+    fn new(key: &Key<Self>) -> Self {
+        Self {
+            deck: F::init(key.as_ref()),
+            message: Vec::new(),
+            tag_len: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<F, N> MacMarker for DeckMac<F, N> {}
+
+impl<F, N> Update for DeckMac<F, N> {
+    fn update(&mut self, data: &[u8]) {
+        self.message.extend_from_slice(data);
+    }
+}
+
+impl<F, N: ArrayLength<u8> + 'static> OutputSizeUser for DeckMac<F, N> {
+    type OutputSize = N;
+}
+
+impl<F: DeckFunction + Clone, N: ArrayLength<u8> + 'static> FixedOutput for DeckMac<F, N> {
+    fn finalize_into(mut self, out: &mut Output<Self>) {
+        self.finalize_into_reset(out);
+    }
+}
+
+impl<F, N> Reset for DeckMac<F, N> {
+    fn reset(&mut self) {
+        self.message.clear();
+    }
+}
+
+impl<F: DeckFunction + Clone, N: ArrayLength<u8> + 'static> FixedOutputReset for DeckMac<F, N> {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        let mut deck = self.deck.clone();
+        let mut writer = deck.input_writer();
+        writer
+            .write_bytes(&self.message)
+            .expect("deck function accepts the message");
+        writer.finish();
+        deck.output_reader()
+            .write_to_slice(out)
+            .expect("deck function produces the tag");
+        self.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeckMac;
+    use crypto_permutation::{DeckFunction, Reader, Writer};
+    use deck_farfalle::xoofff::Xoofff;
+    use digest::consts::U16;
+    use digest::Mac;
+
+    const KEY: [u8; 32] = [0x3a; 32];
+
+    /// The tag is the output of the deck function on the message.
+    #[test]
+    fn tag() {
+        let mut mac = DeckMac::<Xoofff, U16>::new(&KEY.into());
+        mac.update(b"hello ");
+        mac.update(b"world");
+        let tag = mac.finalize_reset().into_bytes();
+
+        let mut deck = Xoofff::init(&KEY);
+        let mut writer = deck.input_writer();
+        writer.write_bytes(b"hello world").unwrap();
+        writer.finish();
+        let mut expected = [0; 16];
+        deck.output_reader().write_to_slice(&mut expected).unwrap();
+        assert_eq!(tag[..], expected);
+
+        // After a reset the MAC starts over.
+        mac.update(b"hello world");
+        mac.verify_slice(&expected).unwrap();
+    }
+
+    #[test]
+    fn verify() {
+        let mut mac = DeckMac::<Xoofff>::new(&KEY.into());
+        mac.update(b"message");
+        let mut tag = mac.clone().finalize().into_bytes();
+        mac.clone().verify(&tag).unwrap();
+        tag[31] ^= 1;
+        assert!(mac.clone().verify(&tag).is_err());
+        assert!(mac.verify_truncated_left(&tag[..16]).is_ok());
+    }
+}
@@ -0,0 +1,110 @@
+//! One-shot nonce-based encryption of byte strings on top of Deck-BO.
+
+use super::{DeckBo, Error, Tag};
+use alloc::vec::Vec;
+use crypto_permutation::DeckFunction;
+
+/// Length in bytes of the tag appended by [`encrypt`], and hence its
+/// overhead.
+pub const ENCRYPT_TAG_LEN: usize = 16;
+
+/// Encrypt and authenticate `plaintext` together with the associated data
+/// `aad`, under `key` and `nonce`.
+///
+/// For encrypting a blob without dealing with sessions or in place
+/// encryption. The ciphertext is the ciphertext of [`DeckBo`] followed by
+/// its [`ENCRYPT_TAG_LEN`] byte tag.
+///
+/// # Crypto
+/// A nonce must never be used twice under the same key. Random 16 byte
+/// nonces are fine.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use deck_modes::{decrypt, encrypt};
+///
+/// let key = [0x42; 32];
+/// let ciphertext = encrypt::<Xoofff>(&key, b"nonce", b"header", b"hello world").unwrap();
+/// let plaintext = decrypt::<Xoofff>(&key, b"nonce", b"header", &ciphertext).unwrap();
+/// assert_eq!(plaintext, b"hello world");
+/// ```
+///
+/// # Errors
+/// Errors when the deck function does not accept the input.
+pub fn encrypt<F: DeckFunction + Clone>(
+    key: &[u8; 32],
+    nonce: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let mut ciphertext = Vec::with_capacity(plaintext.len() + ENCRYPT_TAG_LEN);
+    ciphertext.extend_from_slice(plaintext);
+    let tag = DeckBo::<F, ENCRYPT_TAG_LEN>::new(F::init(key)).wrap(nonce, aad, &mut ciphertext)?;
+    ciphertext.extend_from_slice(tag.as_ref());
+    Ok(ciphertext)
+}
+
+/// Verify and decrypt `ciphertext` output by [`encrypt`] with the same `key`,
+/// `nonce` and associated data `aad`.
+///
+/// The plaintext is only returned when the tag matches.
+///
+/// # Errors
+/// Errors with [`Error::TagMismatch`] when `ciphertext` is not authentic,
+/// including when it is shorter than a tag. Errors when the deck function
+/// does not accept the input.
+pub fn decrypt<F: DeckFunction + Clone>(
+    key: &[u8; 32],
+    nonce: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let len = ciphertext
+        .len()
+        .checked_sub(ENCRYPT_TAG_LEN)
+        .ok_or(Error::TagMismatch)?;
+    let (data, tag) = ciphertext.split_at(len);
+    let tag: Tag<ENCRYPT_TAG_LEN> = tag.try_into().unwrap();
+    let mut plaintext = data.to_vec();
+    DeckBo::<F, ENCRYPT_TAG_LEN>::new(F::init(key)).unwrap(nonce, aad, &mut plaintext, &tag)?;
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decrypt, encrypt, ENCRYPT_TAG_LEN};
+    use crate::Error;
+    use deck_farfalle::xoofff::Xoofff;
+
+    const KEY: [u8; 32] = [0x0e; 32];
+
+    #[test]
+    fn roundtrip() {
+        for len in [0, 1, 100] {
+            let plaintext = vec![0x33; len];
+            let ciphertext = encrypt::<Xoofff>(&KEY, b"nonce", b"aad", &plaintext).unwrap();
+            assert_eq!(ciphertext.len(), len + ENCRYPT_TAG_LEN);
+            let decrypted = decrypt::<Xoofff>(&KEY, b"nonce", b"aad", &ciphertext).unwrap();
+            assert_eq!(decrypted, plaintext);
+        }
+    }
+
+    #[test]
+    fn forgery_rejected() {
+        let ciphertext = encrypt::<Xoofff>(&KEY, b"nonce", b"aad", b"message").unwrap();
+        let rejected = |key: &[u8; 32], nonce: &[u8], aad: &[u8], ciphertext: &[u8]| {
+            matches!(
+                decrypt::<Xoofff>(key, nonce, aad, ciphertext),
+                Err(Error::TagMismatch)
+            )
+        };
+        assert!(rejected(&[0x0f; 32], b"nonce", b"aad", &ciphertext));
+        assert!(rejected(&KEY, b"noncf", b"aad", &ciphertext));
+        assert!(rejected(&KEY, b"nonce", b"aae", &ciphertext));
+        assert!(rejected(&KEY, b"nonce", b"aad", &ciphertext[1..]));
+        assert!(rejected(&KEY, b"nonce", b"aad", &ciphertext[..15]));
+        let mut modified = ciphertext;
+        modified[0] ^= 1;
+        assert!(rejected(&KEY, b"nonce", b"aad", &modified));
+    }
+}
@@ -0,0 +1,325 @@
+//! Strobe-like framework for symmetric protocols.
+
+use super::{input_parts, output_at, xor_output, Error};
+use crypto_permutation::{DeckFunction, Reader};
+use subtle::{Choice, ConstantTimeEq};
+
+/// Domain separation byte of the protocol label given to
+/// [`DeckProtocol::new`].
+const INIT: u8 = 0;
+/// Domain separation byte of associated data.
+const AD: u8 = 1;
+/// Domain separation byte of key material.
+const KEY: u8 = 2;
+/// Domain separation byte of pseudorandom output requests.
+const PRF: u8 = 3;
+/// Domain separation byte of keystream requests.
+const ENC: u8 = 4;
+/// Domain separation byte of ciphertexts.
+const CIPHERTEXT: u8 = 5;
+/// Domain separation byte of tag requests.
+const MAC: u8 = 6;
+/// Domain separation byte of ratchet requests.
+const RATCHET: u8 = 7;
+
+/// Framework for lightweight symmetric protocols, modelled after Strobe.
+///
+/// Both parties run the same sequence of operations on their own instance,
+/// which keeps the history of the protocol so far as a sequence of strings
+/// input into the deck function. Every operation inputs strings ending in its
+/// own domain separation byte, so the history is unambiguous, and all output
+/// depends on everything that happened before it:
+/// * [`Self::ad`] inputs associated data, e.g. public protocol messages.
+/// * [`Self::key`] mixes in key material, e.g. a Diffie-Hellman shared
+///   secret, and rekeys the deck function.
+/// * [`Self::prf`] outputs pseudorandom bytes, e.g. a session identifier.
+/// * [`Self::send_enc`] and [`Self::recv_enc`] encrypt and decrypt, and input
+///   the ciphertext.
+/// * [`Self::send_mac`] and [`Self::recv_mac`] compute and verify a tag on the
+///   history, which authenticates all earlier operations, including the
+///   ciphertexts.
+/// * [`Self::ratchet`] rekeys the deck function by its own output, so that a
+///   later compromise of the state doesn't reveal earlier keys.
+///
+/// Rekeying replaces the deck function by one keyed with 32 bytes of output
+/// of the history, and starts an empty history.
+///
+/// Unlike Strobe there are no roles: an instance sending a message and one
+/// receiving it end up in the same state. Protocols where both parties send
+/// should e.g. input the role of the sender with [`Self::ad`].
+///
+/// # Crypto
+/// Encryption is not authenticated until a tag is verified with
+/// [`Self::recv_mac`]. The same history must not be used to encrypt twice, e.g.
+/// by repeating a protocol run without fresh key material or a nonce in the
+/// associated data.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use crypto_permutation::DeckFunction;
+/// use deck_modes::DeckProtocol;
+///
+/// let key = [0x42; 32];
+/// let mut alice = DeckProtocol::new(Xoofff::init(&key), b"example protocol").unwrap();
+/// let mut bob = alice.clone();
+///
+/// alice.ad(b"nonce").unwrap();
+/// let mut message = *b"hello bob";
+/// alice.send_enc(&mut message).unwrap();
+/// let mut tag = [0; 16];
+/// alice.send_mac(&mut tag).unwrap();
+///
+/// bob.ad(b"nonce").unwrap();
+/// bob.recv_enc(&mut message).unwrap();
+/// bob.recv_mac(&tag).unwrap();
+/// assert_eq!(&message, b"hello bob");
+/// ```
+#[derive(Clone)]
+pub struct DeckProtocol<F> {
+    deck: F,
+}
+
+impl<F> core::fmt::Debug for DeckProtocol<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DeckProtocol").finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction + Clone> DeckProtocol<F> {
+    /// Start the protocol named `label`, with the keyed deck function `deck`.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the label.
+    pub fn new(mut deck: F, label: &[u8]) -> Result<Self, Error> {
+        input_parts(&mut deck, &[label], INIT)?;
+        Ok(Self { deck })
+    }
+
+    /// Replace the deck function by the one keyed with the output of the
+    /// history extended by `data || trailer`.
+    fn rekey(&mut self, data: &[u8], trailer: u8) -> Result<(), Error> {
+        let mut deck = self.deck.clone();
+        input_parts(&mut deck, &[data], trailer)?;
+        self.deck = F::init(&output_at(&deck, 0)?);
+        Ok(())
+    }
+
+    /// Input the associated data `data`.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the data. The state is
+    /// left unchanged.
+    pub fn ad(&mut self, data: &[u8]) -> Result<(), Error> {
+        let mut deck = self.deck.clone();
+        input_parts(&mut deck, &[data], AD)?;
+        self.deck = deck;
+        Ok(())
+    }
+
+    /// Mix the key material `key` into the state, and rekey.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the key material. The
+    /// state is left unchanged.
+    pub fn key(&mut self, key: &[u8]) -> Result<(), Error> {
+        self.rekey(key, KEY)
+    }
+
+    /// Fill `out` with pseudorandom output of the history.
+    ///
+    /// Outputs of different lengths are prefixes of each other.
+    ///
+    /// # Errors
+    /// Errors when the deck function can't generate `out.len()` bytes. The
+    /// state is left unchanged.
+    pub fn prf(&mut self, out: &mut [u8]) -> Result<(), Error> {
+        let mut deck = self.deck.clone();
+        input_parts(&mut deck, &[], PRF)?;
+        deck.output_reader().write_to_slice(out)?;
+        self.deck = deck;
+        Ok(())
+    }
+
+    /// Xor the keystream into `data`, and input the ciphertext: `data` after
+    /// encryption or before decryption.
+    fn enc(&mut self, data: &mut [u8], encrypt: bool) -> Result<(), Error> {
+        let mut deck = self.deck.clone();
+        input_parts(&mut deck, &[], ENC)?;
+        let mut keystream = deck.output_reader();
+        if encrypt {
+            xor_output(&mut keystream, data)?;
+            input_parts(&mut deck, &[data], CIPHERTEXT)?;
+        } else {
+            input_parts(&mut deck, &[data], CIPHERTEXT)?;
+            xor_output(&mut keystream, data)?;
+        }
+        self.deck = deck;
+        Ok(())
+    }
+
+    /// Encrypt `data` in place.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the ciphertext or can't
+    /// generate the keystream. The state is left unchanged, but `data` may be
+    /// partially encrypted.
+    pub fn send_enc(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        self.enc(data, true)
+    }
+
+    /// Decrypt `data` in place.
+    ///
+    /// The plaintext is not authentic until a tag is verified with
+    /// [`Self::recv_mac`].
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the ciphertext or can't
+    /// generate the keystream. The state is left unchanged, but `data` may be
+    /// partially decrypted.
+    pub fn recv_enc(&mut self, data: &mut [u8]) -> Result<(), Error> {
+        self.enc(data, false)
+    }
+
+    /// Fill `tag` with a tag on the history.
+    ///
+    /// # Errors
+    /// Errors when the deck function can't generate the tag. The state is left
+    /// unchanged.
+    pub fn send_mac(&mut self, tag: &mut [u8]) -> Result<(), Error> {
+        let mut deck = self.deck.clone();
+        input_parts(&mut deck, &[], MAC)?;
+        deck.output_reader().write_to_slice(tag)?;
+        self.deck = deck;
+        Ok(())
+    }
+
+    /// Verify the tag `tag` on the history, in constant time.
+    ///
+    /// # Errors
+    /// Errors with [`Error::TagMismatch`] when the tag doesn't match. The
+    /// protocol should then be aborted: the state is updated as if the tag
+    /// matched. Errors when the deck function can't generate the tag, leaving
+    /// the state unchanged.
+    pub fn recv_mac(&mut self, tag: &[u8]) -> Result<(), Error> {
+        const CHUNK: usize = 64;
+        let mut deck = self.deck.clone();
+        input_parts(&mut deck, &[], MAC)?;
+        let mut reader = deck.output_reader();
+        let mut expected = [0; CHUNK];
+        let mut equal = Choice::from(1);
+        for chunk in tag.chunks(CHUNK) {
+            let expected = &mut expected[..chunk.len()];
+            reader.write_to_slice(expected)?;
+            equal &= expected.ct_eq(chunk);
+        }
+        self.deck = deck;
+        if bool::from(equal) {
+            Ok(())
+        } else {
+            Err(Error::TagMismatch)
+        }
+    }
+
+    /// Rekey by the output of the history, for forward secrecy.
+    ///
+    /// # Errors
+    /// Errors when the deck function can't generate the key. The state is left
+    /// unchanged.
+    pub fn ratchet(&mut self) -> Result<(), Error> {
+        self.rekey(&[], RATCHET)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeckProtocol;
+    use crate::Error;
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::xoofff::Xoofff;
+
+    fn protocol() -> DeckProtocol<Xoofff> {
+        DeckProtocol::new(Xoofff::init(&[0x57; 32]), b"test").unwrap()
+    }
+
+    fn prf(protocol: &mut DeckProtocol<Xoofff>) -> [u8; 32] {
+        let mut out = [0; 32];
+        protocol.prf(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut sender = protocol();
+        let mut receiver = protocol();
+        for len in [0, 1, 63, 64, 65, 200] {
+            let plaintext: Vec<u8> = (0..len as u8).collect();
+            let mut data = plaintext.clone();
+            sender.send_enc(&mut data).unwrap();
+            if len > 0 {
+                assert_ne!(data, plaintext);
+            }
+            let mut tag = [0; 16];
+            sender.send_mac(&mut tag).unwrap();
+
+            receiver.recv_enc(&mut data).unwrap();
+            assert_eq!(data, plaintext);
+            receiver.recv_mac(&tag).unwrap();
+        }
+        assert_eq!(prf(&mut sender), prf(&mut receiver));
+    }
+
+    /// The tag authenticates the whole history.
+    #[test]
+    fn authentication() {
+        let mut sender = protocol();
+        sender.ad(b"header").unwrap();
+        let mut data = *b"message";
+        sender.send_enc(&mut data).unwrap();
+        let mut tag = [0; 100];
+        sender.send_mac(&mut tag).unwrap();
+
+        let receive = |ad: &[u8], data: &[u8], tag: &[u8]| {
+            let mut receiver = protocol();
+            receiver.ad(ad).unwrap();
+            receiver.recv_enc(&mut data.to_vec()).unwrap();
+            receiver.recv_mac(tag)
+        };
+        receive(b"header", &data, &tag).unwrap();
+        assert!(matches!(
+            receive(b"headex", &data, &tag),
+            Err(Error::TagMismatch)
+        ));
+        let mut forged = data;
+        forged[0] ^= 1;
+        assert!(matches!(
+            receive(b"header", &forged, &tag),
+            Err(Error::TagMismatch)
+        ));
+        let mut forged = tag;
+        forged[99] ^= 1;
+        assert!(matches!(
+            receive(b"header", &data, &forged),
+            Err(Error::TagMismatch)
+        ));
+    }
+
+    /// Every operation changes the subsequent output.
+    #[test]
+    fn operations() {
+        let mut protocol = protocol();
+        let mut outputs = vec![prf(&mut protocol)];
+        protocol.ad(b"").unwrap();
+        outputs.push(prf(&mut protocol));
+        protocol.key(b"").unwrap();
+        outputs.push(prf(&mut protocol));
+        protocol.send_enc(&mut []).unwrap();
+        outputs.push(prf(&mut protocol));
+        protocol.send_mac(&mut []).unwrap();
+        outputs.push(prf(&mut protocol));
+        protocol.ratchet().unwrap();
+        outputs.push(prf(&mut protocol));
+        for (i, output) in outputs.iter().enumerate() {
+            assert!(!outputs[i + 1..].contains(output));
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Symmetric key ratchet for secure messaging.
+
+use super::kdf::DeckKdf;
+use crypto_permutation::DeckFunction;
+
+/// KDF label of the next chain key.
+const CHAIN_KEY: &str = "chain key";
+/// KDF label of a message key.
+const MESSAGE_KEY: &str = "message key";
+
+/// Symmetric ratchet deriving a key for every message of a chain, like the
+/// sending and receiving chains of the Double Ratchet, with [`DeckKdf`] in
+/// place of HKDF and HMAC.
+///
+/// The key of message `i` and the chain key of step `i + 1` are derived from
+/// the chain key of step `i` with the labels `"message key"` and
+/// `"chain key"`. The chain key is overwritten on every step, so the ratchet
+/// state doesn't reveal the keys of earlier messages.
+///
+/// The sender takes the keys in order with [`Self::next_key`]. The receiver
+/// gets the key of message `i` with [`Self::key`], which also stores the keys
+/// of skipped messages, up to `WINDOW` of them, for messages that arrive out
+/// of order. Every key is returned at most once, and the stored copy erased.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use deck_modes::SymmetricRatchet;
+///
+/// let mut sender = SymmetricRatchet::<Xoofff>::new([0x42; 32]);
+/// let mut receiver = SymmetricRatchet::<Xoofff>::new([0x42; 32]);
+/// let (first, first_key) = sender.next_key();
+/// let (second, second_key) = sender.next_key();
+///
+/// assert_eq!(receiver.key(second), Some(second_key));
+/// assert_eq!(receiver.key(first), Some(first_key));
+/// assert_eq!(receiver.key(first), None);
+/// ```
+#[derive(Clone)]
+pub struct SymmetricRatchet<F, const WINDOW: usize = 32> {
+    chain_key: [u8; 32],
+    /// Index of the next message.
+    index: u64,
+    /// Keys of skipped messages, with their indices.
+    skipped: [Option<(u64, [u8; 32])>; WINDOW],
+    deck: core::marker::PhantomData<F>,
+}
+
+impl<F, const WINDOW: usize> core::fmt::Debug for SymmetricRatchet<F, WINDOW> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SymmetricRatchet")
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction + Clone, const WINDOW: usize> SymmetricRatchet<F, WINDOW> {
+    /// Start the chain with chain key `chain_key`, e.g. output by the
+    /// Diffie-Hellman ratchet.
+    pub fn new(chain_key: [u8; 32]) -> Self {
+        Self {
+            chain_key,
+            index: 0,
+            skipped: [None; WINDOW],
+            deck: core::marker::PhantomData,
+        }
+    }
+
+    /// Index of the next message key in the chain.
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Advance the chain, returning the index and key of the next message.
+    pub fn next_key(&mut self) -> (u64, [u8; 32]) {
+        let kdf = DeckKdf::new(F::init(&self.chain_key));
+        let mut message_key = [0; 32];
+        kdf.derive(MESSAGE_KEY, &[], &mut message_key)
+            .expect("deck function generates a key");
+        kdf.derive(CHAIN_KEY, &[], &mut self.chain_key)
+            .expect("deck function generates a key");
+        let index = self.index;
+        self.index = index.checked_add(1).expect("index doesn't overflow");
+        (index, message_key)
+    }
+
+    /// The key of message `index`, for a receiver.
+    ///
+    /// Messages before `index` that weren't received yet have their keys
+    /// stored, evicting the keys of the oldest messages when more than
+    /// `WINDOW` are stored.
+    ///
+    /// Returns `None` when the key was already returned or evicted, or when
+    /// more than `WINDOW` messages would be skipped. The state is then left
+    /// unchanged.
+    pub fn key(&mut self, index: u64) -> Option<[u8; 32]> {
+        if index < self.index {
+            let slot = self
+                .skipped
+                .iter_mut()
+                .find(|slot| matches!(slot, Some((skipped, _)) if *skipped == index))?;
+            let (_, key) = slot.take()?;
+            return Some(key);
+        }
+        if index - self.index > WINDOW as u64 {
+            return None;
+        }
+        while self.index < index {
+            let skipped = self.next_key();
+            self.store(skipped);
+        }
+        Some(self.next_key().1)
+    }
+
+    /// Store the key of the skipped message, in a free slot or else in the
+    /// slot of the oldest message.
+    fn store(&mut self, skipped: (u64, [u8; 32])) {
+        let slot = self
+            .skipped
+            .iter_mut()
+            .min_by_key(|slot| slot.map(|(index, _)| index));
+        if let Some(slot) = slot {
+            *slot = Some(skipped);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SymmetricRatchet;
+    use deck_farfalle::xoofff::Xoofff;
+
+    const CHAIN_KEY: [u8; 32] = [0x7e; 32];
+
+    fn keys(count: usize) -> Vec<[u8; 32]> {
+        let mut sender = SymmetricRatchet::<Xoofff>::new(CHAIN_KEY);
+        (0..count).map(|_| sender.next_key().1).collect()
+    }
+
+    #[test]
+    fn in_order() {
+        let keys = keys(10);
+        for (i, key) in keys.iter().enumerate() {
+            assert!(!keys[i + 1..].contains(key));
+        }
+        let mut receiver = SymmetricRatchet::<Xoofff>::new(CHAIN_KEY);
+        for (index, key) in keys.iter().enumerate() {
+            assert_eq!(receiver.key(index as u64), Some(*key));
+        }
+        assert_eq!(receiver.index(), 10);
+    }
+
+    #[test]
+    fn out_of_order() {
+        let keys = keys(10);
+        let mut receiver = SymmetricRatchet::<Xoofff, 4>::new(CHAIN_KEY);
+        assert_eq!(receiver.key(3), Some(keys[3]));
+        assert_eq!(receiver.key(1), Some(keys[1]));
+        assert_eq!(receiver.key(1), None);
+        assert_eq!(receiver.key(3), None);
+        // Skipping 4 more messages evicts the oldest keys, of messages 0 and 2.
+        assert_eq!(receiver.key(8), Some(keys[8]));
+        assert_eq!(receiver.key(0), None);
+        assert_eq!(receiver.key(2), None);
+        for index in 4..8 {
+            assert_eq!(receiver.key(index), Some(keys[index as usize]));
+        }
+    }
+
+    #[test]
+    fn window() {
+        let keys = keys(10);
+        let mut receiver = SymmetricRatchet::<Xoofff, 4>::new(CHAIN_KEY);
+        assert_eq!(receiver.key(5), None);
+        assert_eq!(receiver.index(), 0);
+        assert_eq!(receiver.key(4), Some(keys[4]));
+    }
+}
@@ -0,0 +1,181 @@
+//! Keystream that rekeys automatically.
+
+use super::{input_parts, output_at, xor_output, Error};
+use crypto_permutation::{DeckFunction, Reader};
+
+/// Domain separation byte following the nonce.
+const NONCE: u8 = 0;
+/// Domain separation byte of the epoch requests.
+const EPOCH: u8 = 1;
+
+/// Keystream of the deck function `F` that ratchets its key after every
+/// `EPOCH_LEN` bytes.
+///
+/// The stream is split into epochs of `EPOCH_LEN` bytes. The initial chain
+/// key is `F(N || 0)` under the key, with `N` the nonce. Every epoch runs the
+/// deck function keyed by the current chain key on the single byte `1`: the
+/// first 32 bytes of its output replace the chain key, and the next
+/// `EPOCH_LEN` bytes are the keystream of the epoch. Hence no key state
+/// processes more than `EPOCH_LEN + 32` bytes, and as every chain key is
+/// overwritten once its epoch starts, compromise of the state doesn't reveal
+/// the keystream of earlier epochs. For the same reason the keystream can't
+/// be seeked.
+///
+/// # Crypto
+/// The keystream is not authenticated, and a nonce must never be used twice
+/// under the same key.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use deck_modes::RekeyingKeystream;
+///
+/// let key = [0x42; 32];
+/// let mut data = *b"hello world";
+/// RekeyingKeystream::<Xoofff, 4>::new(&key, b"nonce")
+///     .unwrap()
+///     .apply_keystream(&mut data)
+///     .unwrap();
+///
+/// let mut keystream = RekeyingKeystream::<Xoofff, 4>::new(&key, b"nonce").unwrap();
+/// keystream.apply_keystream(&mut data[..5]).unwrap();
+/// keystream.apply_keystream(&mut data[5..]).unwrap();
+/// assert_eq!(&data, b"hello world");
+/// ```
+pub struct RekeyingKeystream<F: DeckFunction, const EPOCH_LEN: usize = 1048576> {
+    chain_key: [u8; 32],
+    keystream: Option<F::OutputGenerator>,
+    /// Number of keystream bytes left in the current epoch.
+    remaining: usize,
+    /// Position in the keystream in bytes.
+    pos: u64,
+}
+
+impl<F: DeckFunction, const EPOCH_LEN: usize> core::fmt::Debug for RekeyingKeystream<F, EPOCH_LEN> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RekeyingKeystream")
+            .field("epoch_len", &EPOCH_LEN)
+            .field("pos", &self.pos)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction, const EPOCH_LEN: usize> RekeyingKeystream<F, EPOCH_LEN> {
+    /// Start the keystream for `key` and `nonce`.
+    ///
+    /// # Panics
+    /// Panics when `EPOCH_LEN` is zero.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the nonce.
+    pub fn new(key: &[u8; 32], nonce: &[u8]) -> Result<Self, Error> {
+        assert!(EPOCH_LEN > 0, "epochs must not be empty");
+        let mut deck = F::init(key);
+        input_parts(&mut deck, &[nonce], NONCE)?;
+        Ok(Self {
+            chain_key: output_at(&deck, 0)?,
+            keystream: None,
+            remaining: 0,
+            pos: 0,
+        })
+    }
+
+    /// Position in the keystream in bytes.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Ratchet the chain key, starting the next epoch.
+    fn next_epoch(&mut self) -> Result<(), Error> {
+        let mut deck = F::init(&self.chain_key);
+        input_parts(&mut deck, &[], EPOCH)?;
+        let mut keystream = deck.output_reader();
+        keystream.write_to_slice(&mut self.chain_key)?;
+        self.keystream = Some(keystream);
+        self.remaining = EPOCH_LEN;
+        Ok(())
+    }
+
+    /// Xor the next `data.len()` bytes of the keystream into `data`.
+    ///
+    /// # Errors
+    /// Errors when the deck function can't generate the keystream. The
+    /// contents of `data` and the position in the keystream are unspecified
+    /// afterwards.
+    pub fn apply_keystream(&mut self, mut data: &mut [u8]) -> Result<(), Error> {
+        while !data.is_empty() {
+            if self.remaining == 0 {
+                self.next_epoch()?;
+            }
+            let len = data.len().min(self.remaining);
+            let (chunk, rest) = data.split_at_mut(len);
+            let keystream = self.keystream.as_mut().expect("an epoch has started");
+            xor_output(keystream, chunk)?;
+            self.remaining -= len;
+            self.pos += len as u64;
+            data = rest;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RekeyingKeystream;
+    use crypto_permutation::{DeckFunction, Reader};
+    use deck_farfalle::xoofff::Xoofff;
+
+    const KEY: [u8; 32] = [0x6b; 32];
+
+    fn keystream(len: usize) -> Vec<u8> {
+        let mut data = vec![0; len];
+        RekeyingKeystream::<Xoofff, 100>::new(&KEY, b"nonce")
+            .unwrap()
+            .apply_keystream(&mut data)
+            .unwrap();
+        data
+    }
+
+    /// Splitting the data differently gives the same keystream, also across
+    /// epoch boundaries.
+    #[test]
+    fn splits() {
+        let expected = keystream(350);
+        for split in [1, 99, 100, 101, 250] {
+            let mut data = vec![0; 350];
+            let mut keystream = RekeyingKeystream::<Xoofff, 100>::new(&KEY, b"nonce").unwrap();
+            keystream.apply_keystream(&mut data[..split]).unwrap();
+            keystream.apply_keystream(&mut data[split..]).unwrap();
+            assert_eq!(keystream.position(), 350);
+            assert_eq!(data, expected);
+        }
+    }
+
+    /// Every epoch is keyed by the chain key output at the start of the
+    /// previous one.
+    #[test]
+    fn epochs() {
+        let data = keystream(200);
+        let mut keystream = RekeyingKeystream::<Xoofff, 100>::new(&KEY, b"nonce").unwrap();
+        keystream.apply_keystream(&mut [0; 100]).unwrap();
+        let chain_key = keystream.chain_key;
+
+        let mut deck = Xoofff::init(&chain_key);
+        super::input_parts(&mut deck, &[], super::EPOCH).unwrap();
+        let mut output = deck.output_reader();
+        output.skip(32).unwrap();
+        let mut expected = [0; 100];
+        output.write_to_slice(&mut expected).unwrap();
+        assert_eq!(data[100..], expected);
+        assert_ne!(data[..100], data[100..]);
+    }
+
+    #[test]
+    fn nonce_separation() {
+        let mut other = vec![0; 50];
+        RekeyingKeystream::<Xoofff, 100>::new(&KEY, b"other nonce")
+            .unwrap()
+            .apply_keystream(&mut other)
+            .unwrap();
+        assert_ne!(other, keystream(50));
+    }
+}
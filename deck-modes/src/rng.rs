@@ -0,0 +1,190 @@
+//! Cryptographically secure random number generator with fast key erasure.
+
+use super::{input_parts, output_at};
+use crypto_permutation::{DeckFunction, Reader};
+use rand_core::{impls, CryptoRng, RngCore, SeedableRng};
+
+/// Domain separation byte following reseeding entropy.
+const RESEED: u8 = 0;
+/// Domain separation byte of the output requests.
+const GENERATE: u8 = 1;
+/// Size in bytes of the buffer of output for small requests.
+const BUFFER: usize = 64;
+
+/// Random number generator built from the deck function `F`, with fast key
+/// erasure.
+///
+/// Every request for output runs the deck function keyed by the current
+/// 32 byte key. The first 32 bytes of its output replace the key, and the
+/// rest is the random output. As the old key is overwritten before any output
+/// is returned, compromise of the generator state doesn't reveal earlier
+/// output. Small requests are served from a buffer, whose bytes are erased
+/// as they are handed out.
+///
+/// Seed it with 32 bytes of entropy using [`SeedableRng::from_seed`] or, with
+/// `rand_core`'s `getrandom` feature, [`SeedableRng::from_entropy`], and mix
+/// in fresh entropy with [`Self::reseed`].
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use deck_modes::DeckRng;
+/// use rand_core::{RngCore, SeedableRng};
+///
+/// let mut rng = DeckRng::<Xoofff>::from_seed([0x42; 32]);
+/// let mut key = [0; 32];
+/// rng.fill_bytes(&mut key);
+/// let nonce = rng.next_u64();
+/// ```
+pub struct DeckRng<F> {
+    key: [u8; 32],
+    buffer: [u8; BUFFER],
+    /// Number of bytes of `buffer` already handed out.
+    used: usize,
+    deck: core::marker::PhantomData<F>,
+}
+
+impl<F> core::fmt::Debug for DeckRng<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DeckRng").finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction> DeckRng<F> {
+    /// The deck function keyed by the current key, after replacing the key by
+    /// the first 32 bytes of output. Returns the output reader positioned
+    /// after the new key.
+    fn rekey(&mut self) -> F::OutputGenerator {
+        let mut deck = F::init(&self.key);
+        input_parts(&mut deck, &[], GENERATE).expect("deck function accepts a request");
+        let mut output = deck.output_reader();
+        output
+            .write_to_slice(&mut self.key)
+            .expect("deck function generates a key");
+        output
+    }
+
+    /// Mix `entropy` into the key, e.g. after waking from sleep or on fresh
+    /// entropy from the operating system. Discards the buffered output.
+    pub fn reseed(&mut self, entropy: &[u8]) {
+        let mut deck = F::init(&self.key);
+        input_parts(&mut deck, &[entropy], RESEED).expect("deck function accepts the entropy");
+        self.key = output_at(&deck, 0).expect("deck function generates a key");
+        self.buffer = [0; BUFFER];
+        self.used = BUFFER;
+    }
+}
+
+impl<F: DeckFunction> SeedableRng for DeckRng<F> {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self {
+            key: seed,
+            buffer: [0; BUFFER],
+            used: BUFFER,
+            deck: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<F: DeckFunction> RngCore for DeckRng<F> {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        if dest.len() > BUFFER {
+            // Serve large requests directly, discarding the buffer.
+            self.buffer = [0; BUFFER];
+            self.used = BUFFER;
+            self.rekey()
+                .write_to_slice(dest)
+                .expect("deck function generates the output");
+            return;
+        }
+        for byte in dest {
+            if self.used == BUFFER {
+                self.rekey()
+                    .write_to_slice(&mut self.buffer)
+                    .expect("deck function generates the output");
+                self.used = 0;
+            }
+            *byte = core::mem::take(&mut self.buffer[self.used]);
+            self.used += 1;
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl<F: DeckFunction> CryptoRng for DeckRng<F> {}
+
+#[cfg(test)]
+mod tests {
+    use super::DeckRng;
+    use deck_farfalle::xoofff::Xoofff;
+    use rand_core::{RngCore, SeedableRng};
+
+    fn rng() -> DeckRng<Xoofff> {
+        DeckRng::from_seed([0x12; 32])
+    }
+
+    #[test]
+    fn deterministic() {
+        let mut first = [0; 100];
+        rng().fill_bytes(&mut first);
+        let mut second = [0; 100];
+        rng().fill_bytes(&mut second);
+        assert_eq!(first, second);
+
+        let mut other = [0; 100];
+        DeckRng::<Xoofff>::from_seed([0x13; 32]).fill_bytes(&mut other);
+        assert_ne!(first, other);
+    }
+
+    /// Consecutive requests give fresh output, both from the buffer and
+    /// directly.
+    #[test]
+    fn fresh_output() {
+        let mut rng = rng();
+        let mut outputs = Vec::new();
+        for len in [1, 7, 56, 64, 65, 200, 32] {
+            let mut output = vec![0; len];
+            rng.fill_bytes(&mut output);
+            outputs.push(output);
+        }
+        for (i, output) in outputs.iter().enumerate() {
+            for other in &outputs[i + 1..] {
+                let len = output.len().min(other.len());
+                assert_ne!(output[..len], other[..len]);
+            }
+        }
+    }
+
+    /// The key is erased: it changes with every refill.
+    #[test]
+    fn key_erasure() {
+        let mut rng = rng();
+        let key = rng.key;
+        rng.next_u32();
+        assert_ne!(rng.key, key);
+        assert!(rng.buffer[..4].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn reseed() {
+        let mut reseeded = rng();
+        reseeded.reseed(b"entropy");
+        let mut other = rng();
+        other.reseed(b"entropz");
+        assert_ne!(reseeded.next_u64(), other.next_u64());
+        assert_ne!(reseeded.next_u64(), rng().next_u64());
+    }
+}
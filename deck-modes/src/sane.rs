@@ -0,0 +1,345 @@
+//! Deck-SANE: session authenticated encryption.
+
+use super::{input_suffixed, output_at, xor_output, Error, Tag};
+use crypto_permutation::{DeckFunction, ExportableDeckFunction, InvalidStateError, Reader, Writer};
+use subtle::ConstantTimeEq;
+
+/// Deck-SANE session authenticated encryption, with `TAG_LEN` byte tags.
+///
+/// A session is started with a nonce, after which a sequence of messages,
+/// each consisting of metadata (associated data) and plaintext, is wrapped.
+/// Every tag authenticates the whole session up to and including the current
+/// message, so messages can neither be modified nor reordered or dropped.
+///
+/// The keystream for a message skips the first `TAG_LEN` bytes of the deck
+/// function output, i.e. the alignment unit `ℓ` of the specification is one
+/// byte. The metadata and the ciphertext are followed by the domain separation
+/// bits `0 || e` and `1 || e` respectively.
+///
+/// # Crypto
+/// A nonce must never be used for two sessions under the same key. The
+/// receiver must verify the tag returned by [`Self::start_session`] against
+/// the tag computed by the sender.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use crypto_permutation::DeckFunction;
+/// use deck_modes::DeckSane;
+///
+/// let key = [0x42; 32];
+/// let (mut sender, start_tag) =
+///     DeckSane::<Xoofff>::start_session(Xoofff::init(&key), b"nonce").unwrap();
+/// let mut message = *b"hello world";
+/// let tag = sender.wrap(b"metadata", &mut message).unwrap();
+///
+/// let (mut receiver, receiver_start_tag) =
+///     DeckSane::<Xoofff>::start_session(Xoofff::init(&key), b"nonce").unwrap();
+/// assert_eq!(start_tag, receiver_start_tag);
+/// receiver.unwrap(b"metadata", &mut message, &tag).unwrap();
+/// assert_eq!(&message, b"hello world");
+/// ```
+#[derive(Clone)]
+pub struct DeckSane<F, const TAG_LEN: usize = 16> {
+    deck: F,
+    /// The bit `e`, alternating between consecutive messages.
+    e: bool,
+}
+
+impl<F, const TAG_LEN: usize> core::fmt::Debug for DeckSane<F, TAG_LEN> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DeckSane")
+            .field("tag_len", &TAG_LEN)
+            .field("e", &self.e)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction + Clone, const TAG_LEN: usize> DeckSane<F, TAG_LEN> {
+    /// Start a session using the keyed deck function `deck` and `nonce`.
+    ///
+    /// Returns the session and the tag authenticating the start of the
+    /// session.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the nonce or can't
+    /// generate the tag.
+    pub fn start_session(mut deck: F, nonce: &[u8]) -> Result<(Self, Tag<TAG_LEN>), Error> {
+        let mut writer = deck.input_writer();
+        writer.write_bytes(nonce)?;
+        writer.finish();
+        let tag = Tag::from(output_at(&deck, 0)?);
+        Ok((Self { deck, e: false }, tag))
+    }
+
+    /// The two domain separation bits for metadata and ciphertext, least
+    /// significant bit first.
+    fn suffixes(&self) -> (u8, u8) {
+        let e = u8::from(self.e) << 1;
+        (e, e | 1)
+    }
+
+    /// The session history after absorbing the message with `metadata` and
+    /// `ciphertext`.
+    fn absorb(&self, metadata: &[u8], ciphertext: &[u8]) -> Result<F, Error> {
+        let (metadata_suffix, ciphertext_suffix) = self.suffixes();
+        let mut deck = self.deck.clone();
+        if !metadata.is_empty() || ciphertext.is_empty() {
+            input_suffixed(&mut deck, &[metadata], metadata_suffix, 2)?;
+        }
+        if !ciphertext.is_empty() {
+            input_suffixed(&mut deck, &[ciphertext], ciphertext_suffix, 2)?;
+        }
+        Ok(deck)
+    }
+
+    /// Encrypt `data` in place and authenticate it together with `metadata`.
+    /// Either may be empty.
+    ///
+    /// Returns the tag authenticating the session so far.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the message. The session
+    /// is left unchanged, but the contents of `data` are unspecified.
+    pub fn wrap(&mut self, metadata: &[u8], data: &mut [u8]) -> Result<Tag<TAG_LEN>, Error> {
+        let mut keystream = self.deck.output_reader();
+        keystream.skip(TAG_LEN)?;
+        xor_output(&mut keystream, data)?;
+        let deck = self.absorb(metadata, data)?;
+        let tag = Tag::from(output_at(&deck, 0)?);
+        self.deck = deck;
+        self.e = !self.e;
+        Ok(tag)
+    }
+
+    /// Verify `tag` for the ciphertext `data` and `metadata`, and decrypt
+    /// `data` in place.
+    ///
+    /// The tag is compared in constant time. Only when it matches is `data`
+    /// decrypted and the session advanced to the next message.
+    ///
+    /// # Errors
+    /// Errors with [`Error::TagMismatch`] when the tag doesn't match, or when
+    /// the deck function does not accept the message. In both cases the
+    /// session and `data` are left unchanged.
+    pub fn unwrap(
+        &mut self,
+        metadata: &[u8],
+        data: &mut [u8],
+        tag: &Tag<TAG_LEN>,
+    ) -> Result<(), Error> {
+        let deck = self.absorb(metadata, data)?;
+        let expected = Tag::<TAG_LEN>::from(output_at(&deck, 0)?);
+        if !bool::from(expected.ct_eq(tag)) {
+            return Err(Error::TagMismatch);
+        }
+        let mut keystream = self.deck.output_reader();
+        keystream.skip(TAG_LEN)?;
+        xor_output(&mut keystream, data)?;
+        self.deck = deck;
+        self.e = !self.e;
+        Ok(())
+    }
+}
+
+impl<F: ExportableDeckFunction, const TAG_LEN: usize> DeckSane<F, TAG_LEN> {
+    /// Number of bytes of an exported session.
+    pub const SESSION_LEN: usize = F::EXPORT_LEN + 1;
+
+    /// Export the state of the session into `out`, so it can be resumed later
+    /// using [`Self::import_session`], e.g. after a restart.
+    ///
+    /// The exported session is the exported deck function state followed by
+    /// the bit `e` as a byte.
+    ///
+    /// # Crypto
+    /// The exported session contains the key, so it must be protected like
+    /// the key. Resuming the same exported session twice and
+    /// wrapping different messages in both reuses keystream.
+    ///
+    /// # Panics
+    /// Panics when `out.len() != Self::SESSION_LEN`.
+    pub fn export_session(&self, out: &mut [u8]) {
+        assert_eq!(out.len(), Self::SESSION_LEN);
+        let (deck, e) = out.split_at_mut(F::EXPORT_LEN);
+        self.deck.export_state(deck);
+        e[0] = u8::from(self.e);
+    }
+
+    /// Resume the session exported into `bytes` using
+    /// [`Self::export_session`].
+    ///
+    /// `deck` provides the parameters of the deck function; its key and state
+    /// are replaced by the exported ones. Typically it is the deck function
+    /// the session was started with.
+    ///
+    /// # Errors
+    /// Errors when `bytes` is not a valid exported session.
+    pub fn import_session(mut deck: F, bytes: &[u8]) -> Result<Self, InvalidStateError> {
+        let (deck_bytes, e) = bytes
+            .split_last()
+            .map(|(e, deck)| (deck, *e))
+            .ok_or(InvalidStateError)?;
+        if e > 1 {
+            return Err(InvalidStateError);
+        }
+        deck.import_state(deck_bytes)?;
+        Ok(Self { deck, e: e == 1 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeckSane;
+    use crate::{Error, Tag};
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::xoofff::Xoofff;
+
+    const KEY: [u8; 32] = [0x5a; 32];
+
+    fn session() -> (DeckSane<Xoofff>, Tag<16>) {
+        DeckSane::start_session(Xoofff::init(&KEY), b"nonce").unwrap()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let messages: [(&[u8], &[u8]); 5] = [
+            (b"header", b"first message"),
+            (b"", b"no metadata"),
+            (b"only metadata", b""),
+            (b"", b""),
+            (&[7; 100], &[3; 300]),
+        ];
+
+        let (mut sender, start_tag) = session();
+        let (mut receiver, receiver_start_tag) = session();
+        assert_eq!(start_tag, receiver_start_tag);
+
+        for (metadata, plaintext) in messages {
+            let mut data = plaintext.to_vec();
+            let tag = sender.wrap(metadata, &mut data).unwrap();
+            if !plaintext.is_empty() {
+                assert_ne!(data, plaintext);
+            }
+            receiver.unwrap(metadata, &mut data, &tag).unwrap();
+            assert_eq!(data, plaintext);
+        }
+    }
+
+    #[test]
+    fn nonce_separation() {
+        let (_, start_tag) = session();
+        let (_, other_start_tag) =
+            DeckSane::<Xoofff>::start_session(Xoofff::init(&KEY), b"other nonce").unwrap();
+        assert_ne!(start_tag, other_start_tag);
+    }
+
+    /// Identical messages encrypt differently within a session.
+    #[test]
+    fn history_dependence() {
+        let (mut sender, _) = session();
+        let mut first = *b"same plaintext";
+        let mut second = first;
+        let first_tag = sender.wrap(b"", &mut first).unwrap();
+        let second_tag = sender.wrap(b"", &mut second).unwrap();
+        assert_ne!(first, second);
+        assert_ne!(first_tag, second_tag);
+    }
+
+    #[test]
+    fn forgery_rejected() {
+        let (mut sender, _) = session();
+        let (mut receiver, _) = session();
+        let mut data = *b"attack at dawn";
+        let tag = sender.wrap(b"metadata", &mut data).unwrap();
+
+        let mut forged = data;
+        forged[0] ^= 1;
+        let result = receiver.unwrap(b"metadata", &mut forged, &tag);
+        assert!(matches!(result, Err(Error::TagMismatch)));
+        let mut forged_tag = tag.into_bytes();
+        forged_tag[15] ^= 0x80;
+        let result = receiver.unwrap(b"metadata", &mut data.clone(), &forged_tag.into());
+        assert!(matches!(result, Err(Error::TagMismatch)));
+        let result = receiver.unwrap(b"metadata!", &mut data.clone(), &tag);
+        assert!(matches!(result, Err(Error::TagMismatch)));
+
+        // A rejected message leaves the receiving session unchanged.
+        receiver.unwrap(b"metadata", &mut data, &tag).unwrap();
+        assert_eq!(&data, b"attack at dawn");
+    }
+
+    #[test]
+    fn tag_length() {
+        let (mut sender, start_tag) =
+            DeckSane::<Xoofff, 32>::start_session(Xoofff::init(&KEY), b"nonce").unwrap();
+        let (_, short_start_tag) = session();
+        assert_eq!(start_tag.as_ref()[..16], *short_start_tag.as_ref());
+
+        let mut data = *b"message";
+        let tag = sender.wrap(b"", &mut data).unwrap();
+        assert_eq!(tag.as_ref().len(), 32);
+    }
+
+    /// Metadata and ciphertext are followed by the two bits `0 || e` and
+    /// `1 || e`, sharing the final byte with the padding of the deck function.
+    #[test]
+    fn suffix_bits() {
+        use crypto_permutation::io::FinishWithSuffix;
+        use crypto_permutation::{Reader, Writer};
+
+        fn input(deck: &mut Xoofff, data: &[u8], bits: u8, n: u32) {
+            let mut writer = deck.input_writer();
+            writer.write_bytes(data).unwrap();
+            writer.finish_with_suffix(bits, n);
+        }
+
+        let (mut session, _) = session();
+        let mut history = Xoofff::init(&KEY);
+        input(&mut history, b"nonce", 0, 0);
+        for (e, metadata) in [(0, &b"metadata"[..]), (1, b"")] {
+            let mut data = *b"plaintext";
+            let mut keystream = [0; 9];
+            let mut reader = history.output_reader();
+            reader.skip(16).unwrap();
+            reader.write_to_slice(&mut keystream).unwrap();
+            let tag = session.wrap(metadata, &mut data).unwrap();
+            for ((byte, key), plain) in data.iter().zip(keystream).zip(b"plaintext") {
+                assert_eq!(*byte, key ^ plain);
+            }
+
+            if !metadata.is_empty() {
+                input(&mut history, metadata, e << 1, 2);
+            }
+            input(&mut history, &data, e << 1 | 1, 2);
+            let mut expected = [0; 16];
+            history
+                .output_reader()
+                .write_to_slice(&mut expected)
+                .unwrap();
+            assert_eq!(tag.into_bytes(), expected);
+        }
+    }
+
+    /// A session continues after being exported and imported again.
+    #[test]
+    fn export_import() {
+        let (mut sender, _) = session();
+        let (mut receiver, _) = session();
+        let mut data = *b"first message";
+        let tag = sender.wrap(b"", &mut data).unwrap();
+        receiver.unwrap(b"", &mut data, &tag).unwrap();
+
+        let mut exported = [0; DeckSane::<Xoofff>::SESSION_LEN];
+        sender.export_session(&mut exported);
+        let mut resumed =
+            DeckSane::<Xoofff>::import_session(Xoofff::init(&[0; 32]), &exported).unwrap();
+        let mut data = *b"second message";
+        let tag = resumed.wrap(b"metadata", &mut data).unwrap();
+        receiver.unwrap(b"metadata", &mut data, &tag).unwrap();
+        assert_eq!(&data, b"second message");
+
+        exported[DeckSane::<Xoofff>::SESSION_LEN - 1] = 2;
+        assert!(DeckSane::<Xoofff>::import_session(Xoofff::init(&KEY), &exported).is_err());
+        assert!(DeckSane::<Xoofff>::import_session(Xoofff::init(&KEY), &[]).is_err());
+    }
+}
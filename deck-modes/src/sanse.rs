@@ -0,0 +1,391 @@
+//! Deck-SANSE: nonce-misuse resistant session authenticated encryption.
+
+use super::{input_suffixed, output_at, xor_output, Error, Tag};
+use crypto_permutation::{DeckFunction, ExportableDeckFunction, InvalidStateError};
+use subtle::ConstantTimeEq;
+
+/// Domain separation bits `00` following metadata, least significant bit
+/// first.
+const METADATA: u8 = 0b00;
+/// Domain separation bits `01` following plaintext, least significant bit
+/// first.
+const PLAINTEXT: u8 = 0b10;
+/// Domain separation bits `11` following the tag, for the keystream.
+const TAG: u8 = 0b11;
+
+/// Deck-SANSE session authenticated encryption, with `TAG_LEN` byte tags.
+///
+/// Like [`DeckSane`](crate::DeckSane), a session wraps a sequence of
+/// messages, each consisting of metadata (associated data) and plaintext, and
+/// every tag authenticates the whole session so far. Deck-SANSE does not need
+/// a nonce: it is a two-pass, SIV-like mode that computes the tag from the
+/// plaintext and derives the keystream from the tag. Repeating a session only
+/// leaks whether the messages were equal.
+///
+/// The metadata can be given in several parts, which are concatenated, using
+/// [`Self::wrap_parts`] and [`Self::unwrap_parts`].
+///
+/// The metadata, plaintext and tag are followed by the domain separation bits
+/// `00 || e`, `01 || e` and `11 || e` respectively.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use crypto_permutation::DeckFunction;
+/// use deck_modes::DeckSanse;
+///
+/// let key = [0x42; 32];
+/// let mut sender = DeckSanse::<Xoofff>::new(Xoofff::init(&key));
+/// let mut message = *b"hello world";
+/// let tag = sender.wrap(b"metadata", &mut message).unwrap();
+///
+/// let mut receiver = DeckSanse::<Xoofff>::new(Xoofff::init(&key));
+/// receiver.unwrap(b"metadata", &mut message, &tag).unwrap();
+/// assert_eq!(&message, b"hello world");
+/// ```
+#[derive(Clone)]
+pub struct DeckSanse<F, const TAG_LEN: usize = 32> {
+    deck: F,
+    /// The bit `e`, alternating between consecutive messages.
+    e: bool,
+}
+
+impl<F, const TAG_LEN: usize> core::fmt::Debug for DeckSanse<F, TAG_LEN> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DeckSanse")
+            .field("tag_len", &TAG_LEN)
+            .field("e", &self.e)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction + Clone, const TAG_LEN: usize> DeckSanse<F, TAG_LEN> {
+    /// Start a session using the keyed deck function `deck`.
+    pub fn new(deck: F) -> Self {
+        Self { deck, e: false }
+    }
+
+    /// Input the concatenation of `parts` followed by the two domain
+    /// separation bits `bits` and the bit `e` into `deck`.
+    fn input(&self, deck: &mut F, parts: &[&[u8]], bits: u8) -> Result<(), Error> {
+        input_suffixed(deck, parts, u8::from(self.e) << 2 | bits, 3)?;
+        Ok(())
+    }
+
+    /// The session history after absorbing `metadata`.
+    fn absorb_metadata(&self, metadata: &[&[u8]], data: &[u8]) -> Result<F, Error> {
+        let mut deck = self.deck.clone();
+        if metadata.iter().any(|part| !part.is_empty()) || data.is_empty() {
+            self.input(&mut deck, metadata, METADATA)?;
+        }
+        Ok(deck)
+    }
+
+    /// Xor the keystream derived from `tag` and `history` into `data`.
+    fn xor_keystream(&self, history: &F, tag: &Tag<TAG_LEN>, data: &mut [u8]) -> Result<(), Error> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let mut deck = history.clone();
+        self.input(&mut deck, &[tag.as_ref()], TAG)?;
+        xor_output(&mut deck.output_reader(), data)?;
+        Ok(())
+    }
+
+    /// Encrypt `data` in place and authenticate it together with `metadata`.
+    /// Either may be empty.
+    ///
+    /// Returns the tag, which authenticates the session so far and serves as
+    /// synthetic IV.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the message. The session
+    /// is left unchanged, but the contents of `data` are unspecified.
+    pub fn wrap(&mut self, metadata: &[u8], data: &mut [u8]) -> Result<Tag<TAG_LEN>, Error> {
+        self.wrap_parts(&[metadata], data)
+    }
+
+    /// Like [`Self::wrap`], with the metadata being the concatenation of
+    /// `metadata`.
+    ///
+    /// # Errors
+    /// See [`Self::wrap`].
+    pub fn wrap_parts(
+        &mut self,
+        metadata: &[&[u8]],
+        data: &mut [u8],
+    ) -> Result<Tag<TAG_LEN>, Error> {
+        let history = self.absorb_metadata(metadata, data)?;
+        let mut deck = history.clone();
+        if !data.is_empty() {
+            self.input(&mut deck, &[data], PLAINTEXT)?;
+        }
+        let tag = Tag::from(output_at(&deck, 0)?);
+        self.xor_keystream(&history, &tag, data)?;
+        self.deck = deck;
+        self.e = !self.e;
+        Ok(tag)
+    }
+
+    /// Decrypt `data` in place and verify `tag` for the plaintext and
+    /// `metadata`.
+    ///
+    /// The tag is compared in constant time. Only when it matches is the
+    /// session advanced to the next message; otherwise `data` is encrypted
+    /// again, so the unverified plaintext is not released.
+    ///
+    /// # Errors
+    /// Errors with [`Error::TagMismatch`] when the tag doesn't match, or when
+    /// the deck function does not accept the message. The session is left
+    /// unchanged. After a tag mismatch `data` holds the ciphertext again; after
+    /// other errors its contents are unspecified.
+    pub fn unwrap(
+        &mut self,
+        metadata: &[u8],
+        data: &mut [u8],
+        tag: &Tag<TAG_LEN>,
+    ) -> Result<(), Error> {
+        self.unwrap_parts(&[metadata], data, tag)
+    }
+
+    /// Like [`Self::unwrap`], with the metadata being the concatenation of
+    /// `metadata`.
+    ///
+    /// # Errors
+    /// See [`Self::unwrap`].
+    pub fn unwrap_parts(
+        &mut self,
+        metadata: &[&[u8]],
+        data: &mut [u8],
+        tag: &Tag<TAG_LEN>,
+    ) -> Result<(), Error> {
+        let history = self.absorb_metadata(metadata, data)?;
+        self.xor_keystream(&history, tag, data)?;
+        let mut deck = history.clone();
+        if !data.is_empty() {
+            self.input(&mut deck, &[data], PLAINTEXT)?;
+        }
+        let expected = Tag::<TAG_LEN>::from(output_at(&deck, 0)?);
+        if !bool::from(expected.ct_eq(tag)) {
+            self.xor_keystream(&history, tag, data)?;
+            return Err(Error::TagMismatch);
+        }
+        self.deck = deck;
+        self.e = !self.e;
+        Ok(())
+    }
+}
+
+impl<F: ExportableDeckFunction, const TAG_LEN: usize> DeckSanse<F, TAG_LEN> {
+    /// Number of bytes of an exported session.
+    pub const SESSION_LEN: usize = F::EXPORT_LEN + 1;
+
+    /// Export the state of the session into `out`, so it can be resumed later
+    /// using [`Self::import_session`], e.g. after a restart.
+    ///
+    /// The exported session is the exported deck function state followed by
+    /// the bit `e` as a byte.
+    ///
+    /// # Crypto
+    /// The exported session contains the key, so it must be protected like
+    /// the key. Resuming the same exported session twice and
+    /// wrapping messages in both leaks whether these messages were equal.
+    ///
+    /// # Panics
+    /// Panics when `out.len() != Self::SESSION_LEN`.
+    pub fn export_session(&self, out: &mut [u8]) {
+        assert_eq!(out.len(), Self::SESSION_LEN);
+        let (deck, e) = out.split_at_mut(F::EXPORT_LEN);
+        self.deck.export_state(deck);
+        e[0] = u8::from(self.e);
+    }
+
+    /// Resume the session exported into `bytes` using
+    /// [`Self::export_session`].
+    ///
+    /// `deck` provides the parameters of the deck function; its key and state
+    /// are replaced by the exported ones. Typically it is the deck function
+    /// the session was created with.
+    ///
+    /// # Errors
+    /// Errors when `bytes` is not a valid exported session.
+    pub fn import_session(mut deck: F, bytes: &[u8]) -> Result<Self, InvalidStateError> {
+        let (deck_bytes, e) = bytes
+            .split_last()
+            .map(|(e, deck)| (deck, *e))
+            .ok_or(InvalidStateError)?;
+        if e > 1 {
+            return Err(InvalidStateError);
+        }
+        deck.import_state(deck_bytes)?;
+        Ok(Self { deck, e: e == 1 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeckSanse;
+    use crate::Error;
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::xoofff::Xoofff;
+
+    const KEY: [u8; 32] = [0xa5; 32];
+
+    fn session() -> DeckSanse<Xoofff> {
+        DeckSanse::new(Xoofff::init(&KEY))
+    }
+
+    #[test]
+    fn roundtrip() {
+        let messages: [(&[u8], &[u8]); 5] = [
+            (b"header", b"first message"),
+            (b"", b"no metadata"),
+            (b"only metadata", b""),
+            (b"", b""),
+            (&[7; 100], &[3; 300]),
+        ];
+
+        let mut sender = session();
+        let mut receiver = session();
+        for (metadata, plaintext) in messages {
+            let mut data = plaintext.to_vec();
+            let tag = sender.wrap(metadata, &mut data).unwrap();
+            if !plaintext.is_empty() {
+                assert_ne!(data, plaintext);
+            }
+            receiver.unwrap(metadata, &mut data, &tag).unwrap();
+            assert_eq!(data, plaintext);
+        }
+    }
+
+    /// Metadata given in parts is concatenated.
+    #[test]
+    fn metadata_parts() {
+        let mut data = *b"message";
+        let mut parts_data = data;
+        let tag = session().wrap(b"metadata", &mut data).unwrap();
+        let parts_tag = session()
+            .wrap_parts(&[b"meta", b"", b"data"], &mut parts_data)
+            .unwrap();
+        assert_eq!(tag, parts_tag);
+        assert_eq!(data, parts_data);
+
+        let mut receiver = session();
+        receiver
+            .unwrap_parts(&[b"me", b"tadata"], &mut data, &tag)
+            .unwrap();
+        assert_eq!(&data, b"message");
+    }
+
+    /// Without a nonce, equal sessions give equal output, but identical
+    /// messages within a session encrypt differently.
+    #[test]
+    fn determinism() {
+        let mut first = session();
+        let mut second = session();
+        let mut data = *b"same plaintext";
+        let mut second_data = data;
+        let tag = first.wrap(b"", &mut data).unwrap();
+        assert_eq!(second.wrap(b"", &mut second_data).unwrap(), tag);
+        assert_eq!(data, second_data);
+
+        let mut next = *b"same plaintext";
+        let next_tag = first.wrap(b"", &mut next).unwrap();
+        assert_ne!(next, data);
+        assert_ne!(next_tag, tag);
+    }
+
+    /// Metadata, plaintext and tag are followed by the three bits `00 || e`,
+    /// `01 || e` and `11 || e`, sharing the final byte with the padding of the
+    /// deck function.
+    #[test]
+    fn suffix_bits() {
+        use crypto_permutation::io::FinishWithSuffix;
+        use crypto_permutation::{Reader, Writer};
+
+        fn input(deck: &Xoofff, data: &[u8], bits: u8) -> Xoofff {
+            let mut deck = deck.clone();
+            let mut writer = deck.input_writer();
+            writer.write_bytes(data).unwrap();
+            writer.finish_with_suffix(bits, 3);
+            deck
+        }
+
+        let mut session = session();
+        let mut history = Xoofff::init(&KEY);
+        for (e, metadata) in [(0, &b"metadata"[..]), (1, b"")] {
+            let mut data = *b"plaintext";
+            let tag = session.wrap(metadata, &mut data).unwrap();
+
+            if !metadata.is_empty() {
+                history = input(&history, metadata, e << 2);
+            }
+            let next = input(&history, b"plaintext", e << 2 | 0b10);
+            let mut expected = [0; 32];
+            next.output_reader().write_to_slice(&mut expected).unwrap();
+            assert_eq!(tag.into_bytes(), expected);
+
+            let mut keystream = [0; 9];
+            input(&history, &expected, e << 2 | 0b11)
+                .output_reader()
+                .write_to_slice(&mut keystream)
+                .unwrap();
+            for ((byte, key), plain) in data.iter().zip(keystream).zip(b"plaintext") {
+                assert_eq!(*byte, key ^ plain);
+            }
+            history = next;
+        }
+    }
+
+    #[test]
+    fn forgery_rejected() {
+        let mut sender = session();
+        let mut receiver = session();
+        let mut data = *b"attack at dawn";
+        let tag = sender.wrap(b"metadata", &mut data).unwrap();
+        let ciphertext = data;
+
+        let mut forged = data;
+        forged[3] ^= 4;
+        let result = receiver.unwrap(b"metadata", &mut forged, &tag);
+        assert!(matches!(result, Err(Error::TagMismatch)));
+        let mut expected = ciphertext;
+        expected[3] ^= 4;
+        assert_eq!(forged, expected);
+
+        let mut forged_tag = tag.into_bytes();
+        forged_tag[0] ^= 1;
+        let result = receiver.unwrap(b"metadata", &mut data, &forged_tag.into());
+        assert!(matches!(result, Err(Error::TagMismatch)));
+        assert_eq!(data, ciphertext);
+        let result = receiver.unwrap(b"", &mut data, &tag);
+        assert!(matches!(result, Err(Error::TagMismatch)));
+        assert_eq!(data, ciphertext);
+
+        // A rejected message leaves the receiving session unchanged.
+        receiver.unwrap(b"metadata", &mut data, &tag).unwrap();
+        assert_eq!(&data, b"attack at dawn");
+    }
+
+    /// A session continues after being exported and imported again.
+    #[test]
+    fn export_import() {
+        let mut sender = session();
+        let mut receiver = session();
+        let mut data = *b"first message";
+        let tag = sender.wrap(b"", &mut data).unwrap();
+        receiver.unwrap(b"", &mut data, &tag).unwrap();
+
+        let mut exported = [0; DeckSanse::<Xoofff>::SESSION_LEN];
+        sender.export_session(&mut exported);
+        let mut resumed =
+            DeckSanse::<Xoofff>::import_session(Xoofff::init(&[0; 32]), &exported).unwrap();
+        let mut data = *b"second message";
+        let tag = resumed.wrap(b"metadata", &mut data).unwrap();
+        receiver.unwrap(b"metadata", &mut data, &tag).unwrap();
+        assert_eq!(&data, b"second message");
+
+        exported[DeckSanse::<Xoofff>::SESSION_LEN - 1] = 2;
+        assert!(DeckSanse::<Xoofff>::import_session(Xoofff::init(&KEY), &exported).is_err());
+        assert!(DeckSanse::<Xoofff>::import_session(Xoofff::init(&KEY), &[]).is_err());
+    }
+}
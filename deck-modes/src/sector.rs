@@ -0,0 +1,111 @@
+//! Disk sector encryption on top of Deck-WBC.
+
+use super::{DeckWbc, Error, HalfSplit, WbcSplit};
+use crypto_permutation::DeckFunction;
+
+/// Length preserving encryption of `N` byte disk sectors, e.g. 512 or 4096
+/// bytes.
+///
+/// Every sector is enciphered with [`DeckWbc`], using the little endian
+/// encoding of its index as tweak. Like Adiantum, it suits disk encryption,
+/// where there is no room for a nonce or tag: changing a single byte of a
+/// sector changes the whole sector, and equal sectors at different indices
+/// encrypt differently.
+///
+/// # Crypto
+/// The encryption is deterministic and unauthenticated: writing the same data
+/// to a sector twice gives the same ciphertext, and an attacker can roll a
+/// sector back to an older version.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use crypto_permutation::DeckFunction;
+/// use deck_modes::SectorCipher;
+///
+/// let disk = SectorCipher::<Xoofff, 512>::new(Xoofff::init(&[0x42; 32]));
+/// let mut sector = [0; 512];
+/// disk.encrypt_sector(7, &mut sector).unwrap();
+/// disk.decrypt_sector(7, &mut sector).unwrap();
+/// assert_eq!(sector, [0; 512]);
+/// ```
+#[derive(Clone)]
+pub struct SectorCipher<F, const N: usize, S = HalfSplit> {
+    wbc: DeckWbc<F, S>,
+}
+
+impl<F, const N: usize, S: core::fmt::Debug> core::fmt::Debug for SectorCipher<F, N, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SectorCipher")
+            .field("sector_len", &N)
+            .field("wbc", &self.wbc)
+            .finish()
+    }
+}
+
+impl<F: DeckFunction + Clone, const N: usize, S: WbcSplit> SectorCipher<F, N, S> {
+    /// Create the sector cipher keyed by the deck function `deck`.
+    pub fn new(deck: F) -> Self {
+        Self {
+            wbc: DeckWbc::new(deck),
+        }
+    }
+
+    /// Encrypt the sector with index `sector_index` in place.
+    ///
+    /// # Errors
+    /// Errors with [`Error::BlockTooShort`] when `N` is smaller than
+    /// [`DeckWbc::MIN_LEN`]. Errors when the deck function does not accept the
+    /// input, leaving the contents of `sector` unspecified.
+    pub fn encrypt_sector(&self, sector_index: u64, sector: &mut [u8; N]) -> Result<(), Error> {
+        self.wbc.encrypt(&sector_index.to_le_bytes(), sector)
+    }
+
+    /// Decrypt the sector with index `sector_index` in place.
+    ///
+    /// # Errors
+    /// See [`Self::encrypt_sector`].
+    pub fn decrypt_sector(&self, sector_index: u64, sector: &mut [u8; N]) -> Result<(), Error> {
+        self.wbc.decrypt(&sector_index.to_le_bytes(), sector)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SectorCipher;
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::xoofff::Xoofff;
+
+    fn key() -> Xoofff {
+        Xoofff::init(&[0x5e; 32])
+    }
+
+    #[test]
+    fn roundtrip() {
+        let disk = SectorCipher::<Xoofff, 4096>::new(key());
+        let plaintext: [u8; 4096] = core::array::from_fn(|i| (i % 251) as u8);
+        for index in [0, 1, u64::MAX] {
+            let mut sector = plaintext;
+            disk.encrypt_sector(index, &mut sector).unwrap();
+            assert_ne!(sector, plaintext);
+            disk.decrypt_sector(index, &mut sector).unwrap();
+            assert_eq!(sector, plaintext);
+        }
+    }
+
+    /// The sector index is the tweak of the wide block cipher.
+    #[test]
+    fn index_tweak() {
+        let disk = SectorCipher::<Xoofff, 512>::new(key());
+        let mut sector = [0; 512];
+        disk.encrypt_sector(1, &mut sector).unwrap();
+        let mut other = [0; 512];
+        disk.encrypt_sector(2, &mut other).unwrap();
+        assert_ne!(sector, other);
+
+        let mut expected = [0; 512];
+        crate::DeckWbc::<Xoofff>::new(key())
+            .encrypt(&1u64.to_le_bytes(), &mut expected)
+            .unwrap();
+        assert_eq!(sector, expected);
+    }
+}
@@ -0,0 +1,189 @@
+//! Deck-SIV: deterministic authenticated encryption.
+
+use super::{input_parts, output_at, xor_output, Error, Tag};
+use crypto_permutation::DeckFunction;
+use subtle::ConstantTimeEq;
+
+/// Domain separation byte following metadata.
+const METADATA: u8 = 0;
+/// Domain separation byte following plaintext.
+const PLAINTEXT: u8 = 1;
+/// Domain separation byte following the tag, for the keystream.
+const TAG: u8 = 2;
+
+/// Deck-SIV deterministic authenticated encryption, with `TAG_LEN` byte tags.
+///
+/// The single-message counterpart of [`DeckSanse`](crate::DeckSanse): the
+/// tag is `F(P || 1 ∘ A || 0)` and serves as synthetic IV, and the ciphertext
+/// is the plaintext xored with the keystream `F(T || 2 ∘ A || 0)`, with `A`
+/// the metadata, `P` the plaintext and `T` the tag. There is no nonce, so
+/// wrapping the same message twice gives the same ciphertext. This suits key
+/// wrapping and de-duplicating storage, where that is either harmless or
+/// wanted.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use crypto_permutation::DeckFunction;
+/// use deck_modes::DeckSiv;
+///
+/// let siv = DeckSiv::<Xoofff>::new(Xoofff::init(&[0x42; 32]));
+/// let mut key = [0x17; 32];
+/// let tag = siv.wrap(b"key id", &mut key).unwrap();
+/// siv.unwrap(b"key id", &mut key, &tag).unwrap();
+/// assert_eq!(key, [0x17; 32]);
+/// ```
+#[derive(Clone)]
+pub struct DeckSiv<F, const TAG_LEN: usize = 32> {
+    deck: F,
+}
+
+impl<F, const TAG_LEN: usize> core::fmt::Debug for DeckSiv<F, TAG_LEN> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DeckSiv")
+            .field("tag_len", &TAG_LEN)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction + Clone, const TAG_LEN: usize> DeckSiv<F, TAG_LEN> {
+    /// Create the scheme keyed by the deck function `deck`.
+    pub fn new(deck: F) -> Self {
+        Self { deck }
+    }
+
+    /// The deck function after absorbing `metadata`.
+    fn absorb(&self, metadata: &[u8]) -> Result<F, Error> {
+        let mut deck = self.deck.clone();
+        input_parts(&mut deck, &[metadata], METADATA)?;
+        Ok(deck)
+    }
+
+    /// The tag for `plaintext`, given the absorbed metadata.
+    fn tag(prefix: &F, plaintext: &[u8]) -> Result<Tag<TAG_LEN>, Error> {
+        let mut deck = prefix.clone();
+        input_parts(&mut deck, &[plaintext], PLAINTEXT)?;
+        Ok(output_at(&deck, 0)?.into())
+    }
+
+    /// Xor the keystream derived from `tag` and the absorbed metadata into
+    /// `data`.
+    fn xor_keystream(prefix: &F, tag: &Tag<TAG_LEN>, data: &mut [u8]) -> Result<(), Error> {
+        let mut deck = prefix.clone();
+        input_parts(&mut deck, &[tag.as_ref()], TAG)?;
+        xor_output(&mut deck.output_reader(), data)?;
+        Ok(())
+    }
+
+    /// Encrypt `data` in place and authenticate it together with `metadata`.
+    /// Returns the tag.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the input, leaving the
+    /// contents of `data` unspecified.
+    pub fn wrap(&self, metadata: &[u8], data: &mut [u8]) -> Result<Tag<TAG_LEN>, Error> {
+        let prefix = self.absorb(metadata)?;
+        let tag = Self::tag(&prefix, data)?;
+        Self::xor_keystream(&prefix, &tag, data)?;
+        Ok(tag)
+    }
+
+    /// Decrypt `data` in place and verify `tag` for the plaintext and
+    /// `metadata`.
+    ///
+    /// The tag is compared in constant time. When it doesn't match, `data` is
+    /// encrypted again, so the unverified plaintext is not released.
+    ///
+    /// # Errors
+    /// Errors with [`Error::TagMismatch`] when the tag doesn't match, leaving
+    /// `data` unchanged. Errors when the deck function does not accept the
+    /// input, leaving the contents of `data` unspecified.
+    pub fn unwrap(
+        &self,
+        metadata: &[u8],
+        data: &mut [u8],
+        tag: &Tag<TAG_LEN>,
+    ) -> Result<(), Error> {
+        let prefix = self.absorb(metadata)?;
+        Self::xor_keystream(&prefix, tag, data)?;
+        if !bool::from(Self::tag(&prefix, data)?.ct_eq(tag)) {
+            Self::xor_keystream(&prefix, tag, data)?;
+            return Err(Error::TagMismatch);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeckSiv;
+    use crate::Error;
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::xoofff::Xoofff;
+
+    fn siv() -> DeckSiv<Xoofff> {
+        DeckSiv::new(Xoofff::init(&[0x5a; 32]))
+    }
+
+    #[test]
+    fn roundtrip() {
+        let siv = siv();
+        for len in [0, 1, 32, 48, 100] {
+            let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let mut data = plaintext.clone();
+            let tag = siv.wrap(b"metadata", &mut data).unwrap();
+            if len > 0 {
+                assert_ne!(data, plaintext);
+            }
+            siv.unwrap(b"metadata", &mut data, &tag).unwrap();
+            assert_eq!(data, plaintext);
+        }
+    }
+
+    /// Equal messages give equal ciphertexts, and a change in either the
+    /// metadata or the plaintext changes both the tag and the ciphertext.
+    #[test]
+    fn determinism() {
+        let siv = siv();
+        let mut data = *b"same plaintext";
+        let tag = siv.wrap(b"", &mut data).unwrap();
+        let mut again = *b"same plaintext";
+        assert_eq!(siv.wrap(b"", &mut again).unwrap(), tag);
+        assert_eq!(again, data);
+
+        let mut other = *b"same plaintext";
+        let other_tag = siv.wrap(b"metadata", &mut other).unwrap();
+        assert_ne!(other_tag, tag);
+        assert_ne!(other, data);
+
+        let mut other = *b"same plaintexu";
+        let other_tag = siv.wrap(b"", &mut other).unwrap();
+        assert_ne!(other_tag, tag);
+        assert_ne!(other[..13], data[..13]);
+    }
+
+    #[test]
+    fn forgery_rejected() {
+        let siv = siv();
+        let mut data = *b"attack at dawn";
+        let tag = siv.wrap(b"metadata", &mut data).unwrap();
+        let ciphertext = data;
+
+        data[0] ^= 1;
+        let result = siv.unwrap(b"metadata", &mut data, &tag);
+        assert!(matches!(result, Err(Error::TagMismatch)));
+        data[0] ^= 1;
+        assert_eq!(data, ciphertext);
+
+        let mut forged_tag = tag.into_bytes();
+        forged_tag[31] ^= 1;
+        let result = siv.unwrap(b"metadata", &mut data, &forged_tag.into());
+        assert!(matches!(result, Err(Error::TagMismatch)));
+        assert_eq!(data, ciphertext);
+        let result = siv.unwrap(b"other", &mut data, &tag);
+        assert!(matches!(result, Err(Error::TagMismatch)));
+        assert_eq!(data, ciphertext);
+
+        siv.unwrap(b"metadata", &mut data, &tag).unwrap();
+        assert_eq!(&data, b"attack at dawn");
+    }
+}
@@ -0,0 +1,196 @@
+//! Implementations of the [`cipher`] crate stream cipher traits.
+
+use crate::RekeyingKeystream;
+use cipher::consts::{U16, U32};
+use cipher::inout::InOutBuf;
+use cipher::{
+    Iv, IvSizeUser, Key, KeyIvInit, KeySizeUser, OverflowError, SeekNum, StreamCipher,
+    StreamCipherError, StreamCipherSeek,
+};
+use crypto_permutation::{DeckFunction, Reader, Writer};
+
+/// Stream cipher for the [`cipher`] crate traits, xoring the keystream of a
+/// deck function into the data.
+///
+/// The keystream is the output of the deck function on the 16 byte nonce
+/// (IV). It is seekable, so it can replace e.g. ChaCha20 wherever the
+/// [`StreamCipher`] and [`StreamCipherSeek`] traits are accepted.
+///
+/// # Crypto
+/// The stream cipher is not authenticated, and a nonce must never be used
+/// twice under the same key.
+///
+/// ```
+/// use cipher::{KeyIvInit, StreamCipher, StreamCipherSeek};
+/// use deck_farfalle::xoofff::Xoofff;
+/// use deck_modes::DeckStreamCipher;
+///
+/// let key = [0x42; 32].into();
+/// let nonce = [0x17; 16].into();
+/// let mut data = *b"hello world";
+/// DeckStreamCipher::<Xoofff>::new(&key, &nonce).apply_keystream(&mut data);
+///
+/// let mut cipher = DeckStreamCipher::<Xoofff>::new(&key, &nonce);
+/// cipher.seek(6);
+/// cipher.apply_keystream(&mut data[6..]);
+/// assert_eq!(&data[6..], b"world");
+/// ```
+pub struct DeckStreamCipher<F: DeckFunction> {
+    deck: F,
+    keystream: F::OutputGenerator,
+    /// Position in the keystream in bytes.
+    pos: u64,
+}
+
+impl<F: DeckFunction> core::fmt::Debug for DeckStreamCipher<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DeckStreamCipher")
+            .field("pos", &self.pos)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction> KeySizeUser for DeckStreamCipher<F> {
+    type KeySize = U32;
+}
+
+impl<F: DeckFunction> IvSizeUser for DeckStreamCipher<F> {
+    type IvSize = U16;
+}
+
+impl<F: DeckFunction> KeyIvInit for DeckStreamCipher<F> {
+    fn new(key: &Key<Self>, iv: &Iv<Self>) -> Self {
+        let mut deck = F::init(key.as_ref());
+        let mut writer = deck.input_writer();
+        writer
+            .write_bytes(iv)
+            .expect("deck function accepts a 16 byte input");
+        writer.finish();
+        let keystream = deck.output_reader();
+        Self {
+            deck,
+            keystream,
+            pos: 0,
+        }
+    }
+}
+
+impl<F: DeckFunction> StreamCipher for DeckStreamCipher<F> {
+    fn try_apply_keystream_inout(
+        &mut self,
+        mut buf: InOutBuf<'_, '_, u8>,
+    ) -> Result<(), StreamCipherError> {
+        const CHUNK: usize = 64;
+        let mut keystream = [0; CHUNK];
+        while !buf.is_empty() {
+            let len = buf.len().min(CHUNK);
+            let (mut chunk, rest) = buf.split_at(len);
+            self.keystream
+                .write_to_slice(&mut keystream[..len])
+                .map_err(|_| StreamCipherError)?;
+            chunk.xor_in2out(&keystream[..len]);
+            self.pos += len as u64;
+            buf = rest;
+        }
+        Ok(())
+    }
+}
+
+impl<F: DeckFunction> StreamCipherSeek for DeckStreamCipher<F> {
+    fn try_current_pos<T: SeekNum>(&self) -> Result<T, OverflowError> {
+        T::from_block_byte(self.pos, 0, 1)
+    }
+
+    fn try_seek<T: SeekNum>(&mut self, pos: T) -> Result<(), StreamCipherError> {
+        let (pos, _): (u64, u8) = pos.into_block_byte(1).map_err(|_| StreamCipherError)?;
+        let mut keystream = self.deck.output_reader();
+        keystream
+            .skip(usize::try_from(pos).map_err(|_| StreamCipherError)?)
+            .map_err(|_| StreamCipherError)?;
+        self.keystream = keystream;
+        self.pos = pos;
+        Ok(())
+    }
+}
+
+/// Not seekable, as the keys of earlier epochs are erased.
+impl<F: DeckFunction, const EPOCH_LEN: usize> StreamCipher for RekeyingKeystream<F, EPOCH_LEN> {
+    fn try_apply_keystream_inout(
+        &mut self,
+        mut buf: InOutBuf<'_, '_, u8>,
+    ) -> Result<(), StreamCipherError> {
+        const CHUNK: usize = 64;
+        while !buf.is_empty() {
+            let len = buf.len().min(CHUNK);
+            let (mut chunk, rest) = buf.split_at(len);
+            let mut keystream = [0; CHUNK];
+            self.apply_keystream(&mut keystream[..len])
+                .map_err(|_| StreamCipherError)?;
+            chunk.xor_in2out(&keystream[..len]);
+            buf = rest;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeckStreamCipher;
+    use crate::RekeyingKeystream;
+    use cipher::{KeyIvInit, StreamCipher, StreamCipherSeek};
+    use crypto_permutation::{DeckFunction, Reader, Writer};
+    use deck_farfalle::xoofff::Xoofff;
+
+    const KEY: [u8; 32] = [0x5c; 32];
+    const NONCE: [u8; 16] = [0x01; 16];
+
+    fn cipher() -> DeckStreamCipher<Xoofff> {
+        DeckStreamCipher::new(&KEY.into(), &NONCE.into())
+    }
+
+    /// The keystream is the output of the deck function on the nonce.
+    #[test]
+    fn keystream() {
+        let mut data = [0; 200];
+        let mut cipher = cipher();
+        cipher.apply_keystream(&mut data[..7]);
+        cipher.apply_keystream(&mut data[7..]);
+        assert_eq!(cipher.current_pos::<u64>(), 200);
+
+        let mut deck = Xoofff::init(&KEY);
+        let mut writer = deck.input_writer();
+        writer.write_bytes(&NONCE).unwrap();
+        writer.finish();
+        let mut expected = [0; 200];
+        deck.output_reader().write_to_slice(&mut expected).unwrap();
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn seek() {
+        let mut data = [0; 100];
+        cipher().apply_keystream(&mut data);
+
+        let mut cipher = cipher();
+        for pos in [99u32, 0, 50, 64] {
+            let mut byte = [0];
+            cipher.seek(pos);
+            cipher.apply_keystream(&mut byte);
+            assert_eq!(byte[0], data[pos as usize]);
+            assert_eq!(cipher.current_pos::<u32>(), pos + 1);
+        }
+    }
+
+    #[test]
+    fn rekeying_keystream() {
+        let mut data = [0; 200];
+        let mut keystream = RekeyingKeystream::<Xoofff, 100>::new(&KEY, &NONCE).unwrap();
+        StreamCipher::apply_keystream(&mut keystream, &mut data);
+        let mut expected = [0; 200];
+        RekeyingKeystream::<Xoofff, 100>::new(&KEY, &NONCE)
+            .unwrap()
+            .apply_keystream(&mut expected)
+            .unwrap();
+        assert_eq!(data, expected);
+    }
+}
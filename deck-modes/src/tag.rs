@@ -0,0 +1,109 @@
+//! Authentication tags.
+
+use subtle::{Choice, ConstantTimeEq};
+
+/// Authentication tag of `N` bytes, as returned by the authenticated
+/// encryption modes.
+///
+/// Equality is checked in constant time, both by [`ConstantTimeEq`] and by
+/// [`PartialEq`], so comparing tags doesn't leak the position of the first
+/// differing byte through timing. Received tags are converted from bytes
+/// with [`From`] or [`TryFrom`], and tags are converted to bytes with
+/// [`AsRef`] or [`Self::into_bytes`]. [`Display`](core::fmt::Display) formats
+/// the tag as lowercase hex.
+///
+/// ```
+/// use deck_modes::Tag;
+///
+/// let tag = Tag::<4>::try_from(&[0xde, 0xad, 0xbe, 0xef][..]).unwrap();
+/// assert_eq!(tag.to_string(), "deadbeef");
+/// assert_eq!(tag, Tag::from([0xde, 0xad, 0xbe, 0xef]));
+/// assert!(Tag::<4>::try_from(&[0; 3][..]).is_err());
+/// ```
+#[derive(Clone, Copy)]
+pub struct Tag<const N: usize> {
+    bytes: [u8; N],
+}
+
+impl<const N: usize> Tag<N> {
+    /// The bytes of the tag.
+    pub fn into_bytes(self) -> [u8; N] {
+        self.bytes
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for Tag<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        Self { bytes }
+    }
+}
+
+impl<const N: usize> TryFrom<&[u8]> for Tag<N> {
+    type Error = core::array::TryFromSliceError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            bytes: bytes.try_into()?,
+        })
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for Tag<N> {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl<const N: usize> ConstantTimeEq for Tag<N> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.bytes.ct_eq(&other.bytes)
+    }
+}
+
+impl<const N: usize> PartialEq for Tag<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<const N: usize> Eq for Tag<N> {}
+
+impl<const N: usize> core::fmt::Display for Tag<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for byte in &self.bytes {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> core::fmt::Debug for Tag<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Tag({self})")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tag;
+    use subtle::ConstantTimeEq;
+
+    #[test]
+    fn equality() {
+        let tag = Tag::from([1, 2, 3]);
+        assert!(bool::from(tag.ct_eq(&Tag::from([1, 2, 3]))));
+        assert!(!bool::from(tag.ct_eq(&Tag::from([1, 2, 4]))));
+        assert_eq!(tag, Tag::from([1, 2, 3]));
+        assert_ne!(tag, Tag::from([0, 2, 3]));
+    }
+
+    #[test]
+    fn conversions() {
+        let tag = Tag::<3>::try_from(&[1, 2, 3][..]).unwrap();
+        assert_eq!(tag.as_ref(), [1, 2, 3]);
+        assert_eq!(tag.into_bytes(), [1, 2, 3]);
+        assert!(Tag::<3>::try_from(&[1, 2, 3, 4][..]).is_err());
+        assert_eq!(format!("{tag}"), "010203");
+        assert_eq!(format!("{tag:?}"), "Tag(010203)");
+    }
+}
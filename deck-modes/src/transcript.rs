@@ -0,0 +1,178 @@
+//! Fiat-Shamir transcripts.
+
+use super::{input_parts, Error};
+use crypto_permutation::{DeckFunction, Reader};
+
+/// Domain separation byte of the protocol label given to [`Transcript::new`].
+const PROTOCOL: u8 = 0;
+/// Domain separation byte of appended messages.
+const MESSAGE: u8 = 1;
+/// Domain separation byte of challenges.
+const CHALLENGE: u8 = 2;
+
+/// Transcript of a public-coin protocol, for the Fiat-Shamir transform, in
+/// the style of Merlin.
+///
+/// The prover and verifier both append the protocol messages to the
+/// transcript, and derive the verifier's challenges from it. Every operation
+/// inputs a single string into the deck function, consisting of the length
+/// of the label as a 4 byte little endian number, the label, and either the
+/// message or the 8 byte little endian length of the challenge. Hence the
+/// transcript is unambiguous, and a challenge depends on all operations
+/// before it, including earlier challenges.
+///
+/// # Crypto
+/// A transcript of a non-interactive proof uses a public key. The security of
+/// the Fiat-Shamir transform then relies on the deck function behaving like a
+/// random oracle even though its key is known. This is not part of the
+/// security claims of Farfalle: its parallel compression layer is not
+/// designed for collision resistance under a known key. Use a secret key
+/// shared by the parties where the protocol allows it.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use crypto_permutation::DeckFunction;
+/// use deck_modes::Transcript;
+///
+/// let mut prover = Transcript::new(Xoofff::init(&[0; 32]), b"schnorr proof").unwrap();
+/// prover.append_message(b"commitment", b"...").unwrap();
+/// let mut challenge = [0; 32];
+/// prover.challenge_bytes(b"challenge", &mut challenge).unwrap();
+///
+/// let mut verifier = Transcript::new(Xoofff::init(&[0; 32]), b"schnorr proof").unwrap();
+/// verifier.append_message(b"commitment", b"...").unwrap();
+/// let mut expected = [0; 32];
+/// verifier.challenge_bytes(b"challenge", &mut expected).unwrap();
+/// assert_eq!(challenge, expected);
+/// ```
+#[derive(Clone)]
+pub struct Transcript<F> {
+    deck: F,
+}
+
+impl<F> core::fmt::Debug for Transcript<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Transcript").finish_non_exhaustive()
+    }
+}
+
+/// Input the string `len(label) || label || data || trailer` into `deck`.
+fn input_labeled<F: DeckFunction>(
+    deck: &mut F,
+    label: &[u8],
+    data: &[u8],
+    trailer: u8,
+) -> Result<(), Error> {
+    let label_len = u32::try_from(label.len()).expect("label shorter than 4 GiB");
+    input_parts(deck, &[&label_len.to_le_bytes(), label, data], trailer)?;
+    Ok(())
+}
+
+impl<F: DeckFunction + Clone> Transcript<F> {
+    /// Start a transcript for the protocol named `label`, using the keyed deck
+    /// function `deck`.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the label.
+    pub fn new(mut deck: F, label: &[u8]) -> Result<Self, Error> {
+        input_labeled(&mut deck, label, &[], PROTOCOL)?;
+        Ok(Self { deck })
+    }
+
+    /// Append `message` with label `label` to the transcript.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the message. The
+    /// transcript is left unchanged.
+    pub fn append_message(&mut self, label: &[u8], message: &[u8]) -> Result<(), Error> {
+        let mut deck = self.deck.clone();
+        input_labeled(&mut deck, label, message, MESSAGE)?;
+        self.deck = deck;
+        Ok(())
+    }
+
+    /// Append the number `value` with label `label` to the transcript, as 8
+    /// little endian bytes.
+    ///
+    /// # Errors
+    /// See [`Self::append_message`].
+    pub fn append_u64(&mut self, label: &[u8], value: u64) -> Result<(), Error> {
+        self.append_message(label, &value.to_le_bytes())
+    }
+
+    /// Fill `out` with the challenge with label `label`, and append the
+    /// request for it to the transcript.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the request or can't
+    /// generate `out.len()` bytes. The transcript is left unchanged.
+    pub fn challenge_bytes(&mut self, label: &[u8], out: &mut [u8]) -> Result<(), Error> {
+        let mut deck = self.deck.clone();
+        input_labeled(
+            &mut deck,
+            label,
+            &(out.len() as u64).to_le_bytes(),
+            CHALLENGE,
+        )?;
+        deck.output_reader().write_to_slice(out)?;
+        self.deck = deck;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transcript;
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::xoofff::Xoofff;
+
+    fn transcript(messages: &[(&[u8], &[u8])]) -> Transcript<Xoofff> {
+        let mut transcript = Transcript::new(Xoofff::init(&[0; 32]), b"test").unwrap();
+        for (label, message) in messages {
+            transcript.append_message(label, message).unwrap();
+        }
+        transcript
+    }
+
+    fn challenge(transcript: &mut Transcript<Xoofff>) -> [u8; 32] {
+        let mut challenge = [0; 32];
+        transcript
+            .challenge_bytes(b"challenge", &mut challenge)
+            .unwrap();
+        challenge
+    }
+
+    /// Moving bytes between labels and messages, or splitting messages,
+    /// changes the challenge.
+    #[test]
+    fn framing() {
+        let expected = challenge(&mut transcript(&[(b"label", b"message")]));
+        assert_eq!(
+            challenge(&mut transcript(&[(b"label", b"message")])),
+            expected
+        );
+        let other: [&[(&[u8], &[u8])]; 4] = [
+            &[(b"labelm", b"essage")],
+            &[(b"label", b"mess"), (b"", b"age")],
+            &[(b"label", b"message"), (b"", b"")],
+            &[(b"label", b"messagf")],
+        ];
+        for messages in other {
+            assert_ne!(challenge(&mut transcript(messages)), expected);
+        }
+    }
+
+    /// Challenges depend on earlier challenges and on their length.
+    #[test]
+    fn challenges() {
+        let mut transcript = transcript(&[]);
+        let first = challenge(&mut transcript);
+        assert_ne!(challenge(&mut transcript), first);
+
+        let mut short = [0; 16];
+        self::transcript(&[])
+            .challenge_bytes(b"challenge", &mut short)
+            .unwrap();
+        assert_ne!(short, first[..16]);
+    }
+}
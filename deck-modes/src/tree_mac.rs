@@ -0,0 +1,179 @@
+//! Tree-parallel message authentication.
+
+use super::{input_parts, output_at, Error, Tag};
+use crypto_permutation::{DeckFunction, Writer};
+
+/// Domain separation byte of messages of at most one chunk.
+const SINGLE: u8 = 0;
+/// Domain separation byte of the chunks of longer messages.
+const LEAF: u8 = 1;
+/// Domain separation byte of the string of chaining values.
+const ROOT: u8 = 2;
+
+/// Length in bytes of the chunks of [`DeckTreeMac`].
+pub const TREE_CHUNK_LEN: usize = 8192;
+/// Length in bytes of the chaining values of [`DeckTreeMac`].
+const CV_LEN: usize = 32;
+
+/// Message authentication code with a tree mode like KangarooTwelve, so that
+/// the chunks of long messages can be processed in parallel.
+///
+/// A message of at most [`TREE_CHUNK_LEN`] bytes `M` has tag `F(M || 0)`.
+/// Longer messages are split into chunks `M_0, ..., M_{n-1}` of
+/// [`TREE_CHUNK_LEN`] bytes, except for the last one. Each chunk gives a
+/// 32 byte chaining value `CV_i = F(M_i || 1)`, and the tag is
+/// `F(CV_0 || ... || CV_{n-1} || n || 2)`, with `n` encoded as 8 little
+/// endian bytes. The tag doesn't depend on how the chunks are scheduled, so
+/// [`Self::tag`] and, with the `rayon` feature, `Self::par_tag` give the same
+/// result.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use crypto_permutation::DeckFunction;
+/// use deck_modes::DeckTreeMac;
+///
+/// let mac = DeckTreeMac::new(Xoofff::init(&[0x42; 32]));
+/// let file = vec![0x17; 100_000];
+/// let tag = mac.tag::<32>(&file).unwrap();
+/// ```
+#[derive(Clone)]
+pub struct DeckTreeMac<F> {
+    deck: F,
+}
+
+impl<F> core::fmt::Debug for DeckTreeMac<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DeckTreeMac").finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction + Clone> DeckTreeMac<F> {
+    /// Create the MAC keyed by the deck function `deck`.
+    pub fn new(deck: F) -> Self {
+        Self { deck }
+    }
+
+    /// The tag of a message of at most one chunk.
+    fn single<const N: usize>(&self, message: &[u8]) -> Result<Tag<N>, Error> {
+        let mut deck = self.deck.clone();
+        input_parts(&mut deck, &[message], SINGLE)?;
+        Ok(output_at(&deck, 0)?.into())
+    }
+
+    /// The chaining value of `chunk`.
+    fn leaf(&self, chunk: &[u8]) -> Result<[u8; CV_LEN], Error> {
+        let mut deck = self.deck.clone();
+        input_parts(&mut deck, &[chunk], LEAF)?;
+        Ok(output_at(&deck, 0)?)
+    }
+
+    /// The tag from the chaining values `cvs` of all `count` chunks.
+    fn root<const N: usize>(
+        &self,
+        cvs: impl Iterator<Item = Result<[u8; CV_LEN], Error>>,
+        count: usize,
+    ) -> Result<Tag<N>, Error> {
+        let mut deck = self.deck.clone();
+        let mut writer = deck.input_writer();
+        for cv in cvs {
+            writer.write_bytes(&cv?)?;
+        }
+        writer.write_bytes(&(count as u64).to_le_bytes())?;
+        writer.write_bytes(&[ROOT])?;
+        writer.finish();
+        Ok(output_at(&deck, 0)?.into())
+    }
+
+    /// The `N` byte tag of `message`, processing its chunks sequentially.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the input.
+    pub fn tag<const N: usize>(&self, message: &[u8]) -> Result<Tag<N>, Error> {
+        if message.len() <= TREE_CHUNK_LEN {
+            return self.single(message);
+        }
+        let chunks = message.chunks(TREE_CHUNK_LEN);
+        let count = chunks.len();
+        self.root(chunks.map(|chunk| self.leaf(chunk)), count)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<F: DeckFunction + Clone + Sync> DeckTreeMac<F> {
+    /// The `N` byte tag of `message`, processing its chunks in parallel on
+    /// the rayon thread pool.
+    ///
+    /// # Errors
+    /// Errors when the deck function does not accept the input.
+    pub fn par_tag<const N: usize>(&self, message: &[u8]) -> Result<Tag<N>, Error> {
+        use rayon::prelude::*;
+
+        if message.len() <= TREE_CHUNK_LEN {
+            return self.single(message);
+        }
+        let cvs: alloc::vec::Vec<_> = message
+            .par_chunks(TREE_CHUNK_LEN)
+            .map(|chunk| self.leaf(chunk))
+            .collect();
+        let count = cvs.len();
+        self.root(cvs.into_iter(), count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DeckTreeMac, TREE_CHUNK_LEN};
+    use crate::Tag;
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::xoofff::Xoofff;
+
+    fn mac() -> DeckTreeMac<Xoofff> {
+        DeckTreeMac::new(Xoofff::init(&[0x3e; 32]))
+    }
+
+    fn message(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    /// Every length gives a different tag, also around chunk boundaries.
+    #[test]
+    fn lengths() {
+        let lengths = [
+            0,
+            1,
+            TREE_CHUNK_LEN - 1,
+            TREE_CHUNK_LEN,
+            TREE_CHUNK_LEN + 1,
+            2 * TREE_CHUNK_LEN,
+            3 * TREE_CHUNK_LEN + 5,
+        ];
+        let tags: Vec<Tag<32>> = lengths
+            .iter()
+            .map(|&len| mac().tag(&message(len)).unwrap())
+            .collect();
+        for (i, tag) in tags.iter().enumerate() {
+            assert!(!tags[i + 1..].contains(tag));
+        }
+    }
+
+    /// Swapping chunks changes the tag.
+    #[test]
+    fn chunk_order() {
+        let mut message = message(2 * TREE_CHUNK_LEN);
+        let tag = mac().tag::<16>(&message).unwrap();
+        message.rotate_left(TREE_CHUNK_LEN);
+        assert_ne!(mac().tag::<16>(&message).unwrap(), tag);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel() {
+        for len in [0, TREE_CHUNK_LEN, 10 * TREE_CHUNK_LEN + 7] {
+            let message = message(len);
+            assert_eq!(
+                mac().par_tag::<32>(&message).unwrap(),
+                mac().tag::<32>(&message).unwrap()
+            );
+        }
+    }
+}
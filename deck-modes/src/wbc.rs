@@ -0,0 +1,332 @@
+//! Deck-WBC: tweakable wide block cipher.
+
+use super::{input_suffixed, xor_output, Error};
+use crypto_permutation::DeckFunction;
+
+/// Deck-WBC tweakable wide block cipher.
+///
+/// Enciphers blocks of any length of at least [`Self::MIN_LEN`] bytes, such
+/// that every ciphertext byte depends on every plaintext byte and on the
+/// tweak. The block is split into halves `L` and `R`, which go through a four
+/// round Feistel network
+/// ```text
+/// R₀ ← R₀ + H(L || 0)
+/// L ← L + G(R || 1, W)
+/// R ← R + G(L || 0, W)
+/// L₀ ← L₀ + H(R || 1)
+/// ```
+/// with `W` the tweak and `|| 0` and `|| 1` appending a single bit. Both `H`
+/// and `G` are the deck function; `G` takes the tweak as an additional input
+/// string. `L₀` and `R₀` are the leading bytes of the halves given by
+/// [`WbcSplit::first_block_len`].
+///
+/// The [`WbcSplit`] `S` determines the length of `L` and of `L₀` and `R₀`.
+/// With [`FarfalleSplit`] this is the Farfalle-WBC construction. By default
+/// it is [`HalfSplit`], for which `L` is the first `⌊n / 2⌋` bytes of an `n`
+/// byte block and the `H` rounds mask the full halves, which works for deck
+/// functions of unknown block size but is not Farfalle-WBC.
+///
+/// # Crypto
+/// Deck-WBC is not authenticated: any ciphertext deciphers to some
+/// plaintext; use [`DeckWbcAe`](crate::DeckWbcAe) for authenticated
+/// encryption. Short blocks offer little security, since an attacker can
+/// guess a half.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use crypto_permutation::DeckFunction;
+/// use deck_modes::DeckWbc;
+///
+/// let wbc = DeckWbc::<Xoofff>::new(Xoofff::init(&[0x42; 32]));
+/// let mut block = *b"sector contents";
+/// wbc.encrypt(b"sector 7", &mut block).unwrap();
+/// wbc.decrypt(b"sector 7", &mut block).unwrap();
+/// assert_eq!(&block, b"sector contents");
+/// ```
+#[derive(Clone)]
+pub struct DeckWbc<F, S = HalfSplit> {
+    deck: F,
+    split: S,
+}
+
+impl<F, S: core::fmt::Debug> core::fmt::Debug for DeckWbc<F, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DeckWbc")
+            .field("split", &self.split)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Split of a block into the halves `L` and `R` of [`DeckWbc`].
+pub trait WbcSplit: Copy + Default {
+    /// Length of `L` for a block of `len >= 2` bytes. Must be at least 1 and
+    /// at most `len - 1`.
+    fn left_len(self, len: usize) -> usize;
+
+    /// Number of leading bytes of a half that the `H` rounds mask, or `None`
+    /// to mask the full half. Must not be zero.
+    fn first_block_len(self) -> Option<usize> {
+        None
+    }
+}
+
+/// Split a block in halves, `L` being the shorter one for odd lengths.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct HalfSplit;
+
+impl WbcSplit for HalfSplit {
+    fn left_len(self, len: usize) -> usize {
+        len / 2
+    }
+}
+
+/// The split function of Farfalle-WBC, for a deck function absorbing blocks
+/// of `BLOCK` bytes.
+///
+/// Blocks of at most two permutation blocks are split in halves, with `L`
+/// the longer one for odd lengths. For longer blocks, `L` followed by its
+/// domain separation bit and the padding fills the largest power of two
+/// number of permutation blocks that is shorter than the block, so that `L`
+/// is absorbed without an extra block. The `H` rounds mask only the first
+/// permutation block of a half.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct FarfalleSplit<const BLOCK: usize>;
+
+impl<const BLOCK: usize> WbcSplit for FarfalleSplit<BLOCK> {
+    fn left_len(self, len: usize) -> usize {
+        if len <= 2 * BLOCK {
+            return (len + 1) / 2;
+        }
+        let mut blocks = 2;
+        while 2 * blocks * BLOCK < len {
+            blocks *= 2;
+        }
+        blocks * BLOCK - 1
+    }
+
+    fn first_block_len(self) -> Option<usize> {
+        Some(BLOCK)
+    }
+}
+
+impl<F: DeckFunction + Clone, S: WbcSplit> DeckWbc<F, S> {
+    /// Minimum length of a block in bytes, such that both halves are
+    /// non-empty.
+    pub const MIN_LEN: usize = 2;
+
+    /// Create the wide block cipher keyed by the deck function `deck`.
+    pub fn new(deck: F) -> Self {
+        Self {
+            deck,
+            split: S::default(),
+        }
+    }
+
+    /// Xor the output of the deck function on `input || bit`, preceded by the
+    /// string `tweak` if given, into `target`.
+    fn round(
+        &self,
+        tweak: Option<&[u8]>,
+        input: &[u8],
+        bit: u8,
+        target: &mut [u8],
+    ) -> Result<(), Error> {
+        let mut deck = self.deck.clone();
+        if let Some(tweak) = tweak {
+            input_suffixed(&mut deck, &[tweak], 0, 0)?;
+        }
+        input_suffixed(&mut deck, &[input], bit, 1)?;
+        xor_output(&mut deck.output_reader(), target)?;
+        Ok(())
+    }
+
+    /// Split `block` in its halves `L` and `R`.
+    fn split<'a>(&self, block: &'a mut [u8]) -> Result<(&'a mut [u8], &'a mut [u8]), Error> {
+        if block.len() < Self::MIN_LEN {
+            return Err(Error::BlockTooShort);
+        }
+        Ok(block.split_at_mut(self.split.left_len(block.len())))
+    }
+
+    /// The leading bytes of `half` masked by the `H` rounds.
+    fn first_block<'a>(&self, half: &'a mut [u8]) -> &'a mut [u8] {
+        match self.split.first_block_len() {
+            Some(len) if len < half.len() => &mut half[..len],
+            _ => half,
+        }
+    }
+
+    /// Encipher `block` in place under `tweak`.
+    ///
+    /// # Errors
+    /// Errors with [`Error::BlockTooShort`] when `block` is shorter than
+    /// [`Self::MIN_LEN`], leaving it unchanged. Errors when the deck function
+    /// does not accept the input, leaving the contents of `block` unspecified.
+    pub fn encrypt(&self, tweak: &[u8], block: &mut [u8]) -> Result<(), Error> {
+        let (left, right) = self.split(block)?;
+        self.round(None, left, 0, self.first_block(right))?;
+        self.round(Some(tweak), right, 1, left)?;
+        self.round(Some(tweak), left, 0, right)?;
+        self.round(None, right, 1, self.first_block(left))
+    }
+
+    /// Decipher `block` in place under `tweak`.
+    ///
+    /// # Errors
+    /// See [`Self::encrypt`].
+    pub fn decrypt(&self, tweak: &[u8], block: &mut [u8]) -> Result<(), Error> {
+        let (left, right) = self.split(block)?;
+        self.round(None, right, 1, self.first_block(left))?;
+        self.round(Some(tweak), left, 0, right)?;
+        self.round(Some(tweak), right, 1, left)?;
+        self.round(None, left, 0, self.first_block(right))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DeckWbc, FarfalleSplit, HalfSplit, WbcSplit};
+    use crate::Error;
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::xoofff::Xoofff;
+
+    fn wbc() -> DeckWbc<Xoofff> {
+        DeckWbc::new(Xoofff::init(&[0x3c; 32]))
+    }
+
+    #[test]
+    fn roundtrip() {
+        let wbc = wbc();
+        for len in [2, 3, 16, 47, 48, 49, 200, 1000] {
+            let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let mut block = plaintext.clone();
+            wbc.encrypt(b"tweak", &mut block).unwrap();
+            assert_ne!(block, plaintext);
+            wbc.decrypt(b"tweak", &mut block).unwrap();
+            assert_eq!(block, plaintext, "length: {len}");
+        }
+    }
+
+    fn equal_bytes(a: &[u8], b: &[u8]) -> usize {
+        a.iter().zip(b.iter()).filter(|(a, b)| a == b).count()
+    }
+
+    /// Changing any byte of the plaintext or the tweak changes all of the
+    /// ciphertext.
+    #[test]
+    fn diffusion() {
+        let wbc = wbc();
+        let mut block = [0; 100];
+        wbc.encrypt(b"tweak", &mut block).unwrap();
+
+        for changed in [0, 49, 50, 99] {
+            let mut other = [0; 100];
+            other[changed] = 1;
+            wbc.encrypt(b"tweak", &mut other).unwrap();
+            assert!(equal_bytes(&block, &other) < 10);
+        }
+
+        let mut other = [0; 100];
+        wbc.encrypt(b"tweal", &mut other).unwrap();
+        assert!(equal_bytes(&block, &other) < 10);
+    }
+
+    /// Xor the output of the deck function on `input || bit`, preceded by
+    /// `tweak` if given, into `target`, finishing the inputs bit by bit.
+    fn round(tweak: Option<&[u8]>, input: &[u8], bit: u8, target: &mut [u8]) {
+        use crypto_permutation::io::FinishWithSuffix;
+        use crypto_permutation::{Reader, Writer};
+
+        let mut deck = Xoofff::init(&[0x3c; 32]);
+        if let Some(tweak) = tweak {
+            let mut writer = deck.input_writer();
+            writer.write_bytes(tweak).unwrap();
+            writer.finish_with_suffix(0, 0);
+        }
+        let mut writer = deck.input_writer();
+        writer.write_bytes(input).unwrap();
+        writer.finish_with_suffix(bit, 1);
+        let mut output = vec![0; target.len()];
+        deck.output_reader().write_to_slice(&mut output).unwrap();
+        for (byte, key) in target.iter_mut().zip(output) {
+            *byte ^= key;
+        }
+    }
+
+    /// The halves are followed by a single domain separation bit, the tweak by
+    /// none.
+    #[test]
+    fn suffix_bits() {
+        let plaintext = *b"sector contents";
+        let mut expected = plaintext;
+        let (left, right) = expected.split_at_mut(7);
+        round(None, left, 0, right);
+        round(Some(b"tweak"), right, 1, left);
+        round(Some(b"tweak"), left, 0, right);
+        round(None, right, 1, left);
+
+        let mut block = plaintext;
+        wbc().encrypt(b"tweak", &mut block).unwrap();
+        assert_eq!(block, expected);
+    }
+
+    #[test]
+    fn farfalle_split() {
+        let split = FarfalleSplit::<48>;
+        assert_eq!(split.left_len(2), 1);
+        assert_eq!(split.left_len(3), 2);
+        assert_eq!(split.left_len(96), 48);
+        assert_eq!(split.left_len(97), 95);
+        assert_eq!(split.left_len(192), 95);
+        assert_eq!(split.left_len(193), 191);
+        for len in 2..1000 {
+            let left = split.left_len(len);
+            assert!(0 < left && left < len, "length: {len}");
+        }
+        assert_eq!(split.first_block_len(), Some(48));
+        assert_eq!(HalfSplit.first_block_len(), None);
+    }
+
+    /// With [`FarfalleSplit`], the `H` rounds mask only the first block of a
+    /// half.
+    #[test]
+    fn first_block_masking() {
+        let wbc = DeckWbc::<Xoofff, FarfalleSplit<48>>::new(Xoofff::init(&[0x3c; 32]));
+        for len in [20, 150, 300] {
+            let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let mut expected = plaintext.clone();
+            let (left, right) = expected.split_at_mut(FarfalleSplit::<48>.left_len(len));
+            let right_block = right.len().min(48);
+            round(None, left, 0, &mut right[..right_block]);
+            round(Some(b"tweak"), right, 1, left);
+            round(Some(b"tweak"), left, 0, right);
+            let left_block = left.len().min(48);
+            round(None, right, 1, &mut left[..left_block]);
+
+            let mut block = plaintext.clone();
+            wbc.encrypt(b"tweak", &mut block).unwrap();
+            assert_eq!(block, expected, "length: {len}");
+            wbc.decrypt(b"tweak", &mut block).unwrap();
+            assert_eq!(block, plaintext);
+        }
+    }
+
+    #[test]
+    fn too_short() {
+        let wbc = wbc();
+        let mut block = [5];
+        assert!(matches!(
+            wbc.encrypt(b"", &mut block),
+            Err(Error::BlockTooShort)
+        ));
+        assert!(matches!(
+            wbc.decrypt(b"", &mut block),
+            Err(Error::BlockTooShort)
+        ));
+        assert_eq!(block, [5]);
+        assert!(matches!(
+            wbc.encrypt(b"", &mut []),
+            Err(Error::BlockTooShort)
+        ));
+    }
+}
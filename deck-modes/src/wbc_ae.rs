@@ -0,0 +1,161 @@
+//! Deck-WBC-AE: authenticated encryption with minimal expansion.
+
+use super::{DeckWbc, Error, HalfSplit, WbcSplit};
+use crypto_permutation::DeckFunction;
+use subtle::ConstantTimeEq;
+
+/// Deck-WBC-AE authenticated encryption, with `TAG_LEN` bytes of
+/// redundancy.
+///
+/// The plaintext is extended with `TAG_LEN` zero bytes and enciphered with
+/// [`DeckWbc`], using the metadata as tweak and splitting blocks with the
+/// [`WbcSplit`] `S`. Unwrapping deciphers and checks that these bytes are
+/// still zero. With [`FarfalleSplit`](crate::FarfalleSplit) this is the
+/// Farfalle-WBC-AE construction. As the wide block cipher mixes every byte
+/// into every other byte, the ciphertext only expands by `TAG_LEN` bytes, and
+/// there is no nonce.
+/// This suits encrypting e.g. database records, with the record location as
+/// metadata.
+///
+/// Wrapping the same plaintext with the same metadata twice gives the same
+/// ciphertext, so it reveals equality of messages.
+///
+/// ```
+/// use deck_farfalle::xoofff::Xoofff;
+/// use crypto_permutation::DeckFunction;
+/// use deck_modes::DeckWbcAe;
+///
+/// let wbc_ae = DeckWbcAe::<Xoofff>::new(Xoofff::init(&[0x42; 32]));
+/// let mut record = [0; 11 + 16];
+/// record[..11].copy_from_slice(b"secret data");
+/// wbc_ae.wrap(b"table 1, row 3", &mut record).unwrap();
+/// let plaintext = wbc_ae.unwrap(b"table 1, row 3", &mut record).unwrap();
+/// assert_eq!(plaintext, b"secret data");
+/// ```
+#[derive(Clone)]
+pub struct DeckWbcAe<F, const TAG_LEN: usize = 16, S = HalfSplit> {
+    wbc: DeckWbc<F, S>,
+}
+
+impl<F, const TAG_LEN: usize, S> core::fmt::Debug for DeckWbcAe<F, TAG_LEN, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DeckWbcAe")
+            .field("tag_len", &TAG_LEN)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: DeckFunction + Clone, const TAG_LEN: usize, S: WbcSplit> DeckWbcAe<F, TAG_LEN, S> {
+    /// Create the scheme keyed by the deck function `deck`.
+    pub fn new(deck: F) -> Self {
+        Self {
+            wbc: DeckWbc::new(deck),
+        }
+    }
+
+    /// Encrypt and authenticate the plaintext in `data` in place, together
+    /// with `metadata`.
+    ///
+    /// The plaintext is `data` without its last `TAG_LEN` bytes, which are
+    /// overwritten.
+    ///
+    /// # Errors
+    /// Errors with [`Error::BlockTooShort`] when `data` is shorter than
+    /// `TAG_LEN` bytes or than [`DeckWbc::MIN_LEN`]. Errors when the deck
+    /// function does not accept the input, leaving the contents of `data`
+    /// unspecified.
+    pub fn wrap(&self, metadata: &[u8], data: &mut [u8]) -> Result<(), Error> {
+        let len = data
+            .len()
+            .checked_sub(TAG_LEN)
+            .ok_or(Error::BlockTooShort)?;
+        data[len..].fill(0);
+        self.wbc.encrypt(metadata, data)
+    }
+
+    /// Decrypt `data` in place and verify it together with `metadata`.
+    /// Returns the plaintext, which is `data` without its last `TAG_LEN`
+    /// bytes.
+    ///
+    /// The redundancy is checked in constant time. When it is wrong, `data`
+    /// is encrypted again, so the unverified plaintext is not released.
+    ///
+    /// # Errors
+    /// Errors with [`Error::TagMismatch`] when the ciphertext or metadata is
+    /// not authentic, leaving `data` unchanged. Other errors are as for
+    /// [`Self::wrap`].
+    pub fn unwrap<'a>(&self, metadata: &[u8], data: &'a mut [u8]) -> Result<&'a mut [u8], Error> {
+        let len = data
+            .len()
+            .checked_sub(TAG_LEN)
+            .ok_or(Error::BlockTooShort)?;
+        self.wbc.decrypt(metadata, data)?;
+        if !bool::from(data[len..].ct_eq(&[0; TAG_LEN])) {
+            self.wbc.encrypt(metadata, data)?;
+            return Err(Error::TagMismatch);
+        }
+        Ok(&mut data[..len])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeckWbcAe;
+    use crate::Error;
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::xoofff::Xoofff;
+
+    fn wbc_ae() -> DeckWbcAe<Xoofff> {
+        DeckWbcAe::new(Xoofff::init(&[0x96; 32]))
+    }
+
+    #[test]
+    fn roundtrip() {
+        let wbc_ae = wbc_ae();
+        for len in [0, 1, 31, 32, 100] {
+            let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let mut data = plaintext.clone();
+            data.extend_from_slice(&[0xff; 16]);
+            wbc_ae.wrap(b"metadata", &mut data).unwrap();
+            assert_eq!(data.len(), len + 16);
+            assert_eq!(wbc_ae.unwrap(b"metadata", &mut data).unwrap(), plaintext);
+        }
+    }
+
+    #[test]
+    fn forgery_rejected() {
+        let wbc_ae = wbc_ae();
+        let mut data = [0; 20 + 16];
+        data[..20].copy_from_slice(b"balance: 100 credits");
+        wbc_ae.wrap(b"account 1", &mut data).unwrap();
+        let ciphertext = data;
+
+        let result = wbc_ae.unwrap(b"account 2", &mut data);
+        assert!(matches!(result, Err(Error::TagMismatch)));
+        assert_eq!(data, ciphertext);
+
+        for changed in [0, 19, 20, 35] {
+            let mut forged = ciphertext;
+            forged[changed] ^= 0x10;
+            let result = wbc_ae.unwrap(b"account 1", &mut forged);
+            assert!(matches!(result, Err(Error::TagMismatch)));
+        }
+
+        let plaintext = wbc_ae.unwrap(b"account 1", &mut data).unwrap();
+        assert_eq!(plaintext, b"balance: 100 credits");
+    }
+
+    #[test]
+    fn too_short() {
+        let wbc_ae = wbc_ae();
+        let mut data = [0; 15];
+        assert!(matches!(
+            wbc_ae.wrap(b"", &mut data),
+            Err(Error::BlockTooShort)
+        ));
+        assert!(matches!(
+            wbc_ae.unwrap(b"", &mut data),
+            Err(Error::BlockTooShort)
+        ));
+    }
+}
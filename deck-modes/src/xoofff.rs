@@ -0,0 +1,82 @@
+//! Xoofff instantiations of the modes.
+//!
+//! The parameters follow the Keccak team's definitions of Xoofff-SANE and
+//! Xoofff-SANSE. The official test vectors of the XKCP were not available
+//! when these instantiations were written, so they have not been checked
+//! against them; the tests pin their output instead.
+
+use crate::{DeckSane, DeckSanse};
+use deck_farfalle::xoofff::Xoofff;
+
+/// Xoofff-SANE: [`DeckSane`] over [`Xoofff`] with 128 bit tags.
+pub type XoofffSane = DeckSane<Xoofff, 16>;
+
+/// Xoofff-SANSE: [`DeckSanse`] over [`Xoofff`] with 256 bit tags.
+pub type XoofffSanse = DeckSanse<Xoofff, 32>;
+
+/// Xoofff-SANE as a nonce-based AEAD for the `aead` crate traits.
+#[cfg(feature = "aead")]
+pub type XoofffSaneAead = crate::SaneAead<Xoofff>;
+
+/// Xoofff-SANSE as a nonce-misuse resistant AEAD for the `aead` crate traits.
+#[cfg(feature = "aead")]
+pub type XoofffSanseAead = crate::SanseAead<Xoofff>;
+
+/// Stream cipher for the `cipher` crate traits, using the Xoofff keystream.
+#[cfg(feature = "cipher")]
+pub type XoofffStreamCipher = crate::DeckStreamCipher<Xoofff>;
+
+/// Xoofff-MAC for the `digest` crate traits, with 256 bit tags.
+#[cfg(feature = "digest")]
+pub type XoofffMac = crate::DeckMac<Xoofff>;
+
+#[cfg(test)]
+mod tests {
+    use super::{XoofffSane, XoofffSanse};
+    use crypto_permutation::DeckFunction;
+    use deck_farfalle::xoofff::Xoofff;
+
+    fn key() -> Xoofff {
+        Xoofff::init(&core::array::from_fn(|i| i as u8))
+    }
+
+    /// Regression vector.
+    #[test]
+    fn xoofff_sane() {
+        let (mut session, start_tag) = XoofffSane::start_session(key(), b"nonce").unwrap();
+        assert_eq!(
+            start_tag.into_bytes(),
+            [
+                0xdd, 0x97, 0xbe, 0xb5, 0x4f, 0x5b, 0xe9, 0x5b, 0x50, 0xe8, 0xb9, 0xa3, 0x05, 0xce,
+                0xc8, 0x45
+            ]
+        );
+        let mut data = *b"plaintext";
+        let tag = session.wrap(b"metadata", &mut data).unwrap();
+        assert_eq!(data, [0x5c, 0x1b, 0xdb, 0x36, 0xab, 0x36, 0x11, 0x73, 0x33]);
+        assert_eq!(
+            tag.into_bytes(),
+            [
+                0xc4, 0x44, 0xf3, 0x6f, 0x60, 0x11, 0x94, 0xbe, 0xc9, 0xe1, 0x29, 0x6b, 0x95, 0xd3,
+                0x4d, 0x56
+            ]
+        );
+    }
+
+    /// Regression vector.
+    #[test]
+    fn xoofff_sanse() {
+        let mut session = XoofffSanse::new(key());
+        let mut data = *b"plaintext";
+        let tag = session.wrap(b"metadata", &mut data).unwrap();
+        assert_eq!(data, [0xdd, 0x5e, 0x3d, 0xc3, 0x90, 0xff, 0x24, 0x58, 0x3d]);
+        assert_eq!(
+            tag.into_bytes(),
+            [
+                0x28, 0x0f, 0x83, 0x27, 0x7d, 0xdc, 0x87, 0x0a, 0xef, 0x26, 0x0f, 0x0c, 0x7c, 0x79,
+                0x91, 0x20, 0x83, 0xff, 0x47, 0x02, 0x93, 0x16, 0x3a, 0x30, 0xd6, 0x03, 0x33, 0xc2,
+                0x97, 0x69, 0x64, 0x8c
+            ]
+        );
+    }
+}
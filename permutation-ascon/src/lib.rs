@@ -0,0 +1,126 @@
+//! [Ascon] permutation in the [`crypto-permutation`] framework.
+//!
+//! `Ascon-p: Permutation`
+//!
+//! The byte interface of [`AsconState`] maps bytes onto the five 64-bit words
+//! of the state in big endian order, like the Ascon specification.
+//!
+//! [`crypto-permutation`]: https://crates.io/crates/crypto-permutation
+//! [Ascon]: https://ascon.iaik.tugraz.at
+
+#![no_std]
+#![allow(clippy::needless_lifetimes)]
+
+use crypto_permutation::{ParallelPermutation, Permutation, PermutationState};
+
+mod state;
+pub use state::AsconState;
+
+/// Maximum number of rounds of the Ascon permutation.
+pub const MAX_ROUNDS: usize = 12;
+
+/// Round constants of the 12 round Ascon permutation. The `n` round
+/// permutation uses the last `n` constants.
+const ROUND_CONSTANTS: [u64; MAX_ROUNDS] = [
+    0xf0, 0xe1, 0xd2, 0xc3, 0xb4, 0xa5, 0x96, 0x87, 0x78, 0x69, 0x5a, 0x4b,
+];
+
+/// Apply a single Ascon round with round constant `rc` to `state`.
+#[inline(always)]
+fn round(state: &mut [u64; 5], rc: u64) {
+    let [mut x0, mut x1, mut x2, mut x3, mut x4] = *state;
+
+    // constant addition
+    x2 ^= rc;
+
+    // substitution layer
+    x0 ^= x4;
+    x4 ^= x3;
+    x2 ^= x1;
+    let t0 = !x0 & x1;
+    let t1 = !x1 & x2;
+    let t2 = !x2 & x3;
+    let t3 = !x3 & x4;
+    let t4 = !x4 & x0;
+    x0 ^= t1;
+    x1 ^= t2;
+    x2 ^= t3;
+    x3 ^= t4;
+    x4 ^= t0;
+    x1 ^= x0;
+    x0 ^= x4;
+    x3 ^= x2;
+    x2 = !x2;
+
+    // linear diffusion layer
+    x0 ^= x0.rotate_right(19) ^ x0.rotate_right(28);
+    x1 ^= x1.rotate_right(61) ^ x1.rotate_right(39);
+    x2 ^= x2.rotate_right(1) ^ x2.rotate_right(6);
+    x3 ^= x3.rotate_right(10) ^ x3.rotate_right(17);
+    x4 ^= x4.rotate_right(7) ^ x4.rotate_right(41);
+
+    *state = [x0, x1, x2, x3, x4];
+}
+
+/// Ascon permutation with `ROUNDS` rounds. `ROUNDS` must be at most 12.
+///
+/// The Ascon specification uses `AsconP<12>` as p^a and `AsconP<6>` or
+/// `AsconP<8>` as p^b.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AsconP<const ROUNDS: usize>;
+
+impl<const ROUNDS: usize> AsconP<ROUNDS> {
+    const _ROUNDS_CHECK: () = {
+        assert!(ROUNDS > 0);
+        assert!(ROUNDS <= MAX_ROUNDS);
+    };
+}
+
+impl<const ROUNDS: usize> Permutation for AsconP<ROUNDS> {
+    type State = AsconState;
+
+    fn apply(self, state: &mut Self::State) {
+        let state = state.get_state_mut();
+        for &rc in &ROUND_CONSTANTS[MAX_ROUNDS - ROUNDS..] {
+            round(state, rc);
+        }
+    }
+}
+
+impl<const ROUNDS: usize> ParallelPermutation for AsconP<ROUNDS> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{AsconP, AsconState};
+    use crypto_permutation::{Permutation, PermutationState, Reader};
+
+    /// The initial state of Ascon-Hash is the result of applying the 12 round
+    /// permutation to the IV. Expected value from the Ascon reference
+    /// implementation.
+    #[test]
+    fn ascon_hash_iv() {
+        let mut state = AsconState::from_state([0x00400c0000000100, 0, 0, 0, 0]);
+        AsconP::<12>.apply(&mut state);
+        let expected = [
+            0xee9398aadb67f03d,
+            0x8bb21831c60f1002,
+            0xb48a92db98d5da62,
+            0x43189921b8f8e3e8,
+            0x348fa5c9d525e140,
+        ];
+        assert_eq!(state.get_state(), &expected);
+    }
+
+    /// Bytes map onto the words in big endian order.
+    #[test]
+    fn big_endian_bytes() {
+        let mut state = AsconState::default();
+        state.copy_bytes_at(6, &[0x01, 0x00, 0x40, 0x0c]);
+        state.xor_bytes_at(39, &[0x01]);
+        assert_eq!(state.get_state(), &[0x0100, 0x400c << 48, 0, 0, 1]);
+
+        let mut bytes = [0; 10];
+        state.reader().write_to_slice(&mut bytes).unwrap();
+        assert_eq!(bytes, [0, 0, 0, 0, 0, 0, 0x01, 0x00, 0x40, 0x0c]);
+    }
+}
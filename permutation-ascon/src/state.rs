@@ -0,0 +1,82 @@
+//! Ascon permutation state struct.
+
+use crypto_permutation::PermutationState;
+
+const LEN: usize = 5;
+type StateRepresentation = [u64; LEN];
+
+/// State for the Ascon permutation. 40 bytes, internally represented by 5
+/// `u64`s in big endian encoding, like the Ascon specification.
+#[derive(Clone)]
+#[cfg_attr(feature = "debug", derive(Debug, PartialEq))]
+pub struct AsconState {
+    state: StateRepresentation,
+}
+
+/// Writer into the ascon permutation state.
+///
+/// Does nothing fancy except for big-endian to native-endian conversion.
+type CopyWriter<'a> = crypto_permutation::io::be_uint_slice_writer::BeU64SliceWriter<'a>;
+/// Writer that xors into the ascon permutation state.
+///
+/// Does nothing fancy except for big-endian to native-endian conversion.
+type XorWriter<'a> = crypto_permutation::io::be_uint_slice_writer::BeU64SliceXorWriter<'a>;
+/// Reader that reads from the ascon permutation state and outputs it's bytes
+/// in big endian order.
+type StateReader<'a> = crypto_permutation::io::be_uint_slice_reader::BeU64SliceReader<'a>;
+
+impl Default for AsconState {
+    fn default() -> Self {
+        Self { state: [0; LEN] }
+    }
+}
+
+impl core::ops::BitXorAssign<&Self> for AsconState {
+    fn bitxor_assign(&mut self, rhs: &Self) {
+        for (self_chunk, other_chunk) in self.get_state_mut().iter_mut().zip(rhs.get_state().iter())
+        {
+            *self_chunk ^= *other_chunk;
+        }
+    }
+}
+
+#[cfg(feature = "subtle")]
+impl subtle::ConstantTimeEq for AsconState {
+    fn ct_eq(&self, other: &Self) -> subtle::Choice {
+        self.state[..].ct_eq(&other.state[..])
+    }
+}
+
+impl PermutationState for AsconState {
+    type Block = [u8; 40];
+    type CopyWriter<'a> = CopyWriter<'a>;
+    type Representation = StateRepresentation;
+    type StateReader<'a> = StateReader<'a>;
+    type XorWriter<'a> = XorWriter<'a>;
+
+    const SIZE: usize = 40;
+
+    fn from_state(state: Self::Representation) -> Self {
+        Self { state }
+    }
+
+    fn get_state(&self) -> &Self::Representation {
+        &self.state
+    }
+
+    fn get_state_mut(&mut self) -> &mut Self::Representation {
+        &mut self.state
+    }
+
+    fn reader<'a>(&'a self) -> Self::StateReader<'a> {
+        StateReader::new(self.get_state())
+    }
+
+    fn copy_writer<'a>(&'a mut self) -> Self::CopyWriter<'a> {
+        CopyWriter::new(self.get_state_mut())
+    }
+
+    fn xor_writer<'a>(&'a mut self) -> Self::XorWriter<'a> {
+        XorWriter::new(self.get_state_mut())
+    }
+}
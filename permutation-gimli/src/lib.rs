@@ -0,0 +1,117 @@
+//! [Gimli] permutation in the [`crypto-permutation`] framework.
+//!
+//! `Gimli: Permutation`
+//!
+//! [`crypto-permutation`]: https://crates.io/crates/crypto-permutation
+//! [Gimli]: https://gimli.cr.yp.to
+
+#![no_std]
+#![allow(clippy::needless_lifetimes)]
+
+use crypto_permutation::{ParallelPermutation, Permutation, PermutationState};
+
+mod state;
+pub use state::GimliState;
+
+/// Number of rounds of the full Gimli permutation.
+pub const MAX_ROUNDS: usize = 24;
+
+/// Apply round `round` (counting down from 24 to 1) of the Gimli permutation
+/// to `state`.
+#[inline(always)]
+fn round(state: &mut [u32; 12], round: u32) {
+    // SP-box applied to every column
+    for column in 0..4 {
+        let x = state[column].rotate_left(24);
+        let y = state[4 + column].rotate_left(9);
+        let z = state[8 + column];
+
+        state[8 + column] = x ^ (z << 1) ^ ((y & z) << 2);
+        state[4 + column] = y ^ x ^ ((x | z) << 1);
+        state[column] = z ^ y ^ ((x & y) << 3);
+    }
+
+    match round & 3 {
+        0 => {
+            // small swap
+            state.swap(0, 1);
+            state.swap(2, 3);
+            // round constant
+            state[0] ^= 0x9e377900 ^ round;
+        }
+        2 => {
+            // big swap
+            state.swap(0, 2);
+            state.swap(1, 3);
+        }
+        _ => {}
+    }
+}
+
+/// Gimli permutation reduced to its last `ROUNDS` rounds. `ROUNDS` must be at
+/// most 24.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GimliP<const ROUNDS: usize>;
+
+/// The full 24 round Gimli permutation.
+pub type Gimli = GimliP<MAX_ROUNDS>;
+
+impl<const ROUNDS: usize> GimliP<ROUNDS> {
+    const _ROUNDS_CHECK: () = {
+        assert!(ROUNDS > 0);
+        assert!(ROUNDS <= MAX_ROUNDS);
+    };
+}
+
+impl<const ROUNDS: usize> Permutation for GimliP<ROUNDS> {
+    type State = GimliState;
+
+    fn apply(self, state: &mut Self::State) {
+        let state = state.get_state_mut();
+        for r in (1..=ROUNDS as u32).rev() {
+            round(state, r);
+        }
+    }
+}
+
+impl<const ROUNDS: usize> ParallelPermutation for GimliP<ROUNDS> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gimli, GimliState};
+    use crypto_permutation::{Permutation, PermutationState};
+
+    /// Test vector from the Gimli paper.
+    #[test]
+    fn paper_test_vector() {
+        let mut input = [0_u32; 12];
+        for (i, word) in input.iter_mut().enumerate() {
+            let i = i as u32;
+            *word = i
+                .wrapping_mul(i)
+                .wrapping_mul(i)
+                .wrapping_add(i.wrapping_mul(0x9e3779b9));
+        }
+        let mut state = GimliState::from_state(input);
+        Gimli::default().apply(&mut state);
+        let expected = [
+            0xba11c85a, 0x91bad119, 0x380ce880, 0xd24c2c68, 0x3eceffea, 0x277a921c, 0x4f73a0bd,
+            0xda5a9cd8, 0x84b673f0, 0x34e52ff7, 0x9e2bef49, 0xf41bb8d6,
+        ];
+        assert_eq!(state.get_state(), &expected);
+    }
+
+    #[cfg(all(feature = "bytemuck", target_endian = "little"))]
+    #[test]
+    fn bytemuck_views() {
+        use crypto_permutation::io::Reader;
+
+        let mut state: GimliState = bytemuck::Zeroable::zeroed();
+        state.get_state_mut()[1] = 0x0403_0201;
+        let mut expected = [0; 48];
+        state.reader().write_to_slice(&mut expected).unwrap();
+        assert_eq!(state.as_le_bytes(), expected);
+        state.as_le_bytes_mut()[0] = 0xff;
+        assert_eq!(state.get_state()[0], 0xff);
+    }
+}
@@ -0,0 +1,5 @@
+//! Gimli permutation state.
+
+/// State for the Gimli permutation. 48 bytes, internally represented by 12
+/// `u32`s in little endian encoding.
+pub type GimliState = crypto_permutation::UintArrayState<u32, 12>;
@@ -0,0 +1,640 @@
+//! Bit-interleaved implementation of Keccak-p\[1600\] for 32-bit targets.
+//!
+//! Every 64-bit lane is stored as a pair of 32-bit words `[even, odd]`: `even`
+//! holds the bits at the even positions of the lane and `odd` the bits at the
+//! odd positions. A rotation of a lane then becomes two 32-bit rotations,
+//! which makes this representation efficient on targets without 64-bit
+//! rotations, like the Cortex-M family.
+//!
+//! The readers and writers of [`KeccakState1600Interleaved`] convert between
+//! the bit-interleaved representation and the little endian byte encoding of
+//! the lanes, so that the state behaves exactly like
+//! [`KeccakState1600`](crate::KeccakState1600) to everything using the
+//! generic API.
+
+use crate::constants::{PI, RC, RHO};
+use crypto_permutation::io::{check_write_size, Reader, WriteTooLargeError, Writer};
+use crypto_permutation::{ParallelPermutation, Permutation, PermutationState};
+
+/// A bit-interleaved lane `[even, odd]`.
+type Lane = [u32; 2];
+
+/// Number of lanes in a keccak state.
+const LEN: usize = 25;
+
+/// Number of bytes in a lane.
+const LANE_SIZE: usize = 8;
+
+#[cold]
+fn cold() {}
+
+/// Gather the bits at the even positions of `x` into a `u32`.
+const fn compress_even(x: u64) -> u32 {
+    let mut x = x & 0x5555555555555555;
+    x = (x | (x >> 1)) & 0x3333333333333333;
+    x = (x | (x >> 2)) & 0x0f0f0f0f0f0f0f0f;
+    x = (x | (x >> 4)) & 0x00ff00ff00ff00ff;
+    x = (x | (x >> 8)) & 0x0000ffff0000ffff;
+    x = (x | (x >> 16)) & 0x00000000ffffffff;
+    x as u32
+}
+
+/// Spread the bits of `x` over the even positions of a `u64`.
+const fn spread_even(x: u32) -> u64 {
+    let mut x = x as u64;
+    x = (x | (x << 16)) & 0x0000ffff0000ffff;
+    x = (x | (x << 8)) & 0x00ff00ff00ff00ff;
+    x = (x | (x << 4)) & 0x0f0f0f0f0f0f0f0f;
+    x = (x | (x << 2)) & 0x3333333333333333;
+    x = (x | (x << 1)) & 0x5555555555555555;
+    x
+}
+
+/// Convert a lane to its bit-interleaved representation.
+const fn interleave(lane: u64) -> Lane {
+    [compress_even(lane), compress_even(lane >> 1)]
+}
+
+/// Convert a bit-interleaved lane back to the ordinary representation.
+const fn deinterleave(lane: Lane) -> u64 {
+    spread_even(lane[0]) | (spread_even(lane[1]) << 1)
+}
+
+/// Round constants of Keccak-f\[1600\] in bit-interleaved representation.
+const RC_INTERLEAVED: [Lane; 24] = {
+    let mut rc = [[0; 2]; 24];
+    let mut i = 0;
+    while i < 24 {
+        rc[i] = interleave(RC[i]);
+        i += 1;
+    }
+    rc
+};
+
+/// Rotate a bit-interleaved lane left by `n` bits, `n < 64`.
+///
+/// An even rotation rotates both words by `n / 2`. An odd rotation moves the
+/// odd bits to the even positions and vice versa.
+#[inline(always)]
+fn rotate_left(lane: Lane, n: u32) -> Lane {
+    let [even, odd] = lane;
+    if n % 2 == 0 {
+        [even.rotate_left(n / 2), odd.rotate_left(n / 2)]
+    } else {
+        [odd.rotate_left(n / 2 + 1), even.rotate_left(n / 2)]
+    }
+}
+
+/// Wordwise xor of two bit-interleaved lanes.
+#[inline(always)]
+fn xor(a: Lane, b: Lane) -> Lane {
+    [a[0] ^ b[0], a[1] ^ b[1]]
+}
+
+/// Wordwise `a ^ (!b & c)` of three bit-interleaved lanes.
+#[inline(always)]
+fn chi(a: Lane, b: Lane, c: Lane) -> Lane {
+    [a[0] ^ (!b[0] & c[0]), a[1] ^ (!b[1] & c[1])]
+}
+
+/// A single Keccak-p\[1600\] round with bit-interleaved round constant `rc`.
+#[inline(always)]
+#[allow(clippy::needless_range_loop)]
+fn round(a: &mut [Lane; LEN], rc: Lane) {
+    // θ
+    let mut c = [[0; 2]; 5];
+    for x in 0..5 {
+        c[x] = xor(
+            xor(xor(a[x], a[x + 5]), xor(a[x + 10], a[x + 15])),
+            a[x + 20],
+        );
+    }
+    for x in 0..5 {
+        let d = xor(c[(x + 4) % 5], rotate_left(c[(x + 1) % 5], 1));
+        for y in 0..5 {
+            a[5 * y + x] = xor(a[5 * y + x], d);
+        }
+    }
+
+    // ρ and π
+    let mut last = a[1];
+    for i in 0..24 {
+        let tmp = a[PI[i]];
+        a[PI[i]] = rotate_left(last, RHO[i]);
+        last = tmp;
+    }
+
+    // χ
+    for y in 0..5 {
+        let b = [
+            a[5 * y],
+            a[5 * y + 1],
+            a[5 * y + 2],
+            a[5 * y + 3],
+            a[5 * y + 4],
+        ];
+        for x in 0..5 {
+            a[5 * y + x] = chi(b[x], b[(x + 1) % 5], b[(x + 2) % 5]);
+        }
+    }
+
+    // ι
+    a[0] = xor(a[0], rc);
+}
+
+/// Keccak-p\[1600, `rounds`\] on a bit-interleaved state.
+///
+/// # Panics
+/// Panics when `rounds` exceeds 24.
+fn keccak_p(state: &mut [Lane; LEN], rounds: usize) {
+    assert!(rounds <= 24);
+    for &rc in &RC_INTERLEAVED[24 - rounds..] {
+        round(state, rc);
+    }
+}
+
+/// 1600 bit state for the bit-interleaved Keccak-p\[1600, `n`\] permutation.
+/// 200 bytes, internally represented by 25 lanes in bit-interleaved
+/// representation (see [`interleave`](KeccakState1600Interleaved::interleave)).
+///
+/// Reading and writing bytes gives the same results as for
+/// [`KeccakState1600`](crate::KeccakState1600).
+#[derive(Clone)]
+#[cfg_attr(feature = "debug", derive(Debug, PartialEq))]
+pub struct KeccakState1600Interleaved {
+    state: [Lane; LEN],
+}
+
+impl KeccakState1600Interleaved {
+    /// Convert a lane to the bit-interleaved representation `[even, odd]`,
+    /// where `even` holds the bits at the even positions of `lane` and `odd`
+    /// the bits at the odd positions.
+    pub const fn interleave(lane: u64) -> [u32; 2] {
+        interleave(lane)
+    }
+
+    /// Inverse of [`interleave`](Self::interleave).
+    pub const fn deinterleave(lane: [u32; 2]) -> u64 {
+        deinterleave(lane)
+    }
+}
+
+impl Default for KeccakState1600Interleaved {
+    fn default() -> Self {
+        Self {
+            state: [[0; 2]; LEN],
+        }
+    }
+}
+
+impl core::ops::BitXorAssign<&Self> for KeccakState1600Interleaved {
+    fn bitxor_assign(&mut self, rhs: &Self) {
+        for (self_lane, other_lane) in self.state.iter_mut().zip(rhs.state.iter()) {
+            *self_lane = xor(*self_lane, *other_lane);
+        }
+    }
+}
+
+#[cfg(feature = "subtle")]
+impl subtle::ConstantTimeEq for KeccakState1600Interleaved {
+    fn ct_eq(&self, other: &Self) -> subtle::Choice {
+        self.state
+            .iter()
+            .zip(other.state.iter())
+            .fold(subtle::Choice::from(1), |acc, (a, b)| {
+                acc & a[..].ct_eq(&b[..])
+            })
+    }
+}
+
+impl PermutationState for KeccakState1600Interleaved {
+    type Block = [u8; LEN * LANE_SIZE];
+    /// Writer into the keccak permutation state.
+    ///
+    /// Converts little endian encoded lanes to bit-interleaved representation.
+    type CopyWriter<'a> = InterleavedWriter<'a>;
+    type Representation = [[u32; 2]; LEN];
+    /// Reader that reads from the keccak permutation state and outputs it's
+    /// lanes in little endian order.
+    type StateReader<'a> = InterleavedReader<'a>;
+    /// Writer that xors into the keccak permutation state.
+    ///
+    /// Converts little endian encoded lanes to bit-interleaved representation.
+    type XorWriter<'a> = InterleavedXorWriter<'a>;
+
+    const SIZE: usize = LEN * LANE_SIZE;
+
+    fn from_state(state: Self::Representation) -> Self {
+        Self { state }
+    }
+
+    fn get_state(&self) -> &Self::Representation {
+        &self.state
+    }
+
+    fn get_state_mut(&mut self) -> &mut Self::Representation {
+        &mut self.state
+    }
+
+    fn reader<'a>(&'a self) -> Self::StateReader<'a> {
+        InterleavedReader::new(self.get_state())
+    }
+
+    fn copy_writer<'a>(&'a mut self) -> Self::CopyWriter<'a> {
+        InterleavedWriter::new(self.get_state_mut())
+    }
+
+    fn xor_writer<'a>(&'a mut self) -> Self::XorWriter<'a> {
+        InterleavedXorWriter::new(self.get_state_mut())
+    }
+}
+
+// Requires separetely provided methods `write` and `reset_partial_block` for
+// `$name`.
+macro_rules! impl_interleaved_writer_core {
+    ($name:ident) => {
+        /// Writer that writes/xors into the buffer `self.buffer` of
+        /// bit-interleaved lanes, interpreting bytes as little endian encoded
+        /// lanes.
+        pub struct $name<'a> {
+            /// A slice of the part of the buffer that can still be written to.
+            buffer: &'a mut [Lane],
+            /// Small buffer to aggregate bytes until we have enough for a lane.
+            partial_block: [u8; LANE_SIZE],
+            /// Number of bytes currently cached in `partial_block`.
+            partial_filled: u8,
+        }
+
+        impl<'a> $name<'a> {
+            /// `self.partial_filled as usize`
+            fn partial_filled_usize(&self) -> usize {
+                usize::from(self.partial_filled)
+            }
+
+            /// Step `n` lanes forward in the buffer view.
+            fn increment_view(&mut self, n: usize) {
+                // We temporarily take ownership of `self.buffer` by swapping in an empty slice
+                // instead. We can then mutate `buffer` without changing the lifetime and swap
+                // it back in `self`.
+                let mut buffer: &'a mut [Lane] = core::mem::take(&mut self.buffer);
+                buffer = &mut buffer[n..];
+                let _ = core::mem::replace(&mut self.buffer, buffer);
+            }
+
+            /// Write the partial block to the next lane of the buffer.
+            fn write_partial_block(&mut self) {
+                let x = u64::from_le_bytes(self.partial_block);
+                self.write(x);
+                self.increment_view(1);
+                self.partial_filled = 0;
+            }
+
+            /// Create a new writer that writes/xors into `buffer`, interpreting
+            /// bytes as little endian encoded lanes.
+            pub fn new(buffer: &'a mut [Lane]) -> Self {
+                Self {
+                    buffer,
+                    partial_block: [0; LANE_SIZE],
+                    partial_filled: 0,
+                }
+            }
+        }
+
+        impl<'a> Writer for $name<'a> {
+            type Return = ();
+
+            fn capacity(&self) -> usize {
+                self.buffer.len() * LANE_SIZE - self.partial_filled_usize()
+            }
+
+            fn skip(&mut self, mut n: usize) -> Result<(), WriteTooLargeError> {
+                check_write_size(n, self.capacity())?;
+
+                if self.partial_filled != 0 {
+                    cold();
+                    let add_partial = core::cmp::min(n, LANE_SIZE - self.partial_filled_usize());
+                    self.partial_filled += add_partial as u8;
+                    n -= add_partial;
+                    if self.partial_filled == LANE_SIZE as u8 {
+                        self.write_partial_block();
+                    }
+                }
+
+                let remainder = n % LANE_SIZE;
+                self.increment_view(n / LANE_SIZE);
+
+                if remainder != 0 {
+                    cold();
+                    self.partial_filled = remainder as u8;
+                    self.reset_partial_block();
+                }
+
+                Ok(())
+            }
+
+            fn write_bytes(&mut self, mut data: &[u8]) -> Result<(), WriteTooLargeError> {
+                check_write_size(data.len(), self.capacity())?;
+
+                if self.partial_filled != 0 {
+                    cold();
+                    let add_partial =
+                        core::cmp::min(data.len(), LANE_SIZE - self.partial_filled_usize());
+                    let old_partial_filled = self.partial_filled_usize();
+                    self.partial_filled += add_partial as u8;
+                    let partial =
+                        &mut self.partial_block[old_partial_filled..self.partial_filled.into()];
+                    partial.copy_from_slice(&data[..add_partial]);
+                    data = &data[add_partial..];
+                    if self.partial_filled == LANE_SIZE as u8 {
+                        self.write_partial_block();
+                    }
+                }
+
+                let mut chunks = data.chunks_exact(LANE_SIZE);
+                for chunk in &mut chunks {
+                    let chunk: &[u8; LANE_SIZE] = chunk.try_into().unwrap();
+                    self.write(u64::from_le_bytes(*chunk));
+                    self.increment_view(1);
+                }
+
+                let remainder = chunks.remainder();
+                if !remainder.is_empty() {
+                    cold();
+                    self.partial_filled = remainder.len() as u8;
+                    self.reset_partial_block();
+                    let n = remainder.len();
+                    self.partial_block[..n].copy_from_slice(remainder);
+                }
+
+                Ok(())
+            }
+
+            fn finish(mut self) -> Self::Return {
+                if self.partial_filled != 0 {
+                    cold();
+                    self.write_partial_block();
+                }
+            }
+        }
+    };
+}
+
+impl_interleaved_writer_core!(InterleavedWriter);
+
+impl<'a> InterleavedWriter<'a> {
+    /// Write `val` to first lane of the buffer.
+    fn write(&mut self, val: u64) {
+        self.buffer[0] = interleave(val);
+    }
+
+    /// Reset the partial block to a new clean state before use.
+    fn reset_partial_block(&mut self) {
+        self.partial_block = deinterleave(self.buffer[0]).to_le_bytes();
+    }
+}
+
+impl_interleaved_writer_core!(InterleavedXorWriter);
+
+impl<'a> InterleavedXorWriter<'a> {
+    /// Xor `val` into the first lane of the buffer.
+    fn write(&mut self, val: u64) {
+        self.buffer[0] = xor(self.buffer[0], interleave(val));
+    }
+
+    /// Reset the partial block to a new clean state before use.
+    fn reset_partial_block(&mut self) {
+        self.partial_block = [0; LANE_SIZE];
+    }
+}
+
+/// Reader that reads from a buffer `self.buffer` of bit-interleaved lanes, and
+/// outputs their bytes in little endian order.
+pub struct InterleavedReader<'a> {
+    /// A slice of the part of the buffer that can still be read.
+    buffer: &'a [Lane],
+    /// Number of bytes of the first lane of `buffer` that have already been
+    /// read.
+    partial_read: u8,
+}
+
+impl<'a> InterleavedReader<'a> {
+    /// `self.partial_read as usize`
+    fn partial_read_usize(&self) -> usize {
+        usize::from(self.partial_read)
+    }
+
+    /// Step `n` lanes forward in the buffer view.
+    fn increment_view(&mut self, n: usize) {
+        self.buffer = &self.buffer[n..];
+    }
+
+    /// Little endian bytes of the first lane of the buffer.
+    fn lane_bytes(&self) -> [u8; LANE_SIZE] {
+        deinterleave(self.buffer[0]).to_le_bytes()
+    }
+
+    /// Create a new reader that reads from `buffer`, and outputs it's lanes in
+    /// little endian order.
+    pub fn new(buffer: &'a [Lane]) -> Self {
+        Self {
+            buffer,
+            partial_read: 0,
+        }
+    }
+}
+
+impl<'a> Reader for InterleavedReader<'a> {
+    fn capacity(&self) -> usize {
+        self.buffer.len() * LANE_SIZE - self.partial_read_usize()
+    }
+
+    fn skip(&mut self, mut n: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(n, self.capacity())?;
+
+        if self.partial_read != 0 {
+            cold();
+            let partial_read = core::cmp::min(n, LANE_SIZE - self.partial_read_usize());
+            self.partial_read += partial_read as u8;
+            n -= partial_read;
+            if self.partial_read == LANE_SIZE as u8 {
+                self.increment_view(1);
+                self.partial_read = 0;
+            }
+        }
+
+        let remainder = n % LANE_SIZE;
+        self.increment_view(n / LANE_SIZE);
+
+        if remainder != 0 {
+            cold();
+            self.partial_read = remainder as u8;
+        }
+
+        Ok(())
+    }
+
+    fn write_to<W: Writer>(
+        &mut self,
+        writer: &mut W,
+        mut n: usize,
+    ) -> Result<(), WriteTooLargeError> {
+        check_write_size(n, self.capacity())?;
+
+        if self.partial_read != 0 {
+            cold();
+            let partial_read = core::cmp::min(n, LANE_SIZE - self.partial_read_usize());
+            {
+                let old_partial_read = self.partial_read_usize();
+                self.partial_read += partial_read as u8;
+                let bytes = self.lane_bytes();
+                writer.write_bytes(&bytes[old_partial_read..self.partial_read_usize()])?;
+            }
+            n -= partial_read;
+            if self.partial_read == LANE_SIZE as u8 {
+                self.increment_view(1);
+                self.partial_read = 0;
+            }
+        }
+
+        let remainder = n % LANE_SIZE;
+        for _ in 0..n / LANE_SIZE {
+            writer.write_bytes(&self.lane_bytes())?;
+            self.increment_view(1);
+        }
+
+        if remainder != 0 {
+            cold();
+            writer.write_bytes(&self.lane_bytes()[..remainder])?;
+            self.partial_read = remainder as u8;
+        }
+
+        Ok(())
+    }
+}
+
+/// Bit-interleaved Keccak-f\[1600\] permutation (i.e. full 24 rounds
+/// Keccak-p).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KeccakF1600Interleaved;
+
+impl Permutation for KeccakF1600Interleaved {
+    type State = KeccakState1600Interleaved;
+
+    fn apply(self, state: &mut Self::State) {
+        keccak_p(state.get_state_mut(), 24);
+    }
+}
+
+impl ParallelPermutation for KeccakF1600Interleaved {}
+
+/// Bit-interleaved Keccak-p\[1600, ROUNDS\] permutation (i.e. `ROUNDS` rounds
+/// Keccak-p).
+/// `ROUNDS` can be at most 24.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KeccakP1600Interleaved<const ROUNDS: usize>;
+
+impl<const ROUNDS: usize> KeccakP1600Interleaved<ROUNDS> {
+    const _ROUNDS_CHECK: () = {
+        assert!(ROUNDS > 0);
+        assert!(ROUNDS <= 24);
+    };
+}
+
+impl<const ROUNDS: usize> Permutation for KeccakP1600Interleaved<ROUNDS> {
+    type State = KeccakState1600Interleaved;
+
+    fn apply(self, state: &mut Self::State) {
+        keccak_p(state.get_state_mut(), ROUNDS);
+    }
+}
+
+impl<const ROUNDS: usize> ParallelPermutation for KeccakP1600Interleaved<ROUNDS> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{KeccakF1600, KeccakP1600, KeccakState1600};
+
+    #[test]
+    fn interleave_roundtrip() {
+        for lane in [0, 1, 2, 0x8000000000000000, 0x0123456789abcdef, u64::MAX] {
+            assert_eq!(deinterleave(interleave(lane)), lane);
+        }
+        assert_eq!(interleave(0xaaaaaaaaaaaaaaaa), [0, u32::MAX]);
+        assert_eq!(interleave(0x0000000000000003), [1, 1]);
+    }
+
+    #[test]
+    fn rotate_matches_u64_rotate() {
+        let lane = 0x0123456789abcdef_u64;
+        for n in 0..64 {
+            assert_eq!(
+                deinterleave(rotate_left(interleave(lane), n)),
+                lane.rotate_left(n)
+            );
+        }
+    }
+
+    /// Known answer for Keccak-f\[1600\] applied to the all zero state.
+    #[test]
+    fn f1600_zero_state() {
+        let mut state = KeccakState1600Interleaved::default();
+        KeccakF1600Interleaved.apply(&mut state);
+        assert_eq!(deinterleave(state.get_state()[0]), 0xf1258f7940e1dde7);
+        assert_eq!(deinterleave(state.get_state()[1]), 0x84d5ccf933c0478a);
+        assert_eq!(deinterleave(state.get_state()[24]), 0xeaf1ff7b5ceca249);
+    }
+
+    /// Writing, permuting and reading through the byte interface gives the
+    /// same results as for [`KeccakState1600`].
+    #[test]
+    fn matches_keccak_state_1600() {
+        let data: [u8; 200] = core::array::from_fn(|i| (i as u8).wrapping_mul(0x5b));
+
+        let mut interleaved = KeccakState1600Interleaved::default();
+        let mut plain = KeccakState1600::default();
+        // unaligned writes to exercise the partial lane handling
+        for (offset, len) in [(3, 50), (0, 200), (17, 100)] {
+            let mut writer = interleaved.xor_writer();
+            writer.skip(offset).unwrap();
+            writer.write_bytes(&data[..len - offset]).unwrap();
+            writer.finish();
+            let mut writer = plain.xor_writer();
+            writer.skip(offset).unwrap();
+            writer.write_bytes(&data[..len - offset]).unwrap();
+            writer.finish();
+        }
+        let mut writer = interleaved.copy_writer();
+        writer.skip(41).unwrap();
+        writer.write_bytes(&data[..5]).unwrap();
+        writer.finish();
+        let mut writer = plain.copy_writer();
+        writer.skip(41).unwrap();
+        writer.write_bytes(&data[..5]).unwrap();
+        writer.finish();
+
+        KeccakF1600Interleaved.apply(&mut interleaved);
+        KeccakF1600.apply(&mut plain);
+        KeccakP1600Interleaved::<12>.apply(&mut interleaved);
+        KeccakP1600::<12>.apply(&mut plain);
+
+        let mut output_interleaved = [0; 200];
+        let mut output_plain = [0; 200];
+        let mut reader = interleaved.reader();
+        reader.skip(5).unwrap();
+        reader
+            .write_to_slice(&mut output_interleaved[..60])
+            .unwrap();
+        reader
+            .write_to_slice(&mut output_interleaved[60..195])
+            .unwrap();
+        let mut reader = plain.reader();
+        reader.skip(5).unwrap();
+        reader.write_to_slice(&mut output_plain[..195]).unwrap();
+        assert_eq!(output_interleaved, output_plain);
+
+        for (lane, plain_lane) in interleaved.get_state().iter().zip(plain.get_state()) {
+            assert_eq!(deinterleave(*lane), *plain_lane);
+        }
+    }
+}
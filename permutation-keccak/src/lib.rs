@@ -3,8 +3,57 @@
 //!
 //! `Keccak-p: Permutation`
 //!
-//! Uses the RustCrypto [`keccak` crate] internally for the actual permutation
-//! invocation.
+//! All Keccak-p widths with lanes of at least 8 bits are supported:
+//! * Keccak-p\[1600\]: [`KeccakF1600`], [`KeccakP1600`] and [`KeccakP1600Dyn`]
+//!   (runtime number of rounds) acting on [`KeccakState1600`],
+//! * Keccak-p\[800\]: [`KeccakF800`], [`KeccakP800`] acting on
+//!   [`KeccakState800`],
+//! * Keccak-p\[400\]: [`KeccakF400`], [`KeccakP400`] acting on
+//!   [`KeccakState400`],
+//! * Keccak-p\[200\]: [`KeccakF200`], [`KeccakP200`] acting on
+//!   [`KeccakState200`].
+//!
+//! On 32-bit targets without 64-bit rotations, like the Cortex-M family, the
+//! `interleaved` feature provides [`KeccakF1600Interleaved`] and
+//! [`KeccakP1600Interleaved`] acting on [`KeccakState1600Interleaved`]. This
+//! state stores the lanes in bit-interleaved representation, but reads and
+//! writes the same bytes as [`KeccakState1600`].
+//!
+//! All permutations implement [`ParallelPermutation`]. For Keccak-p\[1600\]
+//! the `simd` feature enables SIMD kernels processing multiple states at once,
+//! and on `x86` and `x86_64` a single state AVX2 kernel.
+//!
+//! By default the RustCrypto [`keccak` crate] is used internally for the
+//! actual permutation invocation. With the `native` crate-feature an in-crate
+//! implementation is used instead, so the `keccak` crate dependency can be
+//! dropped using `default-features = false, features = ["native"]`.
+//!
+//! # Features
+//! * `keccak` (default): Use the [`keccak` crate] for the permutations.
+//! * `native`: Use the in-crate implementation of the permutations, even when
+//!   the `keccak` feature is enabled as well.
+//! * `native-unroll`: Fully unroll the loops in the step mappings of the
+//!   in-crate implementation. Implies `native`.
+//! * `asm`: On `aarch64`, use the ARMv8 SHA3 instructions (`EOR3`, `RAX1`,
+//!   `XAR` and `BCAX`) for Keccak-p\[1600\] when they are detected at runtime,
+//!   falling back to the portable implementation otherwise. Implemented by the
+//!   [`keccak` crate], so this has no effect when `native` is enabled.
+//! * `simd`: Use SIMD kernels in [`ParallelPermutation::apply_parallel`] for
+//!   Keccak-p\[1600\]: on `x86` and `x86_64` runtime detected AVX2 kernels
+//!   processing four states at once, and on `aarch64` NEON kernels processing
+//!   two states at once, unless the `asm` feature uses the SHA3 instructions.
+//!   On `x86` and `x86_64` single states are processed by a runtime detected
+//!   AVX2 kernel as well.
+//! * `avx512`: Additionally use runtime detected AVX-512F/AVX-512VL kernels
+//!   for four and two states at once. Implies `simd`; requires Rust 1.89 or
+//!   later.
+//! * `interleaved`: Provide the bit-interleaved Keccak-p\[1600\]
+//!   implementation in the [`interleaved`] module.
+//! * `subtle`: Implements `subtle::ConstantTimeEq` for the states.
+//! * `bytemuck`: Implements `bytemuck::Zeroable` for the states, and provides
+//!   zero-copy byte views `as_le_bytes` and `as_le_bytes_mut` of them on little
+//!   endian targets.
+//! * `debug`: Used for tests. Don't use!
 //!
 //! [`crypto-permutation`]: https://crates.io/crates/crypto-permutation
 //! [`keccak` crate]: https://crates.io/crates/keccak
@@ -13,40 +62,258 @@
 #![no_std]
 #![allow(clippy::needless_lifetimes)]
 
-use crypto_permutation::{Permutation, PermutationState};
-use keccak::{f1600, keccak_p};
+use crypto_permutation::{ParallelPermutation, Permutation, PermutationState};
+#[cfg(not(feature = "native"))]
+use keccak::{f1600, f200, f400, f800, p1600, p200, p400, p800};
+#[cfg(feature = "native")]
+use native::{f1600, f200, f400, f800, p1600, p200, p400, p800};
 
+#[cfg(not(any(feature = "keccak", feature = "native")))]
+compile_error!("at least one of the `keccak` and `native` features must be enabled");
+
+#[cfg(any(feature = "native", feature = "simd", feature = "interleaved"))]
+mod constants;
+#[cfg(feature = "interleaved")]
+pub mod interleaved;
+#[cfg(feature = "interleaved")]
+pub use interleaved::{KeccakF1600Interleaved, KeccakP1600Interleaved, KeccakState1600Interleaved};
+#[cfg(test)]
+mod kat;
+#[cfg(feature = "native")]
+mod native;
+#[cfg(feature = "simd")]
+mod simd;
 mod state;
-pub use state::KeccakState1600;
+pub use state::{KeccakState1600, KeccakState200, KeccakState400, KeccakState800};
+
+macro_rules! impl_keccak_permutations {
+    (
+        $f_name:ident, $p_name:ident, $state:ident, $f:ident, $p:ident, $width:literal, $max_rounds:literal
+    ) => {
+        #[doc = concat!(
+                            "Keccak-f\\[", $width, "\\] permutation (i.e. full ", $max_rounds,
+                            " rounds Keccak-p)."
+                        )]
+        #[derive(Clone, Copy, Debug, Default)]
+        pub struct $f_name;
+
+        impl Permutation for $f_name {
+            type State = $state;
+
+            fn apply(self, state: &mut Self::State) {
+                $f(state.get_state_mut());
+            }
+        }
+
+        #[doc = concat!(
+                            "Keccak-p\\[", $width, ", ROUNDS\\] permutation (i.e. `ROUNDS` rounds ",
+                            "Keccak-p).\n`ROUNDS` can be at most ", $max_rounds, "."
+                        )]
+        #[derive(Clone, Copy, Debug, Default)]
+        pub struct $p_name<const ROUNDS: usize>;
+
+        impl<const ROUNDS: usize> $p_name<ROUNDS> {
+            const _ROUNDS_CHECK: () = {
+                assert!(ROUNDS > 0);
+                assert!(ROUNDS <= $max_rounds);
+            };
+        }
+
+        impl<const ROUNDS: usize> Permutation for $p_name<ROUNDS> {
+            type State = $state;
+
+            fn apply(self, state: &mut Self::State) {
+                $p(state.get_state_mut(), ROUNDS);
+            }
+        }
+    };
+}
 
-/// Keccak-f\[1600\] permutation (i.e. full 24 rounds Keccak-p).
-#[derive(Clone, Copy, Debug, Default)]
-pub struct KeccakF1600;
+/// Keccak-p\[1600, `rounds`\] on a single state, using the single state SIMD
+/// kernel when the `simd` feature is enabled and the kernel is available.
+fn p1600_single(state: &mut [u64; 25], rounds: usize) {
+    #[cfg(feature = "simd")]
+    simd::keccak_p(state, rounds);
+    #[cfg(not(feature = "simd"))]
+    p1600(state, rounds);
+}
+
+/// Keccak-f\[1600\] on a single state, see [`p1600_single`].
+fn f1600_single(state: &mut [u64; 25]) {
+    #[cfg(feature = "simd")]
+    simd::keccak_f(state);
+    #[cfg(not(feature = "simd"))]
+    f1600(state);
+}
+
+impl_keccak_permutations!(
+    KeccakF1600,
+    KeccakP1600,
+    KeccakState1600,
+    f1600_single,
+    p1600_single,
+    1600,
+    24
+);
+impl_keccak_permutations!(KeccakF800, KeccakP800, KeccakState800, f800, p800, 800, 22);
+impl_keccak_permutations!(KeccakF400, KeccakP400, KeccakState400, f400, p400, 400, 20);
+impl_keccak_permutations!(KeccakF200, KeccakP200, KeccakState200, f200, p200, 200, 18);
+
+/// Keccak-p\[1600, `rounds`\] permutation with the number of rounds chosen at
+/// runtime. Use [`KeccakP1600`] when the number of rounds is known at compile
+/// time.
+///
+/// Defaults to 24 rounds, i.e. Keccak-f\[1600\].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeccakP1600Dyn {
+    rounds: usize,
+}
+
+impl KeccakP1600Dyn {
+    /// Create a Keccak-p\[1600, `rounds`\] permutation. Returns `None` unless
+    /// `1 <= rounds <= 24`.
+    pub const fn new(rounds: usize) -> Option<Self> {
+        if rounds > 0 && rounds <= 24 {
+            Some(Self { rounds })
+        } else {
+            None
+        }
+    }
+
+    /// Number of rounds of the permutation.
+    pub const fn rounds(self) -> usize {
+        self.rounds
+    }
+}
+
+impl Default for KeccakP1600Dyn {
+    fn default() -> Self {
+        Self { rounds: 24 }
+    }
+}
 
-impl Permutation for KeccakF1600 {
+impl Permutation for KeccakP1600Dyn {
     type State = KeccakState1600;
 
     fn apply(self, state: &mut Self::State) {
-        f1600(state.get_state_mut());
+        p1600_single(state.get_state_mut(), self.rounds);
     }
 }
 
-/// Keccak-\[1600, ROUNDS\] permutation (i.e. `ROUNDS` rounds Keccak-p).
-/// `ROUNDS` can be at most 24.
-#[derive(Clone, Copy, Debug, Default)]
-pub struct KeccakP1600<const ROUNDS: usize>;
+impl ParallelPermutation for KeccakP1600Dyn {
+    #[cfg(feature = "simd")]
+    fn parallelism(self) -> usize {
+        simd::parallelism()
+    }
 
-impl<const ROUNDS: usize> KeccakP1600<ROUNDS> {
-    const _ROUNDS_CHECK: () = {
-        assert!(ROUNDS > 0);
-        assert!(ROUNDS <= 24);
-    };
+    #[cfg(feature = "simd")]
+    fn apply_parallel(self, states: &mut [Self::State]) {
+        simd::keccak_p_parallel(states, self.rounds);
+    }
 }
 
-impl<const ROUNDS: usize> Permutation for KeccakP1600<ROUNDS> {
-    type State = KeccakState1600;
+impl ParallelPermutation for KeccakF1600 {
+    #[cfg(feature = "simd")]
+    fn parallelism(self) -> usize {
+        simd::parallelism()
+    }
 
-    fn apply(self, state: &mut Self::State) {
-        keccak_p(state.get_state_mut(), ROUNDS);
+    #[cfg(feature = "simd")]
+    fn apply_parallel(self, states: &mut [Self::State]) {
+        simd::keccak_p_parallel(states, 24);
+    }
+}
+
+impl<const ROUNDS: usize> ParallelPermutation for KeccakP1600<ROUNDS> {
+    #[cfg(feature = "simd")]
+    fn parallelism(self) -> usize {
+        simd::parallelism()
+    }
+
+    #[cfg(feature = "simd")]
+    fn apply_parallel(self, states: &mut [Self::State]) {
+        simd::keccak_p_parallel(states, ROUNDS);
+    }
+}
+
+impl ParallelPermutation for KeccakF800 {}
+impl<const ROUNDS: usize> ParallelPermutation for KeccakP800<ROUNDS> {}
+impl ParallelPermutation for KeccakF400 {}
+impl<const ROUNDS: usize> ParallelPermutation for KeccakP400<ROUNDS> {}
+impl ParallelPermutation for KeccakF200 {}
+impl<const ROUNDS: usize> ParallelPermutation for KeccakP200<ROUNDS> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeccakF1600, KeccakP1600, KeccakP1600Dyn, KeccakState1600};
+    use crypto_permutation::{ParallelPermutation, Permutation, PermutationState};
+
+    /// Known answer for Keccak-f\[1600\] applied to the all zero state, from
+    /// the Keccak code package. Exercises whichever backend is selected.
+    #[test]
+    fn keccak_f1600_zero_state() {
+        let expected_first = 0xf1258f7940e1dde7;
+        let expected_last = 0xeaf1ff7b5ceca249;
+
+        let mut state = KeccakState1600::default();
+        KeccakF1600.apply(&mut state);
+        assert_eq!(state.get_state()[0], expected_first);
+        assert_eq!(state.get_state()[24], expected_last);
+
+        let mut state = KeccakState1600::default();
+        KeccakP1600::<24>.apply(&mut state);
+        assert_eq!(state.get_state()[0], expected_first);
+        assert_eq!(state.get_state()[24], expected_last);
+    }
+
+    /// [`KeccakP1600Dyn`] agrees with [`KeccakP1600`] and rejects invalid
+    /// round numbers.
+    #[test]
+    fn keccak_p1600_dyn() {
+        assert!(KeccakP1600Dyn::new(0).is_none());
+        assert!(KeccakP1600Dyn::new(25).is_none());
+        assert_eq!(KeccakP1600Dyn::default(), KeccakP1600Dyn::new(24).unwrap());
+
+        let mut state = KeccakState1600::default();
+        let mut expected = KeccakState1600::default();
+        for (i, lane) in state.get_state_mut().iter_mut().enumerate() {
+            *lane = i as u64;
+        }
+        expected.get_state_mut().copy_from_slice(state.get_state());
+
+        KeccakP1600Dyn::new(12).unwrap().apply(&mut state);
+        KeccakP1600::<12>.apply(&mut expected);
+        assert_eq!(state.get_state(), expected.get_state());
+
+        KeccakP1600Dyn::new(1).unwrap().apply(&mut state);
+        KeccakP1600::<1>.apply(&mut expected);
+        assert_eq!(state.get_state(), expected.get_state());
+
+        let mut states: [KeccakState1600; 5] = Default::default();
+        KeccakP1600Dyn::default().apply_parallel(&mut states);
+        let mut zero = KeccakState1600::default();
+        KeccakF1600.apply(&mut zero);
+        for state in states.iter() {
+            assert_eq!(state.get_state(), zero.get_state());
+        }
+    }
+
+    /// [`ParallelPermutation::apply_parallel`] is equivalent to applying the
+    /// permutation to every state.
+    #[test]
+    fn apply_parallel() {
+        let mut states: [KeccakState1600; 6] = Default::default();
+        for (j, state) in states.iter_mut().enumerate() {
+            state.get_state_mut()[j] = j as u64 + 1;
+        }
+        let mut expected = states.clone();
+        for state in expected.iter_mut() {
+            KeccakP1600::<12>.apply(state);
+        }
+
+        KeccakP1600::<12>.apply_parallel(&mut states);
+        for (state, expected) in states.iter().zip(expected.iter()) {
+            assert_eq!(state.get_state(), expected.get_state());
+        }
     }
 }
@@ -0,0 +1,254 @@
+//! Native implementation of the Keccak-p permutations.
+//!
+//! A single generic implementation covers all lane widths. Loop unrolling of
+//! the step mappings is controlled by the `native-unroll` crate-feature.
+//!
+//! The rounds use the lane complementing transform: during the permutation the
+//! lanes in [`COMPLEMENTED`] are stored complemented, which lets χ be computed
+//! with 8 instead of 25 NOTs per round. The transform is applied on entry and
+//! undone on exit of [`keccak_p`], so the states always hold the plain lanes.
+
+use crate::constants::{PI, RC, RHO};
+use core::ops::{BitAnd, BitOr, BitXor, BitXorAssign, Not};
+
+/// Lanes that are complemented by the lane complementing transform. This is
+/// the pattern that is mapped to itself by a round (see the Keccak
+/// implementation overview, section 2.2).
+const COMPLEMENTED: [usize; 6] = [1, 2, 8, 12, 17, 20];
+
+/// Unsigned integer type used as a Keccak lane.
+pub(crate) trait Lane:
+    Copy
+    + Default
+    + BitXor<Output = Self>
+    + BitXorAssign
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + Not<Output = Self>
+{
+    /// Number of rounds of Keccak-f with this lane width.
+    const MAX_ROUNDS: usize;
+
+    /// Rotate left by `n` bits, modulo the lane width.
+    fn rotate_left(self, n: u32) -> Self;
+
+    /// Truncate a Keccak-f\[1600\] round constant to the lane width.
+    fn truncate(rc: u64) -> Self;
+}
+
+macro_rules! impl_lane {
+    ($uint:ty, $max_rounds:literal) => {
+        impl Lane for $uint {
+            const MAX_ROUNDS: usize = $max_rounds;
+
+            #[inline(always)]
+            fn rotate_left(self, n: u32) -> Self {
+                <$uint>::rotate_left(self, n)
+            }
+
+            #[inline(always)]
+            fn truncate(rc: u64) -> Self {
+                rc as $uint
+            }
+        }
+    };
+}
+
+impl_lane!(u64, 24);
+impl_lane!(u32, 22);
+impl_lane!(u16, 20);
+impl_lane!(u8, 18);
+
+/// Repeat `$body` for `$var` in `0..$n`, either as a loop or fully unrolled
+/// depending on the `native-unroll` crate-feature.
+#[cfg(not(feature = "native-unroll"))]
+macro_rules! repeat {
+    ($n:tt, $var:ident, $body:block) => {
+        for $var in 0..$n $body
+    };
+}
+
+/// Repeat `$body` for `$var` in `0..$n`, either as a loop or fully unrolled
+/// depending on the `native-unroll` crate-feature.
+#[cfg(feature = "native-unroll")]
+macro_rules! repeat {
+    (5, $var:ident, $body:block) => {
+        repeat!(@unroll $var, $body, 0 1 2 3 4)
+    };
+    (24, $var:ident, $body:block) => {
+        repeat!(@unroll $var, $body, 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23)
+    };
+    (@unroll $var:ident, $body:block, $($i:literal)*) => {
+        $({
+            let $var: usize = $i;
+            $body
+        })*
+    };
+}
+
+/// A single Keccak-p round with round constant `rc`, acting on the lane
+/// complemented representation of the state.
+#[inline(always)]
+#[allow(clippy::needless_range_loop)]
+// When unrolled, the last assignment to `last` in ρ and π is never read.
+#[cfg_attr(feature = "native-unroll", allow(unused_assignments))]
+fn round<L: Lane>(a: &mut [L; 25], rc: L) {
+    // θ
+    let mut c = [L::default(); 5];
+    repeat!(5, x, {
+        c[x] = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+    });
+    repeat!(5, x, {
+        let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+        repeat!(5, y, {
+            a[5 * y + x] ^= d;
+        });
+    });
+
+    // ρ and π
+    let mut last = a[1];
+    repeat!(24, i, {
+        let tmp = a[PI[i]];
+        a[PI[i]] = last.rotate_left(RHO[i]);
+        last = tmp;
+    });
+
+    // χ, with the NOTs placed according to the complemented lanes
+    let [b0, b1, b2, b3, b4] = row(a, 0);
+    a[0] = b0 ^ (b1 | b2);
+    a[1] = b1 ^ (!b2 | b3);
+    a[2] = b2 ^ (b3 & b4);
+    a[3] = b3 ^ (b4 | b0);
+    a[4] = b4 ^ (b0 & b1);
+
+    let [b0, b1, b2, b3, b4] = row(a, 1);
+    a[5] = b0 ^ (b1 | b2);
+    a[6] = b1 ^ (b2 & b3);
+    a[7] = b2 ^ (b3 | !b4);
+    a[8] = b3 ^ (b4 | b0);
+    a[9] = b4 ^ (b0 & b1);
+
+    let [b0, b1, b2, b3, b4] = row(a, 2);
+    let not_b3 = !b3;
+    a[10] = b0 ^ (b1 | b2);
+    a[11] = b1 ^ (b2 & b3);
+    a[12] = b2 ^ (not_b3 & b4);
+    a[13] = not_b3 ^ (b4 | b0);
+    a[14] = b4 ^ (b0 & b1);
+
+    let [b0, b1, b2, b3, b4] = row(a, 3);
+    let not_b3 = !b3;
+    a[15] = b0 ^ (b1 & b2);
+    a[16] = b1 ^ (b2 | b3);
+    a[17] = b2 ^ (not_b3 | b4);
+    a[18] = not_b3 ^ (b4 & b0);
+    a[19] = b4 ^ (b0 | b1);
+
+    let [b0, b1, b2, b3, b4] = row(a, 4);
+    let not_b1 = !b1;
+    a[20] = b0 ^ (not_b1 & b2);
+    a[21] = not_b1 ^ (b2 | b3);
+    a[22] = b2 ^ (b3 & b4);
+    a[23] = b3 ^ (b4 | b0);
+    a[24] = b4 ^ (b0 & b1);
+
+    // ι
+    a[0] ^= rc;
+}
+
+/// Row `y` of the state `a`.
+#[inline(always)]
+fn row<L: Lane>(a: &[L; 25], y: usize) -> [L; 5] {
+    [
+        a[5 * y],
+        a[5 * y + 1],
+        a[5 * y + 2],
+        a[5 * y + 3],
+        a[5 * y + 4],
+    ]
+}
+
+/// Apply (or undo) the lane complementing transform.
+#[inline(always)]
+fn complement_lanes<L: Lane>(state: &mut [L; 25]) {
+    for i in COMPLEMENTED {
+        state[i] = !state[i];
+    }
+}
+
+/// Keccak-p permutation with `rounds` rounds, i.e. the last `rounds` rounds of
+/// Keccak-f.
+///
+/// # Panics
+/// Panics when `rounds` exceeds the number of rounds of Keccak-f for this lane
+/// width.
+pub(crate) fn keccak_p<L: Lane>(state: &mut [L; 25], rounds: usize) {
+    assert!(rounds <= L::MAX_ROUNDS);
+    complement_lanes(state);
+    for &rc in &RC[L::MAX_ROUNDS - rounds..L::MAX_ROUNDS] {
+        round(state, L::truncate(rc));
+    }
+    complement_lanes(state);
+}
+
+macro_rules! impl_widths {
+    ($($p:ident, $f:ident, $uint:ty, $width:literal;)*) => {
+        $(
+            #[doc = concat!("Keccak-p\\[", $width, ", `rounds`\\] permutation.")]
+            pub(crate) fn $p(state: &mut [$uint; 25], rounds: usize) {
+                keccak_p(state, rounds);
+            }
+
+            #[doc = concat!("Keccak-f\\[", $width, "\\] permutation.")]
+            pub(crate) fn $f(state: &mut [$uint; 25]) {
+                keccak_p(state, <$uint as Lane>::MAX_ROUNDS);
+            }
+        )*
+    };
+}
+
+impl_widths!(
+    p1600, f1600, u64, 1600;
+    p800, f800, u32, 800;
+    p400, f400, u16, 400;
+    p200, f200, u8, 200;
+);
+
+#[cfg(test)]
+mod tests {
+    use super::f1600;
+
+    /// Known answer for Keccak-f\[1600\] applied to the all zero state.
+    #[test]
+    fn f1600_zero_state() {
+        let mut state = [0_u64; 25];
+        f1600(&mut state);
+        assert_eq!(state[0], 0xf1258f7940e1dde7);
+        assert_eq!(state[1], 0x84d5ccf933c0478a);
+        assert_eq!(state[24], 0xeaf1ff7b5ceca249);
+    }
+
+    /// Compare against the `keccak` crate for all widths and round numbers.
+    #[cfg(feature = "keccak")]
+    #[test]
+    fn matches_keccak_crate() {
+        macro_rules! check {
+            ($uint:ty, $max_rounds:literal) => {
+                for rounds in 1..=$max_rounds {
+                    let mut native = [0 as $uint; 25];
+                    for (i, lane) in native.iter_mut().enumerate() {
+                        *lane = (i as $uint).wrapping_mul(0x5b) ^ (rounds as $uint);
+                    }
+                    let mut reference = native;
+                    super::keccak_p(&mut native, rounds);
+                    keccak::keccak_p(&mut reference, rounds);
+                    assert_eq!(native, reference);
+                }
+            };
+        }
+        check!(u64, 24);
+        check!(u32, 22);
+        check!(u16, 20);
+        check!(u8, 18);
+    }
+}
@@ -0,0 +1,133 @@
+//! SIMD kernels applying Keccak-p\[1600\] to several states at once.
+//!
+//! Every 64-bit element of a vector holds the same lane of a different state,
+//! so the kernels are a direct translation of the scalar round function. Each
+//! architecture module provides modules with the vector operations used by
+//! `keccak_round` and defines its kernels using `keccak_kernel`.
+
+/// One Keccak-p\[1600\] round on the vectors of lanes `$a`, using the vector
+/// operations from the module `$ops`.
+macro_rules! keccak_round {
+    ($ops:ident, $a:ident, $rc:expr) => {{
+        // θ
+        let mut c = [$ops::splat(0); 5];
+        for (x, c) in c.iter_mut().enumerate() {
+            *c = $ops::xor5($a[x], $a[x + 5], $a[x + 10], $a[x + 15], $a[x + 20]);
+        }
+        for x in 0..5 {
+            let d = $ops::xor(c[(x + 4) % 5], $ops::rol(c[(x + 1) % 5], 1));
+            for y in 0..5 {
+                $a[5 * y + x] = $ops::xor($a[5 * y + x], d);
+            }
+        }
+
+        // ρ and π
+        let mut last = $a[1];
+        for (&pi, &rho) in $crate::constants::PI
+            .iter()
+            .zip($crate::constants::RHO.iter())
+        {
+            let tmp = $a[pi];
+            $a[pi] = $ops::rol(last, rho);
+            last = tmp;
+        }
+
+        // χ
+        for y in 0..5 {
+            let mut row = [$ops::splat(0); 5];
+            row.copy_from_slice(&$a[5 * y..5 * y + 5]);
+            for x in 0..5 {
+                $a[5 * y + x] = $ops::chi(row[x], row[(x + 1) % 5], row[(x + 2) % 5]);
+            }
+        }
+
+        // ι
+        $a[0] = $ops::xor($a[0], $ops::splat($rc));
+    }};
+}
+
+/// Define a kernel applying Keccak-p\[1600, `rounds`\] to exactly `$n` states,
+/// using the vector operations from the module `$ops`.
+macro_rules! keccak_kernel {
+    ($name:ident, $ops:ident, $n:literal, $features:literal) => {
+        #[doc = concat!("Apply Keccak-p\\[1600, `rounds`\\] to ", $n, " states.")]
+        ///
+        /// # Safety
+        #[doc = concat!("The `", $features, "` target features must be available.")]
+        ///
+        /// # Panics
+        #[doc = concat!("Panics when `states.len() != ", $n, "` or `rounds > 24`.")]
+        #[target_feature(enable = $features)]
+        #[allow(clippy::needless_range_loop)]
+        pub(crate) unsafe fn $name(states: &mut [$crate::KeccakState1600], rounds: usize) {
+            use crypto_permutation::PermutationState;
+
+            assert_eq!(states.len(), $n);
+            let round_constants = &$crate::constants::RC[24 - rounds..];
+
+            let mut a = [$ops::splat(0); 25];
+            for (i, lane) in a.iter_mut().enumerate() {
+                let mut lanes = [0; $n];
+                for (lane, state) in lanes.iter_mut().zip(states.iter()) {
+                    *lane = state.get_state()[i];
+                }
+                *lane = $ops::load(&lanes);
+            }
+
+            for &rc in round_constants {
+                keccak_round!($ops, a, rc);
+            }
+
+            for (i, lane) in a.iter().enumerate() {
+                for (lane, state) in $ops::store(*lane).iter().zip(states.iter_mut()) {
+                    state.get_state_mut()[i] = *lane;
+                }
+            }
+        }
+    };
+}
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+mod aarch64;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86;
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+use aarch64 as arch;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use x86 as arch;
+
+/// Fallback for architectures without SIMD kernels.
+#[cfg(not(any(
+    target_arch = "x86",
+    target_arch = "x86_64",
+    all(target_arch = "aarch64", target_feature = "neon")
+)))]
+mod arch {
+    use crate::KeccakState1600;
+    use crypto_permutation::PermutationState;
+
+    /// Number of states processed at once by the fastest kernel available.
+    pub(crate) fn parallelism() -> usize {
+        1
+    }
+
+    /// Apply Keccak-p\[1600, `rounds`\] to a single state.
+    pub(crate) fn keccak_p(state: &mut [u64; 25], rounds: usize) {
+        crate::p1600(state, rounds);
+    }
+
+    /// Apply Keccak-f\[1600\] to a single state.
+    pub(crate) fn keccak_f(state: &mut [u64; 25]) {
+        crate::f1600(state);
+    }
+
+    /// Apply Keccak-p\[1600, `rounds`\] to all `states`.
+    pub(crate) fn keccak_p_parallel(states: &mut [KeccakState1600], rounds: usize) {
+        for state in states {
+            crate::p1600(state.get_state_mut(), rounds);
+        }
+    }
+}
+
+pub(crate) use arch::{keccak_f, keccak_p, keccak_p_parallel, parallelism};
@@ -0,0 +1,150 @@
+//! NEON kernel for `aarch64`, processing two states at once.
+
+use core::arch::aarch64::*;
+
+use crate::KeccakState1600;
+use crypto_permutation::PermutationState;
+
+#[cfg(all(feature = "asm", not(feature = "native")))]
+cpufeatures::new!(sha3_intrinsics, "sha3");
+
+/// Whether the scalar implementation uses the ARMv8 SHA3 instructions, which
+/// process a single state at least as fast as the NEON kernel processes two.
+#[cfg(all(feature = "asm", not(feature = "native")))]
+fn scalar_uses_sha3() -> bool {
+    sha3_intrinsics::get()
+}
+
+/// Whether the scalar implementation uses the ARMv8 SHA3 instructions, never
+/// without the `asm` feature.
+#[cfg(not(all(feature = "asm", not(feature = "native"))))]
+fn scalar_uses_sha3() -> bool {
+    false
+}
+
+/// Vector operations on two lanes using NEON.
+mod neon {
+    use super::*;
+
+    pub(super) type Lanes = uint64x2_t;
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn splat(x: u64) -> Lanes {
+        vdupq_n_u64(x)
+    }
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn load(lanes: &[u64; 2]) -> Lanes {
+        vld1q_u64(lanes.as_ptr())
+    }
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn store(v: Lanes) -> [u64; 2] {
+        let mut lanes = [0; 2];
+        vst1q_u64(lanes.as_mut_ptr(), v);
+        lanes
+    }
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn xor(a: Lanes, b: Lanes) -> Lanes {
+        veorq_u64(a, b)
+    }
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn xor5(a: Lanes, b: Lanes, c: Lanes, d: Lanes, e: Lanes) -> Lanes {
+        xor(xor(xor(a, b), xor(c, d)), e)
+    }
+
+    /// Rotate left by `n` bits, with `0 < n < 64`. A negative shift count makes
+    /// `vshlq_u64` shift right.
+    #[inline]
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn rol(a: Lanes, n: u32) -> Lanes {
+        let left = vshlq_u64(a, vdupq_n_s64(i64::from(n)));
+        let right = vshlq_u64(a, vdupq_n_s64(i64::from(n) - 64));
+        vorrq_u64(left, right)
+    }
+
+    /// `a ^ (!b & c)`
+    #[inline]
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn chi(a: Lanes, b: Lanes, c: Lanes) -> Lanes {
+        xor(a, vbicq_u64(c, b))
+    }
+
+    keccak_kernel!(keccak_p_x2, neon, 2, "neon");
+}
+
+/// Apply Keccak-p\[1600, `rounds`\] to a single state. There is no single
+/// state NEON kernel, as the scalar implementation (using the SHA3 instructions
+/// with the `asm` feature) is faster.
+pub(crate) fn keccak_p(state: &mut [u64; 25], rounds: usize) {
+    crate::p1600(state, rounds);
+}
+
+/// Apply Keccak-f\[1600\] to a single state, see [`keccak_p`].
+pub(crate) fn keccak_f(state: &mut [u64; 25]) {
+    crate::f1600(state);
+}
+
+/// Number of states processed at once by the fastest kernel available.
+pub(crate) fn parallelism() -> usize {
+    if scalar_uses_sha3() {
+        1
+    } else {
+        2
+    }
+}
+
+/// Apply Keccak-p\[1600, `rounds`\] to all `states`, using the NEON kernel for
+/// pairs of states unless the scalar implementation uses the SHA3
+/// instructions.
+pub(crate) fn keccak_p_parallel(states: &mut [KeccakState1600], rounds: usize) {
+    if scalar_uses_sha3() {
+        for state in states {
+            crate::p1600(state.get_state_mut(), rounds);
+        }
+        return;
+    }
+    let mut pairs = states.chunks_exact_mut(2);
+    for pair in &mut pairs {
+        // SAFETY: this module is only compiled when the `neon` target feature
+        // is enabled.
+        unsafe { neon::keccak_p_x2(pair, rounds) };
+    }
+    for state in pairs.into_remainder() {
+        crate::p1600(state.get_state_mut(), rounds);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neon_kernel() {
+        for rounds in [1, 12, 24] {
+            let mut states: [KeccakState1600; 2] = Default::default();
+            for (j, state) in states.iter_mut().enumerate() {
+                for (i, lane) in state.get_state_mut().iter_mut().enumerate() {
+                    *lane = (i as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ j as u64;
+                }
+            }
+            let mut expected = states.clone();
+            // SAFETY: this module is only compiled when the `neon` target
+            // feature is enabled.
+            unsafe { neon::keccak_p_x2(&mut states, rounds) };
+            for state in expected.iter_mut() {
+                crate::p1600(state.get_state_mut(), rounds);
+            }
+            for (state, expected) in states.iter().zip(expected.iter()) {
+                assert_eq!(state.get_state(), expected.get_state());
+            }
+        }
+    }
+}
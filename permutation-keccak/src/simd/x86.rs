@@ -0,0 +1,521 @@
+//! SIMD kernels for `x86` and `x86_64`.
+//!
+//! The kernel is selected at runtime:
+//! * AVX-512F + AVX-512VL (with the `avx512` crate-feature): four states in
+//!   256-bit vectors and two states in 128-bit vectors, using native rotations
+//!   and ternary logic for θ and χ.
+//! * AVX2: four states in 256-bit vectors, and a single state in five 256-bit
+//!   vectors.
+//!
+//! States that don't fill a vector are processed by the single state
+//! kernel.
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+use crate::KeccakState1600;
+use crypto_permutation::PermutationState;
+
+cpufeatures::new!(avx2_intrinsics, "avx2");
+#[cfg(feature = "avx512")]
+cpufeatures::new!(avx512_intrinsics, "avx512f", "avx512vl");
+
+/// Vector operations on four lanes using AVX2.
+mod avx2 {
+    use super::*;
+
+    pub(super) type Lanes = __m256i;
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn splat(x: u64) -> Lanes {
+        _mm256_set1_epi64x(x as i64)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn load(lanes: &[u64; 4]) -> Lanes {
+        _mm256_loadu_si256(lanes.as_ptr().cast())
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn store(v: Lanes) -> [u64; 4] {
+        let mut lanes = [0; 4];
+        _mm256_storeu_si256(lanes.as_mut_ptr().cast(), v);
+        lanes
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn xor(a: Lanes, b: Lanes) -> Lanes {
+        _mm256_xor_si256(a, b)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn xor5(a: Lanes, b: Lanes, c: Lanes, d: Lanes, e: Lanes) -> Lanes {
+        xor(xor(xor(a, b), xor(c, d)), e)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn rol(a: Lanes, n: u32) -> Lanes {
+        let left = _mm_cvtsi32_si128(n as i32);
+        let right = _mm_cvtsi32_si128(64 - n as i32);
+        _mm256_or_si256(_mm256_sll_epi64(a, left), _mm256_srl_epi64(a, right))
+    }
+
+    /// `a ^ (!b & c)`
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn chi(a: Lanes, b: Lanes, c: Lanes) -> Lanes {
+        xor(a, _mm256_andnot_si256(b, c))
+    }
+
+    keccak_kernel!(keccak_p_x4, avx2, 4, "avx2");
+}
+
+/// Single state kernel using AVX2.
+///
+/// The lanes `x = 0..4` of plane `y` are held in `rows[y]`, the lanes
+/// `(4, y)` for `y = 0..4` in `col4` and lane `(4, 4)` in every element of
+/// `corner`. θ, ρ and χ then work on whole rows, while π gathers every new
+/// row from one lane of each old row.
+mod avx2_x1 {
+    use super::*;
+    use crate::constants::{PI, RC, RHO};
+
+    /// ρ offsets indexed by lane `x + 5 * y`.
+    const RHO_OFFSETS: [u32; 25] = {
+        let mut offsets = [0; 25];
+        let mut lane = 1;
+        let mut i = 0;
+        while i < 24 {
+            offsets[lane] = RHO[i];
+            lane = PI[i];
+            i += 1;
+        }
+        offsets
+    };
+
+    /// Copy element `lane` of `v` to all elements.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn broadcast(v: __m256i, lane: usize) -> __m256i {
+        let low = 2 * lane as i32;
+        let high = low + 1;
+        let index = _mm256_setr_epi32(low, high, low, high, low, high, low, high);
+        _mm256_permutevar8x32_epi32(v, index)
+    }
+
+    /// Rotate every element left by the corresponding element of `n`, with
+    /// `n < 64`.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn rolv(a: __m256i, n: [u32; 4]) -> __m256i {
+        let n = _mm256_setr_epi64x(
+            i64::from(n[0]),
+            i64::from(n[1]),
+            i64::from(n[2]),
+            i64::from(n[3]),
+        );
+        let left = _mm256_sllv_epi64(a, n);
+        let right = _mm256_srlv_epi64(a, _mm256_sub_epi64(_mm256_set1_epi64x(64), n));
+        _mm256_or_si256(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    #[allow(clippy::needless_range_loop)]
+    unsafe fn round(rows: &mut [__m256i; 5], col4: &mut __m256i, corner: &mut __m256i, rc: u64) {
+        use avx2::{rol, xor};
+
+        // θ
+        let c = xor(xor(xor(rows[0], rows[1]), xor(rows[2], rows[3])), rows[4]);
+        let t = xor(*col4, _mm256_permute4x64_epi64::<0b01_00_11_10>(*col4));
+        let t = xor(t, _mm256_permute4x64_epi64::<0b10_11_00_01>(t));
+        let c4 = xor(t, *corner);
+        let c_prev =
+            _mm256_blend_epi32::<0b0000_0011>(_mm256_permute4x64_epi64::<0b10_01_00_00>(c), c4);
+        let c_next =
+            _mm256_blend_epi32::<0b1100_0000>(_mm256_permute4x64_epi64::<0b11_11_10_01>(c), c4);
+        let d = xor(c_prev, rol(c_next, 1));
+        let d4 = xor(
+            _mm256_permute4x64_epi64::<0b11_11_11_11>(c),
+            rol(_mm256_permute4x64_epi64::<0b00_00_00_00>(c), 1),
+        );
+        for row in rows.iter_mut() {
+            *row = xor(*row, d);
+        }
+        *col4 = xor(*col4, d4);
+        *corner = xor(*corner, d4);
+
+        // ρ
+        for (y, row) in rows.iter_mut().enumerate() {
+            let o = &RHO_OFFSETS[5 * y..];
+            *row = rolv(*row, [o[0], o[1], o[2], o[3]]);
+        }
+        let o = &RHO_OFFSETS;
+        *col4 = rolv(*col4, [o[4], o[9], o[14], o[19]]);
+        *corner = rol(*corner, o[24]);
+
+        // π: lane (x, y) of the new state is lane (x + 3 y, x) of the old one.
+        // Row `y` is gathered by blending lane `x + 3 y` of `rows[x]` into one
+        // vector, permuting the lanes into place and blending in the lane
+        // taken from `col4`.
+        let [r0, r1, r2, r3, r4] = *rows;
+        let b = _mm256_blend_epi32::<0b0000_1100>(r0, r1);
+        let b = _mm256_blend_epi32::<0b0011_0000>(b, r2);
+        rows[0] = _mm256_blend_epi32::<0b1100_0000>(b, r3);
+        let b = _mm256_blend_epi32::<0b0000_0011>(r0, r2);
+        let b = _mm256_blend_epi32::<0b0000_1100>(b, r3);
+        let b = _mm256_permute4x64_epi64::<0b01_00_01_11>(b);
+        rows[1] = _mm256_blend_epi32::<0b0000_1100>(b, *col4);
+        let b = _mm256_blend_epi32::<0b0011_0000>(r0, r1);
+        let b = _mm256_blend_epi32::<0b1100_0000>(b, r2);
+        let b = _mm256_permute4x64_epi64::<0b11_11_10_01>(b);
+        rows[2] = _mm256_blend_epi32::<0b1100_0000>(b, *col4);
+        let b = _mm256_blend_epi32::<0b0000_1100>(r1, r2);
+        let b = _mm256_blend_epi32::<0b0011_0000>(b, r3);
+        let b = _mm256_permute4x64_epi64::<0b10_01_00_00>(b);
+        rows[3] = _mm256_blend_epi32::<0b0000_0011>(b, *col4);
+        let b = _mm256_blend_epi32::<0b1100_0000>(r0, r1);
+        let b = _mm256_blend_epi32::<0b0000_0011>(b, r3);
+        let b = _mm256_permute4x64_epi64::<0b00_10_11_10>(b);
+        rows[4] = _mm256_blend_epi32::<0b0011_0000>(b, *col4);
+        *col4 = _mm256_blend_epi32::<0b0000_0011>(
+            _mm256_permute4x64_epi64::<0b11_00_10_00>(r4),
+            *corner,
+        );
+        *corner = _mm256_permute4x64_epi64::<0b01_01_01_01>(r4);
+
+        // χ
+        let mut t = [_mm256_setzero_si256(); 5];
+        for y in 0..5 {
+            let x4 = if y < 4 { broadcast(*col4, y) } else { *corner };
+            let next = _mm256_blend_epi32::<0b1100_0000>(
+                _mm256_permute4x64_epi64::<0b11_11_10_01>(rows[y]),
+                x4,
+            );
+            let next2 = _mm256_blend_epi32::<0b0011_0000>(
+                _mm256_permute4x64_epi64::<0b00_11_11_10>(rows[y]),
+                x4,
+            );
+            // Element 0 is `!a[0] & a[1]`, as needed for lane (4, y).
+            t[y] = _mm256_andnot_si256(rows[y], next);
+            rows[y] = xor(rows[y], _mm256_andnot_si256(next, next2));
+        }
+        let t01 = _mm256_unpacklo_epi64(t[0], t[1]);
+        let t23 = _mm256_unpacklo_epi64(t[2], t[3]);
+        *col4 = xor(*col4, _mm256_permute2x128_si256::<0x20>(t01, t23));
+        *corner = xor(*corner, _mm256_permute4x64_epi64::<0b00_00_00_00>(t[4]));
+
+        // ι
+        rows[0] = xor(rows[0], _mm256_setr_epi64x(rc as i64, 0, 0, 0));
+    }
+
+    /// Apply Keccak-p\[1600, `rounds`\] to `state`.
+    ///
+    /// # Safety
+    /// The `avx2` target feature must be available.
+    ///
+    /// # Panics
+    /// Panics when `rounds > 24`.
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn keccak_p(state: &mut [u64; 25], rounds: usize) {
+        let round_constants = &RC[24 - rounds..];
+
+        let mut rows = [_mm256_setzero_si256(); 5];
+        for (y, row) in rows.iter_mut().enumerate() {
+            *row = _mm256_loadu_si256(state[5 * y..5 * y + 4].as_ptr().cast());
+        }
+        let mut col4 = _mm256_setr_epi64x(
+            state[4] as i64,
+            state[9] as i64,
+            state[14] as i64,
+            state[19] as i64,
+        );
+        let mut corner = _mm256_set1_epi64x(state[24] as i64);
+
+        for &rc in round_constants {
+            round(&mut rows, &mut col4, &mut corner, rc);
+        }
+
+        for (y, row) in rows.iter().enumerate() {
+            _mm256_storeu_si256(state[5 * y..5 * y + 4].as_mut_ptr().cast(), *row);
+        }
+        let col4 = avx2::store(col4);
+        for (y, lane) in col4.iter().enumerate() {
+            state[5 * y + 4] = *lane;
+        }
+        state[24] = avx2::store(corner)[0];
+    }
+}
+
+/// Truth table of `a ^ b ^ c` for the ternary logic instructions.
+#[cfg(feature = "avx512")]
+const XOR3: i32 = 0x96;
+/// Truth table of `a ^ (!b & c)` for the ternary logic instructions.
+#[cfg(feature = "avx512")]
+const CHI: i32 = 0xd2;
+
+/// Define a module with vector operations on `$n` lanes using AVX-512F and
+/// AVX-512VL.
+///
+/// The AVX-512 intrinsics are stable since Rust 1.89, which is documented as
+/// the minimum Rust version for the `avx512` feature.
+#[cfg(feature = "avx512")]
+macro_rules! avx512_ops {
+    (
+        $module:ident, $n:literal, $lanes:ty, $set1:ident, $loadu:ident, $storeu:ident, $xor:ident,
+        $rolv:ident, $ternarylogic:ident
+    ) => {
+        #[doc = concat!("Vector operations on ", $n, " lanes using AVX-512F and AVX-512VL.")]
+        #[allow(clippy::incompatible_msrv)]
+        mod $module {
+            use super::*;
+
+            pub(super) type Lanes = $lanes;
+
+            #[inline]
+            #[target_feature(enable = "avx512f,avx512vl")]
+            pub(super) unsafe fn splat(x: u64) -> Lanes {
+                $set1(x as i64)
+            }
+
+            #[inline]
+            #[target_feature(enable = "avx512f,avx512vl")]
+            pub(super) unsafe fn load(lanes: &[u64; $n]) -> Lanes {
+                $loadu(lanes.as_ptr().cast())
+            }
+
+            #[inline]
+            #[target_feature(enable = "avx512f,avx512vl")]
+            pub(super) unsafe fn store(v: Lanes) -> [u64; $n] {
+                let mut lanes = [0; $n];
+                $storeu(lanes.as_mut_ptr().cast(), v);
+                lanes
+            }
+
+            #[inline]
+            #[target_feature(enable = "avx512f,avx512vl")]
+            pub(super) unsafe fn xor(a: Lanes, b: Lanes) -> Lanes {
+                $xor(a, b)
+            }
+
+            #[inline]
+            #[target_feature(enable = "avx512f,avx512vl")]
+            pub(super) unsafe fn xor5(a: Lanes, b: Lanes, c: Lanes, d: Lanes, e: Lanes) -> Lanes {
+                $ternarylogic::<XOR3>($ternarylogic::<XOR3>(a, b, c), d, e)
+            }
+
+            #[inline]
+            #[target_feature(enable = "avx512f,avx512vl")]
+            pub(super) unsafe fn rol(a: Lanes, n: u32) -> Lanes {
+                $rolv(a, splat(u64::from(n)))
+            }
+
+            /// `a ^ (!b & c)`
+            #[inline]
+            #[target_feature(enable = "avx512f,avx512vl")]
+            pub(super) unsafe fn chi(a: Lanes, b: Lanes, c: Lanes) -> Lanes {
+                $ternarylogic::<CHI>(a, b, c)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "avx512")]
+avx512_ops!(
+    avx512_x4,
+    4,
+    __m256i,
+    _mm256_set1_epi64x,
+    _mm256_loadu_si256,
+    _mm256_storeu_si256,
+    _mm256_xor_si256,
+    _mm256_rolv_epi64,
+    _mm256_ternarylogic_epi64
+);
+
+#[cfg(feature = "avx512")]
+avx512_ops!(
+    avx512_x2,
+    2,
+    __m128i,
+    _mm_set1_epi64x,
+    _mm_loadu_si128,
+    _mm_storeu_si128,
+    _mm_xor_si128,
+    _mm_rolv_epi64,
+    _mm_ternarylogic_epi64
+);
+
+#[cfg(feature = "avx512")]
+mod avx512 {
+    use super::*;
+
+    keccak_kernel!(keccak_p_x4, avx512_x4, 4, "avx512f,avx512vl");
+    keccak_kernel!(keccak_p_x2, avx512_x2, 2, "avx512f,avx512vl");
+}
+
+/// Number of states processed at once by the fastest kernel available.
+pub(crate) fn parallelism() -> usize {
+    if avx2_intrinsics::get() {
+        4
+    } else {
+        1
+    }
+}
+
+/// Apply Keccak-p\[1600, `rounds`\] to a single state, using the AVX2 kernel
+/// when available.
+pub(crate) fn keccak_p(state: &mut [u64; 25], rounds: usize) {
+    if avx2_intrinsics::get() {
+        // SAFETY: the `avx2` target feature is available.
+        unsafe { avx2_x1::keccak_p(state, rounds) };
+    } else {
+        crate::p1600(state, rounds);
+    }
+}
+
+/// Apply Keccak-f\[1600\] to a single state, using the AVX2 kernel when
+/// available.
+pub(crate) fn keccak_f(state: &mut [u64; 25]) {
+    if avx2_intrinsics::get() {
+        // SAFETY: the `avx2` target feature is available.
+        unsafe { avx2_x1::keccak_p(state, 24) };
+    } else {
+        crate::f1600(state);
+    }
+}
+
+/// Apply Keccak-p\[1600, `rounds`\] to all `states`, using the fastest kernels
+/// available.
+pub(crate) fn keccak_p_parallel(states: &mut [KeccakState1600], rounds: usize) {
+    #[cfg(feature = "avx512")]
+    if avx512_intrinsics::get() {
+        let mut chunks = states.chunks_exact_mut(4);
+        for chunk in &mut chunks {
+            // SAFETY: the `avx512f` and `avx512vl` target features are available.
+            unsafe { avx512::keccak_p_x4(chunk, rounds) };
+        }
+        let mut pairs = chunks.into_remainder().chunks_exact_mut(2);
+        for pair in &mut pairs {
+            // SAFETY: the `avx512f` and `avx512vl` target features are available.
+            unsafe { avx512::keccak_p_x2(pair, rounds) };
+        }
+        for state in pairs.into_remainder() {
+            keccak_p(state.get_state_mut(), rounds);
+        }
+        return;
+    }
+
+    if avx2_intrinsics::get() {
+        let mut chunks = states.chunks_exact_mut(4);
+        for chunk in &mut chunks {
+            // SAFETY: the `avx2` target feature is available.
+            unsafe { avx2::keccak_p_x4(chunk, rounds) };
+        }
+        for state in chunks.into_remainder() {
+            // SAFETY: the `avx2` target feature is available.
+            unsafe { avx2_x1::keccak_p(state.get_state_mut(), rounds) };
+        }
+        return;
+    }
+
+    for state in states {
+        crate::p1600(state.get_state_mut(), rounds);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_states(n: usize) -> [KeccakState1600; 7] {
+        let mut states: [KeccakState1600; 7] = Default::default();
+        for (j, state) in states.iter_mut().take(n).enumerate() {
+            for (i, lane) in state.get_state_mut().iter_mut().enumerate() {
+                *lane = (i as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ j as u64;
+            }
+        }
+        states
+    }
+
+    fn assert_states_eq(left: &[KeccakState1600], right: &[KeccakState1600]) {
+        assert_eq!(left.len(), right.len());
+        for (left, right) in left.iter().zip(right) {
+            assert_eq!(left.get_state(), right.get_state());
+        }
+    }
+
+    fn reference(states: &mut [KeccakState1600], rounds: usize) {
+        for state in states {
+            crate::p1600(state.get_state_mut(), rounds);
+        }
+    }
+
+    #[test]
+    fn avx2_kernel() {
+        if !avx2_intrinsics::get() {
+            return;
+        }
+        for rounds in [1, 12, 24] {
+            let mut states = test_states(4);
+            let mut expected = states.clone();
+            // SAFETY: the `avx2` target feature is available.
+            unsafe { avx2::keccak_p_x4(&mut states[..4], rounds) };
+            reference(&mut expected[..4], rounds);
+            assert_states_eq(&states[..4], &expected[..4]);
+        }
+    }
+
+    #[test]
+    fn avx2_single_kernel() {
+        if !avx2_intrinsics::get() {
+            return;
+        }
+        for rounds in 1..=24 {
+            let mut states = test_states(1);
+            let mut expected = states.clone();
+            // SAFETY: the `avx2` target feature is available.
+            unsafe { avx2_x1::keccak_p(states[0].get_state_mut(), rounds) };
+            reference(&mut expected[..1], rounds);
+            assert_states_eq(&states[..1], &expected[..1]);
+        }
+    }
+
+    #[cfg(feature = "avx512")]
+    #[test]
+    fn avx512_kernels() {
+        if !avx512_intrinsics::get() {
+            return;
+        }
+        for rounds in [1, 12, 24] {
+            let mut states = test_states(4);
+            let mut expected = states.clone();
+            // SAFETY: the `avx512f` and `avx512vl` target features are available.
+            unsafe { avx512::keccak_p_x4(&mut states[..4], rounds) };
+            // SAFETY: the `avx512f` and `avx512vl` target features are available.
+            unsafe { avx512::keccak_p_x2(&mut states[4..6], rounds) };
+            reference(&mut expected[..6], rounds);
+            assert_states_eq(&states[..6], &expected[..6]);
+        }
+    }
+
+    /// Dispatch handles any number of states, including partial vectors.
+    #[test]
+    fn parallel_dispatch() {
+        for n in 0..=7 {
+            let mut states = test_states(7);
+            let mut expected = states.clone();
+            keccak_p_parallel(&mut states[..n], 24);
+            reference(&mut expected[..n], 24);
+            assert_states_eq(&states, &expected);
+        }
+    }
+}
@@ -1,74 +1,363 @@
-//! Keccak permutation state struct.
+//! Keccak permutation state structs.
 
+use crypto_permutation::io::le_uint_slice_reader::{
+    LeU16SliceReader, LeU32SliceReader, LeU64SliceReader, LeU8SliceReader,
+};
+use crypto_permutation::io::le_uint_slice_writer::{
+    LeU16SliceWriter, LeU16SliceXorWriter, LeU32SliceWriter, LeU32SliceXorWriter, LeU64SliceWriter,
+    LeU64SliceXorWriter, LeU8SliceWriter, LeU8SliceXorWriter,
+};
+use crypto_permutation::uint_array_state::{copy_le_bytes_at, xor_le_bytes_at};
 use crypto_permutation::PermutationState;
 
+/// Number of lanes in a keccak state.
 const LEN: usize = 25;
-type StateRepresentation = [u64; LEN];
-
-/// 1600 bit state for the Keccak-p\[1600, `n`\] permutation. 200 bytes,
-/// internally represented by 25 `u64`s in little endian encoding.
-#[derive(Clone)]
-#[cfg_attr(feature = "debug", derive(Debug, PartialEq))]
-pub struct KeccakState1600 {
-    state: StateRepresentation,
-}
 
-/// Writer into the keccak permutation state.
-///
-/// Does nothing fancy except for little-endian to native-endian conversion.
-type CopyWriter<'a> = crypto_permutation::io::le_uint_slice_writer::LeU64SliceWriter<'a>;
-/// Writer that xors into the keccak permutation state.
-///
-/// Does nothing fancy except for little-endian to native-endian conversion.
-type XorWriter<'a> = crypto_permutation::io::le_uint_slice_writer::LeU64SliceXorWriter<'a>;
-/// Reader that reads from the keccak permutation state and outputs it's bytes
-/// in little endian order.
-type StateReader<'a> = crypto_permutation::io::le_uint_slice_reader::LeU64SliceReader<'a>;
-
-impl Default for KeccakState1600 {
-    fn default() -> Self {
-        Self { state: [0; LEN] }
+/// Alignment of the keccak states in bytes. The states are xored in blocks of
+/// this size, which lets the compiler use wide aligned loads and stores.
+const ALIGN: usize = 32;
+
+// The lane at position `(x, y)` is stored at index `5 * y + x`.
+
+/// Xor `rhs` into `lhs` in blocks of `BLOCK` lanes, `BLOCK` being chosen such
+/// that a block is [`ALIGN`] bytes.
+#[inline(always)]
+fn xor_blocks<T: Copy + core::ops::BitXorAssign, const BLOCK: usize>(lhs: &mut [T], rhs: &[T]) {
+    let mut lhs_blocks = lhs.chunks_exact_mut(BLOCK);
+    let mut rhs_blocks = rhs.chunks_exact(BLOCK);
+    for (lhs_block, rhs_block) in (&mut lhs_blocks).zip(&mut rhs_blocks) {
+        let lhs_block: &mut [T; BLOCK] = lhs_block.try_into().unwrap();
+        let rhs_block: &[T; BLOCK] = rhs_block.try_into().unwrap();
+        for (lhs_lane, rhs_lane) in lhs_block.iter_mut().zip(rhs_block.iter()) {
+            *lhs_lane ^= *rhs_lane;
+        }
+    }
+    let remainder = lhs_blocks.into_remainder();
+    for (lhs_lane, rhs_lane) in remainder.iter_mut().zip(rhs_blocks.remainder()) {
+        *lhs_lane ^= *rhs_lane;
     }
 }
 
-impl core::ops::BitXorAssign<&Self> for KeccakState1600 {
-    fn bitxor_assign(&mut self, rhs: &Self) {
-        for (self_chunk, other_chunk) in self.get_state_mut().iter_mut().zip(rhs.get_state().iter())
-        {
-            *self_chunk ^= *other_chunk;
+macro_rules! impl_keccak_state {
+    (
+        $(#[$meta:meta])*
+        $name:ident, $uint:ty, $reader:ident, $writer:ident, $xor_writer:ident
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone)]
+        #[cfg_attr(feature = "debug", derive(Debug, PartialEq))]
+        #[repr(align(32))]
+        pub struct $name {
+            state: [$uint; LEN],
         }
-    }
+
+        impl $name {
+            /// View the state as its byte encoding, without copying.
+            ///
+            /// Only available on little endian targets, where the in-memory
+            /// representation coincides with the encoding.
+            #[cfg(all(feature = "bytemuck", target_endian = "little"))]
+            pub fn as_le_bytes(&self) -> &[u8; LEN * core::mem::size_of::<$uint>()] {
+                bytemuck::cast_ref(&self.state)
+            }
+
+            /// Mutably view the state as its byte encoding, without copying.
+            ///
+            /// Only available on little endian targets, where the in-memory
+            /// representation coincides with the encoding.
+            #[cfg(all(feature = "bytemuck", target_endian = "little"))]
+            pub fn as_le_bytes_mut(&mut self) -> &mut [u8; LEN * core::mem::size_of::<$uint>()] {
+                bytemuck::cast_mut(&mut self.state)
+            }
+
+            /// The lane at position `(x, y)`, i.e. index `5 * y + x` of the state
+            /// representation.
+            ///
+            /// # Panics
+            /// Panics if `x` or `y` is not less than 5.
+            pub fn lane(&self, x: usize, y: usize) -> $uint {
+                self.plane(y)[x]
+            }
+
+            /// Mutable reference to the lane at position `(x, y)`, i.e. index
+            /// `5 * y + x` of the state representation.
+            ///
+            /// # Panics
+            /// Panics if `x` or `y` is not less than 5.
+            pub fn lane_mut(&mut self, x: usize, y: usize) -> &mut $uint {
+                &mut self.plane_mut(y)[x]
+            }
+
+            /// The plane `y`, i.e. the five lanes with this `y` coordinate.
+            ///
+            /// # Panics
+            /// Panics if `y` is not less than 5.
+            pub fn plane(&self, y: usize) -> &[$uint; 5] {
+                self.state[5 * y..5 * y + 5].try_into().unwrap()
+            }
+
+            /// Mutable reference to the plane `y`, i.e. the five lanes with this `y`
+            /// coordinate.
+            ///
+            /// # Panics
+            /// Panics if `y` is not less than 5.
+            pub fn plane_mut(&mut self, y: usize) -> &mut [$uint; 5] {
+                (&mut self.state[5 * y..5 * y + 5]).try_into().unwrap()
+            }
+
+            /// Iterator over the rows of lanes, i.e. the planes for `y` from 0 to 4.
+            pub fn rows(&self) -> impl Iterator<Item = &[$uint; 5]> {
+                self.state.chunks_exact(5).map(|row| row.try_into().unwrap())
+            }
+
+            /// Iterator over mutable references to the rows of lanes, i.e. the
+            /// planes for `y` from 0 to 4.
+            pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [$uint; 5]> {
+                self.state
+                    .chunks_exact_mut(5)
+                    .map(|row| row.try_into().unwrap())
+            }
+
+            /// Iterator over the column of lanes with coordinate `x`, for `y` from 0
+            /// to 4.
+            ///
+            /// # Panics
+            /// Panics if `x` is not less than 5.
+            pub fn column(&self, x: usize) -> impl Iterator<Item = $uint> + '_ {
+                assert!(x < 5);
+                self.state[x..].iter().step_by(5).copied()
+            }
+
+            /// Iterator over mutable references to the column of lanes with
+            /// coordinate `x`, for `y` from 0 to 4.
+            ///
+            /// # Panics
+            /// Panics if `x` is not less than 5.
+            pub fn column_mut(&mut self, x: usize) -> impl Iterator<Item = &mut $uint> {
+                assert!(x < 5);
+                self.state[x..].iter_mut().step_by(5)
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self { state: [0; LEN] }
+            }
+        }
+
+        impl core::ops::BitXorAssign<&Self> for $name {
+            #[inline]
+            fn bitxor_assign(&mut self, rhs: &Self) {
+                xor_blocks::<$uint, { ALIGN / core::mem::size_of::<$uint>() }>(
+                    self.get_state_mut(),
+                    rhs.get_state(),
+                );
+            }
+        }
+
+        // SAFETY: the all zero bit pattern is the all zero state. The state is
+        // not `Pod` since the alignment adds padding.
+        #[cfg(feature = "bytemuck")]
+        unsafe impl bytemuck::Zeroable for $name {}
+
+        #[cfg(feature = "subtle")]
+        impl subtle::ConstantTimeEq for $name {
+            fn ct_eq(&self, other: &Self) -> subtle::Choice {
+                self.state[..].ct_eq(&other.state[..])
+            }
+        }
+
+        impl PermutationState for $name {
+            type Block = [u8; LEN * core::mem::size_of::<$uint>()];
+            /// Writer into the keccak permutation state.
+            ///
+            /// Does nothing fancy except for little-endian to native-endian
+            /// conversion.
+            type CopyWriter<'a> = $writer<'a>;
+            type Representation = [$uint; LEN];
+            /// Reader that reads from the keccak permutation state and outputs
+            /// it's bytes in little endian order.
+            type StateReader<'a> = $reader<'a>;
+            /// Writer that xors into the keccak permutation state.
+            ///
+            /// Does nothing fancy except for little-endian to native-endian
+            /// conversion.
+            type XorWriter<'a> = $xor_writer<'a>;
+
+            const SIZE: usize = LEN * core::mem::size_of::<$uint>();
+
+            fn from_state(state: Self::Representation) -> Self {
+                Self { state }
+            }
+
+            fn get_state(&self) -> &Self::Representation {
+                &self.state
+            }
+
+            fn get_state_mut(&mut self) -> &mut Self::Representation {
+                &mut self.state
+            }
+
+            fn reader<'a>(&'a self) -> Self::StateReader<'a> {
+                $reader::new(self.get_state())
+            }
+
+            fn copy_writer<'a>(&'a mut self) -> Self::CopyWriter<'a> {
+                $writer::new(self.get_state_mut())
+            }
+
+            fn xor_writer<'a>(&'a mut self) -> Self::XorWriter<'a> {
+                $xor_writer::new(self.get_state_mut())
+            }
+
+            fn xor_bytes_at(&mut self, offset: usize, data: &[u8]) {
+                xor_le_bytes_at(&mut self.state, offset, data);
+            }
+
+            fn copy_bytes_at(&mut self, offset: usize, data: &[u8]) {
+                copy_le_bytes_at(&mut self.state, offset, data);
+            }
+        }
+    };
 }
 
-impl PermutationState for KeccakState1600 {
-    type CopyWriter<'a> = CopyWriter<'a>;
-    type Representation = StateRepresentation;
-    type StateReader<'a> = StateReader<'a>;
-    type XorWriter<'a> = XorWriter<'a>;
+impl_keccak_state!(
+    /// 1600 bit state for the Keccak-p\[1600, `n`\] permutation. 200 bytes,
+    /// internally represented by 25 `u64`s in little endian encoding.
+    ///
+    /// The state is aligned to 32 bytes, so that xoring two states can use
+    /// wide aligned vector loads and stores.
+    KeccakState1600,
+    u64,
+    LeU64SliceReader,
+    LeU64SliceWriter,
+    LeU64SliceXorWriter
+);
 
-    const SIZE: usize = 200;
+impl_keccak_state!(
+    /// 800 bit state for the Keccak-p\[800, `n`\] permutation. 100 bytes,
+    /// internally represented by 25 `u32`s in little endian encoding.
+    KeccakState800,
+    u32,
+    LeU32SliceReader,
+    LeU32SliceWriter,
+    LeU32SliceXorWriter
+);
 
-    fn from_state(state: Self::Representation) -> Self {
-        Self { state }
-    }
+impl_keccak_state!(
+    /// 400 bit state for the Keccak-p\[400, `n`\] permutation. 50 bytes,
+    /// internally represented by 25 `u16`s in little endian encoding.
+    KeccakState400,
+    u16,
+    LeU16SliceReader,
+    LeU16SliceWriter,
+    LeU16SliceXorWriter
+);
+
+impl_keccak_state!(
+    /// 200 bit state for the Keccak-p\[200, `n`\] permutation. 25 bytes,
+    /// internally represented by 25 `u8`s.
+    KeccakState200,
+    u8,
+    LeU8SliceReader,
+    LeU8SliceWriter,
+    LeU8SliceXorWriter
+);
 
-    fn get_state(&self) -> &Self::Representation {
-        &self.state
+// `repr(align)` only accepts a literal, keep it in sync with `ALIGN`.
+const _: () = assert!(core::mem::align_of::<KeccakState1600>() == ALIGN);
+
+#[cfg(test)]
+mod tests {
+    use super::{KeccakState1600, KeccakState200, KeccakState400};
+    use crypto_permutation::io::Reader;
+    use crypto_permutation::{Block, PermutationState};
+
+    /// Read the full state into a stack buffer of one block.
+    fn read_block<S: PermutationState>(state: &S) -> S::Block {
+        let mut block = S::Block::ZERO;
+        assert_eq!(block.as_bytes().len(), S::SIZE);
+        state.reader().write_to_slice(block.as_bytes_mut()).unwrap();
+        block
     }
 
-    fn get_state_mut(&mut self) -> &mut Self::Representation {
-        &mut self.state
+    #[test]
+    fn block_size() {
+        assert_eq!(
+            core::mem::size_of::<<KeccakState1600 as PermutationState>::Block>(),
+            200
+        );
+        assert_eq!(
+            read_block(&KeccakState1600::from_state([0x0101_0101_0101_0101; 25])),
+            [1; 200]
+        );
+        assert_eq!(
+            read_block(&KeccakState400::from_state([0x0202; 25])),
+            [2; 50]
+        );
+        assert_eq!(read_block(&KeccakState200::from_state([3; 25])), [3; 25]);
     }
 
-    fn reader<'a>(&'a self) -> Self::StateReader<'a> {
-        StateReader::new(self.get_state())
+    #[test]
+    fn lane_accessors() {
+        let mut state = KeccakState1600::default();
+        for (i, lane) in state.get_state_mut().iter_mut().enumerate() {
+            *lane = i as u64;
+        }
+
+        assert_eq!(state.lane(0, 0), 0);
+        assert_eq!(state.lane(3, 1), 8);
+        assert_eq!(state.lane(4, 4), 24);
+        assert_eq!(state.plane(2), &[10, 11, 12, 13, 14]);
+        assert!(state.rows().eq(state.get_state().chunks(5)));
+        assert!(state.column(2).eq([2, 7, 12, 17, 22]));
+
+        *state.lane_mut(1, 3) = 100;
+        assert_eq!(state.get_state()[16], 100);
+        state.plane_mut(0)[4] = 101;
+        assert_eq!(state.get_state()[4], 101);
+        for lane in state.column_mut(0) {
+            *lane = 0;
+        }
+        for row in state.rows_mut() {
+            row[0] += 1;
+        }
+        assert!(state.column(0).eq([1; 5]));
     }
 
-    fn copy_writer<'a>(&'a mut self) -> Self::CopyWriter<'a> {
-        CopyWriter::new(self.get_state_mut())
+    #[test]
+    fn xor_states() {
+        let mut state = KeccakState1600::default();
+        let mut other = KeccakState1600::default();
+        for (i, (lane, other_lane)) in state
+            .get_state_mut()
+            .iter_mut()
+            .zip(other.get_state_mut().iter_mut())
+            .enumerate()
+        {
+            *lane = i as u64;
+            *other_lane = (i as u64) << 32 | 1;
+        }
+        state ^= &other;
+        for (i, lane) in state.get_state().iter().enumerate() {
+            assert_eq!(*lane, (i as u64) ^ ((i as u64) << 32 | 1));
+        }
+
+        let mut state = KeccakState200::from_state([0x0f; 25]);
+        state ^= &KeccakState200::from_state([0xff; 25]);
+        assert_eq!(state.get_state(), &[0xf0; 25]);
     }
 
-    fn xor_writer<'a>(&'a mut self) -> Self::XorWriter<'a> {
-        XorWriter::new(self.get_state_mut())
+    #[cfg(all(feature = "bytemuck", target_endian = "little"))]
+    #[test]
+    fn bytemuck_views() {
+        let mut state: KeccakState1600 = bytemuck::Zeroable::zeroed();
+        state.get_state_mut()[1] = 0x0807_0605_0403_0201;
+        assert_eq!(state.as_le_bytes(), &read_block(&state));
+        state.as_le_bytes_mut()[0] = 0xff;
+        assert_eq!(state.lane(0, 0), 0xff);
+
+        let state = KeccakState400::from_state([0x0102; 25]);
+        assert_eq!(state.as_le_bytes(), &read_block(&state));
     }
 }
@@ -0,0 +1,193 @@
+//! [PHOTON-256] permutation in the [`crypto-permutation`] framework.
+//!
+//! `Photon256: Permutation`
+//!
+//! This is the 256-bit PHOTON permutation (`d = 8`, 4-bit cells, 12 rounds)
+//! used by the [PHOTON-Beetle] authenticated encryption and hash family. The
+//! byte order of [`PhotonState`] matches the PHOTON-Beetle reference
+//! implementation.
+//!
+//! The implementation is a straightforward, table based one and is not
+//! hardened against cache timing attacks: `SubCells` uses a 16 entry table
+//! lookup indexed by secret data.
+//!
+//! [`crypto-permutation`]: https://crates.io/crates/crypto-permutation
+//! [PHOTON-256]: https://sites.google.com/site/photonhashfunction/
+//! [PHOTON-Beetle]: https://csrc.nist.gov/Projects/lightweight-cryptography/finalists
+
+#![no_std]
+#![allow(clippy::needless_lifetimes)]
+
+use crypto_permutation::{Permutation, PermutationState};
+
+#[cfg(test)]
+mod reference;
+mod state;
+pub use state::PhotonState;
+use state::LEN;
+
+/// Number of rows and columns of the state matrix.
+const D: usize = 8;
+/// Number of rounds of the PHOTON-256 permutation.
+pub const ROUNDS: usize = 12;
+
+/// Round constants.
+const RC: [u8; ROUNDS] = [1, 3, 7, 14, 13, 11, 6, 12, 9, 2, 5, 10];
+/// Internal constants, added to the first column of every row.
+const IC: [u8; D] = [0, 1, 3, 7, 15, 14, 12, 8];
+/// The PRESENT S-box.
+const SBOX: [u8; 16] = [
+    0xc, 0x5, 0x6, 0xb, 0x9, 0x0, 0xa, 0xd, 0x3, 0xe, 0xf, 0x8, 0x4, 0x7, 0x1, 0x2,
+];
+/// The matrix `A^8` applied by `MixColumnsSerial`, where `A` is the serial
+/// matrix with last row `(2, 4, 2, 11, 2, 8, 5, 6)`.
+const MIX_COLUMN_MATRIX: [[u8; D]; D] = [
+    [2, 4, 2, 11, 2, 8, 5, 6],
+    [12, 9, 8, 13, 7, 7, 5, 2],
+    [4, 4, 13, 13, 9, 4, 13, 9],
+    [1, 6, 5, 1, 12, 13, 15, 14],
+    [15, 12, 9, 13, 14, 5, 14, 13],
+    [9, 14, 5, 15, 4, 12, 9, 6],
+    [12, 2, 2, 10, 3, 1, 1, 14],
+    [15, 1, 13, 10, 5, 10, 2, 3],
+];
+
+/// Multiplication in GF(2^4) with reduction polynomial `x^4 + x + 1`.
+#[inline(always)]
+fn gf16_mul(a: u8, b: u8) -> u8 {
+    let mut product = 0;
+    for i in 0..4 {
+        // Branch free conditional xor.
+        product ^= (a << i) & 0_u8.wrapping_sub((b >> i) & 1);
+    }
+    for i in (4..7).rev() {
+        product ^= (0x13 << (i - 4)) & 0_u8.wrapping_sub((product >> i) & 1);
+    }
+    product
+}
+
+type Cells = [[u8; D]; D];
+
+fn unpack(bytes: &[u8; LEN]) -> Cells {
+    let mut cells = [[0; D]; D];
+    for (i, cell) in cells.iter_mut().flatten().enumerate() {
+        *cell = (bytes[i / 2] >> (4 * (i % 2))) & 0xf;
+    }
+    cells
+}
+
+fn pack(cells: &Cells, bytes: &mut [u8; LEN]) {
+    *bytes = [0; LEN];
+    for (i, cell) in cells.iter().flatten().enumerate() {
+        bytes[i / 2] |= cell << (4 * (i % 2));
+    }
+}
+
+fn round(cells: &mut Cells, round_constant: u8) {
+    // AddConstant
+    for (row, ic) in cells.iter_mut().zip(IC) {
+        row[0] ^= round_constant ^ ic;
+    }
+
+    // SubCells
+    for cell in cells.iter_mut().flatten() {
+        *cell = SBOX[usize::from(*cell)];
+    }
+
+    // ShiftRows
+    for (i, row) in cells.iter_mut().enumerate() {
+        row.rotate_left(i);
+    }
+
+    // MixColumnsSerial
+    let mut mixed = [[0; D]; D];
+    for (mixed_row, matrix_row) in mixed.iter_mut().zip(MIX_COLUMN_MATRIX.iter()) {
+        for (column, mixed_cell) in mixed_row.iter_mut().enumerate() {
+            *mixed_cell = matrix_row
+                .iter()
+                .zip(cells.iter())
+                .fold(0, |acc, (&m, row)| acc ^ gf16_mul(m, row[column]));
+        }
+    }
+    *cells = mixed;
+}
+
+/// The PHOTON-256 permutation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Photon256;
+
+impl Permutation for Photon256 {
+    type State = PhotonState;
+
+    fn apply(self, state: &mut Self::State) {
+        let mut cells = unpack(state.get_state());
+        for round_constant in RC {
+            round(&mut cells, round_constant);
+        }
+        pack(&cells, state.get_state_mut());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gf16_mul, Photon256, PhotonState, D, MIX_COLUMN_MATRIX};
+    use crypto_permutation::{Permutation, PermutationState};
+
+    /// The `MixColumnsSerial` matrix is the eighth power of the serial matrix.
+    #[test]
+    fn mix_column_matrix() {
+        let mut serial = [[0; D]; D];
+        for (i, row) in serial.iter_mut().take(D - 1).enumerate() {
+            row[i + 1] = 1;
+        }
+        serial[D - 1] = [2, 4, 2, 11, 2, 8, 5, 6];
+
+        let mut power = serial;
+        for _ in 1..D {
+            let mut product = [[0; D]; D];
+            for i in 0..D {
+                for j in 0..D {
+                    product[i][j] =
+                        (0..D).fold(0, |acc, k| acc ^ gf16_mul(power[i][k], serial[k][j]));
+                }
+            }
+            power = product;
+        }
+        assert_eq!(power, MIX_COLUMN_MATRIX);
+    }
+
+    fn check(input: [u8; 32], expected: [u8; 32]) {
+        let mut reference = input;
+        crate::reference::photon_permutation(&mut reference);
+        assert_eq!(reference, expected);
+        let mut state = PhotonState::from_state(input);
+        Photon256.apply(&mut state);
+        assert_eq!(state.get_state(), &expected);
+    }
+
+    /// Test vectors for the full permutation, as computed by the port of the
+    /// PHOTON-Beetle reference code in the `reference` module.
+    #[test]
+    fn permutation_vectors() {
+        check(
+            [0; 32],
+            [
+                0x10, 0x61, 0x95, 0x70, 0xbd, 0xad, 0x56, 0xc9, 0xa2, 0x1f, 0x07, 0xb4, 0xab, 0x39,
+                0x7e, 0xb4, 0x0a, 0xc5, 0xa1, 0x3b, 0xb8, 0xd8, 0x54, 0x28, 0x06, 0xfd, 0x0f, 0xc4,
+                0x60, 0xd2, 0x27, 0x5e,
+            ],
+        );
+        let mut input = [0; 32];
+        for (i, byte) in input.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        check(
+            input,
+            [
+                0x25, 0x5e, 0x27, 0x0d, 0x37, 0xe9, 0x0d, 0x76, 0xbc, 0xa8, 0x38, 0x53, 0x65, 0xba,
+                0xae, 0x7d, 0x4a, 0xcc, 0x71, 0x33, 0x8f, 0x26, 0x5b, 0x0c, 0x1b, 0x52, 0x09, 0x3f,
+                0x4d, 0x48, 0xee, 0xf9,
+            ],
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Differential tests against a port of the PHOTON-Beetle reference code.
+//!
+//! The port follows `photon.c` of the PHOTON-Beetle reference implementation
+//! submitted to the NIST lightweight cryptography project closely, including
+//! its round constant table, field multiplication and state conversion, so it
+//! is independent of the table layout and helpers used by this crate.
+
+// Keep the index based loops of the reference code.
+#![allow(clippy::needless_range_loop)]
+
+const D: usize = 8;
+const ROUND: usize = 12;
+const S: usize = 4;
+const REDUCTION_POLY: u8 = 0x3;
+const WORDFILTER: u8 = (1 << S) - 1;
+
+const RC: [[u8; 12]; D] = [
+    [1, 3, 7, 14, 13, 11, 6, 12, 9, 2, 5, 10],
+    [0, 2, 6, 15, 12, 10, 7, 13, 8, 3, 4, 11],
+    [2, 0, 4, 13, 14, 8, 5, 15, 10, 1, 6, 9],
+    [6, 4, 0, 9, 10, 12, 1, 11, 14, 5, 2, 13],
+    [14, 12, 8, 1, 2, 4, 9, 3, 6, 13, 10, 5],
+    [15, 13, 9, 0, 3, 5, 8, 2, 7, 12, 11, 4],
+    [13, 15, 11, 2, 1, 7, 10, 0, 5, 14, 9, 6],
+    [9, 11, 15, 6, 5, 3, 14, 4, 1, 10, 13, 2],
+];
+
+const MIX_COL_MATRIX: [[u8; D]; D] = [
+    [2, 4, 2, 11, 2, 8, 5, 6],
+    [12, 9, 8, 13, 7, 7, 5, 2],
+    [4, 4, 13, 13, 9, 4, 13, 9],
+    [1, 6, 5, 1, 12, 13, 15, 14],
+    [15, 12, 9, 13, 14, 5, 14, 13],
+    [9, 14, 5, 15, 4, 12, 9, 6],
+    [12, 2, 2, 10, 3, 1, 1, 14],
+    [15, 1, 13, 10, 5, 10, 2, 3],
+];
+
+const SBOX: [u8; 16] = [12, 5, 6, 11, 9, 0, 10, 13, 3, 14, 15, 8, 4, 7, 1, 2];
+
+fn field_mult(a: u8, b: u8) -> u8 {
+    let mut x = a;
+    let mut ret = 0;
+    for i in 0..S {
+        if (b >> i) & 1 != 0 {
+            ret ^= x;
+        }
+        if (x >> (S - 1)) & 1 != 0 {
+            x <<= 1;
+            x ^= REDUCTION_POLY;
+        } else {
+            x <<= 1;
+        }
+    }
+    ret & WORDFILTER
+}
+
+fn add_key(state: &mut [[u8; D]; D], round: usize) {
+    for i in 0..D {
+        state[i][0] ^= RC[i][round];
+    }
+}
+
+fn sub_cell(state: &mut [[u8; D]; D]) {
+    for i in 0..D {
+        for j in 0..D {
+            state[i][j] = SBOX[usize::from(state[i][j])];
+        }
+    }
+}
+
+fn shift_row(state: &mut [[u8; D]; D]) {
+    let mut tmp = [0; D];
+    for i in 1..D {
+        tmp.copy_from_slice(&state[i]);
+        for j in 0..D {
+            state[i][j] = tmp[(j + i) % D];
+        }
+    }
+}
+
+fn mix_column(state: &mut [[u8; D]; D]) {
+    let mut tmp = [0; D];
+    for j in 0..D {
+        for i in 0..D {
+            let mut sum = 0;
+            for k in 0..D {
+                sum ^= field_mult(MIX_COL_MATRIX[i][k], state[k][j]);
+            }
+            tmp[i] = sum;
+        }
+        for i in 0..D {
+            state[i][j] = tmp[i];
+        }
+    }
+}
+
+/// `PHOTON_Permutation`: apply PHOTON-256 to the 32 byte `state_in_bytes`.
+pub(crate) fn photon_permutation(state_in_bytes: &mut [u8; 32]) {
+    let mut state = [[0; D]; D];
+    for i in 0..D * D {
+        state[i / D][i % D] = (state_in_bytes[i / 2] >> (4 * (i & 1))) & 0xf;
+    }
+    for round in 0..ROUND {
+        add_key(&mut state, round);
+        sub_cell(&mut state);
+        shift_row(&mut state);
+        mix_column(&mut state);
+    }
+    *state_in_bytes = [0; 32];
+    for i in 0..D * D {
+        state_in_bytes[i / 2] |= (state[i / D][i % D] & 0xf) << (4 * (i & 1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::photon_permutation;
+    use crate::{Photon256, PhotonState};
+    use crypto_permutation::{Permutation, PermutationState};
+
+    /// Deterministic xorshift generator for the test states.
+    struct TestRng(u64);
+
+    impl TestRng {
+        fn fill(&mut self, bytes: &mut [u8]) {
+            for byte in bytes {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                *byte = (self.0 >> 56) as u8;
+            }
+        }
+    }
+
+    /// The permutation agrees with the reference on pseudorandom states.
+    #[test]
+    fn photon_matches_reference() {
+        let mut rng = TestRng(0x0123_4567_89ab_cdef);
+        for _ in 0..64 {
+            let mut bytes = [0; 32];
+            rng.fill(&mut bytes);
+            let mut state = PhotonState::from_state(bytes);
+            Photon256.apply(&mut state);
+            photon_permutation(&mut bytes);
+            assert_eq!(state.get_state(), &bytes);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! PHOTON-256 permutation state struct.
+
+use crypto_permutation::io::le_uint_slice_reader::LeU8SliceReader;
+use crypto_permutation::io::le_uint_slice_writer::{LeU8SliceWriter, LeU8SliceXorWriter};
+use crypto_permutation::PermutationState;
+
+/// Number of bytes in a PHOTON-256 state.
+pub(crate) const LEN: usize = 32;
+type StateRepresentation = [u8; LEN];
+
+/// State for the PHOTON-256 permutation. 256 bits, internally represented by
+/// 32 bytes in the byte order of the PHOTON-Beetle reference implementation.
+///
+/// The state consists of an 8 by 8 matrix of 4-bit cells. Cell `(row, column)`
+/// has index `i = 8 * row + column` and is stored in byte `i / 2`; the low
+/// nibble holds the cell with even index and the high nibble the cell with odd
+/// index.
+#[derive(Clone)]
+#[cfg_attr(feature = "debug", derive(Debug, PartialEq))]
+pub struct PhotonState {
+    state: StateRepresentation,
+}
+
+/// Writer into the PHOTON-256 permutation state.
+type CopyWriter<'a> = LeU8SliceWriter<'a>;
+/// Writer that xors into the PHOTON-256 permutation state.
+type XorWriter<'a> = LeU8SliceXorWriter<'a>;
+/// Reader that reads from the PHOTON-256 permutation state.
+type StateReader<'a> = LeU8SliceReader<'a>;
+
+impl Default for PhotonState {
+    fn default() -> Self {
+        Self { state: [0; LEN] }
+    }
+}
+
+impl core::ops::BitXorAssign<&Self> for PhotonState {
+    fn bitxor_assign(&mut self, rhs: &Self) {
+        for (self_chunk, other_chunk) in self.get_state_mut().iter_mut().zip(rhs.get_state().iter())
+        {
+            *self_chunk ^= *other_chunk;
+        }
+    }
+}
+
+#[cfg(feature = "subtle")]
+impl subtle::ConstantTimeEq for PhotonState {
+    fn ct_eq(&self, other: &Self) -> subtle::Choice {
+        self.state[..].ct_eq(&other.state[..])
+    }
+}
+
+impl PermutationState for PhotonState {
+    type Block = [u8; LEN];
+    type CopyWriter<'a> = CopyWriter<'a>;
+    type Representation = StateRepresentation;
+    type StateReader<'a> = StateReader<'a>;
+    type XorWriter<'a> = XorWriter<'a>;
+
+    const SIZE: usize = LEN;
+
+    fn from_state(state: Self::Representation) -> Self {
+        Self { state }
+    }
+
+    fn get_state(&self) -> &Self::Representation {
+        &self.state
+    }
+
+    fn get_state_mut(&mut self) -> &mut Self::Representation {
+        &mut self.state
+    }
+
+    fn reader<'a>(&'a self) -> Self::StateReader<'a> {
+        StateReader::new(self.get_state())
+    }
+
+    fn copy_writer<'a>(&'a mut self) -> Self::CopyWriter<'a> {
+        CopyWriter::new(self.get_state_mut())
+    }
+
+    fn xor_writer<'a>(&'a mut self) -> Self::XorWriter<'a> {
+        XorWriter::new(self.get_state_mut())
+    }
+}
@@ -0,0 +1,144 @@
+//! [Simpira v2] permutations in the [`crypto-permutation`] framework.
+//!
+//! `Simpira2: Permutation`, `Simpira4: Permutation`
+//!
+//! Simpira v2 is a family of permutations on `b` AES blocks, built as a
+//! generalised Feistel structure with `F_{c,b}(x) = AESENC(AESENC(x, C_{c,b}),
+//! 0)` as round function. The constant `C_{c,b}` consists of the four little
+//! endian 32-bit words `0x00 ^ c ^ b`, `0x10 ^ c ^ b`, `0x20 ^ c ^ b` and
+//! `0x30 ^ c ^ b`, where the counter `c` starts at 1 and is incremented after
+//! every application of `F`. This crate implements the variants with
+//! * `b = 2` ([`Simpira2`]): 15 rounds of `x[r + 1] ^= F(x[r])`, and
+//! * `b = 4` ([`Simpira4`]): 15 rounds of `x[r + 1] ^= F(x[r])` followed by
+//!   `x[r + 3] ^= F(x[r + 2])`,
+//!
+//! where `r` is the (zero based) round number and block indices are taken
+//! modulo `b`.
+//!
+//! # Backends
+//! On `x86` and `x86_64` the AES-NI instructions are used when they are
+//! detected at runtime. Otherwise a portable implementation is used. The
+//! portable implementation uses a table based S-box and is therefore not
+//! hardened against cache timing attacks.
+//!
+//! [`crypto-permutation`]: https://crates.io/crates/crypto-permutation
+//! [Simpira v2]: https://eprint.iacr.org/2016/122
+
+#![no_std]
+#![allow(clippy::needless_lifetimes)]
+
+use crypto_permutation::{Permutation, PermutationState};
+
+mod portable;
+mod state;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86;
+
+use state::BLOCK;
+pub use state::{SimpiraState2, SimpiraState4};
+
+/// Number of rounds of the Simpira v2 permutations with `b = 2` and `b = 4`.
+pub const ROUNDS: usize = 15;
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+cpufeatures::new!(aes_intrinsics, "aes", "sse2");
+
+/// The round constant `C_{c,b}`, as the bytes of an AES block.
+fn round_constant(c: u32, b: u32) -> [u8; BLOCK] {
+    let mut constant = [0; BLOCK];
+    for (i, word) in (0_u32..).zip(constant.chunks_exact_mut(4)) {
+        word.copy_from_slice(&((0x10 * i) ^ c ^ b).to_le_bytes());
+    }
+    constant
+}
+
+/// Iterator over the applications of `F` in Simpira v2 with `blocks` blocks,
+/// yielding the counter `c` and the indices of the source and destination
+/// blocks.
+fn schedule(blocks: usize) -> impl Iterator<Item = (u32, usize, usize)> {
+    let per_round = blocks / 2;
+    (0..ROUNDS * per_round).map(move |i| {
+        let (round, j) = (i / per_round, i % per_round);
+        let src = (round + 2 * j) % blocks;
+        (i as u32 + 1, src, (src + 1) % blocks)
+    })
+}
+
+/// Apply Simpira v2 with `B` blocks to `state`, selecting the fastest backend
+/// available.
+fn simpira<const B: usize>(state: &mut [u8]) {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    if aes_intrinsics::get() {
+        // SAFETY: the `aes` and `sse2` target features are available.
+        unsafe { x86::simpira::<B>(state) };
+        return;
+    }
+    portable::simpira::<B>(state);
+}
+
+/// The Simpira v2 permutation with `b = 2`, a 256 bit permutation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Simpira2;
+
+impl Permutation for Simpira2 {
+    type State = SimpiraState2;
+
+    fn apply(self, state: &mut Self::State) {
+        simpira::<2>(state.get_state_mut());
+    }
+}
+
+/// The Simpira v2 permutation with `b = 4`, a 512 bit permutation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Simpira4;
+
+impl Permutation for Simpira4 {
+    type State = SimpiraState4;
+
+    fn apply(self, state: &mut Self::State) {
+        simpira::<4>(state.get_state_mut());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{round_constant, schedule};
+
+    #[test]
+    fn constants() {
+        assert_eq!(
+            round_constant(1, 2),
+            [3, 0, 0, 0, 0x13, 0, 0, 0, 0x23, 0, 0, 0, 0x33, 0, 0, 0]
+        );
+        assert!(schedule(2).take(3).eq([(1, 0, 1), (2, 1, 0), (3, 0, 1)]));
+        assert!(schedule(4)
+            .take(4)
+            .eq([(1, 0, 1), (2, 2, 3), (3, 1, 2), (4, 3, 0)]));
+        assert_eq!(schedule(2).count(), 15);
+        assert_eq!(schedule(4).count(), 30);
+    }
+
+    /// The AES-NI and portable backends compute the same permutation.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[test]
+    fn backends_agree() {
+        if !super::aes_intrinsics::get() {
+            return;
+        }
+        let mut state = [0_u8; 64];
+        for (i, byte) in state.iter_mut().enumerate() {
+            *byte = (i as u8).wrapping_mul(0x9d);
+        }
+
+        let mut portable = state;
+        super::portable::simpira::<2>(&mut portable[..32]);
+        // SAFETY: the `aes` and `sse2` target features are available.
+        unsafe { super::x86::simpira::<2>(&mut state[..32]) };
+        assert_eq!(portable, state);
+
+        super::portable::simpira::<4>(&mut portable);
+        // SAFETY: the `aes` and `sse2` target features are available.
+        unsafe { super::x86::simpira::<4>(&mut state) };
+        assert_eq!(portable, state);
+    }
+}
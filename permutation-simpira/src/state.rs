@@ -0,0 +1,98 @@
+//! Simpira permutation state structs.
+
+use crypto_permutation::io::le_uint_slice_reader::LeU8SliceReader;
+use crypto_permutation::io::le_uint_slice_writer::{LeU8SliceWriter, LeU8SliceXorWriter};
+use crypto_permutation::PermutationState;
+
+/// Size in bytes of a single AES block.
+pub(crate) const BLOCK: usize = 16;
+
+macro_rules! impl_simpira_state {
+    (
+        $(#[$meta:meta])*
+        $name:ident, $blocks:literal
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone)]
+        #[cfg_attr(feature = "debug", derive(Debug, PartialEq))]
+        pub struct $name {
+            state: [u8; $blocks * BLOCK],
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self {
+                    state: [0; $blocks * BLOCK],
+                }
+            }
+        }
+
+        impl core::ops::BitXorAssign<&Self> for $name {
+            fn bitxor_assign(&mut self, rhs: &Self) {
+                for (self_chunk, other_chunk) in
+                    self.get_state_mut().iter_mut().zip(rhs.get_state().iter())
+                {
+                    *self_chunk ^= *other_chunk;
+                }
+            }
+        }
+
+        #[cfg(feature = "subtle")]
+        impl subtle::ConstantTimeEq for $name {
+            fn ct_eq(&self, other: &Self) -> subtle::Choice {
+                self.state[..].ct_eq(&other.state[..])
+            }
+        }
+
+        impl PermutationState for $name {
+            type Block = [u8; $blocks * BLOCK];
+            /// Writer into the simpira permutation state.
+            type CopyWriter<'a> = LeU8SliceWriter<'a>;
+            type Representation = [u8; $blocks * BLOCK];
+            /// Reader that reads from the simpira permutation state.
+            type StateReader<'a> = LeU8SliceReader<'a>;
+            /// Writer that xors into the simpira permutation state.
+            type XorWriter<'a> = LeU8SliceXorWriter<'a>;
+
+            const SIZE: usize = $blocks * BLOCK;
+
+            fn from_state(state: Self::Representation) -> Self {
+                Self { state }
+            }
+
+            fn get_state(&self) -> &Self::Representation {
+                &self.state
+            }
+
+            fn get_state_mut(&mut self) -> &mut Self::Representation {
+                &mut self.state
+            }
+
+            fn reader<'a>(&'a self) -> Self::StateReader<'a> {
+                LeU8SliceReader::new(self.get_state())
+            }
+
+            fn copy_writer<'a>(&'a mut self) -> Self::CopyWriter<'a> {
+                LeU8SliceWriter::new(self.get_state_mut())
+            }
+
+            fn xor_writer<'a>(&'a mut self) -> Self::XorWriter<'a> {
+                LeU8SliceXorWriter::new(self.get_state_mut())
+            }
+        }
+    };
+}
+
+impl_simpira_state!(
+    /// 256 bit state for the Simpira v2 permutation with `b = 2`. Two AES
+    /// blocks of 16 bytes each, stored consecutively.
+    SimpiraState2,
+    2
+);
+
+impl_simpira_state!(
+    /// 512 bit state for the Simpira v2 permutation with `b = 4`. Four AES
+    /// blocks of 16 bytes each, stored consecutively.
+    SimpiraState4,
+    4
+);
@@ -0,0 +1,35 @@
+//! Implementation of the Simpira permutations using the AES-NI instructions.
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+use super::state::BLOCK;
+use super::{round_constant, schedule};
+
+/// Apply Simpira v2 with `B` blocks to `state`, which must be `B * 16` bytes
+/// long.
+///
+/// # Safety
+/// The `aes` and `sse2` target features must be available.
+#[target_feature(enable = "aes,sse2")]
+pub(crate) unsafe fn simpira<const B: usize>(state: &mut [u8]) {
+    debug_assert_eq!(state.len(), B * BLOCK);
+    let zero = _mm_setzero_si128();
+    let mut x = [zero; B];
+    for (x, chunk) in x.iter_mut().zip(state.chunks_exact(BLOCK)) {
+        *x = _mm_loadu_si128(chunk.as_ptr().cast());
+    }
+
+    for (c, src, dst) in schedule(B) {
+        let constant = round_constant(c, B as u32);
+        let constant = _mm_loadu_si128(constant.as_ptr().cast());
+        let f = _mm_aesenc_si128(_mm_aesenc_si128(x[src], constant), zero);
+        x[dst] = _mm_xor_si128(x[dst], f);
+    }
+
+    for (x, chunk) in x.iter().zip(state.chunks_exact_mut(BLOCK)) {
+        _mm_storeu_si128(chunk.as_mut_ptr().cast(), *x);
+    }
+}
@@ -0,0 +1,138 @@
+//! [Subterranean 2.0] round function and permutation in the
+//! [`crypto-permutation`] framework.
+//!
+//! `Subterranean: Permutation`
+//!
+//! The Subterranean 2.0 round function acts on a 257-bit state. It is the
+//! composition `π ∘ θ ∘ ι ∘ χ` of
+//! * `χ`: `s[i] += (s[i + 1] + 1) * s[i + 2]`,
+//! * `ι`: `s[0] += 1`,
+//! * `θ`: `s[i] += s[i + 3] + s[i + 8]`,
+//! * `π`: `s[i] = s[12 * i]`,
+//!
+//! where all indices are taken modulo 257. [`SubterraneanP`] iterates this
+//! round function, and [`SubterraneanRound`] is the single round used by the
+//! Subterranean 2.0 duplex.
+//!
+//! This implementation favours simplicity over speed: every round unpacks the
+//! state into individual bits.
+//!
+//! [`crypto-permutation`]: https://crates.io/crates/crypto-permutation
+//! [Subterranean 2.0]: https://cs.ru.nl/~joan/subterranean.html
+
+#![no_std]
+#![allow(clippy::needless_lifetimes)]
+
+use crypto_permutation::{Permutation, PermutationState};
+
+mod state;
+pub use state::SubterraneanState;
+use state::{WIDTH, WORDS};
+
+/// Apply a single Subterranean round to the packed state `state`.
+// The cyclic indexing is most clearly expressed using index loops.
+#[allow(clippy::needless_range_loop)]
+fn round(state: &mut [u32; WORDS]) {
+    let mut s = [0_u8; WIDTH];
+    for i in 0..WIDTH {
+        s[i] = ((state[i / 32] >> (i % 32)) & 1) as u8;
+    }
+
+    // χ
+    let mut t = [0_u8; WIDTH];
+    for i in 0..WIDTH {
+        t[i] = s[i] ^ ((s[(i + 1) % WIDTH] ^ 1) & s[(i + 2) % WIDTH]);
+    }
+
+    // ι
+    t[0] ^= 1;
+
+    // θ
+    for i in 0..WIDTH {
+        s[i] = t[i] ^ t[(i + 3) % WIDTH] ^ t[(i + 8) % WIDTH];
+    }
+
+    // π, combined with packing
+    *state = [0; WORDS];
+    for i in 0..WIDTH {
+        state[i / 32] |= u32::from(s[(12 * i) % WIDTH]) << (i % 32);
+    }
+}
+
+/// `ROUNDS` iterations of the Subterranean 2.0 round function.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SubterraneanP<const ROUNDS: usize>;
+
+/// A single round of the Subterranean 2.0 round function.
+pub type SubterraneanRound = SubterraneanP<1>;
+
+impl<const ROUNDS: usize> SubterraneanP<ROUNDS> {
+    const _ROUNDS_CHECK: () = {
+        assert!(ROUNDS > 0);
+    };
+}
+
+impl<const ROUNDS: usize> Permutation for SubterraneanP<ROUNDS> {
+    type State = SubterraneanState;
+
+    fn apply(self, state: &mut Self::State) {
+        for _ in 0..ROUNDS {
+            round(state.get_state_mut());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SubterraneanP, SubterraneanRound, SubterraneanState};
+    use crypto_permutation::{Permutation, PermutationState, Reader, Writer};
+
+    fn set_bits(state: &SubterraneanState) -> impl Iterator<Item = usize> + '_ {
+        (0..257).filter(|&i| state.bit(i))
+    }
+
+    /// Starting from the zero state, only `ι` introduces non-zero bits, so the
+    /// result of the first rounds can be derived by hand from the
+    /// specification.
+    #[test]
+    fn rounds_from_zero_state() {
+        let mut state = SubterraneanState::default();
+        SubterraneanRound::default().apply(&mut state);
+        assert!(set_bits(&state).eq([0, 64, 85]));
+
+        SubterraneanRound::default().apply(&mut state);
+        let expected = [
+            21, 42, 48, 91, 112, 114, 133, 155, 157, 176, 178, 199, 214, 221, 242,
+        ];
+        assert!(set_bits(&state).eq(expected));
+
+        let mut state2 = SubterraneanState::default();
+        SubterraneanP::<2>.apply(&mut state2);
+        assert_eq!(state.get_state(), state2.get_state());
+    }
+
+    /// The unused bits of the packed representation stay zero.
+    #[test]
+    fn unused_bits_stay_zero() {
+        let mut representation = [0xa5a5_5a5a; 9];
+        representation[8] = 1;
+        let mut state = SubterraneanState::from_state(representation);
+        SubterraneanP::<10>.apply(&mut state);
+        assert!(state.get_state()[8] <= 1);
+    }
+
+    /// The byte interface covers the first 256 bits of the state.
+    #[test]
+    fn byte_interface() {
+        let mut state = SubterraneanState::default();
+        let mut writer = state.copy_writer();
+        assert_eq!(writer.capacity(), 32);
+        writer.write_bytes(&[0xff; 32]).unwrap();
+        writer.finish();
+        assert!(set_bits(&state).eq(0..256));
+
+        let mut output = [0_u8; 32];
+        state.reader().write_to_slice(&mut output).unwrap();
+        assert_eq!(output, [0xff; 32]);
+    }
+}
@@ -0,0 +1,116 @@
+//! Subterranean permutation state struct.
+
+use crypto_permutation::PermutationState;
+
+/// Number of bits of the Subterranean state.
+pub(crate) const WIDTH: usize = 257;
+/// Number of `u32` words used to store the state.
+pub(crate) const WORDS: usize = 9;
+/// Number of full words, accessible through the byte interface.
+const FULL_WORDS: usize = 8;
+type StateRepresentation = [u32; WORDS];
+
+/// State for the Subterranean permutation. 257 bits, internally represented
+/// by 9 `u32`s in which bit `i` of the state is bit `i % 32` of word `i / 32`.
+/// Only the least significant bit of the last word is used; the other bits of
+/// the last word must be zero.
+///
+/// The byte interface ([`PermutationState::reader`],
+/// [`PermutationState::copy_writer`] and [`PermutationState::xor_writer`])
+/// covers the first 256 bits, i.e. 32 bytes, in little endian order. The last
+/// bit can only be accessed through [`PermutationState::get_state`] and
+/// [`PermutationState::get_state_mut`].
+#[derive(Clone)]
+#[cfg_attr(feature = "debug", derive(Debug, PartialEq))]
+pub struct SubterraneanState {
+    state: StateRepresentation,
+}
+
+/// Writer into the subterranean permutation state.
+///
+/// Does nothing fancy except for little-endian to native-endian conversion.
+type CopyWriter<'a> = crypto_permutation::io::le_uint_slice_writer::LeU32SliceWriter<'a>;
+/// Writer that xors into the subterranean permutation state.
+///
+/// Does nothing fancy except for little-endian to native-endian conversion.
+type XorWriter<'a> = crypto_permutation::io::le_uint_slice_writer::LeU32SliceXorWriter<'a>;
+/// Reader that reads from the subterranean permutation state and outputs it's
+/// bytes in little endian order.
+type StateReader<'a> = crypto_permutation::io::le_uint_slice_reader::LeU32SliceReader<'a>;
+
+impl SubterraneanState {
+    /// Get bit `index` of the state.
+    ///
+    /// # Panics
+    /// Panics when `index >= 257`.
+    pub fn bit(&self, index: usize) -> bool {
+        assert!(index < WIDTH);
+        (self.state[index / 32] >> (index % 32)) & 1 == 1
+    }
+
+    /// Flip bit `index` of the state.
+    ///
+    /// # Panics
+    /// Panics when `index >= 257`.
+    pub fn flip_bit(&mut self, index: usize) {
+        assert!(index < WIDTH);
+        self.state[index / 32] ^= 1 << (index % 32);
+    }
+}
+
+impl Default for SubterraneanState {
+    fn default() -> Self {
+        Self { state: [0; WORDS] }
+    }
+}
+
+impl core::ops::BitXorAssign<&Self> for SubterraneanState {
+    fn bitxor_assign(&mut self, rhs: &Self) {
+        for (self_chunk, other_chunk) in self.get_state_mut().iter_mut().zip(rhs.get_state().iter())
+        {
+            *self_chunk ^= *other_chunk;
+        }
+    }
+}
+
+#[cfg(feature = "subtle")]
+impl subtle::ConstantTimeEq for SubterraneanState {
+    fn ct_eq(&self, other: &Self) -> subtle::Choice {
+        self.state[..].ct_eq(&other.state[..])
+    }
+}
+
+impl PermutationState for SubterraneanState {
+    type Block = [u8; 4 * FULL_WORDS];
+    type CopyWriter<'a> = CopyWriter<'a>;
+    type Representation = StateRepresentation;
+    type StateReader<'a> = StateReader<'a>;
+    type XorWriter<'a> = XorWriter<'a>;
+
+    const SIZE: usize = 4 * FULL_WORDS;
+
+    fn from_state(state: Self::Representation) -> Self {
+        debug_assert!(state[WORDS - 1] <= 1, "unused state bits must be zero");
+        Self { state }
+    }
+
+    fn get_state(&self) -> &Self::Representation {
+        &self.state
+    }
+
+    fn get_state_mut(&mut self) -> &mut Self::Representation {
+        &mut self.state
+    }
+
+    fn reader<'a>(&'a self) -> Self::StateReader<'a> {
+        StateReader::new(&self.get_state()[..FULL_WORDS])
+    }
+
+    fn copy_writer<'a>(&'a mut self) -> Self::CopyWriter<'a> {
+        CopyWriter::new(&mut self.get_state_mut()[..FULL_WORDS])
+    }
+
+    fn xor_writer<'a>(&'a mut self) -> Self::XorWriter<'a> {
+        XorWriter::new(&mut self.get_state_mut()[..FULL_WORDS])
+    }
+}
@@ -0,0 +1,93 @@
+//! Size optimized Xoodoo implementation.
+//!
+//! The round function is a plain loop over the lanes, and a single non-generic
+//! function implements all numbers of rounds. This trades speed for a small
+//! code size, for targets where flash is the constraint.
+
+/// Number of lanes in a Xoodoo state.
+const LEN: usize = 12;
+
+/// Apply θ and ρ_west to `a`.
+#[inline(always)]
+pub(crate) fn theta_rho_west(a: &mut [u32; LEN]) {
+    let mut e = [0; 4];
+    for x in 0..4 {
+        let p = a[x] ^ a[x + 4] ^ a[x + 8];
+        e[(x + 1) % 4] = p.rotate_left(5) ^ p.rotate_left(14);
+    }
+    for (i, lane) in a.iter_mut().enumerate() {
+        *lane ^= e[i % 4];
+    }
+    a[4..8].rotate_right(1);
+    for lane in a[8..].iter_mut() {
+        *lane = lane.rotate_left(11);
+    }
+}
+
+/// Apply ρ_east to `a`.
+#[inline(always)]
+pub(crate) fn rho_east(a: &mut [u32; LEN]) {
+    for lane in a[4..8].iter_mut() {
+        *lane = lane.rotate_left(1);
+    }
+    a[8..].rotate_right(2);
+    for lane in a[8..].iter_mut() {
+        *lane = lane.rotate_left(8);
+    }
+}
+
+/// Apply ι with round constant `rc` and χ to `a`.
+#[cfg(any(all(feature = "opt-size", not(feature = "simd")), test))]
+#[inline(always)]
+fn iota_chi(a: &mut [u32; LEN], rc: u32) {
+    a[0] ^= rc;
+    for x in 0..4 {
+        let (a0, a1, a2) = (a[x], a[x + 4], a[x + 8]);
+        a[x] ^= !a1 & a2;
+        a[x + 4] ^= !a2 & a0;
+        a[x + 8] ^= !a0 & a1;
+    }
+}
+
+/// Apply Xoodoo with `rounds` rounds to `state`.
+///
+/// # Panics
+/// Panics when `rounds > 12`.
+#[cfg(any(all(feature = "opt-size", not(feature = "simd")), test))]
+#[inline(never)]
+fn permute(state: &mut [u32; LEN], rounds: usize) {
+    for &rc in &crate::constants::RC[12 - rounds..] {
+        theta_rho_west(state);
+        iota_chi(state, rc);
+        rho_east(state);
+    }
+}
+
+/// Apply Xoodoo with `ROUNDS` rounds to `state`.
+#[cfg(all(feature = "opt-size", not(feature = "simd")))]
+#[inline]
+pub(crate) fn xoodoo<const ROUNDS: usize>(state: &mut [u32; LEN]) {
+    permute(state, ROUNDS);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::permute;
+
+    #[test]
+    fn matches_xoodoo_p() {
+        let state: [u32; 12] = core::array::from_fn(|i| (i as u32 + 1).wrapping_mul(0x9e37_79b9));
+
+        let mut compact = state;
+        let mut expected = state;
+        permute(&mut compact, 12);
+        xoodoo_p::xoodoo::<12>(&mut expected);
+        assert_eq!(compact, expected);
+
+        let mut compact = state;
+        let mut expected = state;
+        permute(&mut compact, 6);
+        xoodoo_p::xoodoo::<6>(&mut expected);
+        assert_eq!(compact, expected);
+    }
+}
@@ -0,0 +1,97 @@
+//! Xoodoo as a `const fn`, for computing constants at compile time.
+//!
+//! Mutable references are not allowed in `const fn` on the minimum supported
+//! Rust version, so the state is passed by value and all loops are `while`
+//! loops.
+
+use crate::constants::RC;
+
+/// Number of lanes in a Xoodoo state.
+const LEN: usize = 12;
+
+/// A Xoodoo round with round constant `rc`.
+const fn round(a: [u32; LEN], rc: u32) -> [u32; LEN] {
+    // θ
+    let mut e = [0; 4];
+    let mut x = 0;
+    while x < 4 {
+        let p = a[x] ^ a[x + 4] ^ a[x + 8];
+        e[(x + 1) % 4] = p.rotate_left(5) ^ p.rotate_left(14);
+        x += 1;
+    }
+    let mut b = [0; LEN];
+    let mut i = 0;
+    while i < LEN {
+        b[i] = a[i] ^ e[i % 4];
+        i += 1;
+    }
+
+    // ρ_west and ι
+    let mut a = [0; LEN];
+    let mut x = 0;
+    while x < 4 {
+        a[x] = b[x];
+        a[x + 4] = b[(x + 3) % 4 + 4];
+        a[x + 8] = b[x + 8].rotate_left(11);
+        x += 1;
+    }
+    a[0] ^= rc;
+
+    // χ
+    let mut x = 0;
+    while x < 4 {
+        b[x] = a[x] ^ (!a[x + 4] & a[x + 8]);
+        b[x + 4] = a[x + 4] ^ (!a[x + 8] & a[x]);
+        b[x + 8] = a[x + 8] ^ (!a[x] & a[x + 4]);
+        x += 1;
+    }
+
+    // ρ_east
+    let mut x = 0;
+    while x < 4 {
+        a[x] = b[x];
+        a[x + 4] = b[x + 4].rotate_left(1);
+        a[x + 8] = b[(x + 2) % 4 + 8].rotate_left(8);
+        x += 1;
+    }
+    a
+}
+
+/// Apply Xoodoo with `rounds` rounds to the lanes `state` (in the order of
+/// [`XoodooState`](crate::XoodooState)'s representation) and return the
+/// result. Can be evaluated at compile time.
+///
+/// Use [`XoodooP`](crate::XoodooP) at runtime, which is faster.
+///
+/// # Panics
+/// Panics (or fails to compile when evaluated at compile time) when `rounds` is
+/// larger than 12.
+pub const fn xoodoo_const(mut state: [u32; LEN], rounds: usize) -> [u32; LEN] {
+    assert!(rounds <= 12);
+    let mut i = 12 - rounds;
+    while i < 12 {
+        state = round(state, RC[i]);
+        i += 1;
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::xoodoo_const;
+
+    /// Evaluated at compile time.
+    const ZERO_PERMUTED: [u32; 12] = xoodoo_const([0; 12], 12);
+
+    #[test]
+    fn matches_xoodoo_p() {
+        let mut expected = [0; 12];
+        xoodoo_p::xoodoo::<12>(&mut expected);
+        assert_eq!(ZERO_PERMUTED, expected);
+
+        let state: [u32; 12] = core::array::from_fn(|i| (i as u32 + 1).wrapping_mul(0x9e37_79b9));
+        let mut expected = state;
+        xoodoo_p::xoodoo::<6>(&mut expected);
+        assert_eq!(xoodoo_const(state, 6), expected);
+    }
+}
@@ -0,0 +1,8 @@
+//! Constants of the Xoodoo step mappings.
+
+/// Round constants of Xoodoo, in the order in which they are used by the 12
+/// round permutation. Xoodoo with `n` rounds uses the last `n` constants.
+pub(crate) const RC: [u32; 12] = [
+    0x00000058, 0x00000038, 0x000003c0, 0x000000d0, 0x00000120, 0x00000014, 0x00000060, 0x0000002c,
+    0x00000380, 0x000000f0, 0x000001a0, 0x00000012,
+];
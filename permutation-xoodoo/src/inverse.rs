@@ -0,0 +1,99 @@
+//! Inverse of the Xoodoo permutation.
+//!
+//! The inverse round applies the inverses of the step mappings in reverse
+//! order. χ is an involution on the three bit columns of Xoodoo, and the
+//! inverses of ρ_east and ρ_west are the opposite plane shifts. The inverse of
+//! the column parity mixer θ is computed from the column parity `P` of its
+//! output: with `T` the shift by `(1, 5)` plus the shift by `(1, 14)`, θ maps
+//! `P` to `(1 + T) P`, and `(1 + T)^-1 = (1 + T)^127 = (1 + T)(1 + T^2)(1 +
+//! T^4) ... (1 + T^64)` because `(1 + T)^128 = 1 + T^128 = 1`.
+
+use crate::constants::RC;
+
+/// A plane of four lanes.
+type Plane = [u32; 4];
+
+/// Shift the plane `p` by `(t, v)`: lane `x` of the result is lane `x - t` of
+/// `p` rotated left by `v`.
+#[inline(always)]
+fn shift(p: Plane, t: usize, v: u32) -> Plane {
+    core::array::from_fn(|x| p[(x + 4 - t % 4) % 4].rotate_left(v % 32))
+}
+
+/// `a ^ b` lanewise.
+#[inline(always)]
+fn xor(a: Plane, b: Plane) -> Plane {
+    core::array::from_fn(|x| a[x] ^ b[x])
+}
+
+/// The inverse of the Xoodoo round with round constant `rc`.
+fn inverse_round(a: &mut [Plane; 3], rc: u32) {
+    // ρ_east^-1
+    a[1] = shift(a[1], 0, 31);
+    a[2] = shift(a[2], 2, 24);
+
+    // χ^-1 = χ
+    let [a0, a1, a2] = *a;
+    for x in 0..4 {
+        a[0][x] ^= !a1[x] & a2[x];
+        a[1][x] ^= !a2[x] & a0[x];
+        a[2][x] ^= !a0[x] & a1[x];
+    }
+
+    // ι^-1 = ι
+    a[0][0] ^= rc;
+
+    // ρ_west^-1
+    a[1] = shift(a[1], 3, 0);
+    a[2] = shift(a[2], 0, 21);
+
+    // θ^-1
+    let mut p = xor(xor(a[0], a[1]), a[2]);
+    for i in 0..7 {
+        let t = xor(shift(p, 1 << i, 5 << i), shift(p, 1 << i, 14 << i));
+        p = xor(p, t);
+    }
+    let e = xor(shift(p, 1, 5), shift(p, 1, 14));
+    for plane in a.iter_mut() {
+        *plane = xor(*plane, e);
+    }
+}
+
+/// Apply the inverse of Xoodoo with `rounds` rounds to `state`.
+///
+/// # Panics
+/// Panics when `rounds > 12`.
+pub(crate) fn xoodoo_inverse(state: &mut [u32; 12], rounds: usize) {
+    assert!(rounds <= 12);
+    let mut a: [Plane; 3] = core::array::from_fn(|y| state[4 * y..4 * y + 4].try_into().unwrap());
+    for &rc in RC[12 - rounds..].iter().rev() {
+        inverse_round(&mut a, rc);
+    }
+    for (y, plane) in a.iter().enumerate() {
+        state[4 * y..4 * y + 4].copy_from_slice(plane);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::xoodoo_inverse;
+
+    #[test]
+    fn inverts_xoodoo() {
+        let state: [u32; 12] = core::array::from_fn(|i| (i as u32 + 1).wrapping_mul(0x9e37_79b9));
+        let mut permuted = state;
+        xoodoo_p::xoodoo::<12>(&mut permuted);
+        xoodoo_inverse(&mut permuted, 12);
+        assert_eq!(permuted, state);
+
+        let mut permuted = state;
+        xoodoo_p::xoodoo::<6>(&mut permuted);
+        xoodoo_inverse(&mut permuted, 6);
+        assert_eq!(permuted, state);
+
+        let mut permuted = state;
+        xoodoo_inverse(&mut permuted, 1);
+        xoodoo_p::xoodoo::<1>(&mut permuted);
+        assert_eq!(permuted, state);
+    }
+}
@@ -2,19 +2,82 @@
 //!
 //! `Xoodoo: Permutation`
 //!
+//! [`XoodooDyn`] takes the number of rounds at runtime instead of as a const
+//! generic.
+//!
+//! [`xoodoo_const`] is a `const fn` version of the permutation, to compute
+//! constants at compile time.
+//!
+//! The inverse permutation is [`XoodooPInv`]; both implement
+//! [`InvertiblePermutation`].
+//!
+//! [`XoodooP`] implements [`ParallelPermutation`]. With the `simd` feature
+//! SIMD kernels process several states at once.
+//!
 //! Uses the [`xoodoo-p` crate] internally for the actual permutation
-//! invocation.
+//! invocation, unless a SIMD implementation is enabled and available or the
+//! `opt-size` feature is enabled.
+//!
+//! # Features
+//! * `simd`: Use SIMD implementations of the permutation: on `x86` and
+//!   `x86_64` runtime detected SSE2 and AVX2 kernels, and on `wasm32` a SIMD128
+//!   kernel when the `simd128` target feature is enabled at compile time. On
+//!   `x86` and `x86_64` [`ParallelPermutation::apply_parallel`] processes
+//!   eight (AVX2) or four (SSE2) states at once.
+//! * `portable-simd`: Use a single SIMD implementation based on the unstable
+//!   `core::simd` module on all architectures, instead of the architecture
+//!   specific kernels. Also provides parallel kernels for eight and four
+//!   states. Implies `simd`; requires a nightly compiler.
+//! * `opt-size`: Use a compact looped implementation of the permutation,
+//!   optimized for code size instead of speed, for example for bootloaders.
+//!   Has no effect when `simd` is enabled.
+//! * `masked`: Provide the first-order masked Xoodoo implementation
+//!   [`XoodooPMasked`] acting on the two-share [`MaskedXoodooState`] in the
+//!   [`masked`] module, as a countermeasure against side-channel analysis.
+//! * `subtle`: Implements `subtle::ConstantTimeEq` for the state.
+//! * `bytemuck`: Implements `bytemuck::Pod` and
+//!   `bytemuck::TransparentWrapper<[u32; 12]>` for the state, and provides
+//!   zero-copy byte views `as_le_bytes` and `as_le_bytes_mut` of it on little
+//!   endian targets.
+//! * `debug`: Used for tests. Don't use!
 //!
 //! [`crypto-permutation`]: https://crates.io/crates/crypto-permutation
 //! [`xoodoo-p` crate]: https://crates.io/crates/xoodoo-p
 //! [Xoodoo]: https://keccak.team/xoodoo.html
 
 #![no_std]
+#![cfg_attr(feature = "portable-simd", feature(portable_simd))]
 #![allow(clippy::needless_lifetimes)]
 
-use crypto_permutation::{Permutation, PermutationState};
-use xoodoo_p::{xoodoo, MAX_ROUNDS};
+#[cfg(all(feature = "opt-size", not(feature = "simd")))]
+use compact::xoodoo;
+use crypto_permutation::{
+    InvertiblePermutation, ParallelPermutation, Permutation, PermutationState,
+};
+#[cfg(feature = "simd")]
+use simd::xoodoo;
+#[cfg(not(any(feature = "simd", feature = "opt-size")))]
+use xoodoo_p::xoodoo;
+use xoodoo_p::MAX_ROUNDS;
 
+#[cfg(any(
+    all(feature = "opt-size", not(feature = "simd")),
+    feature = "masked",
+    test
+))]
+mod compact;
+mod const_fn;
+pub use const_fn::xoodoo_const;
+mod constants;
+mod inverse;
+#[cfg(feature = "masked")]
+pub mod masked;
+#[cfg(feature = "masked")]
+pub use masked::{ForkRng, MaskedXoodooState, XoodooPMasked};
+#[cfg(test)]
+mod reference;
+#[cfg(feature = "simd")]
+mod simd;
 mod state;
 pub use state::XoodooState;
 
@@ -36,3 +99,162 @@ impl<const ROUNDS: usize> Permutation for XoodooP<ROUNDS> {
         xoodoo::<ROUNDS>(state.get_state_mut());
     }
 }
+
+impl<const ROUNDS: usize> ParallelPermutation for XoodooP<ROUNDS> {
+    #[cfg(feature = "simd")]
+    fn parallelism(self) -> usize {
+        simd::parallelism()
+    }
+
+    #[cfg(feature = "simd")]
+    fn apply_parallel(self, states: &mut [Self::State]) {
+        simd::xoodoo_parallel::<ROUNDS>(states);
+    }
+}
+
+impl<const ROUNDS: usize> InvertiblePermutation for XoodooP<ROUNDS> {
+    type Inverse = XoodooPInv<ROUNDS>;
+
+    fn inverse(self) -> Self::Inverse {
+        XoodooPInv
+    }
+}
+
+/// Call the function `$f` with const generic number of rounds `$rounds`, a
+/// runtime value in `1..=12`.
+macro_rules! dispatch_rounds {
+    ($rounds:expr, $($f:ident)::+, $arg:expr) => {
+        match $rounds {
+            1 => $($f)::+::<1>($arg),
+            2 => $($f)::+::<2>($arg),
+            3 => $($f)::+::<3>($arg),
+            4 => $($f)::+::<4>($arg),
+            5 => $($f)::+::<5>($arg),
+            6 => $($f)::+::<6>($arg),
+            7 => $($f)::+::<7>($arg),
+            8 => $($f)::+::<8>($arg),
+            9 => $($f)::+::<9>($arg),
+            10 => $($f)::+::<10>($arg),
+            11 => $($f)::+::<11>($arg),
+            12 => $($f)::+::<12>($arg),
+            _ => unreachable!(),
+        }
+    };
+}
+
+/// Xoodoo permutation with the number of rounds chosen at runtime. Use
+/// [`XoodooP`] when the number of rounds is known at compile time.
+///
+/// Defaults to the full 12 rounds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct XoodooDyn {
+    rounds: usize,
+}
+
+impl XoodooDyn {
+    /// Create a Xoodoo permutation with `rounds` rounds. Returns `None` unless
+    /// `1 <= rounds <= 12`.
+    pub const fn new(rounds: usize) -> Option<Self> {
+        if rounds > 0 && rounds <= MAX_ROUNDS {
+            Some(Self { rounds })
+        } else {
+            None
+        }
+    }
+
+    /// Number of rounds of the permutation.
+    pub const fn rounds(self) -> usize {
+        self.rounds
+    }
+}
+
+impl Default for XoodooDyn {
+    fn default() -> Self {
+        Self { rounds: MAX_ROUNDS }
+    }
+}
+
+impl Permutation for XoodooDyn {
+    type State = XoodooState;
+
+    fn apply(self, state: &mut Self::State) {
+        dispatch_rounds!(self.rounds, xoodoo, state.get_state_mut());
+    }
+}
+
+impl ParallelPermutation for XoodooDyn {
+    #[cfg(feature = "simd")]
+    fn parallelism(self) -> usize {
+        simd::parallelism()
+    }
+
+    #[cfg(feature = "simd")]
+    fn apply_parallel(self, states: &mut [Self::State]) {
+        dispatch_rounds!(self.rounds, simd::xoodoo_parallel, states);
+    }
+}
+
+/// Inverse of the Xoodoo permutation with `ROUNDS` rounds. `ROUNDS` must be at
+/// most 12.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct XoodooPInv<const ROUNDS: usize>;
+
+impl<const ROUNDS: usize> XoodooPInv<ROUNDS> {
+    const _ROUNDS_CHECK: () = {
+        assert!(ROUNDS > 0);
+        assert!(ROUNDS <= MAX_ROUNDS);
+    };
+}
+
+impl<const ROUNDS: usize> Permutation for XoodooPInv<ROUNDS> {
+    type State = XoodooState;
+
+    fn apply(self, state: &mut Self::State) {
+        inverse::xoodoo_inverse(state.get_state_mut(), ROUNDS);
+    }
+}
+
+impl<const ROUNDS: usize> ParallelPermutation for XoodooPInv<ROUNDS> {}
+
+impl<const ROUNDS: usize> InvertiblePermutation for XoodooPInv<ROUNDS> {
+    type Inverse = XoodooP<ROUNDS>;
+
+    fn inverse(self) -> Self::Inverse {
+        XoodooP
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{XoodooDyn, XoodooP, XoodooState};
+    use crypto_permutation::{ParallelPermutation, Permutation, PermutationState};
+
+    /// [`XoodooDyn`] agrees with [`XoodooP`] and rejects invalid round
+    /// numbers.
+    #[test]
+    fn xoodoo_dyn() {
+        assert!(XoodooDyn::new(0).is_none());
+        assert!(XoodooDyn::new(13).is_none());
+        assert_eq!(XoodooDyn::default(), XoodooDyn::new(12).unwrap());
+
+        let lanes: [u32; 12] = core::array::from_fn(|i| i as u32);
+        let mut state = XoodooState::from_state(lanes);
+        let mut expected = XoodooState::from_state(lanes);
+
+        XoodooDyn::new(6).unwrap().apply(&mut state);
+        XoodooP::<6>.apply(&mut expected);
+        assert_eq!(state.get_state(), expected.get_state());
+
+        XoodooDyn::new(1).unwrap().apply(&mut state);
+        XoodooP::<1>.apply(&mut expected);
+        assert_eq!(state.get_state(), expected.get_state());
+
+        let mut states: [XoodooState; 9] = Default::default();
+        XoodooDyn::default().apply_parallel(&mut states);
+        let mut zero = XoodooState::default();
+        XoodooP::<12>.apply(&mut zero);
+        for state in states.iter() {
+            assert_eq!(state.get_state(), zero.get_state());
+        }
+    }
+}
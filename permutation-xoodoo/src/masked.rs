@@ -0,0 +1,449 @@
+//! First-order masked Xoodoo.
+//!
+//! [`MaskedXoodooState`] holds the Xoodoo state in two Boolean shares: the
+//! state is the xor of the shares. The linear steps of the round are applied
+//! to both shares independently. χ, the only nonlinear step, is computed with
+//! the first-order secure AND gadget of Ishai, Sahai and Wagner, which takes a
+//! fresh random word for every lane.
+//!
+//! The fresh randomness is drawn from the random number generator `R` stored
+//! in the state. It should be a true random number generator (or a handle to
+//! one), or a cryptographically secure generator reseeded from one: the masks
+//! are only as good as this randomness. Cloning a state forks the generator
+//! with [`ForkRng::fork`], so the clone never reuses the randomness of the
+//! original.
+//!
+//! __Warning__: The masking is a countermeasure against first-order power and
+//! electromagnetic side-channel analysis on microcontrollers. The Rust
+//! compiler gives no guarantees about the order in which the operations on the
+//! shares are executed or about the registers holding them, so the resulting
+//! binary must be evaluated on the target device.
+
+use crate::compact::{rho_east, theta_rho_west};
+use crate::constants::RC;
+use crypto_permutation::io::le_uint_slice_reader::LeU32SliceReader;
+use crypto_permutation::io::le_uint_slice_writer::{LeU32SliceWriter, LeU32SliceXorWriter};
+use crypto_permutation::io::{Reader, WriteTooLargeError, Writer};
+use crypto_permutation::{ParallelPermutation, Permutation, PermutationState};
+use rand_core::RngCore;
+
+/// Number of lanes in a Xoodoo state.
+const LEN: usize = 12;
+
+/// Number of bytes unmasked or masked at once by the readers and writers.
+const CHUNK: usize = 16;
+
+/// A random number generator that can be split into independent generators.
+pub trait ForkRng: RngCore {
+    /// Create a generator whose output is independent of the output of `self`,
+    /// e.g. another handle to the same true random number generator.
+    ///
+    /// Returning a copy of the state of a pseudorandom generator does not
+    /// qualify: both copies would produce the same masks.
+    fn fork(&self) -> Self;
+}
+
+/// Xoodoo state in two Boolean shares, together with the random number
+/// generator providing the fresh randomness for the masked permutation.
+///
+/// The [`Default`] state is the all zero state with a fresh random mask, using
+/// a default constructed generator. Only use it when every default constructed
+/// generator is an independent source of randomness, e.g. a handle to a true
+/// random number generator. Otherwise create states with [`Self::new`].
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct MaskedXoodooState<R> {
+    /// The two shares and the random number generator.
+    state: ([[u32; LEN]; 2], R),
+}
+
+impl<R: RngCore> MaskedXoodooState<R> {
+    /// Create a state with the given `shares`, using `rng` for the masked
+    /// permutation. The state is the xor of the shares.
+    pub fn new(shares: [[u32; LEN]; 2], rng: R) -> Self {
+        Self {
+            state: (shares, rng),
+        }
+    }
+
+    /// Replace the mask by a fresh random one, without changing the state.
+    pub fn refresh(&mut self) {
+        let ([share0, share1], rng) = &mut self.state;
+        for (lane0, lane1) in share0.iter_mut().zip(share1.iter_mut()) {
+            let mask = rng.next_u32();
+            *lane0 ^= mask;
+            *lane1 ^= mask;
+        }
+    }
+}
+
+impl<R: ForkRng> Clone for MaskedXoodooState<R> {
+    /// Clone the state with a generator forked from the generator of `self`,
+    /// and a fresh mask.
+    fn clone(&self) -> Self {
+        let mut state = Self::new(self.state.0, self.state.1.fork());
+        state.refresh();
+        state
+    }
+}
+
+impl<R: RngCore + Default> Default for MaskedXoodooState<R> {
+    fn default() -> Self {
+        let mut state = Self::new([[0; LEN]; 2], R::default());
+        state.refresh();
+        state
+    }
+}
+
+impl<R> core::ops::BitXorAssign<&Self> for MaskedXoodooState<R> {
+    fn bitxor_assign(&mut self, rhs: &Self) {
+        for (self_share, other_share) in self.state.0.iter_mut().zip(rhs.state.0.iter()) {
+            for (self_lane, other_lane) in self_share.iter_mut().zip(other_share.iter()) {
+                *self_lane ^= *other_lane;
+            }
+        }
+    }
+}
+
+impl<R: ForkRng + Default> PermutationState for MaskedXoodooState<R> {
+    type Block = [u8; 48];
+    /// Writer into the masked state.
+    ///
+    /// Writes the bytes xored with the second share into the first share.
+    type CopyWriter<'a>
+        = MaskedCopyWriter<'a>
+    where
+        Self: 'a;
+    /// The two shares and the random number generator.
+    type Representation = ([[u32; LEN]; 2], R);
+    /// Reader that reads the unmasked bytes from the masked state.
+    type StateReader<'a>
+        = MaskedReader<'a>
+    where
+        Self: 'a;
+    /// Writer that xors into the masked state.
+    ///
+    /// Xors the bytes into the first share.
+    type XorWriter<'a>
+        = LeU32SliceXorWriter<'a>
+    where
+        Self: 'a;
+
+    const SIZE: usize = 48;
+
+    /// Initialise the state from the given shares and random number
+    /// generator.
+    fn from_state(state: Self::Representation) -> Self {
+        Self { state }
+    }
+
+    fn get_state(&self) -> &Self::Representation {
+        &self.state
+    }
+
+    fn get_state_mut(&mut self) -> &mut Self::Representation {
+        &mut self.state
+    }
+
+    fn reader<'a>(&'a self) -> Self::StateReader<'a> {
+        let [share0, share1] = &self.state.0;
+        MaskedReader {
+            share0: LeU32SliceReader::new(share0),
+            share1: LeU32SliceReader::new(share1),
+        }
+    }
+
+    fn copy_writer<'a>(&'a mut self) -> Self::CopyWriter<'a> {
+        let [share0, share1] = &mut self.state.0;
+        MaskedCopyWriter {
+            share0: LeU32SliceWriter::new(share0),
+            share1: LeU32SliceReader::new(share1),
+        }
+    }
+
+    fn xor_writer<'a>(&'a mut self) -> Self::XorWriter<'a> {
+        LeU32SliceXorWriter::new(&mut self.state.0[0])
+    }
+}
+
+/// Writer into a masked state, see [`MaskedXoodooState`].
+///
+/// Writes the bytes xored with the second share into the first share, so that
+/// the xor of the shares equals the written bytes.
+pub struct MaskedCopyWriter<'a> {
+    share0: LeU32SliceWriter<'a>,
+    share1: LeU32SliceReader<'a>,
+}
+
+impl<'a> Writer for MaskedCopyWriter<'a> {
+    type Return = ();
+
+    fn capacity(&self) -> usize {
+        self.share0.capacity()
+    }
+
+    fn skip(&mut self, n: usize) -> Result<(), WriteTooLargeError> {
+        self.share0.skip(n)?;
+        self.share1.skip(n)
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), WriteTooLargeError> {
+        crypto_permutation::io::check_write_size(data.len(), self.capacity())?;
+        for chunk in data.chunks(CHUNK) {
+            let mut masked = [0; CHUNK];
+            let masked = &mut masked[..chunk.len()];
+            self.share1.write_to_slice(masked)?;
+            for (masked, byte) in masked.iter_mut().zip(chunk) {
+                *masked ^= byte;
+            }
+            self.share0.write_bytes(masked)?;
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Self::Return {
+        self.share0.finish();
+    }
+}
+
+/// Reader that reads the unmasked bytes from a masked state, see
+/// [`MaskedXoodooState`].
+pub struct MaskedReader<'a> {
+    share0: LeU32SliceReader<'a>,
+    share1: LeU32SliceReader<'a>,
+}
+
+impl<'a> Reader for MaskedReader<'a> {
+    fn capacity(&self) -> usize {
+        self.share0.capacity()
+    }
+
+    fn skip(&mut self, n: usize) -> Result<(), WriteTooLargeError> {
+        self.share0.skip(n)?;
+        self.share1.skip(n)
+    }
+
+    fn write_to<W: Writer>(
+        &mut self,
+        writer: &mut W,
+        mut n: usize,
+    ) -> Result<(), WriteTooLargeError> {
+        crypto_permutation::io::check_write_size(n, self.capacity())?;
+        while n > 0 {
+            let len = core::cmp::min(n, CHUNK);
+            let mut bytes = [0; CHUNK];
+            let mut mask = [0; CHUNK];
+            self.share0.write_to_slice(&mut bytes[..len])?;
+            self.share1.write_to_slice(&mut mask[..len])?;
+            for (byte, mask) in bytes.iter_mut().zip(mask.iter()) {
+                *byte ^= mask;
+            }
+            writer.write_bytes(&bytes[..len])?;
+            n -= len;
+        }
+        Ok(())
+    }
+}
+
+/// A masked lane `[share0, share1]`.
+type MaskedLane = [u32; 2];
+
+/// First-order secure `!a & b` on masked lanes, using the fresh random word
+/// `r` (Ishai-Sahai-Wagner AND gadget with the NOT folded into share 0).
+#[inline(always)]
+fn masked_andnot(a: MaskedLane, b: MaskedLane, r: u32) -> MaskedLane {
+    let a0 = !a[0];
+    let c0 = (a0 & b[0]) ^ r;
+    let c1 = (a[1] & b[1]) ^ ((r ^ (a0 & b[1])) ^ (a[1] & b[0]));
+    [c0, c1]
+}
+
+/// A masked Xoodoo round with round constant `rc`.
+fn masked_round<R: RngCore>(state: &mut MaskedXoodooState<R>, rc: u32) {
+    let (shares, rng) = &mut state.state;
+    for share in shares.iter_mut() {
+        theta_rho_west(share);
+    }
+
+    // ι
+    shares[0][0] ^= rc;
+
+    // χ
+    let [share0, share1] = *shares;
+    let lane = |i: usize| [share0[i], share1[i]];
+    for x in 0..4 {
+        let (a0, a1, a2) = (lane(x), lane(x + 4), lane(x + 8));
+        let b0 = masked_andnot(a1, a2, rng.next_u32());
+        let b1 = masked_andnot(a2, a0, rng.next_u32());
+        let b2 = masked_andnot(a0, a1, rng.next_u32());
+        for (y, b) in [b0, b1, b2].iter().enumerate() {
+            shares[0][x + 4 * y] ^= b[0];
+            shares[1][x + 4 * y] ^= b[1];
+        }
+    }
+
+    for share in shares.iter_mut() {
+        rho_east(share);
+    }
+}
+
+/// First-order masked Xoodoo permutation with `ROUNDS` rounds, acting on a
+/// [`MaskedXoodooState`] with random number generator `R`. `ROUNDS` must be at
+/// most 12.
+pub struct XoodooPMasked<const ROUNDS: usize, R>(core::marker::PhantomData<fn() -> R>);
+
+impl<const ROUNDS: usize, R> XoodooPMasked<ROUNDS, R> {
+    const _ROUNDS_CHECK: () = {
+        assert!(ROUNDS > 0);
+        assert!(ROUNDS <= 12);
+    };
+}
+
+impl<const ROUNDS: usize, R> Clone for XoodooPMasked<ROUNDS, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<const ROUNDS: usize, R> Copy for XoodooPMasked<ROUNDS, R> {}
+
+impl<const ROUNDS: usize, R> Default for XoodooPMasked<ROUNDS, R> {
+    fn default() -> Self {
+        Self(core::marker::PhantomData)
+    }
+}
+
+impl<const ROUNDS: usize, R> core::fmt::Debug for XoodooPMasked<ROUNDS, R> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("XoodooPMasked")
+    }
+}
+
+impl<const ROUNDS: usize, R: ForkRng + Default> Permutation for XoodooPMasked<ROUNDS, R> {
+    type State = MaskedXoodooState<R>;
+
+    fn apply(self, state: &mut Self::State) {
+        for &rc in &RC[12 - ROUNDS..] {
+            masked_round(state, rc);
+        }
+    }
+}
+
+impl<const ROUNDS: usize, R: ForkRng + Default> ParallelPermutation for XoodooPMasked<ROUNDS, R> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{XoodooP, XoodooState};
+
+    /// Deterministic xorshift generator, only suitable for testing.
+    struct TestRng(u64);
+
+    impl ForkRng for TestRng {
+        fn fork(&self) -> Self {
+            Self(self.0.rotate_left(32) ^ 0xd1b5_4a32_d192_ed03)
+        }
+    }
+
+    impl Default for TestRng {
+        fn default() -> Self {
+            Self(0x9e37_79b9_7f4a_7c15)
+        }
+    }
+
+    impl RngCore for TestRng {
+        fn next_u32(&mut self) -> u32 {
+            (self.next_u64() >> 32) as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            rand_core::impls::fill_bytes_via_next(self, dest);
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    fn unmask(state: &MaskedXoodooState<TestRng>) -> [u32; LEN] {
+        let [share0, share1] = state.state.0;
+        core::array::from_fn(|i| share0[i] ^ share1[i])
+    }
+
+    /// The masked permutation computes Xoodoo on the unmasked state.
+    #[test]
+    fn masked_matches_unmasked() {
+        let lanes: [u32; LEN] = core::array::from_fn(|i| (i as u32 + 1).wrapping_mul(0x9e37_79b9));
+        let mut masked = MaskedXoodooState::new([lanes, [0; LEN]], TestRng::default());
+        masked.refresh();
+        assert_ne!(masked.state.0[0], lanes);
+
+        let mut expected = XoodooState::from_state(lanes);
+        XoodooPMasked::<12, TestRng>::default().apply(&mut masked);
+        XoodooP::<12>.apply(&mut expected);
+        assert_eq!(&unmask(&masked), expected.get_state());
+
+        XoodooPMasked::<6, TestRng>::default().apply(&mut masked);
+        XoodooP::<6>.apply(&mut expected);
+        assert_eq!(&unmask(&masked), expected.get_state());
+    }
+
+    /// Reading and writing bytes gives the same results as for
+    /// [`XoodooState`].
+    #[test]
+    fn byte_interface() {
+        let data: [u8; 48] = core::array::from_fn(|i| (i as u8).wrapping_mul(0x5b));
+        let mut masked = MaskedXoodooState::<TestRng>::default();
+        let mut expected = XoodooState::default();
+
+        let mut writer = masked.xor_writer();
+        writer.skip(3).unwrap();
+        writer.write_bytes(&data[..40]).unwrap();
+        writer.finish();
+        let mut writer = expected.xor_writer();
+        writer.skip(3).unwrap();
+        writer.write_bytes(&data[..40]).unwrap();
+        writer.finish();
+
+        let mut writer = masked.copy_writer();
+        writer.skip(5).unwrap();
+        writer.write_bytes(&data[7..30]).unwrap();
+        writer.finish();
+        let mut writer = expected.copy_writer();
+        writer.skip(5).unwrap();
+        writer.write_bytes(&data[7..30]).unwrap();
+        writer.finish();
+
+        assert_eq!(&unmask(&masked), expected.get_state());
+
+        let mut output = [0; 46];
+        let mut expected_output = [0; 46];
+        let mut reader = masked.reader();
+        reader.skip(2).unwrap();
+        reader.write_to_slice(&mut output).unwrap();
+        let mut reader = expected.reader();
+        reader.skip(2).unwrap();
+        reader.write_to_slice(&mut expected_output).unwrap();
+        assert_eq!(output, expected_output);
+    }
+
+    /// A clone holds the same state under a fresh mask, and uses a forked
+    /// generator.
+    #[test]
+    fn clone_forks_rng() {
+        let lanes: [u32; LEN] = core::array::from_fn(|i| i as u32);
+        let mut original = MaskedXoodooState::new([lanes, [0; LEN]], TestRng::default());
+        original.refresh();
+        let mut clone = original.clone();
+        assert_eq!(unmask(&clone), unmask(&original));
+        assert_ne!(clone.state.0, original.state.0);
+        assert_ne!(clone.state.1.next_u64(), original.state.1.next_u64());
+    }
+}
@@ -0,0 +1,253 @@
+//! Differential tests against a port of the XKCP Xoodoo reference code.
+//!
+//! The port follows `Xoodoo-reference.c` of the eXtended Keccak Code Package
+//! closely, including its byte oriented interface, so it is independent of the
+//! lane representation and step mapping helpers used by this crate. The tests
+//! compare all implementations in the crate against it on pseudorandom states
+//! for every number of rounds.
+
+const NLANES: usize = 12;
+const NROWS: usize = 3;
+const NCOLUMNS: usize = 4;
+const MAXROUNDS: usize = 12;
+
+/// Round constants `_rc12` down to `_rc1`.
+const RC: [u32; MAXROUNDS] = [
+    0x00000058, 0x00000038, 0x000003C0, 0x000000D0, 0x00000120, 0x00000014, 0x00000060, 0x0000002C,
+    0x00000380, 0x000000F0, 0x000001A0, 0x00000012,
+];
+
+/// Index of the lane in column `x` and plane `y`.
+fn index(x: usize, y: usize) -> usize {
+    (y % NROWS) * NCOLUMNS + (x % NCOLUMNS)
+}
+
+fn xoodoo_round(a: &mut [u32; NLANES], rc: u32) {
+    let mut b = [0u32; NLANES];
+    let mut p = [0u32; NCOLUMNS];
+    let mut e = [0u32; NCOLUMNS];
+
+    // Theta: Column Parity Mixer
+    for x in 0..NCOLUMNS {
+        p[x] = a[index(x, 0)] ^ a[index(x, 1)] ^ a[index(x, 2)];
+    }
+    for x in 0..NCOLUMNS {
+        let p = p[(x + NCOLUMNS - 1) % NCOLUMNS];
+        e[x] = p.rotate_left(5) ^ p.rotate_left(14);
+    }
+    for x in 0..NCOLUMNS {
+        for y in 0..NROWS {
+            a[index(x, y)] ^= e[x];
+        }
+    }
+
+    // Rho-west: plane shift
+    for x in 0..NCOLUMNS {
+        b[index(x, 0)] = a[index(x, 0)];
+        b[index(x, 1)] = a[index(x + NCOLUMNS - 1, 1)];
+        b[index(x, 2)] = a[index(x, 2)].rotate_left(11);
+    }
+    *a = b;
+
+    // Iota: round constant
+    a[0] ^= rc;
+
+    // Chi: non linear layer
+    for x in 0..NCOLUMNS {
+        for y in 0..NROWS {
+            b[index(x, y)] = a[index(x, y)] ^ (!a[index(x, y + 1)] & a[index(x, y + 2)]);
+        }
+    }
+    *a = b;
+
+    // Rho-east: plane shift
+    for x in 0..NCOLUMNS {
+        b[index(x, 0)] = a[index(x, 0)];
+        b[index(x, 1)] = a[index(x, 1)].rotate_left(1);
+        b[index(x, 2)] = a[index(x + 2, 2)].rotate_left(8);
+    }
+    *a = b;
+}
+
+/// `Xoodoo_Permute_Nrounds`: apply Xoodoo with `nr` rounds to the 48 byte
+/// `state`.
+pub(crate) fn xoodoo_permute_nrounds(state: &mut [u8; 48], nr: usize) {
+    let mut a = [0u32; NLANES];
+    for (lane, bytes) in a.iter_mut().zip(state.chunks_exact(4)) {
+        *lane = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+    for &rc in &RC[MAXROUNDS - nr..] {
+        xoodoo_round(&mut a, rc);
+    }
+    for (bytes, lane) in state.chunks_exact_mut(4).zip(a.iter()) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::xoodoo_permute_nrounds;
+    use crate::{XoodooDyn, XoodooState};
+    use crypto_permutation::io::{Reader, Writer};
+    use crypto_permutation::{ParallelPermutation, Permutation, PermutationState};
+
+    /// Number of pseudorandom states per number of rounds.
+    const STATES: usize = 17;
+
+    /// Deterministic xorshift generator for the test states.
+    struct TestRng(u64);
+
+    impl TestRng {
+        fn fill(&mut self, bytes: &mut [u8]) {
+            for byte in bytes {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                *byte = (self.0 >> 56) as u8;
+            }
+        }
+    }
+
+    fn random_states() -> [[u8; 48]; STATES] {
+        let mut rng = TestRng(0x0123_4567_89ab_cdef);
+        let mut states = [[0; 48]; STATES];
+        for state in states.iter_mut() {
+            rng.fill(state);
+        }
+        states
+    }
+
+    fn to_state(bytes: &[u8; 48]) -> XoodooState {
+        let mut state = XoodooState::default();
+        let mut writer = state.copy_writer();
+        writer.write_bytes(bytes).unwrap();
+        writer.finish();
+        state
+    }
+
+    fn to_bytes(state: &XoodooState) -> [u8; 48] {
+        let mut bytes = [0; 48];
+        state.reader().write_to_slice(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// The permutation, both for a single state and in parallel, agrees with
+    /// the reference for all numbers of rounds.
+    #[test]
+    fn xoodoo_matches_reference() {
+        for rounds in 1..=12 {
+            let permutation = XoodooDyn::new(rounds).unwrap();
+            let mut expected = random_states();
+            let mut states = expected.map(|bytes| to_state(&bytes));
+            for bytes in expected.iter_mut() {
+                xoodoo_permute_nrounds(bytes, rounds);
+            }
+
+            for (bytes, expected) in random_states().iter().zip(expected.iter()) {
+                let mut state = to_state(bytes);
+                permutation.apply(&mut state);
+                assert_eq!(&to_bytes(&state), expected, "rounds: {rounds}");
+            }
+
+            permutation.apply_parallel(&mut states);
+            for (state, expected) in states.iter().zip(expected.iter()) {
+                assert_eq!(&to_bytes(state), expected, "rounds: {rounds}");
+            }
+        }
+    }
+
+    /// The `const fn` permutation agrees with the reference for all numbers
+    /// of rounds.
+    #[test]
+    fn const_matches_reference() {
+        for rounds in 1..=12 {
+            for mut bytes in random_states() {
+                let state = to_state(&bytes);
+                let permuted = crate::xoodoo_const(*state.get_state(), rounds);
+                xoodoo_permute_nrounds(&mut bytes, rounds);
+                assert_eq!(
+                    to_bytes(&XoodooState::from_state(permuted)),
+                    bytes,
+                    "rounds: {rounds}"
+                );
+            }
+        }
+    }
+
+    /// The inverse permutation inverts the reference for all numbers of
+    /// rounds.
+    #[test]
+    fn inverse_matches_reference() {
+        for rounds in 1..=12 {
+            for bytes in random_states() {
+                let mut permuted = bytes;
+                xoodoo_permute_nrounds(&mut permuted, rounds);
+                let mut state = to_state(&permuted);
+                crate::inverse::xoodoo_inverse(state.get_state_mut(), rounds);
+                assert_eq!(to_bytes(&state), bytes, "rounds: {rounds}");
+            }
+        }
+    }
+
+    /// The masked permutation agrees with the reference for all numbers of
+    /// rounds.
+    #[cfg(feature = "masked")]
+    #[test]
+    fn masked_matches_reference() {
+        use crate::masked::{ForkRng, MaskedXoodooState, XoodooPMasked};
+
+        struct Rng(TestRng);
+
+        impl Default for Rng {
+            fn default() -> Self {
+                Self(TestRng(0xfedc_ba98_7654_3210))
+            }
+        }
+
+        impl ForkRng for Rng {
+            fn fork(&self) -> Self {
+                Self(TestRng(!self.0 .0))
+            }
+        }
+
+        impl rand_core::RngCore for Rng {
+            fn next_u32(&mut self) -> u32 {
+                let mut bytes = [0; 4];
+                self.fill_bytes(&mut bytes);
+                u32::from_le_bytes(bytes)
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                rand_core::impls::next_u64_via_u32(self)
+            }
+
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                self.0.fill(dest);
+            }
+
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+                self.fill_bytes(dest);
+                Ok(())
+            }
+        }
+
+        macro_rules! check_rounds {
+            ($($rounds:literal)*) => {$(
+                for bytes in random_states() {
+                    let mut expected = bytes;
+                    xoodoo_permute_nrounds(&mut expected, $rounds);
+
+                    let mut state = MaskedXoodooState::<Rng>::default();
+                    let mut writer = state.copy_writer();
+                    writer.write_bytes(&bytes).unwrap();
+                    writer.finish();
+                    XoodooPMasked::<$rounds, Rng>::default().apply(&mut state);
+                    let mut output = [0; 48];
+                    state.reader().write_to_slice(&mut output).unwrap();
+                    assert_eq!(output, expected, "rounds: {}", $rounds);
+                }
+            )*};
+        }
+        check_rounds!(1 2 3 4 5 6 7 8 9 10 11 12);
+    }
+}
@@ -0,0 +1,316 @@
+//! SIMD implementations of the Xoodoo permutation.
+//!
+//! The Xoodoo state consists of three planes of four 32-bit lanes, so every
+//! plane fits in a single 128-bit vector. The shifts of the planes along the
+//! `x` axis become lane shuffles and the rotations along the `z` axis become
+//! lanewise rotations. Each architecture module provides modules with the
+//! vector operations used by `xoodoo_round` and defines its kernels using
+//! `xoodoo_kernel`.
+//!
+//! The parallel kernels instead hold the same lane of several states in one
+//! vector, so that the plane shifts become a renumbering of the vectors. They
+//! use the vector operations from `xoodoo_round_parallel` and are defined by
+//! `xoodoo_kernel_parallel`.
+
+/// One Xoodoo round with round constant `$rc` on the planes `$a0`, `$a1` and
+/// `$a2`, using the vector operations from the module `$ops`.
+macro_rules! xoodoo_round {
+    ($ops:ident, $a0:ident, $a1:ident, $a2:ident, $rc:expr) => {{
+        // θ
+        let p = $ops::shift1($ops::xor($ops::xor($a0, $a1), $a2));
+        let e = $ops::xor($ops::rol(p, 5), $ops::rol(p, 14));
+        $a0 = $ops::xor($a0, e);
+        $a1 = $ops::xor($a1, e);
+        $a2 = $ops::xor($a2, e);
+
+        // ρ_west
+        $a1 = $ops::shift1($a1);
+        $a2 = $ops::rol($a2, 11);
+
+        // ι
+        $a0 = $ops::xor($a0, $ops::first($rc));
+
+        // χ
+        let b0 = $ops::andnot($a1, $a2);
+        let b1 = $ops::andnot($a2, $a0);
+        let b2 = $ops::andnot($a0, $a1);
+        $a0 = $ops::xor($a0, b0);
+        $a1 = $ops::xor($a1, b1);
+        $a2 = $ops::xor($a2, b2);
+
+        // ρ_east
+        $a1 = $ops::rol($a1, 1);
+        $a2 = $ops::rol($ops::shift2($a2), 8);
+    }};
+}
+
+/// Define a kernel applying Xoodoo with `rounds` rounds to a single state,
+/// using the vector operations from the module `$ops`. The kernel is an unsafe
+/// function enabling the target features `$features`, if given.
+macro_rules! xoodoo_kernel {
+    ($name:ident, $ops:ident, $features:literal) => {
+        /// Apply Xoodoo with `rounds` rounds to `state`.
+        ///
+        /// # Safety
+        #[doc = concat!("The `", $features, "` target features must be available.")]
+        ///
+        /// # Panics
+        /// Panics when `rounds > 12`.
+        #[target_feature(enable = $features)]
+        pub(crate) unsafe fn $name(state: &mut [u32; 12], rounds: usize) {
+            xoodoo_kernel!(@body $ops, state, rounds);
+        }
+    };
+    ($name:ident, $ops:ident) => {
+        /// Apply Xoodoo with `rounds` rounds to `state`.
+        ///
+        /// # Panics
+        /// Panics when `rounds > 12`.
+        pub(crate) fn $name(state: &mut [u32; 12], rounds: usize) {
+            xoodoo_kernel!(@body $ops, state, rounds);
+        }
+    };
+    (@body $ops:ident, $state:ident, $rounds:ident) => {{
+        assert!($rounds <= 12);
+        let round_constants = &$crate::constants::RC[12 - $rounds..];
+
+        let mut a0 = $ops::load($state[0..4].try_into().unwrap());
+        let mut a1 = $ops::load($state[4..8].try_into().unwrap());
+        let mut a2 = $ops::load($state[8..12].try_into().unwrap());
+
+        for &rc in round_constants {
+            xoodoo_round!($ops, a0, a1, a2, rc);
+        }
+
+        $state[0..4].copy_from_slice(&$ops::store(a0));
+        $state[4..8].copy_from_slice(&$ops::store(a1));
+        $state[8..12].copy_from_slice(&$ops::store(a2));
+    }};
+}
+
+/// One Xoodoo round with round constant `$rc` on the vectors of lanes `$a`,
+/// using the vector operations from the module `$ops`.
+macro_rules! xoodoo_round_parallel {
+    ($ops:ident, $a:ident, $rc:expr) => {{
+        // θ
+        let mut p = [$ops::splat(0); 4];
+        for (x, p) in p.iter_mut().enumerate() {
+            *p = $ops::xor($ops::xor($a[x], $a[x + 4]), $a[x + 8]);
+        }
+        for x in 0..4 {
+            let p = p[(x + 3) % 4];
+            let e = $ops::xor($ops::rol(p, 5), $ops::rol(p, 14));
+            for y in 0..3 {
+                $a[4 * y + x] = $ops::xor($a[4 * y + x], e);
+            }
+        }
+
+        // ρ_west
+        $a[4..8].rotate_right(1);
+        for x in 8..12 {
+            $a[x] = $ops::rol($a[x], 11);
+        }
+
+        // ι
+        $a[0] = $ops::xor($a[0], $ops::splat($rc));
+
+        // χ
+        for x in 0..4 {
+            let b0 = $ops::andnot($a[x + 4], $a[x + 8]);
+            let b1 = $ops::andnot($a[x + 8], $a[x]);
+            let b2 = $ops::andnot($a[x], $a[x + 4]);
+            $a[x] = $ops::xor($a[x], b0);
+            $a[x + 4] = $ops::xor($a[x + 4], b1);
+            $a[x + 8] = $ops::xor($a[x + 8], b2);
+        }
+
+        // ρ_east
+        for x in 4..8 {
+            $a[x] = $ops::rol($a[x], 1);
+        }
+        $a[8..12].rotate_right(2);
+        for x in 8..12 {
+            $a[x] = $ops::rol($a[x], 8);
+        }
+    }};
+}
+
+/// Define a kernel applying Xoodoo with `rounds` rounds to exactly `$n`
+/// states, using the vector operations from the module `$ops`. The kernel is
+/// an unsafe function enabling the target features `$features`, if given.
+macro_rules! xoodoo_kernel_parallel {
+    ($name:ident, $ops:ident, $n:literal, $features:literal) => {
+        #[doc = concat!("Apply Xoodoo with `rounds` rounds to ", $n, " states.")]
+        ///
+        /// # Safety
+        #[doc = concat!("The `", $features, "` target features must be available.")]
+        ///
+        /// # Panics
+        #[doc = concat!("Panics when `states.len() != ", $n, "` or `rounds > 12`.")]
+        #[target_feature(enable = $features)]
+        pub(crate) unsafe fn $name(states: &mut [$crate::XoodooState], rounds: usize) {
+            xoodoo_kernel_parallel!(@body $ops, $n, states, rounds);
+        }
+    };
+    ($name:ident, $ops:ident, $n:literal) => {
+        #[doc = concat!("Apply Xoodoo with `rounds` rounds to ", $n, " states.")]
+        ///
+        /// # Panics
+        #[doc = concat!("Panics when `states.len() != ", $n, "` or `rounds > 12`.")]
+        pub(crate) fn $name(states: &mut [$crate::XoodooState], rounds: usize) {
+            xoodoo_kernel_parallel!(@body $ops, $n, states, rounds);
+        }
+    };
+    (@body $ops:ident, $n:literal, $states:ident, $rounds:ident) => {{
+        use crypto_permutation::PermutationState;
+
+        assert_eq!($states.len(), $n);
+        assert!($rounds <= 12);
+        let round_constants = &$crate::constants::RC[12 - $rounds..];
+
+        let mut a = [$ops::splat(0); 12];
+        for (i, lane) in a.iter_mut().enumerate() {
+            let mut lanes = [0; $n];
+            for (lane, state) in lanes.iter_mut().zip($states.iter()) {
+                *lane = state.get_state()[i];
+            }
+            *lane = $ops::load(&lanes);
+        }
+
+        for &rc in round_constants {
+            xoodoo_round_parallel!($ops, a, rc);
+        }
+
+        for (i, lane) in a.iter().enumerate() {
+            for (lane, state) in $ops::store(*lane).iter().zip($states.iter_mut()) {
+                state.get_state_mut()[i] = *lane;
+            }
+        }
+    }};
+}
+
+#[cfg(feature = "portable-simd")]
+mod portable;
+#[cfg(feature = "portable-simd")]
+use portable as arch;
+
+#[cfg(all(
+    not(feature = "portable-simd"),
+    target_arch = "wasm32",
+    target_feature = "simd128"
+))]
+mod wasm32;
+#[cfg(all(
+    not(feature = "portable-simd"),
+    any(target_arch = "x86", target_arch = "x86_64")
+))]
+mod x86;
+
+#[cfg(all(
+    not(feature = "portable-simd"),
+    target_arch = "wasm32",
+    target_feature = "simd128"
+))]
+use wasm32 as arch;
+#[cfg(all(
+    not(feature = "portable-simd"),
+    any(target_arch = "x86", target_arch = "x86_64")
+))]
+use x86 as arch;
+
+/// Fallback for architectures without SIMD kernels.
+#[cfg(not(any(
+    feature = "portable-simd",
+    target_arch = "x86",
+    target_arch = "x86_64",
+    all(target_arch = "wasm32", target_feature = "simd128")
+)))]
+mod arch {
+    /// Apply Xoodoo with `ROUNDS` rounds to `state`.
+    pub(crate) fn xoodoo<const ROUNDS: usize>(state: &mut [u32; 12]) {
+        xoodoo_p::xoodoo::<ROUNDS>(state);
+    }
+}
+
+/// Fallback for architectures without parallel kernels.
+#[cfg(not(any(feature = "portable-simd", target_arch = "x86", target_arch = "x86_64")))]
+mod parallel {
+    use crate::XoodooState;
+    use crypto_permutation::PermutationState;
+
+    /// Number of states processed at once by the fastest kernel available.
+    pub(crate) fn parallelism() -> usize {
+        1
+    }
+
+    /// Apply Xoodoo with `ROUNDS` rounds to all `states`.
+    pub(crate) fn xoodoo_parallel<const ROUNDS: usize>(states: &mut [XoodooState]) {
+        for state in states {
+            super::xoodoo::<ROUNDS>(state.get_state_mut());
+        }
+    }
+}
+
+#[cfg(feature = "portable-simd")]
+use portable as parallel;
+#[cfg(all(
+    not(feature = "portable-simd"),
+    any(target_arch = "x86", target_arch = "x86_64")
+))]
+use x86 as parallel;
+
+pub(crate) use arch::xoodoo;
+pub(crate) use parallel::{parallelism, xoodoo_parallel};
+
+#[cfg(test)]
+mod tests {
+    /// Reference implementation from the `xoodoo-p` crate with a runtime
+    /// number of rounds.
+    pub(super) fn reference(state: &mut [u32; 12], rounds: usize) {
+        macro_rules! dispatch {
+            ($($rounds:literal)*) => {
+                match rounds {
+                    $($rounds => xoodoo_p::xoodoo::<$rounds>(state),)*
+                    _ => unreachable!(),
+                }
+            };
+        }
+        dispatch!(1 2 3 4 5 6 7 8 9 10 11 12);
+    }
+
+    /// Test state with distinct non-zero lanes.
+    pub(super) fn test_state() -> [u32; 12] {
+        core::array::from_fn(|i| (i as u32 + 1).wrapping_mul(0x9e37_79b9))
+    }
+
+    /// Ten different test states.
+    pub(super) fn test_states() -> [crate::XoodooState; 10] {
+        use crypto_permutation::PermutationState;
+
+        core::array::from_fn(|j| {
+            let mut state = test_state();
+            for lane in state.iter_mut() {
+                *lane ^= j as u32;
+            }
+            crate::XoodooState::from_state(state)
+        })
+    }
+
+    /// Dispatch handles any number of states, including partial vectors.
+    #[test]
+    fn parallel_dispatch() {
+        use crypto_permutation::PermutationState;
+
+        for n in 0..=10 {
+            let mut states = test_states();
+            super::xoodoo_parallel::<6>(&mut states[..n]);
+            for (j, state) in states.iter().enumerate() {
+                let mut expected = *test_states()[j].get_state();
+                if j < n {
+                    reference(&mut expected, 6);
+                }
+                assert_eq!(state.get_state(), &expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Architecture independent SIMD kernel using the unstable `core::simd`
+//! module.
+//!
+//! Besides the single state kernel there are parallel kernels for four and
+//! eight states. Relies on the compiler to lower the portable vector operations to the
+//! instructions of the target, so the performance depends on the target
+//! features enabled at compile time.
+
+use crate::XoodooState;
+use core::simd::{simd_swizzle, Simd};
+
+/// Portable vector operations on a plane.
+mod ops {
+    use super::*;
+
+    pub(super) type Plane = Simd<u32, 4>;
+
+    #[inline(always)]
+    pub(super) fn load(plane: &[u32; 4]) -> Plane {
+        Simd::from_array(*plane)
+    }
+
+    #[inline(always)]
+    pub(super) fn store(v: Plane) -> [u32; 4] {
+        v.to_array()
+    }
+
+    /// Plane with `rc` in lane 0 and zeros elsewhere.
+    #[inline(always)]
+    pub(super) fn first(rc: u32) -> Plane {
+        Simd::from_array([rc, 0, 0, 0])
+    }
+
+    #[inline(always)]
+    pub(super) fn xor(a: Plane, b: Plane) -> Plane {
+        a ^ b
+    }
+
+    /// `!a & b`
+    #[inline(always)]
+    pub(super) fn andnot(a: Plane, b: Plane) -> Plane {
+        !a & b
+    }
+
+    /// Shift the plane by one position along the `x` axis: lane `x` of the
+    /// result is lane `x - 1` of `a`.
+    #[inline(always)]
+    pub(super) fn shift1(a: Plane) -> Plane {
+        simd_swizzle!(a, [3, 0, 1, 2])
+    }
+
+    /// Shift the plane by two positions along the `x` axis: lane `x` of the
+    /// result is lane `x - 2` of `a`.
+    #[inline(always)]
+    pub(super) fn shift2(a: Plane) -> Plane {
+        simd_swizzle!(a, [2, 3, 0, 1])
+    }
+
+    #[inline(always)]
+    pub(super) fn rol(a: Plane, n: u32) -> Plane {
+        (a << Simd::splat(n)) | (a >> Simd::splat(32 - n))
+    }
+
+    xoodoo_kernel!(xoodoo, ops);
+}
+
+/// Define the portable vector operations on lanes of `$n` states.
+macro_rules! lanes_ops {
+    ($n:literal) => {
+        pub(super) type Lanes = Simd<u32, $n>;
+
+        #[inline(always)]
+        pub(super) fn splat(x: u32) -> Lanes {
+            Simd::splat(x)
+        }
+
+        #[inline(always)]
+        pub(super) fn load(lanes: &[u32; $n]) -> Lanes {
+            Simd::from_array(*lanes)
+        }
+
+        #[inline(always)]
+        pub(super) fn store(v: Lanes) -> [u32; $n] {
+            v.to_array()
+        }
+
+        #[inline(always)]
+        pub(super) fn xor(a: Lanes, b: Lanes) -> Lanes {
+            a ^ b
+        }
+
+        /// `!a & b`
+        #[inline(always)]
+        pub(super) fn andnot(a: Lanes, b: Lanes) -> Lanes {
+            !a & b
+        }
+
+        #[inline(always)]
+        pub(super) fn rol(a: Lanes, n: u32) -> Lanes {
+            (a << Simd::splat(n)) | (a >> Simd::splat(32 - n))
+        }
+    };
+}
+
+/// Portable vector operations on four lanes.
+mod x4 {
+    use super::*;
+
+    lanes_ops!(4);
+
+    xoodoo_kernel_parallel!(xoodoo_x4, x4, 4);
+}
+
+/// Portable vector operations on eight lanes.
+mod x8 {
+    use super::*;
+
+    lanes_ops!(8);
+
+    xoodoo_kernel_parallel!(xoodoo_x8, x8, 8);
+}
+
+/// Apply Xoodoo with `ROUNDS` rounds to `state`.
+pub(crate) fn xoodoo<const ROUNDS: usize>(state: &mut [u32; 12]) {
+    ops::xoodoo(state, ROUNDS);
+}
+
+/// Number of states processed at once by the parallel kernels.
+pub(crate) fn parallelism() -> usize {
+    8
+}
+
+/// Apply Xoodoo with `ROUNDS` rounds to all `states`, eight or four at once
+/// where possible.
+pub(crate) fn xoodoo_parallel<const ROUNDS: usize>(states: &mut [XoodooState]) {
+    let mut chunks = states.chunks_exact_mut(8);
+    for chunk in &mut chunks {
+        x8::xoodoo_x8(chunk, ROUNDS);
+    }
+    let mut chunks = chunks.into_remainder().chunks_exact_mut(4);
+    for chunk in &mut chunks {
+        x4::xoodoo_x4(chunk, ROUNDS);
+    }
+    for state in chunks.into_remainder() {
+        xoodoo::<ROUNDS>(crypto_permutation::PermutationState::get_state_mut(state));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simd::tests::{reference, test_state};
+
+    #[test]
+    fn portable_kernel() {
+        for rounds in 1..=12 {
+            let mut state = test_state();
+            let mut expected = state;
+            super::ops::xoodoo(&mut state, rounds);
+            reference(&mut expected, rounds);
+            assert_eq!(state, expected);
+        }
+    }
+}
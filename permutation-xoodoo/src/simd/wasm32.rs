@@ -0,0 +1,79 @@
+//! SIMD kernel for `wasm32` with the `simd128` target feature.
+//!
+//! WebAssembly has no runtime feature detection: a module using SIMD128
+//! instructions fails to validate on engines without support for them. This
+//! kernel is therefore only compiled in when the `simd128` target feature is
+//! enabled at compile time (e.g. with `-C target-feature=+simd128`).
+
+use core::arch::wasm32::*;
+
+/// Vector operations on a plane using SIMD128.
+mod simd128 {
+    use super::*;
+
+    pub(super) type Plane = v128;
+
+    #[inline(always)]
+    pub(super) fn load(plane: &[u32; 4]) -> Plane {
+        u32x4(plane[0], plane[1], plane[2], plane[3])
+    }
+
+    #[inline(always)]
+    pub(super) fn store(v: Plane) -> [u32; 4] {
+        [
+            u32x4_extract_lane::<0>(v),
+            u32x4_extract_lane::<1>(v),
+            u32x4_extract_lane::<2>(v),
+            u32x4_extract_lane::<3>(v),
+        ]
+    }
+
+    /// Plane with `rc` in lane 0 and zeros elsewhere.
+    #[inline(always)]
+    pub(super) fn first(rc: u32) -> Plane {
+        u32x4(rc, 0, 0, 0)
+    }
+
+    #[inline(always)]
+    pub(super) fn xor(a: Plane, b: Plane) -> Plane {
+        v128_xor(a, b)
+    }
+
+    /// `!a & b`
+    #[inline(always)]
+    pub(super) fn andnot(a: Plane, b: Plane) -> Plane {
+        v128_andnot(b, a)
+    }
+
+    /// Shift the plane by one position along the `x` axis: lane `x` of the
+    /// result is lane `x - 1` of `a`.
+    #[inline(always)]
+    pub(super) fn shift1(a: Plane) -> Plane {
+        i32x4_shuffle::<3, 0, 1, 2>(a, a)
+    }
+
+    /// Shift the plane by two positions along the `x` axis: lane `x` of the
+    /// result is lane `x - 2` of `a`.
+    #[inline(always)]
+    pub(super) fn shift2(a: Plane) -> Plane {
+        i32x4_shuffle::<2, 3, 0, 1>(a, a)
+    }
+
+    #[inline(always)]
+    pub(super) fn rol(a: Plane, n: u32) -> Plane {
+        if n == 8 {
+            // rotating by a whole byte is a byte shuffle
+            i8x16_shuffle::<3, 0, 1, 2, 7, 4, 5, 6, 11, 8, 9, 10, 15, 12, 13, 14>(a, a)
+        } else {
+            v128_or(i32x4_shl(a, n), u32x4_shr(a, 32 - n))
+        }
+    }
+
+    xoodoo_kernel!(xoodoo, simd128, "simd128");
+}
+
+/// Apply Xoodoo with `ROUNDS` rounds to `state`.
+pub(crate) fn xoodoo<const ROUNDS: usize>(state: &mut [u32; 12]) {
+    // SAFETY: the `simd128` target feature is enabled at compile time.
+    unsafe { simd128::xoodoo(state, ROUNDS) };
+}
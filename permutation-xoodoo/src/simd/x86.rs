@@ -0,0 +1,342 @@
+//! SIMD kernels for `x86` and `x86_64`.
+//!
+//! The kernel is selected at runtime:
+//! * AVX2: VEX encoded 128-bit operations, with byte shuffles for the rotation
+//!   by 8 bits.
+//! * SSE2: 128-bit operations.
+//!
+//! Without either the portable implementation is used.
+//!
+//! The parallel kernels process eight states in 256-bit vectors with AVX2 and
+//! four states in 128-bit vectors with SSE2.
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+use crate::XoodooState;
+use crypto_permutation::PermutationState;
+
+cpufeatures::new!(sse2_intrinsics, "sse2");
+cpufeatures::new!(avx2_intrinsics, "avx2");
+
+/// Define the vector operations on a plane for the target features
+/// `$features`, except for `rol`.
+macro_rules! plane_ops {
+    ($features:literal) => {
+        pub(super) type Plane = __m128i;
+
+        #[inline]
+        #[target_feature(enable = $features)]
+        pub(super) unsafe fn load(plane: &[u32; 4]) -> Plane {
+            _mm_loadu_si128(plane.as_ptr().cast())
+        }
+
+        #[inline]
+        #[target_feature(enable = $features)]
+        pub(super) unsafe fn store(v: Plane) -> [u32; 4] {
+            let mut plane = [0; 4];
+            _mm_storeu_si128(plane.as_mut_ptr().cast(), v);
+            plane
+        }
+
+        /// Plane with `rc` in lane 0 and zeros elsewhere.
+        #[inline]
+        #[target_feature(enable = $features)]
+        pub(super) unsafe fn first(rc: u32) -> Plane {
+            _mm_cvtsi32_si128(rc as i32)
+        }
+
+        #[inline]
+        #[target_feature(enable = $features)]
+        pub(super) unsafe fn xor(a: Plane, b: Plane) -> Plane {
+            _mm_xor_si128(a, b)
+        }
+
+        /// `!a & b`
+        #[inline]
+        #[target_feature(enable = $features)]
+        pub(super) unsafe fn andnot(a: Plane, b: Plane) -> Plane {
+            _mm_andnot_si128(a, b)
+        }
+
+        /// Shift the plane by one position along the `x` axis: lane `x` of the
+        /// result is lane `x - 1` of `a`.
+        #[inline]
+        #[target_feature(enable = $features)]
+        pub(super) unsafe fn shift1(a: Plane) -> Plane {
+            _mm_shuffle_epi32(a, 0b10_01_00_11)
+        }
+
+        /// Shift the plane by two positions along the `x` axis: lane `x` of the
+        /// result is lane `x - 2` of `a`.
+        #[inline]
+        #[target_feature(enable = $features)]
+        pub(super) unsafe fn shift2(a: Plane) -> Plane {
+            _mm_shuffle_epi32(a, 0b01_00_11_10)
+        }
+
+        /// Rotate all lanes left by `n` bits using shifts.
+        #[inline]
+        #[target_feature(enable = $features)]
+        unsafe fn rol_shift(a: Plane, n: u32) -> Plane {
+            let left = _mm_cvtsi32_si128(n as i32);
+            let right = _mm_cvtsi32_si128(32 - n as i32);
+            _mm_or_si128(_mm_sll_epi32(a, left), _mm_srl_epi32(a, right))
+        }
+    };
+}
+
+/// Vector operations on a plane using SSE2.
+mod sse2 {
+    use super::*;
+
+    plane_ops!("sse2");
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn rol(a: Plane, n: u32) -> Plane {
+        rol_shift(a, n)
+    }
+
+    xoodoo_kernel!(xoodoo, sse2, "sse2");
+}
+
+/// Vector operations on a plane using AVX2.
+mod avx2 {
+    use super::*;
+
+    plane_ops!("avx2");
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn rol(a: Plane, n: u32) -> Plane {
+        if n == 8 {
+            // rotating by a whole byte is a byte shuffle
+            let indices = _mm_set_epi8(14, 13, 12, 15, 10, 9, 8, 11, 6, 5, 4, 7, 2, 1, 0, 3);
+            _mm_shuffle_epi8(a, indices)
+        } else {
+            rol_shift(a, n)
+        }
+    }
+
+    xoodoo_kernel!(xoodoo, avx2, "avx2");
+}
+
+/// Define the vector operations on lanes of several states for the vector
+/// type `$lanes` and the target features `$features`, except for `rol`.
+macro_rules! lanes_ops {
+    (
+        $features:literal, $lanes:ty, $n:literal, $set1:ident, $loadu:ident, $storeu:ident,
+        $xor:ident, $andnot:ident
+    ) => {
+        pub(super) type Lanes = $lanes;
+
+        #[inline]
+        #[target_feature(enable = $features)]
+        pub(super) unsafe fn splat(x: u32) -> Lanes {
+            $set1(x as i32)
+        }
+
+        #[inline]
+        #[target_feature(enable = $features)]
+        pub(super) unsafe fn load(lanes: &[u32; $n]) -> Lanes {
+            $loadu(lanes.as_ptr().cast())
+        }
+
+        #[inline]
+        #[target_feature(enable = $features)]
+        pub(super) unsafe fn store(v: Lanes) -> [u32; $n] {
+            let mut lanes = [0; $n];
+            $storeu(lanes.as_mut_ptr().cast(), v);
+            lanes
+        }
+
+        #[inline]
+        #[target_feature(enable = $features)]
+        pub(super) unsafe fn xor(a: Lanes, b: Lanes) -> Lanes {
+            $xor(a, b)
+        }
+
+        /// `!a & b`
+        #[inline]
+        #[target_feature(enable = $features)]
+        pub(super) unsafe fn andnot(a: Lanes, b: Lanes) -> Lanes {
+            $andnot(a, b)
+        }
+    };
+}
+
+/// Vector operations on four lanes using SSE2.
+mod sse2_x4 {
+    use super::*;
+
+    lanes_ops!(
+        "sse2",
+        __m128i,
+        4,
+        _mm_set1_epi32,
+        _mm_loadu_si128,
+        _mm_storeu_si128,
+        _mm_xor_si128,
+        _mm_andnot_si128
+    );
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn rol(a: Lanes, n: u32) -> Lanes {
+        let left = _mm_cvtsi32_si128(n as i32);
+        let right = _mm_cvtsi32_si128(32 - n as i32);
+        _mm_or_si128(_mm_sll_epi32(a, left), _mm_srl_epi32(a, right))
+    }
+
+    xoodoo_kernel_parallel!(xoodoo_x4, sse2_x4, 4, "sse2");
+}
+
+/// Vector operations on eight lanes using AVX2.
+mod avx2_x8 {
+    use super::*;
+
+    lanes_ops!(
+        "avx2",
+        __m256i,
+        8,
+        _mm256_set1_epi32,
+        _mm256_loadu_si256,
+        _mm256_storeu_si256,
+        _mm256_xor_si256,
+        _mm256_andnot_si256
+    );
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn rol(a: Lanes, n: u32) -> Lanes {
+        if n == 8 {
+            // rotating by a whole byte is a byte shuffle
+            let indices = _mm256_set_epi8(
+                14, 13, 12, 15, 10, 9, 8, 11, 6, 5, 4, 7, 2, 1, 0, 3, 14, 13, 12, 15, 10, 9, 8, 11,
+                6, 5, 4, 7, 2, 1, 0, 3,
+            );
+            _mm256_shuffle_epi8(a, indices)
+        } else {
+            let left = _mm_cvtsi32_si128(n as i32);
+            let right = _mm_cvtsi32_si128(32 - n as i32);
+            _mm256_or_si256(_mm256_sll_epi32(a, left), _mm256_srl_epi32(a, right))
+        }
+    }
+
+    xoodoo_kernel_parallel!(xoodoo_x8, avx2_x8, 8, "avx2");
+}
+
+/// Apply Xoodoo with `ROUNDS` rounds to `state`, using the fastest kernel
+/// available.
+pub(crate) fn xoodoo<const ROUNDS: usize>(state: &mut [u32; 12]) {
+    if avx2_intrinsics::get() {
+        // SAFETY: the `avx2` target feature is available.
+        unsafe { avx2::xoodoo(state, ROUNDS) };
+    } else if sse2_intrinsics::get() {
+        // SAFETY: the `sse2` target feature is available.
+        unsafe { sse2::xoodoo(state, ROUNDS) };
+    } else {
+        xoodoo_p::xoodoo::<ROUNDS>(state);
+    }
+}
+
+/// Number of states processed at once by the fastest kernel available.
+pub(crate) fn parallelism() -> usize {
+    if avx2_intrinsics::get() {
+        8
+    } else if sse2_intrinsics::get() {
+        4
+    } else {
+        1
+    }
+}
+
+/// Apply Xoodoo with `ROUNDS` rounds to all `states`, using the fastest
+/// kernels available.
+pub(crate) fn xoodoo_parallel<const ROUNDS: usize>(states: &mut [XoodooState]) {
+    let mut states = states;
+    if avx2_intrinsics::get() {
+        let mut chunks = states.chunks_exact_mut(8);
+        for chunk in &mut chunks {
+            // SAFETY: the `avx2` target feature is available.
+            unsafe { avx2_x8::xoodoo_x8(chunk, ROUNDS) };
+        }
+        states = chunks.into_remainder();
+    }
+    if sse2_intrinsics::get() {
+        let mut chunks = states.chunks_exact_mut(4);
+        for chunk in &mut chunks {
+            // SAFETY: the `sse2` target feature is available.
+            unsafe { sse2_x4::xoodoo_x4(chunk, ROUNDS) };
+        }
+        states = chunks.into_remainder();
+    }
+    for state in states {
+        xoodoo::<ROUNDS>(state.get_state_mut());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simd::tests::{reference, test_state, test_states};
+
+    #[test]
+    fn sse2_kernel() {
+        if !sse2_intrinsics::get() {
+            return;
+        }
+        for rounds in 1..=12 {
+            let mut state = test_state();
+            let mut expected = state;
+            // SAFETY: the `sse2` target feature is available.
+            unsafe { sse2::xoodoo(&mut state, rounds) };
+            reference(&mut expected, rounds);
+            assert_eq!(state, expected);
+        }
+    }
+
+    #[test]
+    fn avx2_kernel() {
+        if !avx2_intrinsics::get() {
+            return;
+        }
+        for rounds in 1..=12 {
+            let mut state = test_state();
+            let mut expected = state;
+            // SAFETY: the `avx2` target feature is available.
+            unsafe { avx2::xoodoo(&mut state, rounds) };
+            reference(&mut expected, rounds);
+            assert_eq!(state, expected);
+        }
+    }
+
+    #[test]
+    fn parallel_kernels() {
+        for rounds in [1, 6, 12] {
+            let mut states = test_states();
+            if sse2_intrinsics::get() {
+                // SAFETY: the `sse2` target feature is available.
+                unsafe { sse2_x4::xoodoo_x4(&mut states[..4], rounds) };
+            }
+            if avx2_intrinsics::get() {
+                // SAFETY: the `avx2` target feature is available.
+                unsafe { avx2_x8::xoodoo_x8(&mut states[2..], rounds) };
+            }
+            let mut expected = test_states();
+            for (j, state) in expected.iter_mut().enumerate() {
+                let sse2 = j < 4 && sse2_intrinsics::get();
+                let avx2 = j >= 2 && avx2_intrinsics::get();
+                for _ in 0..(sse2 as usize + avx2 as usize) {
+                    reference(state.get_state_mut(), rounds);
+                }
+            }
+            for (state, expected) in states.iter().zip(expected.iter()) {
+                assert_eq!(state.get_state(), expected.get_state());
+            }
+        }
+    }
+}
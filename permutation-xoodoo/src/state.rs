@@ -1,5 +1,6 @@
 //! Xoodoo permutation state struct.
 
+use crypto_permutation::uint_array_state::{copy_le_bytes_at, xor_le_bytes_at};
 use crypto_permutation::PermutationState;
 
 const LEN: usize = 12;
@@ -7,8 +8,13 @@ type StateRepresentation = [u32; LEN];
 
 /// State for the Xoodoo permutation. 48 bytes, internally represented by 12
 /// `u32`s in little endian encoding.
+///
+/// With the `bytemuck` crate-feature, implements `bytemuck::Pod` (and hence
+/// [`Copy`]) and `bytemuck::TransparentWrapper<[u32; 12]>`.
 #[derive(Clone)]
+#[cfg_attr(feature = "bytemuck", derive(Copy))]
 #[cfg_attr(feature = "debug", derive(Debug, PartialEq))]
+#[repr(transparent)]
 pub struct XoodooState {
     state: StateRepresentation,
 }
@@ -25,6 +31,83 @@ type XorWriter<'a> = crypto_permutation::io::le_uint_slice_writer::LeU32SliceXor
 /// in little endian order.
 type StateReader<'a> = crypto_permutation::io::le_uint_slice_reader::LeU32SliceReader<'a>;
 
+// The lane at position `(x, y)` is stored at index `4 * y + x`.
+
+impl XoodooState {
+    /// Create a state from its 48 byte encoding, i.e. the bytes the
+    /// [`PermutationState::reader`] of the resulting state outputs.
+    pub fn from_bytes(bytes: [u8; 48]) -> Self {
+        let mut state = [0; LEN];
+        for (lane, chunk) in state.iter_mut().zip(bytes.chunks_exact(4)) {
+            *lane = u32::from_le_bytes(chunk.try_into().unwrap());
+        }
+        Self { state }
+    }
+
+    /// The 48 byte encoding of the state, i.e. the bytes
+    /// [`PermutationState::reader`] outputs.
+    pub fn to_bytes(&self) -> [u8; 48] {
+        let mut bytes = [0; 48];
+        for (chunk, lane) in bytes.chunks_exact_mut(4).zip(self.state.iter()) {
+            chunk.copy_from_slice(&lane.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// View the state as its 48 byte encoding, without copying.
+    ///
+    /// Only available on little endian targets, where the in-memory
+    /// representation coincides with the encoding.
+    #[cfg(all(feature = "bytemuck", target_endian = "little"))]
+    pub fn as_le_bytes(&self) -> &[u8; 48] {
+        bytemuck::cast_ref(&self.state)
+    }
+
+    /// Mutably view the state as its 48 byte encoding, without copying.
+    ///
+    /// Only available on little endian targets, where the in-memory
+    /// representation coincides with the encoding.
+    #[cfg(all(feature = "bytemuck", target_endian = "little"))]
+    pub fn as_le_bytes_mut(&mut self) -> &mut [u8; 48] {
+        bytemuck::cast_mut(&mut self.state)
+    }
+
+    /// The lane at position `(x, y)`, i.e. index `4 * y + x` of the state
+    /// representation.
+    ///
+    /// # Panics
+    /// Panics if `x` is not less than 4 or `y` is not less than 3.
+    pub fn lane(&self, x: usize, y: usize) -> u32 {
+        self.plane(y)[x]
+    }
+
+    /// Mutable reference to the lane at position `(x, y)`, i.e. index
+    /// `4 * y + x` of the state representation.
+    ///
+    /// # Panics
+    /// Panics if `x` is not less than 4 or `y` is not less than 3.
+    pub fn lane_mut(&mut self, x: usize, y: usize) -> &mut u32 {
+        &mut self.plane_mut(y)[x]
+    }
+
+    /// The plane `y`, i.e. the four lanes with this `y` coordinate.
+    ///
+    /// # Panics
+    /// Panics if `y` is not less than 3.
+    pub fn plane(&self, y: usize) -> &[u32; 4] {
+        self.state[4 * y..4 * y + 4].try_into().unwrap()
+    }
+
+    /// Mutable reference to the plane `y`, i.e. the four lanes with this `y`
+    /// coordinate.
+    ///
+    /// # Panics
+    /// Panics if `y` is not less than 3.
+    pub fn plane_mut(&mut self, y: usize) -> &mut [u32; 4] {
+        (&mut self.state[4 * y..4 * y + 4]).try_into().unwrap()
+    }
+}
+
 impl Default for XoodooState {
     fn default() -> Self {
         Self { state: [0; LEN] }
@@ -40,7 +123,23 @@ impl core::ops::BitXorAssign<&Self> for XoodooState {
     }
 }
 
+// SAFETY: `XoodooState` is a `repr(transparent)` wrapper around a `Pod` array.
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for XoodooState {}
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for XoodooState {}
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::TransparentWrapper<StateRepresentation> for XoodooState {}
+
+#[cfg(feature = "subtle")]
+impl subtle::ConstantTimeEq for XoodooState {
+    fn ct_eq(&self, other: &Self) -> subtle::Choice {
+        self.state[..].ct_eq(&other.state[..])
+    }
+}
+
 impl PermutationState for XoodooState {
+    type Block = [u8; 48];
     type CopyWriter<'a> = CopyWriter<'a>;
     type Representation = StateRepresentation;
     type StateReader<'a> = StateReader<'a>;
@@ -71,4 +170,90 @@ impl PermutationState for XoodooState {
     fn xor_writer<'a>(&'a mut self) -> Self::XorWriter<'a> {
         XorWriter::new(self.get_state_mut())
     }
+
+    fn xor_bytes_at(&mut self, offset: usize, data: &[u8]) {
+        xor_le_bytes_at(&mut self.state, offset, data);
+    }
+
+    fn copy_bytes_at(&mut self, offset: usize, data: &[u8]) {
+        copy_le_bytes_at(&mut self.state, offset, data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::XoodooState;
+    use crypto_permutation::io::{Reader, Writer};
+    use crypto_permutation::PermutationState;
+
+    #[test]
+    fn lane_accessors() {
+        let mut state = XoodooState::from_state(core::array::from_fn(|i| i as u32));
+
+        assert_eq!(state.lane(0, 0), 0);
+        assert_eq!(state.lane(3, 1), 7);
+        assert_eq!(state.lane(2, 2), 10);
+        assert_eq!(state.plane(1), &[4, 5, 6, 7]);
+
+        *state.lane_mut(1, 2) = 100;
+        state.plane_mut(0)[3] = 200;
+        assert_eq!(state.get_state()[9], 100);
+        assert_eq!(state.get_state()[3], 200);
+    }
+
+    /// The byte offset fast paths agree with the writers.
+    #[test]
+    fn bytes_at() {
+        let data: [u8; 48] = core::array::from_fn(|i| (i as u8).wrapping_mul(0x3d));
+        for (offset, len) in [(0, 48), (4, 40), (3, 20), (8, 5), (47, 1)] {
+            let lanes = core::array::from_fn(|i| i as u32);
+            let mut state = XoodooState::from_state(lanes);
+            let mut expected = XoodooState::from_state(lanes);
+
+            state.xor_bytes_at(offset, &data[..len]);
+            let mut writer = expected.xor_writer();
+            writer.skip(offset).unwrap();
+            writer.write_bytes(&data[..len]).unwrap();
+            writer.finish();
+            assert_eq!(state.get_state(), expected.get_state());
+
+            state.copy_bytes_at(offset, &data[48 - len..]);
+            let mut writer = expected.copy_writer();
+            writer.skip(offset).unwrap();
+            writer.write_bytes(&data[48 - len..]).unwrap();
+            writer.finish();
+            assert_eq!(state.get_state(), expected.get_state());
+        }
+    }
+
+    #[test]
+    fn byte_conversion() {
+        let bytes: [u8; 48] = core::array::from_fn(|i| i as u8);
+        let state = XoodooState::from_bytes(bytes);
+        assert_eq!(state.lane(1, 0), 0x07060504);
+        assert_eq!(state.to_bytes(), bytes);
+
+        let mut read = [0; 48];
+        state.reader().write_to_slice(&mut read).unwrap();
+        assert_eq!(read, bytes);
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn bytemuck_views() {
+        use bytemuck::TransparentWrapper;
+
+        let lanes: [u32; 12] = core::array::from_fn(|i| i as u32);
+        let state: &XoodooState = XoodooState::wrap_ref(&lanes);
+        assert_eq!(state.lane(3, 2), 11);
+        assert_eq!(bytemuck::bytes_of(state).len(), 48);
+
+        #[cfg(target_endian = "little")]
+        {
+            let mut state = *state;
+            assert_eq!(state.as_le_bytes(), &state.to_bytes());
+            state.as_le_bytes_mut()[4] = 0xff;
+            assert_eq!(state.lane(1, 0), 0xff);
+        }
+    }
 }
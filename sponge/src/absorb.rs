@@ -0,0 +1,280 @@
+//! Absorbing phase of the sponge construction.
+
+use super::{SpongeReader, PAD_LAST};
+use crypto_permutation::io::check_write_size;
+use crypto_permutation::{
+    Permutation, PermutationState, Reader, SpongeConstruction, StateView, WriteTooLargeError,
+    Writer,
+};
+
+/// The sponge construction over the permutation `P` with a rate of `RATE`
+/// bytes, in its absorbing phase.
+///
+/// Data written to it through the [`Writer`] trait is absorbed. [`Self::finish`]
+/// pads the input and returns a [`SpongeReader`] squeezing the output.
+/// [`Writer::skip`] absorbs zero bytes.
+///
+/// The capacity is the part of the state that is not in the rate, so the
+/// generic security level is half the capacity in bits.
+///
+/// ```
+/// use crypto_permutation::{Reader, Writer};
+/// use permutation_keccak::KeccakF1600;
+/// use sponge::Sponge;
+///
+/// // SHA3-256, which uses the domain separation suffix bits `01`.
+/// let mut sponge = Sponge::<KeccakF1600, 136>::with_suffix(0x06);
+/// sponge.write_bytes(b"abc").unwrap();
+/// let mut digest = [0; 32];
+/// sponge.finish().write_to_slice(&mut digest).unwrap();
+/// assert_eq!(digest[..4], [0x3a, 0x98, 0x5d, 0xa7]);
+/// ```
+#[derive(Clone)]
+pub struct Sponge<P: Permutation, const RATE: usize> {
+    state: P::State,
+    /// Number of bytes absorbed into the current block.
+    pos: usize,
+    /// Domain separation suffix bits, followed by a delimiting one bit.
+    suffix: u8,
+}
+
+impl<P: Permutation, const RATE: usize> core::fmt::Debug for Sponge<P, RATE> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Sponge")
+            .field("rate", &RATE)
+            .field("pos", &self.pos)
+            .field("suffix", &self.suffix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<P: Permutation, const RATE: usize> Default for Sponge<P, RATE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Permutation, const RATE: usize> Sponge<P, RATE> {
+    /// Create a sponge with an all zero state, without domain separation
+    /// suffix.
+    ///
+    /// # Panics
+    /// Panics when `RATE` is zero or not smaller than the state size.
+    pub fn new() -> Self {
+        Self::with_suffix(0x01)
+    }
+
+    /// Create a sponge with an all zero state, that appends the domain
+    /// separation suffix bits of `suffix` to the input before padding.
+    ///
+    /// The suffix bits are given least significant bit first, followed by a
+    /// delimiting one bit, like the `delimitedSuffix` of the XKCP. E.g. `0x01`
+    /// means no suffix, and `0x06` is the suffix `01` of SHA-3.
+    ///
+    /// # Panics
+    /// Panics when `suffix` is zero, or when `RATE` is zero or not smaller than
+    /// the state size.
+    pub fn with_suffix(suffix: u8) -> Self {
+        assert!(RATE > 0, "the rate must not be zero");
+        assert!(
+            RATE < P::State::SIZE,
+            "the rate must be smaller than the state"
+        );
+        assert_ne!(suffix, 0, "the suffix must contain a delimiting one bit");
+        Self {
+            state: Default::default(),
+            pos: 0,
+            suffix,
+        }
+    }
+
+    /// Hash `data` without domain separation suffix, giving `N` bytes of
+    /// output.
+    ///
+    /// # Panics
+    /// Panics when `RATE` is zero or not smaller than the state size.
+    pub fn hash<const N: usize>(data: &[u8]) -> [u8; N] {
+        let mut sponge = Self::new();
+        sponge
+            .write_bytes(data)
+            .expect("sponge accepts any input length");
+        let mut output = [0; N];
+        sponge
+            .finish()
+            .write_to_slice(&mut output)
+            .expect("sponge generates any output length");
+        output
+    }
+
+    /// The outer part of the state, which the input is absorbed into.
+    fn outer(&mut self) -> StateView<'_, P::State> {
+        StateView::new(&mut self.state, RATE)
+    }
+
+    /// Apply the permutation and start a new block.
+    fn permute(&mut self) {
+        P::default().apply(&mut self.state);
+        self.pos = 0;
+    }
+}
+
+impl<P: Permutation, const RATE: usize> Writer for Sponge<P, RATE> {
+    type Return = SpongeReader<P, RATE>;
+
+    /// Returns `usize::MAX`: the sponge accepts input of any length.
+    fn capacity(&self) -> usize {
+        usize::MAX
+    }
+
+    fn skip(&mut self, mut len: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(len, self.capacity())?;
+        while len > 0 {
+            let n = len.min(RATE - self.pos);
+            self.pos += n;
+            len -= n;
+            if self.pos == RATE {
+                self.permute();
+            }
+        }
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, mut data: &[u8]) -> Result<(), WriteTooLargeError> {
+        check_write_size(data.len(), self.capacity())?;
+        while !data.is_empty() {
+            let n = data.len().min(RATE - self.pos);
+            let pos = self.pos;
+            self.outer().xor_bytes_at(pos, &data[..n]);
+            self.pos += n;
+            data = &data[n..];
+            if self.pos == RATE {
+                self.permute();
+            }
+        }
+        Ok(())
+    }
+
+    /// Pad the input and switch to the squeezing phase.
+    fn finish(mut self) -> Self::Return {
+        let (pos, suffix) = (self.pos, self.suffix);
+        self.outer().xor_bytes_at(pos, &[suffix]);
+        if self.suffix & PAD_LAST != 0 && self.pos == RATE - 1 {
+            // The delimiting bit of the suffix is the last bit of the block.
+            self.permute();
+        }
+        self.outer().xor_bytes_at(RATE - 1, &[PAD_LAST]);
+        self.permute();
+        SpongeReader::new(self.state)
+    }
+}
+
+impl<P: Permutation, const RATE: usize> SpongeConstruction for Sponge<P, RATE> {
+    type Squeezer = SpongeReader<P, RATE>;
+
+    const RATE: usize = RATE;
+
+    /// See [`Sponge::with_suffix`].
+    ///
+    /// # Panics
+    /// Panics when `suffix` is zero, or when `RATE` is zero or not smaller than
+    /// the state size.
+    fn with_suffix(suffix: u8) -> Self {
+        Sponge::with_suffix(suffix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sponge;
+    use crypto_permutation::{Reader, SpongeConstruction, Writer};
+    use permutation_keccak::KeccakF1600;
+
+    fn digest<const RATE: usize, const N: usize>(suffix: u8, parts: &[&[u8]]) -> [u8; N] {
+        let mut sponge = Sponge::<KeccakF1600, RATE>::with_suffix(suffix);
+        for part in parts {
+            sponge.write_bytes(part).unwrap();
+        }
+        let mut output = [0; N];
+        sponge.finish().write_to_slice(&mut output).unwrap();
+        output
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    /// SHA3-256 and SHAKE128 test vectors from FIPS 202.
+    #[test]
+    fn fips202() {
+        assert_eq!(
+            hex(&digest::<136, 32>(0x06, &[])),
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"
+        );
+        assert_eq!(
+            hex(&digest::<136, 32>(0x06, &[b"abc"])),
+            "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"
+        );
+        assert_eq!(
+            hex(&digest::<168, 32>(0x1f, &[])),
+            "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26"
+        );
+    }
+
+    /// Splitting the input differently gives the same output, also around
+    /// block boundaries.
+    #[test]
+    fn split_input() {
+        let message: Vec<u8> = (0..400).map(|i| i as u8).collect();
+        let expected = digest::<136, 64>(0x01, &[&message]);
+        for split in [1, 135, 136, 137, 272, 399] {
+            let (first, second) = message.split_at(split);
+            assert_eq!(digest::<136, 64>(0x01, &[first, second]), expected);
+        }
+        assert_eq!(Sponge::<KeccakF1600, 136>::hash::<64>(&message), expected);
+    }
+
+    /// Skipping absorbs zero bytes.
+    #[test]
+    fn skip() {
+        let mut sponge = Sponge::<KeccakF1600, 136>::new();
+        sponge.write_bytes(b"prefix").unwrap();
+        sponge.skip(200).unwrap();
+        let mut output = [0; 32];
+        sponge.finish().write_to_slice(&mut output).unwrap();
+        assert_eq!(output, digest::<136, 32>(0x01, &[b"prefix", &[0; 200]]));
+    }
+
+    /// Inputs differing only in trailing zero bytes or in the suffix give
+    /// different outputs, also when the suffix ends a block.
+    #[test]
+    fn padding() {
+        let message = [0; 135];
+        let outputs = [
+            digest::<136, 32>(0x01, &[&message]),
+            digest::<136, 32>(0x01, &[&message[..134]]),
+            digest::<136, 32>(0x81, &[&message]),
+            digest::<136, 32>(0x80, &[&message]),
+        ];
+        for (i, output) in outputs.iter().enumerate() {
+            assert!(!outputs[i + 1..].contains(output));
+        }
+    }
+
+    /// Hash with the suffix `0x06` through the [`SpongeConstruction`] trait.
+    fn generic_digest<S: SpongeConstruction>(data: &[u8]) -> [u8; 32] {
+        let mut sponge = S::with_suffix(0x06);
+        sponge.write_bytes(data).unwrap();
+        let mut output = [0; 32];
+        sponge.finish().write_to_slice(&mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn sponge_construction() {
+        assert_eq!(<Sponge<KeccakF1600, 136> as SpongeConstruction>::RATE, 136);
+        assert_eq!(
+            generic_digest::<Sponge<KeccakF1600, 136>>(b"abc"),
+            digest::<136, 32>(0x06, &[b"abc"])
+        );
+    }
+}
@@ -0,0 +1,280 @@
+//! The duplex construction.
+
+use super::PAD_LAST;
+use crypto_permutation::{DuplexState, Permutation, PermutationState, StateView};
+
+/// Pad an input block of `len` bytes in `state` with the delimited suffix
+/// `suffix` and the multi-rate padding for a rate of `rate` bytes.
+///
+/// # Panics
+/// Panics when `suffix` is zero or at least `0x80`.
+pub(super) fn pad<S: PermutationState>(state: &mut S, len: usize, suffix: u8, rate: usize) {
+    assert!(
+        suffix != 0 && suffix < PAD_LAST,
+        "the suffix must be a delimited suffix of at most six bits"
+    );
+    let mut outer = StateView::new(state, rate);
+    outer.xor_bytes_at(len, &[suffix]);
+    outer.xor_bytes_at(rate - 1, &[PAD_LAST]);
+}
+
+/// Zero bytes for clearing the outer state in [`OverwriteMode`].
+const ZEROS: [u8; 64] = [0; 64];
+
+/// How a duplexing call absorbs its input block into the outer state, the
+/// first `rate` bytes of the state.
+pub trait DuplexMode {
+    /// Absorb `input` padded with the delimited suffix `suffix` and the
+    /// multi-rate padding into the outer state of `state`.
+    ///
+    /// # Panics
+    /// Panics when `suffix` is zero or at least `0x80`.
+    fn absorb<S: PermutationState>(state: &mut S, input: &[u8], suffix: u8, rate: usize);
+}
+
+/// Xor the padded input block into the outer state: the duplex construction
+/// as originally defined.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct XorMode;
+
+impl DuplexMode for XorMode {
+    fn absorb<S: PermutationState>(state: &mut S, input: &[u8], suffix: u8, rate: usize) {
+        StateView::new(state, rate).xor_bytes_at(0, input);
+        pad(state, input.len(), suffix, rate);
+    }
+}
+
+/// Overwrite the outer state with the padded input block: the overwrite
+/// duplex.
+///
+/// The new outer state doesn't depend on the old one, so it needn't be read
+/// back or kept in a separate buffer. E.g. a decrypting mode can overwrite the
+/// outer state with the ciphertext, where it would otherwise xor in the
+/// plaintext. The security of the overwrite duplex is close to that of the
+/// xor duplex.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OverwriteMode;
+
+impl DuplexMode for OverwriteMode {
+    fn absorb<S: PermutationState>(state: &mut S, input: &[u8], suffix: u8, rate: usize) {
+        let mut outer = StateView::new(state, rate);
+        outer.copy_bytes_at(0, input);
+        let mut offset = input.len();
+        while offset < rate {
+            let len = (rate - offset).min(ZEROS.len());
+            outer.copy_bytes_at(offset, &ZEROS[..len]);
+            offset += len;
+        }
+        pad(state, input.len(), suffix, rate);
+    }
+}
+
+/// The duplex construction over the permutation `P` with a rate of `RATE`
+/// bytes, absorbing in the mode `M`.
+///
+/// Every duplexing call absorbs an input block of at most
+/// [`Self::MAX_INPUT`] bytes, padded with optional domain separation suffix
+/// bits and the multi-rate padding, applies the permutation, and squeezes at
+/// most `RATE` bytes of output. The output of a call depends on all inputs so
+/// far: it equals the output of the [`Sponge`](crate::Sponge) on the padded
+/// inputs of the earlier calls followed by the input of this call. The duplex
+/// is the basis of authenticated encryption modes, reseedable random number
+/// generators and protocol frameworks.
+///
+/// By default the input blocks are xored into the outer state, see
+/// [`XorMode`]. With [`OverwriteMode`] they overwrite it instead, which saves
+/// memory on small devices; the equivalence with the sponge then doesn't hold.
+///
+/// ```
+/// use permutation_keccak::KeccakF1600;
+/// use sponge::Duplex;
+///
+/// let mut duplex = Duplex::<KeccakF1600, 168>::new();
+/// let mut first = [0; 32];
+/// duplex.duplex(b"hello", &mut first);
+/// let mut second = [0; 32];
+/// duplex.duplex(b"world", &mut second);
+/// assert_ne!(first, second);
+/// ```
+#[derive(Clone)]
+pub struct Duplex<P: Permutation, const RATE: usize, M = XorMode> {
+    pub(super) state: P::State,
+    mode: core::marker::PhantomData<M>,
+}
+
+impl<P: Permutation, const RATE: usize, M> core::fmt::Debug for Duplex<P, RATE, M> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Duplex")
+            .field("rate", &RATE)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<P: Permutation, const RATE: usize, M: DuplexMode> Default for Duplex<P, RATE, M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Permutation, const RATE: usize, M: DuplexMode> Duplex<P, RATE, M> {
+    /// Maximum number of bytes absorbed by a single duplexing call: the rate
+    /// minus one byte for the padding.
+    pub const MAX_INPUT: usize = RATE - 1;
+
+    /// Create a duplex object with an all zero state.
+    ///
+    /// # Panics
+    /// Panics when `RATE` is smaller than two or not smaller than the state
+    /// size.
+    pub fn new() -> Self {
+        assert!(RATE >= 2, "the rate must fit input and padding");
+        assert!(
+            RATE < P::State::SIZE,
+            "the rate must be smaller than the state"
+        );
+        Self {
+            state: Default::default(),
+            mode: core::marker::PhantomData,
+        }
+    }
+
+    /// Absorb `input` without domain separation suffix, apply the permutation
+    /// and squeeze `output.len()` bytes into `output`.
+    ///
+    /// # Panics
+    /// Panics when `input.len() > Self::MAX_INPUT` or `output.len() > RATE`.
+    pub fn duplex(&mut self, input: &[u8], output: &mut [u8]) {
+        self.duplex_with_suffix(input, 0x01, output);
+    }
+
+    /// Like [`Self::duplex`], appending the domain separation suffix bits of
+    /// `suffix` to `input` before padding.
+    ///
+    /// The suffix bits are given least significant bit first, followed by a
+    /// delimiting one bit, as for [`Sponge::with_suffix`]. At most six suffix
+    /// bits are supported, so that suffix and padding fit the last byte of a
+    /// full input block.
+    ///
+    /// # Panics
+    /// Panics when `suffix` is zero or at least `0x80`, when
+    /// `input.len() > Self::MAX_INPUT` or when `output.len() > RATE`.
+    ///
+    /// [`Sponge::with_suffix`]: crate::Sponge::with_suffix
+    pub fn duplex_with_suffix(&mut self, input: &[u8], suffix: u8, output: &mut [u8]) {
+        assert!(input.len() <= Self::MAX_INPUT, "input exceeds the rate");
+        assert!(output.len() <= RATE, "output exceeds the rate");
+        M::absorb(&mut self.state, input, suffix, RATE);
+        P::default().apply(&mut self.state);
+        StateView::new(&mut self.state, RATE).read_bytes_at(0, output);
+    }
+}
+
+impl<P: Permutation, const RATE: usize, M: DuplexMode> DuplexState for Duplex<P, RATE, M> {
+    const MAX_INPUT: usize = RATE - 1;
+    const RATE: usize = RATE;
+
+    fn duplex_with_suffix(&mut self, input: &[u8], suffix: u8, output: &mut [u8]) {
+        Duplex::duplex_with_suffix(self, input, suffix, output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Duplex, OverwriteMode};
+    use crate::Sponge;
+    use crypto_permutation::{DuplexState, Permutation, PermutationState, Reader, Writer};
+    use permutation_keccak::KeccakF1600;
+
+    type TestDuplex = Duplex<KeccakF1600, 136>;
+
+    /// The output of every duplexing call is the sponge output on the padded
+    /// earlier inputs followed by the current input.
+    #[test]
+    fn sponge_equivalence() {
+        let inputs: [&[u8]; 4] = [b"", b"hello", &[0x17; 135], b"world"];
+        let mut duplex = TestDuplex::new();
+        let mut padded = Vec::new();
+        for input in inputs {
+            let mut output = [0; 136];
+            duplex.duplex(input, &mut output);
+
+            let mut sponge = Sponge::<KeccakF1600, 136>::new();
+            sponge.write_bytes(&padded).unwrap();
+            sponge.write_bytes(input).unwrap();
+            let mut expected = [0; 136];
+            sponge.finish().write_to_slice(&mut expected).unwrap();
+            assert_eq!(output, expected);
+
+            padded.extend_from_slice(input);
+            padded.push(0x01);
+            padded.resize((padded.len() + 135) / 136 * 136, 0);
+            *padded.last_mut().unwrap() ^= 0x80;
+        }
+    }
+
+    /// Different suffixes give different outputs.
+    #[test]
+    fn suffix() {
+        let mut outputs = Vec::new();
+        for suffix in [0x01, 0x02, 0x03, 0x7f] {
+            let mut output = [0; 32];
+            TestDuplex::new().duplex_with_suffix(&[0; 135], suffix, &mut output);
+            outputs.push(output);
+        }
+        for (i, output) in outputs.iter().enumerate() {
+            assert!(!outputs[i + 1..].contains(output));
+        }
+    }
+
+    /// The overwrite duplex replaces the outer state by the padded input, so it
+    /// agrees with the xor duplex on the all zero initial state only.
+    #[test]
+    fn overwrite() {
+        let mut duplex = Duplex::<KeccakF1600, 136, OverwriteMode>::new();
+        let mut xor_duplex = TestDuplex::new();
+        let mut output = [0; 136];
+        let mut expected = [0; 136];
+        duplex.duplex(b"hello", &mut output);
+        xor_duplex.duplex(b"hello", &mut expected);
+        assert_eq!(output, expected);
+
+        let mut state = duplex.state.clone();
+        duplex.duplex_with_suffix(b"world", 0x02, &mut output);
+        xor_duplex.duplex_with_suffix(b"world", 0x02, &mut expected);
+        assert_ne!(output, expected);
+
+        let mut block = [0; 136];
+        block[..5].copy_from_slice(b"world");
+        block[5] = 0x02;
+        block[135] = 0x80;
+        state.copy_bytes_at(0, &block);
+        KeccakF1600.apply(&mut state);
+        state.reader().write_to_slice(&mut expected).unwrap();
+        assert_eq!(output, expected);
+    }
+
+    /// Duplexing through the [`DuplexState`] trait is duplexing with the
+    /// inherent methods.
+    #[test]
+    fn duplex_state() {
+        fn generic<D: DuplexState>(duplex: &mut D, output: &mut [u8]) {
+            duplex.duplex(&[0x17; 135], &mut []);
+            duplex.duplex_with_suffix(b"hello", 0x02, output);
+        }
+        assert_eq!(<TestDuplex as DuplexState>::MAX_INPUT, 135);
+        assert_eq!(<TestDuplex as DuplexState>::RATE, 136);
+        let mut output = [0; 136];
+        generic(&mut TestDuplex::new(), &mut output);
+        let mut duplex = TestDuplex::new();
+        let mut expected = [0; 136];
+        duplex.duplex(&[0x17; 135], &mut []);
+        duplex.duplex_with_suffix(b"hello", 0x02, &mut expected);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn input_too_long() {
+        TestDuplex::new().duplex(&[0; 136], &mut []);
+    }
+}
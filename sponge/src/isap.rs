@@ -0,0 +1,364 @@
+//! The ISAP leakage resilient authenticated encryption mode.
+
+use super::TagMismatchError;
+use crypto_permutation::{Permutation, PermutationState, Reader};
+use subtle::ConstantTimeEq;
+
+/// Length in bytes of the key, the nonce and the tag of [`Isap`].
+pub const ISAP_KEY_LEN: usize = 16;
+
+/// First byte of the IV of the MAC.
+const IV_A: u8 = 0x01;
+/// First byte of the IV of the re-keying function for the MAC.
+const IV_KA: u8 = 0x02;
+/// First byte of the IV of the re-keying function for the encryption.
+const IV_KE: u8 = 0x03;
+/// Byte padding the associated data and the ciphertext.
+const PAD: u8 = 0x80;
+/// Byte separating the associated data from the ciphertext, in the last byte
+/// of the state.
+const DOMAIN_SEPARATOR: u8 = 0x01;
+
+/// ISAP authenticated encryption over the permutations `PH`, `PB`, `PE` and
+/// `PK`, with a rate of `RATE` bytes.
+///
+/// ISAP is designed to resist side-channel attacks through leakage resilience
+/// instead of masking. Both the encryption and the MAC are keyed by session
+/// keys derived from the long term key by a re-keying function, which absorbs
+/// the nonce respectively a hash of the associated data and the ciphertext
+/// one bit per call of the permutation `PB`. So an attacker observing many
+/// encryptions or forgery attempts only gets the leakage of a few processings
+/// of every value that depends on the key. The other permutations are used
+/// for hashing (`PH`), encryption (`PE`) and key derivation (`PK`). The
+/// ciphertext is authenticated before it is decrypted, so no plaintext is
+/// released for forgeries.
+///
+/// ISAP-K-128a uses Keccak-p\[400\] with a rate of 18 bytes and ISAP-A-128a
+/// Ascon-p with a rate of 8 bytes. The numbers of rounds of the permutations
+/// are only used for the IVs. These instances have not been checked against
+/// the official test vectors.
+///
+/// # Crypto
+/// A nonce must never be used twice under the same key.
+///
+/// ```
+/// use permutation_keccak::KeccakP400;
+/// use sponge::Isap;
+///
+/// // Parameters like ISAP-K-128a.
+/// type IsapK = Isap<KeccakP400<16>, KeccakP400<1>, KeccakP400<8>, KeccakP400<8>, 18>;
+///
+/// let isap = IsapK::new(&[0x42; 16], [16, 1, 8, 8]);
+/// let mut message = *b"hello world";
+/// let tag = isap.encrypt(&[0x17; 16], b"metadata", &mut message);
+/// isap.decrypt(&[0x17; 16], b"metadata", &mut message, &tag)
+///     .unwrap();
+/// assert_eq!(&message, b"hello world");
+/// ```
+#[derive(Clone)]
+pub struct Isap<PH, PB, PE, PK, const RATE: usize> {
+    key: [u8; ISAP_KEY_LEN],
+    /// The parameters following the first byte of the IVs.
+    parameters: [u8; 7],
+    permutations: core::marker::PhantomData<(PH, PB, PE, PK)>,
+}
+
+impl<PH, PB, PE, PK, const RATE: usize> core::fmt::Debug for Isap<PH, PB, PE, PK, RATE> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Isap")
+            .field("rate", &RATE)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<PH, PB, PE, PK, const RATE: usize> Isap<PH, PB, PE, PK, RATE>
+where
+    PH: Permutation,
+    PB: Permutation<State = PH::State>,
+    PE: Permutation<State = PH::State>,
+    PK: Permutation<State = PH::State>,
+{
+    /// Create ISAP with the key `key`, for permutations with the numbers of
+    /// rounds `rounds` of `PH`, `PB`, `PE` and `PK` in that order.
+    ///
+    /// # Panics
+    /// Panics when `RATE` is zero or more than 31, or when the state can't
+    /// hold a nonce and an IV or is not larger than `RATE`.
+    pub fn new(key: &[u8; ISAP_KEY_LEN], rounds: [u8; 4]) -> Self {
+        assert!(RATE > 0, "the rate must not be zero");
+        assert!(RATE < 32, "the rate in bits must fit a byte of the IV");
+        assert!(
+            RATE < PH::State::SIZE && ISAP_KEY_LEN + 8 <= PH::State::SIZE,
+            "the state is too small"
+        );
+        let [rounds_h, rounds_b, rounds_e, rounds_k] = rounds;
+        let parameters = [
+            (ISAP_KEY_LEN * 8) as u8,
+            (RATE * 8) as u8,
+            1,
+            rounds_h,
+            rounds_b,
+            rounds_e,
+            rounds_k,
+        ];
+        Self {
+            key: *key,
+            parameters,
+            permutations: core::marker::PhantomData,
+        }
+    }
+
+    /// Initialise a state with `value` followed by the IV starting with `iv`.
+    fn init(&self, value: &[u8; ISAP_KEY_LEN], iv: u8) -> PH::State {
+        let mut state = PH::State::default();
+        state.copy_bytes_at(0, value);
+        state.copy_bytes_at(ISAP_KEY_LEN, &[iv]);
+        state.copy_bytes_at(ISAP_KEY_LEN + 1, &self.parameters);
+        state
+    }
+
+    /// The re-keying function: derive a session key from the key and `y` for
+    /// the IV starting with `iv`. The session key is the first bytes of the
+    /// returned state.
+    fn rekey(&self, iv: u8, y: &[u8; ISAP_KEY_LEN]) -> PH::State {
+        let mut state = self.init(&self.key, iv);
+        PK::default().apply(&mut state);
+        for (i, byte) in y.iter().enumerate() {
+            for bit in (0..8).rev() {
+                state.xor_bytes_at(0, &[((byte >> bit) & 1) << 7]);
+                if i == ISAP_KEY_LEN - 1 && bit == 0 {
+                    PK::default().apply(&mut state);
+                } else {
+                    PB::default().apply(&mut state);
+                }
+            }
+        }
+        state
+    }
+
+    /// Absorb `data` padded into `state`.
+    fn absorb(state: &mut PH::State, data: &[u8]) {
+        let mut blocks = data.chunks_exact(RATE);
+        for block in &mut blocks {
+            state.xor_bytes_at(0, block);
+            PH::default().apply(state);
+        }
+        let last = blocks.remainder();
+        state.xor_bytes_at(0, last);
+        state.xor_bytes_at(last.len(), &[PAD]);
+        PH::default().apply(state);
+    }
+
+    /// The tag of `ciphertext` and `ad` under `nonce`.
+    fn mac(&self, nonce: &[u8; ISAP_KEY_LEN], ad: &[u8], ciphertext: &[u8]) -> [u8; ISAP_KEY_LEN] {
+        let mut state = self.init(nonce, IV_A);
+        PH::default().apply(&mut state);
+        Self::absorb(&mut state, ad);
+        state.xor_bytes_at(PH::State::SIZE - 1, &[DOMAIN_SEPARATOR]);
+        Self::absorb(&mut state, ciphertext);
+
+        let mut y = [0; ISAP_KEY_LEN];
+        let mut session_key = [0; ISAP_KEY_LEN];
+        state
+            .reader()
+            .write_to_slice(&mut y)
+            .expect("y fits the state");
+        self.rekey(IV_KA, &y)
+            .reader()
+            .write_to_slice(&mut session_key)
+            .expect("the key fits the state");
+        state.copy_bytes_at(0, &session_key);
+        PH::default().apply(&mut state);
+        let mut tag = [0; ISAP_KEY_LEN];
+        state
+            .reader()
+            .write_to_slice(&mut tag)
+            .expect("the tag fits the state");
+        tag
+    }
+
+    /// Xor the keystream for `nonce` into `data`.
+    fn crypt(&self, nonce: &[u8; ISAP_KEY_LEN], data: &mut [u8]) {
+        let mut state = self.rekey(IV_KE, nonce);
+        state.copy_bytes_at(PH::State::SIZE - ISAP_KEY_LEN, nonce);
+        let mut keystream = [0; RATE];
+        for block in data.chunks_mut(RATE) {
+            PE::default().apply(&mut state);
+            let keystream = &mut keystream[..block.len()];
+            state
+                .reader()
+                .write_to_slice(keystream)
+                .expect("the keystream fits the rate");
+            for (byte, key) in block.iter_mut().zip(keystream.iter()) {
+                *byte ^= key;
+            }
+        }
+    }
+
+    /// Encrypt `data` in place and authenticate it together with the
+    /// associated data `ad`, under `nonce`. Returns the tag.
+    pub fn encrypt(
+        &self,
+        nonce: &[u8; ISAP_KEY_LEN],
+        ad: &[u8],
+        data: &mut [u8],
+    ) -> [u8; ISAP_KEY_LEN] {
+        self.crypt(nonce, data);
+        self.mac(nonce, ad, data)
+    }
+
+    /// Verify `tag` for the ciphertext `data` and the associated data `ad`
+    /// under `nonce`, and decrypt `data` in place.
+    ///
+    /// The tag is compared in constant time, before decrypting.
+    ///
+    /// # Errors
+    /// Errors when the tag doesn't match, leaving `data` unchanged.
+    pub fn decrypt(
+        &self,
+        nonce: &[u8; ISAP_KEY_LEN],
+        ad: &[u8],
+        data: &mut [u8],
+        tag: &[u8; ISAP_KEY_LEN],
+    ) -> Result<(), TagMismatchError> {
+        if !bool::from(self.mac(nonce, ad, data).ct_eq(tag)) {
+            return Err(TagMismatchError);
+        }
+        self.crypt(nonce, data);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Isap;
+    use crate::TagMismatchError;
+    use permutation_ascon::AsconP;
+    use permutation_keccak::KeccakP400;
+
+    type IsapK = Isap<KeccakP400<16>, KeccakP400<1>, KeccakP400<8>, KeccakP400<8>, 18>;
+    type IsapA = Isap<AsconP<12>, AsconP<1>, AsconP<6>, AsconP<12>, 8>;
+
+    const KEY: [u8; 16] = [0x5e; 16];
+    const NONCE: [u8; 16] = [0x0a; 16];
+
+    fn isap_k() -> IsapK {
+        IsapK::new(&KEY, [16, 1, 8, 8])
+    }
+
+    fn isap_a() -> IsapA {
+        IsapA::new(&KEY, [12, 1, 6, 12])
+    }
+
+    /// Roundtrips with empty and multi-block associated data and plaintext,
+    /// around block boundaries for both instances.
+    #[test]
+    fn roundtrip() {
+        for len in [0, 1, 7, 8, 9, 17, 18, 19, 100] {
+            let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let ad = &plaintext[..len / 2];
+
+            let mut data = plaintext.clone();
+            let tag = isap_k().encrypt(&NONCE, ad, &mut data);
+            if len > 0 {
+                assert_ne!(data, plaintext);
+            }
+            isap_k().decrypt(&NONCE, ad, &mut data, &tag).unwrap();
+            assert_eq!(data, plaintext);
+
+            let tag = isap_a().encrypt(&NONCE, ad, &mut data);
+            isap_a().decrypt(&NONCE, ad, &mut data, &tag).unwrap();
+            assert_eq!(data, plaintext);
+        }
+    }
+
+    #[test]
+    fn forgery_rejected() {
+        let isap = isap_k();
+        let mut data = *b"attack at dawn";
+        let tag = isap.encrypt(&NONCE, b"ad", &mut data);
+        let ciphertext = data;
+
+        data[0] ^= 1;
+        let result = isap.decrypt(&NONCE, b"ad", &mut data, &tag);
+        assert_eq!(result, Err(TagMismatchError));
+        data[0] ^= 1;
+        assert_eq!(data, ciphertext);
+
+        let mut forged_tag = tag;
+        forged_tag[15] ^= 1;
+        let result = isap.decrypt(&NONCE, b"ad", &mut data, &forged_tag);
+        assert_eq!(result, Err(TagMismatchError));
+        let result = isap.decrypt(&NONCE, b"ae", &mut data, &tag);
+        assert_eq!(result, Err(TagMismatchError));
+        let result = isap.decrypt(&[0x0b; 16], b"ad", &mut data, &tag);
+        assert_eq!(result, Err(TagMismatchError));
+        assert_eq!(data, ciphertext);
+
+        // Moving bytes from the associated data to the ciphertext changes the
+        // tag.
+        let mut moved = *b"dattack at dawn";
+        let moved_tag = isap.encrypt(&NONCE, b"a", &mut moved);
+        let mut original = *b"attack at dawn";
+        assert_ne!(isap.encrypt(&NONCE, b"ad", &mut original), moved_tag);
+    }
+
+    /// Every bit of the nonce and the key, and the parameters in the IVs,
+    /// change the keystream.
+    #[test]
+    fn separation() {
+        let keystream = |isap: &IsapK, nonce: &[u8; 16]| {
+            let mut data = [0; 32];
+            isap.encrypt(nonce, b"", &mut data);
+            data
+        };
+        let expected = keystream(&isap_k(), &NONCE);
+        for bit in [0, 1, 7, 64, 127] {
+            let mut nonce = NONCE;
+            nonce[bit / 8] ^= 1 << (bit % 8);
+            assert_ne!(keystream(&isap_k(), &nonce), expected);
+            let mut key = KEY;
+            key[bit / 8] ^= 1 << (bit % 8);
+            assert_ne!(
+                keystream(&IsapK::new(&key, [16, 1, 8, 8]), &NONCE),
+                expected
+            );
+        }
+        assert_ne!(
+            keystream(&IsapK::new(&KEY, [16, 1, 8, 12]), &NONCE),
+            expected
+        );
+    }
+
+    /// Regression vectors for the inputs of the NIST lightweight cryptography
+    /// known-answer tests, with key and nonce `00 01 .. 0f` and three bytes of
+    /// associated data and plaintext. The official outputs were not available
+    /// to check them against.
+    #[test]
+    fn lwc_inputs() {
+        let key = core::array::from_fn(|i| i as u8);
+        let nonce = core::array::from_fn(|i| i as u8);
+
+        let mut data = [0, 1, 2];
+        let tag = IsapK::new(&key, [16, 1, 8, 8]).encrypt(&nonce, &[0, 1, 2], &mut data);
+        assert_eq!(data, [0x01, 0xbc, 0x9c]);
+        assert_eq!(
+            tag,
+            [
+                0x8e, 0x76, 0x56, 0x71, 0x62, 0xc7, 0x80, 0x2e, 0xf1, 0xb5, 0x22, 0x2b, 0xc3, 0x75,
+                0xb3, 0xb7
+            ]
+        );
+
+        let mut data = [0, 1, 2];
+        let tag = IsapA::new(&key, [12, 1, 6, 12]).encrypt(&nonce, &[0, 1, 2], &mut data);
+        assert_eq!(data, [0x2c, 0xde, 0x28]);
+        assert_eq!(
+            tag,
+            [
+                0x6a, 0xed, 0x91, 0x72, 0xf7, 0x16, 0x38, 0x3c, 0xd7, 0xb0, 0x82, 0x4b, 0xa8, 0x50,
+                0x73, 0x90
+            ]
+        );
+    }
+}
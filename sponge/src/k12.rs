@@ -0,0 +1,298 @@
+//! KangarooTwelve: a tree hash on top of TurboSHAKE128.
+
+use super::turboshake::TurboShake128;
+use super::SpongeReader;
+use crypto_permutation::{Reader, Writer};
+use permutation_keccak::KeccakP1600;
+
+/// Domain separation byte of inputs of at most one chunk.
+const SINGLE: u8 = 0x07;
+/// Domain separation byte of the leaves.
+const LEAF: u8 = 0x0b;
+/// Domain separation byte of the final node of longer inputs.
+const FINAL: u8 = 0x06;
+/// Separates the first chunk from the chaining values in the final node.
+const FINAL_SEPARATOR: [u8; 8] = [0x03, 0, 0, 0, 0, 0, 0, 0];
+/// Terminates the final node.
+const FINAL_TERMINATOR: [u8; 2] = [0xff, 0xff];
+
+/// Length in bytes of the chunks of KangarooTwelve.
+pub const K12_CHUNK_LEN: usize = 8192;
+/// Length in bytes of the chaining values.
+const CV_LEN: usize = 32;
+
+/// Output stream of KangarooTwelve.
+pub type KangarooTwelveReader = SpongeReader<KeccakP1600<12>, 168>;
+
+/// `length_encode(x)`: the big endian bytes of `x` without leading zeros,
+/// followed by their number. Returns a buffer and the length of the encoding
+/// in it.
+fn length_encode(x: usize) -> ([u8; 9], usize) {
+    let bytes = (x as u64).to_be_bytes();
+    let len = bytes
+        .iter()
+        .position(|&byte| byte != 0)
+        .map_or(0, |i| 8 - i);
+    let mut encoding = [0; 9];
+    encoding[..len].copy_from_slice(&bytes[8 - len..]);
+    encoding[len] = len as u8;
+    (encoding, len + 1)
+}
+
+/// The input string `M || C || length_encode(|C|)` of KangarooTwelve, as a
+/// concatenation of parts.
+struct Input<'a> {
+    parts: [&'a [u8]; 3],
+    len: usize,
+}
+
+impl<'a> Input<'a> {
+    fn new(parts: [&'a [u8]; 3]) -> Self {
+        let len = parts.iter().map(|part| part.len()).sum();
+        Self { parts, len }
+    }
+
+    /// Absorb the `len` bytes of the input starting at `start` into `xof`.
+    fn write_range(&self, xof: &mut TurboShake128, mut start: usize, mut len: usize) {
+        for part in self.parts {
+            if start >= part.len() {
+                start -= part.len();
+                continue;
+            }
+            let n = len.min(part.len() - start);
+            xof.write_bytes(&part[start..start + n])
+                .expect("TurboSHAKE accepts any input length");
+            len -= n;
+            start = 0;
+        }
+    }
+
+    /// Number of chunks following the first one.
+    fn leaves(&self) -> usize {
+        (self.len - 1) / K12_CHUNK_LEN
+    }
+
+    /// The output stream of an input of at most one chunk.
+    fn single(&self) -> KangarooTwelveReader {
+        let mut xof = TurboShake128::new(SINGLE);
+        self.write_range(&mut xof, 0, self.len);
+        xof.finish()
+    }
+
+    /// The chaining value of chunk `i`, with `i > 0`.
+    fn leaf(&self, i: usize) -> [u8; CV_LEN] {
+        let start = i * K12_CHUNK_LEN;
+        let mut xof = TurboShake128::new(LEAF);
+        self.write_range(&mut xof, start, K12_CHUNK_LEN.min(self.len - start));
+        let mut cv = [0; CV_LEN];
+        xof.finish()
+            .write_to_slice(&mut cv)
+            .expect("TurboSHAKE generates any output length");
+        cv
+    }
+
+    /// The output stream from the first chunk and the chaining values `cvs`
+    /// of the other chunks.
+    fn final_node(&self, cvs: impl Iterator<Item = [u8; CV_LEN]>) -> KangarooTwelveReader {
+        let mut xof = TurboShake128::new(FINAL);
+        self.write_range(&mut xof, 0, K12_CHUNK_LEN);
+        let (count, count_len) = length_encode(self.leaves());
+        let mut write = |data: &[u8]| {
+            xof.write_bytes(data)
+                .expect("TurboSHAKE accepts any input length");
+        };
+        write(&FINAL_SEPARATOR);
+        for cv in cvs {
+            write(&cv);
+        }
+        write(&count[..count_len]);
+        write(&FINAL_TERMINATOR);
+        xof.finish()
+    }
+}
+
+/// The KangarooTwelve (KT128) extendable output function of `message` with
+/// customization string `customization`, processing its chunks sequentially.
+///
+/// Inputs longer than [`K12_CHUNK_LEN`] bytes are split into chunks that are
+/// hashed independently, and [`par_kangaroo_twelve`] with the `rayon` feature
+/// does so in parallel. Both give the same output.
+///
+/// ```
+/// use crypto_permutation::Reader;
+/// use sponge::k12::kangaroo_twelve;
+///
+/// let file = vec![0x17; 100_000];
+/// let mut digest = [0; 32];
+/// kangaroo_twelve(&file, b"")
+///     .write_to_slice(&mut digest)
+///     .unwrap();
+/// ```
+pub fn kangaroo_twelve(message: &[u8], customization: &[u8]) -> KangarooTwelveReader {
+    let (encoding, encoding_len) = length_encode(customization.len());
+    let input = Input::new([message, customization, &encoding[..encoding_len]]);
+    if input.len <= K12_CHUNK_LEN {
+        return input.single();
+    }
+    input.final_node((1..=input.leaves()).map(|i| input.leaf(i)))
+}
+
+/// The KangarooTwelve (KT128) extendable output function of `message` with
+/// customization string `customization`, processing its chunks in parallel on
+/// the rayon thread pool.
+///
+/// Gives the same output as [`kangaroo_twelve`].
+#[cfg(feature = "rayon")]
+pub fn par_kangaroo_twelve(message: &[u8], customization: &[u8]) -> KangarooTwelveReader {
+    use rayon::prelude::*;
+
+    let (encoding, encoding_len) = length_encode(customization.len());
+    let input = Input::new([message, customization, &encoding[..encoding_len]]);
+    if input.len <= K12_CHUNK_LEN {
+        return input.single();
+    }
+    let cvs: std::vec::Vec<_> = (1..=input.leaves())
+        .into_par_iter()
+        .map(|i| input.leaf(i))
+        .collect();
+    input.final_node(cvs.into_iter())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{kangaroo_twelve, length_encode, Input, K12_CHUNK_LEN};
+    use crypto_permutation::Reader;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    /// The last `len` bytes of the first `skip + len` bytes of output.
+    fn k12(message: &[u8], customization: &[u8], skip: usize, len: usize) -> String {
+        let mut reader = kangaroo_twelve(message, customization);
+        reader.skip(skip).unwrap();
+        let mut output = vec![0; len];
+        reader.write_to_slice(&mut output).unwrap();
+        hex(&output)
+    }
+
+    /// `ptn(n)`: the bytes `0x00, ..., 0xfa` repeated, truncated to `n` bytes.
+    fn ptn(n: usize) -> Vec<u8> {
+        (0..n).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn length_encoding() {
+        let encode = |x| {
+            let (encoding, len) = length_encode(x);
+            encoding[..len].to_vec()
+        };
+        assert_eq!(encode(0), [0x00]);
+        assert_eq!(encode(12), [0x0c, 0x01]);
+        assert_eq!(encode(65538), [0x01, 0x00, 0x02, 0x03]);
+    }
+
+    /// Test vectors from RFC 9861.
+    #[test]
+    fn rfc9861() {
+        assert_eq!(
+            k12(b"", b"", 0, 32),
+            "1ac2d450fc3b4205d19da7bfca1b37513c0803577ac7167f06fe2ce1f0ef39e5"
+        );
+        assert_eq!(
+            k12(b"", b"", 10000, 32),
+            "e8dc563642f7228c84684c898405d3a834799158c079b12880277a1d28e2ff6d"
+        );
+        assert_eq!(
+            k12(&ptn(17), b"", 0, 32),
+            "6bf75fa2239198db4772e36478f8e19b0f371205f6a9a93a273f51df37122888"
+        );
+        assert_eq!(
+            k12(&ptn(17 * 17), b"", 0, 32),
+            "0c315ebcdedbf61426de7dcf8fb725d1e74675d7f5327a5067f367b108ecb67c"
+        );
+        assert_eq!(
+            k12(&ptn(17 * 17 * 17), b"", 0, 32),
+            "cb552e2ec77d9910701d578b457ddf772c12e322e4ee7fe417f92c758f0d59d0"
+        );
+        assert_eq!(
+            k12(&ptn(17 * 17 * 17 * 17), b"", 0, 32),
+            "8701045e22205345ff4dda05555cbb5c3af1a771c2b89baef37db43d9998b9fe"
+        );
+        assert_eq!(
+            k12(&[0xff; 1], &ptn(41), 0, 32),
+            "d848c5068ced736f4462159b9867fd4c20b808acc3d5bc48e0b06ba0a3762ec4"
+        );
+        assert_eq!(
+            k12(&[0xff; 3], &ptn(41 * 41), 0, 32),
+            "c389e5009ae57120854c2e8c64670ac01358cf4c1baf89447a724234dc7ced74"
+        );
+        assert_eq!(
+            k12(&[0xff; 7], &ptn(41 * 41 * 41), 0, 32),
+            "75d2f86a2e644566726b4fbcfc5657b9dbcf070c7b0dca06450ab291d7443bcf"
+        );
+        assert_eq!(
+            k12(&ptn(8191), b"", 0, 32),
+            "1b577636f723643e990cc7d6a659837436fd6a103626600eb8301cd1dbe553d6"
+        );
+        assert_eq!(
+            k12(&ptn(8192), b"", 0, 32),
+            "48f256f6772f9edfb6a8b661ec92dc93b95ebd05a08a17b39ae3490870c926c3"
+        );
+        assert_eq!(
+            k12(&ptn(8192), &ptn(8189), 0, 32),
+            "3ed12f70fb05ddb58689510ab3e4d23c6c6033849aa01e1d8c220a297fedcd0b"
+        );
+        assert_eq!(
+            k12(&ptn(8192), &ptn(8190), 0, 32),
+            "6a7c1b6a5cd0d8c9ca943a4a216cc64604559a2ea45f78570a15253d67ba00ae"
+        );
+    }
+
+    /// Splitting the input string into the message, the customization string
+    /// and its length encoding at any point gives the same output as hashing
+    /// it as a single part, also around chunk boundaries.
+    #[test]
+    fn input_parts() {
+        let message = ptn(3 * K12_CHUNK_LEN);
+        for len in [
+            0,
+            1,
+            K12_CHUNK_LEN - 2,
+            K12_CHUNK_LEN,
+            2 * K12_CHUNK_LEN + 1,
+        ] {
+            let (message, customization) = message.split_at(len);
+            let (encoding, encoding_len) = length_encode(customization.len());
+            let mut string = message.to_vec();
+            string.extend_from_slice(customization);
+            string.extend_from_slice(&encoding[..encoding_len]);
+            let input = Input::new([&string, b"", b""]);
+            let mut expected = [0; 32];
+            input
+                .final_node((1..=input.leaves()).map(|i| input.leaf(i)))
+                .write_to_slice(&mut expected)
+                .unwrap();
+            assert_eq!(k12(message, customization, 0, 32), hex(&expected));
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel() {
+        use super::par_kangaroo_twelve;
+
+        for len in [0, K12_CHUNK_LEN, 10 * K12_CHUNK_LEN + 7] {
+            let message = ptn(len);
+            let mut expected = [0; 64];
+            kangaroo_twelve(&message, b"custom")
+                .write_to_slice(&mut expected)
+                .unwrap();
+            let mut output = [0; 64];
+            par_kangaroo_twelve(&message, b"custom")
+                .write_to_slice(&mut output)
+                .unwrap();
+            assert_eq!(output, expected);
+        }
+    }
+}
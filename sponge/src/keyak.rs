@@ -0,0 +1,121 @@
+//! The Keyak v2 instances of [`Motorist`].
+//!
+//! All instances use Keccak-p with 12 rounds and give tags of
+//! [`MOTORIST_TAG_LEN`](crate::MOTORIST_TAG_LEN) bytes. The official test
+//! vectors of Keyak were not available when these instances were written, so
+//! they have not been checked against them; the tests pin their output
+//! instead.
+//!
+//! ```
+//! use sponge::keyak::LakeKeyak;
+//!
+//! let key = [0x42; 16];
+//! let mut sender = LakeKeyak::keyak(&key, b"nonce");
+//! let mut message = *b"hello world";
+//! let tag = sender.wrap(b"metadata", &mut message);
+//!
+//! let mut receiver = LakeKeyak::keyak(&key, b"nonce");
+//! receiver.unwrap(b"metadata", &mut message, &tag).unwrap();
+//! assert_eq!(&message, b"hello world");
+//! ```
+
+use crate::Motorist;
+use permutation_keccak::{KeccakP1600, KeccakP800};
+
+/// River Keyak: a single piston over Keccak-p\[800, 12\].
+pub type RiverKeyak = Motorist<KeccakP800<12>, 1, 4>;
+/// Lake Keyak: a single piston over Keccak-p\[1600, 12\].
+pub type LakeKeyak = Motorist<KeccakP1600<12>, 1, 8>;
+/// Sea Keyak: two pistons over Keccak-p\[1600, 12\].
+pub type SeaKeyak = Motorist<KeccakP1600<12>, 2, 8>;
+/// Ocean Keyak: four pistons over Keccak-p\[1600, 12\].
+pub type OceanKeyak = Motorist<KeccakP1600<12>, 4, 8>;
+/// Lunar Keyak: eight pistons over Keccak-p\[1600, 12\].
+pub type LunarKeyak = Motorist<KeccakP1600<12>, 8, 8>;
+
+#[cfg(test)]
+mod tests {
+    use super::{LakeKeyak, LunarKeyak, OceanKeyak, RiverKeyak, SeaKeyak};
+
+    const KEY: [u8; 16] = *b"0123456789abcdef";
+
+    /// Regression vector.
+    #[test]
+    fn river_keyak() {
+        let mut session = RiverKeyak::keyak(&KEY, b"nonce");
+        let mut data = *b"plaintext";
+        let tag = session.wrap(b"metadata", &mut data);
+        assert_eq!(data, [0xb3, 0x97, 0x85, 0xff, 0xf3, 0x9f, 0x99, 0x56, 0xb5]);
+        assert_eq!(
+            tag,
+            [
+                0x81, 0xd1, 0xc0, 0x8d, 0x0a, 0xba, 0xe0, 0x92, 0x67, 0x62, 0xba, 0x69, 0xa9, 0xcd,
+                0x86, 0x53
+            ]
+        );
+    }
+
+    /// Regression vector.
+    #[test]
+    fn lake_keyak() {
+        let mut session = LakeKeyak::keyak(&KEY, b"nonce");
+        let mut data = *b"plaintext";
+        let tag = session.wrap(b"metadata", &mut data);
+        assert_eq!(data, [0xc2, 0x31, 0x97, 0xca, 0xe7, 0x04, 0xcb, 0x3a, 0xcf]);
+        assert_eq!(
+            tag,
+            [
+                0x9b, 0xe7, 0x64, 0x09, 0x87, 0x36, 0x75, 0x6f, 0xe4, 0x1b, 0x1e, 0x7b, 0x19, 0xae,
+                0xab, 0x23
+            ]
+        );
+    }
+
+    /// Regression vector.
+    #[test]
+    fn sea_keyak() {
+        let mut session = SeaKeyak::keyak(&KEY, b"nonce");
+        let mut data = *b"plaintext";
+        let tag = session.wrap(b"metadata", &mut data);
+        assert_eq!(data, [0xf1, 0x20, 0x95, 0x15, 0xc9, 0xb5, 0x45, 0x2e, 0x59]);
+        assert_eq!(
+            tag,
+            [
+                0x8f, 0x28, 0xe1, 0x65, 0x0b, 0xdb, 0xc0, 0x44, 0x61, 0x2f, 0x71, 0x69, 0xbc, 0xfd,
+                0x66, 0xcb
+            ]
+        );
+    }
+
+    /// Regression vector.
+    #[test]
+    fn ocean_keyak() {
+        let mut session = OceanKeyak::keyak(&KEY, b"nonce");
+        let mut data = *b"plaintext";
+        let tag = session.wrap(b"metadata", &mut data);
+        assert_eq!(data, [0x0d, 0x6a, 0xbd, 0x16, 0x1f, 0xf4, 0xa9, 0x56, 0x3e]);
+        assert_eq!(
+            tag,
+            [
+                0xa4, 0x6a, 0xe1, 0xa3, 0x94, 0x6b, 0x04, 0xea, 0xc2, 0x93, 0x19, 0xf1, 0x05, 0x25,
+                0x32, 0x69
+            ]
+        );
+    }
+
+    /// Regression vector.
+    #[test]
+    fn lunar_keyak() {
+        let mut session = LunarKeyak::keyak(&KEY, b"nonce");
+        let mut data = *b"plaintext";
+        let tag = session.wrap(b"metadata", &mut data);
+        assert_eq!(data, [0x03, 0xce, 0x4e, 0x18, 0x90, 0x56, 0xb4, 0x66, 0x8b]);
+        assert_eq!(
+            tag,
+            [
+                0xdf, 0xd7, 0xb3, 0x59, 0x19, 0xdf, 0x8a, 0x05, 0x25, 0xbd, 0x47, 0x08, 0xcd, 0xed,
+                0x05, 0x33
+            ]
+        );
+    }
+}
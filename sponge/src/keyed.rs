@@ -0,0 +1,185 @@
+//! The full-state keyed duplex construction.
+
+use crypto_permutation::{Permutation, PermutationState, Reader};
+
+/// Byte padding the input of a duplexing call.
+const PAD: u8 = 0x01;
+
+/// The full-state keyed duplex construction over the permutation `P`,
+/// squeezing at most `RATE` bytes per call.
+///
+/// The state is initialised with the key followed by an IV, so the key is in
+/// the whole state rather than absorbed through the rate. Every duplexing call
+/// applies the permutation, squeezes up to `RATE` bytes of output and then
+/// absorbs up to [`Self::MAX_INPUT`] bytes across the full width of the state,
+/// padded with a single `0x01` byte. As a whole block of input is processed
+/// with a single permutation call, it has a lower latency for short messages
+/// than [Farfalle], which needs separate permutations for compressing and
+/// expanding.
+///
+/// [`Self::prf`] builds a full-state keyed sponge PRF on top, for MACs and key
+/// derivation.
+///
+/// # Crypto
+/// The security of the construction relies on the secrecy of the key, and on
+/// the capacity, i.e. the state size minus `RATE`, for the outputs. An IV must
+/// never be used twice with the same key when the outputs are used as
+/// keystream.
+///
+/// [Farfalle]: https://keccak.team/farfalle.html
+///
+/// ```
+/// use permutation_keccak::KeccakF1600;
+/// use sponge::KeyedDuplex;
+///
+/// let mut tag = [0; 16];
+/// KeyedDuplex::<KeccakF1600, 168>::prf(&[0x42; 32], b"message", &mut tag);
+/// ```
+#[derive(Clone)]
+pub struct KeyedDuplex<P: Permutation, const RATE: usize> {
+    state: P::State,
+}
+
+impl<P: Permutation, const RATE: usize> core::fmt::Debug for KeyedDuplex<P, RATE> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("KeyedDuplex")
+            .field("rate", &RATE)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<P: Permutation, const RATE: usize> KeyedDuplex<P, RATE> {
+    /// Maximum number of bytes absorbed by a single duplexing call: the state
+    /// size minus one byte for the padding.
+    pub const MAX_INPUT: usize = P::State::SIZE - 1;
+
+    /// Initialise the state with `key` followed by `iv`, padded with zero
+    /// bytes.
+    ///
+    /// # Panics
+    /// Panics when `key` and `iv` together don't fit the state, or when `RATE`
+    /// is zero or not smaller than the state size.
+    pub fn new(key: &[u8], iv: &[u8]) -> Self {
+        assert!(RATE > 0, "the rate must not be zero");
+        assert!(
+            RATE < P::State::SIZE,
+            "the rate must be smaller than the state"
+        );
+        assert!(
+            key.len() + iv.len() <= P::State::SIZE,
+            "key and IV must fit the state"
+        );
+        let mut state = P::State::default();
+        state.copy_bytes_at(0, key);
+        state.copy_bytes_at(key.len(), iv);
+        Self { state }
+    }
+
+    /// Apply the permutation, squeeze `output.len()` bytes into `output` and
+    /// absorb `input`.
+    ///
+    /// # Panics
+    /// Panics when `input.len() > Self::MAX_INPUT` or `output.len() > RATE`.
+    pub fn duplex(&mut self, input: &[u8], output: &mut [u8]) {
+        assert!(input.len() <= Self::MAX_INPUT, "input exceeds the state");
+        assert!(output.len() <= RATE, "output exceeds the rate");
+        P::default().apply(&mut self.state);
+        self.state
+            .reader()
+            .write_to_slice(output)
+            .expect("output fits the state");
+        self.state.xor_bytes_at(0, input);
+        self.state.xor_bytes_at(input.len(), &[PAD]);
+    }
+
+    /// Full-state keyed sponge PRF: write the output of the PRF keyed by `key`
+    /// on `message` into `output`.
+    ///
+    /// The message is absorbed in blocks of [`Self::MAX_INPUT`] bytes, the
+    /// last one of which is shorter, possibly empty. The output is squeezed in
+    /// blocks of `RATE` bytes.
+    ///
+    /// # Panics
+    /// Panics when `key` doesn't fit the state, or when `RATE` is zero or not
+    /// smaller than the state size.
+    pub fn prf(key: &[u8], message: &[u8], output: &mut [u8]) {
+        let mut duplex = Self::new(key, &[]);
+        let mut blocks = message.chunks_exact(Self::MAX_INPUT);
+        for block in &mut blocks {
+            duplex.duplex(block, &mut []);
+        }
+        duplex.duplex(blocks.remainder(), &mut []);
+        for block in output.chunks_mut(RATE) {
+            duplex.duplex(&[], block);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeyedDuplex;
+    use crypto_permutation::{Permutation, PermutationState, Reader};
+    use permutation_keccak::{KeccakF1600, KeccakState1600};
+
+    type TestDuplex = KeyedDuplex<KeccakF1600, 168>;
+
+    const KEY: [u8; 32] = [0x4b; 32];
+
+    /// The first output is the permutation of the key and IV.
+    #[test]
+    fn initial_output() {
+        let mut duplex = TestDuplex::new(&KEY, b"iv");
+        let mut output = [0; 168];
+        duplex.duplex(b"", &mut output);
+
+        let mut state = KeccakState1600::default();
+        state.copy_bytes_at(0, &KEY);
+        state.copy_bytes_at(32, b"iv");
+        KeccakF1600.apply(&mut state);
+        let mut expected = [0; 168];
+        state.reader().write_to_slice(&mut expected).unwrap();
+        assert_eq!(output, expected);
+    }
+
+    /// Inputs are absorbed across the full width: a change in the last
+    /// absorbed byte changes the next output.
+    #[test]
+    fn full_state_absorb() {
+        let mut outputs = Vec::new();
+        for last in [0, 1] {
+            let mut input = [0; TestDuplex::MAX_INPUT];
+            input[TestDuplex::MAX_INPUT - 1] = last;
+            let mut duplex = TestDuplex::new(&KEY, b"");
+            duplex.duplex(&input, &mut []);
+            let mut output = [0; 32];
+            duplex.duplex(b"", &mut output);
+            outputs.push(output);
+        }
+        assert_ne!(outputs[0], outputs[1]);
+    }
+
+    /// Messages of different lengths, also around block boundaries, and
+    /// different keys give different outputs. Longer outputs extend shorter
+    /// ones.
+    #[test]
+    fn prf() {
+        let lengths = [0, 1, 198, 199, 200, 398, 399];
+        let mut outputs = Vec::new();
+        for len in lengths {
+            let mut output = [0; 32];
+            TestDuplex::prf(&KEY, &vec![0; len], &mut output);
+            outputs.push(output);
+        }
+        for (i, output) in outputs.iter().enumerate() {
+            assert!(!outputs[i + 1..].contains(output));
+        }
+
+        let mut other_key = [0; 32];
+        TestDuplex::prf(&[0x4c; 32], &[], &mut other_key);
+        assert_ne!(other_key, outputs[0]);
+
+        let mut long = [0; 400];
+        TestDuplex::prf(&KEY, &[], &mut long);
+        assert_eq!(long[..32], outputs[0]);
+    }
+}
@@ -0,0 +1,116 @@
+//! Generic sponge construction, generic over the cryptographic permutation.
+//!
+//! `Sponge: Permutation -> Hash + XOF`
+//!
+//! `Duplex: Permutation -> Duplex object`
+//!
+//! This crate contains an implementation of the [sponge construction],
+//! [`Sponge`]. It is generic over the [`Permutation`] and the rate, so it
+//! works with any permutation implementing the [`crypto_permutation`] traits.
+//! Data is absorbed by writing it to a [`Sponge`] through the [`Writer`]
+//! trait. Finishing the writer pads the input and returns a [`SpongeReader`],
+//! from which an output stream of arbitrary length is squeezed through the
+//! [`Reader`] trait. Hence the same object serves both as a hash function with
+//! fixed size output and as an extendable output function (XOF).
+//!
+//! The [`Duplex`] object implements the duplex construction, which interleaves
+//! absorbing and squeezing, as the basis for authenticated encryption modes,
+//! random number generators and protocol frameworks. Besides xoring its input
+//! into the state, it supports the overwrite duplex in [`OverwriteMode`],
+//! which saves memory on small devices. [`KeyedDuplex`] is the
+//! full-state keyed duplex, a keyed primitive that absorbs across the whole
+//! state, with a full-state keyed sponge PRF on top.
+//! [`Sponge`] and [`Duplex`] implement the [`SpongeConstruction`] and
+//! [`DuplexState`] traits, so modes generic over these traits can use them.
+//!
+//! On top of the duplex, [`SpongeWrap`] implements session authenticated
+//! encryption, and [`MonkeyWrap`] the variant used by Ketje over the
+//! [`MonkeyDuplex`], which uses round reduced permutations for speed.
+//! [`Isap`] implements the ISAP authenticated encryption mode, which resists
+//! side-channel attacks through leakage resilient re-keying. [`Motorist`]
+//! implements the mode of Keyak, which drives several duplex pistons in
+//! parallel.
+//!
+//! __Note__: No security audits of this crate have ever been performed. Use at
+//! your own risk!
+//!
+//! # Features
+//! * `std`: Implements [`std::error::Error`] for [`TagMismatchError`].
+//! * `sha3`: Provides the SHA-3 hash functions and SHAKE extendable output
+//!   functions in the `sha3` module, implementing the [`digest`] crate
+//!   traits.
+//! * `sp800_185`: Provides cSHAKE, the KMAC message authentication code and
+//!   TupleHash from NIST SP 800-185 in the `sp800_185` module.
+//! * `turboshake`: Provides the TurboSHAKE extendable output functions, the
+//!   sponge over the 12 round Keccak-p\[1600, 12\], in the `turboshake`
+//!   module.
+//! * `k12`: Provides the KangarooTwelve tree hash in the `k12` module. Implies
+//!   `turboshake`.
+//! * `keyak`: Provides the Keyak instances of [`Motorist`] in the `keyak`
+//!   module.
+//! * `rayon`: Provides `k12::par_kangaroo_twelve`, processing the chunks of
+//!   long inputs in parallel on the [`rayon`] thread pool, when combined with
+//!   `k12`. Implies `std`.
+//!
+//! # Padding
+//! The input is padded with the multi-rate padding `pad10*1`, preceded by
+//! optional domain separation suffix bits, like the `delimitedSuffix` of the
+//! [XKCP]. The sponge is byte oriented: the suffix bits and the first bit of
+//! the padding form a single byte, so inputs are always whole bytes.
+//!
+//! [sponge construction]: https://keccak.team/sponge_duplex.html
+//! [XKCP]: https://github.com/XKCP/XKCP
+//! [`digest`]: https://crates.io/crates/digest
+//! [`rayon`]: https://crates.io/crates/rayon
+//! [`Permutation`]: crypto_permutation::Permutation
+//! [`Writer`]: crypto_permutation::Writer
+//! [`Reader`]: crypto_permutation::Reader
+//! [`SpongeConstruction`]: crypto_permutation::SpongeConstruction
+//! [`DuplexState`]: crypto_permutation::DuplexState
+
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+#![allow(clippy::needless_lifetimes)]
+
+mod absorb;
+mod duplex;
+mod isap;
+#[cfg(feature = "k12")]
+pub mod k12;
+#[cfg(feature = "keyak")]
+pub mod keyak;
+mod keyed;
+mod monkey;
+mod motorist;
+#[cfg(feature = "sha3")]
+pub mod sha3;
+#[cfg(feature = "sp800_185")]
+pub mod sp800_185;
+mod squeeze;
+#[cfg(feature = "turboshake")]
+pub mod turboshake;
+mod wrap;
+pub use absorb::Sponge;
+pub use duplex::{Duplex, DuplexMode, OverwriteMode, XorMode};
+pub use isap::{Isap, ISAP_KEY_LEN};
+pub use keyed::KeyedDuplex;
+pub use monkey::MonkeyDuplex;
+pub use motorist::{Motorist, MOTORIST_TAG_LEN};
+pub use squeeze::SpongeReader;
+pub use wrap::{MonkeyWrap, SpongeWrap};
+
+/// Byte that completes the multi-rate padding in the last byte of the rate.
+const PAD_LAST: u8 = 0x80;
+
+/// Error returned when a tag doesn't match: the ciphertext or the metadata is
+/// not authentic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TagMismatchError;
+
+impl core::fmt::Display for TagMismatchError {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        fmt.write_str("Tag mismatch")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TagMismatchError {}
@@ -0,0 +1,186 @@
+//! The MonkeyDuplex construction.
+
+use super::duplex::pad;
+use super::PAD_LAST;
+use crypto_permutation::{Permutation, PermutationState, Reader};
+
+/// The MonkeyDuplex construction, as used by Ketje, with a rate of `RATE`
+/// bytes.
+///
+/// A variant of the [`Duplex`](crate::Duplex) for keyed use, with three
+/// permutations acting on the same state, typically round reduced versions of
+/// one permutation. [`Self::start`] initialises the state with a key and nonce
+/// and applies the strong permutation `PStart`. The duplexing calls
+/// [`Self::step`] and [`Self::stride`] apply the fast permutation `PStep` and
+/// the intermediate permutation `PStride` respectively. As the security relies
+/// on the secret state, `PStep` is only safe when its outputs are never
+/// available to an attacker without a `PStride` call in between, like in
+/// [`MonkeyWrap`](crate::MonkeyWrap).
+///
+/// ```
+/// use permutation_keccak::KeccakP800;
+/// use sponge::MonkeyDuplex;
+///
+/// type KetjeDuplex = MonkeyDuplex<KeccakP800<12>, KeccakP800<1>, KeccakP800<6>, 5>;
+///
+/// let mut duplex = KetjeDuplex::start(b"key and nonce");
+/// let mut output = [0; 4];
+/// duplex.stride(b"data", 0x01, &mut output);
+/// ```
+#[derive(Clone)]
+pub struct MonkeyDuplex<PStart, PStep, PStride, const RATE: usize>
+where
+    PStart: Permutation,
+{
+    pub(super) state: PStart::State,
+    permutations: core::marker::PhantomData<(PStep, PStride)>,
+}
+
+impl<PStart, PStep, PStride, const RATE: usize> core::fmt::Debug
+    for MonkeyDuplex<PStart, PStep, PStride, RATE>
+where
+    PStart: Permutation,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("MonkeyDuplex")
+            .field("rate", &RATE)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<PStart, PStep, PStride, const RATE: usize> MonkeyDuplex<PStart, PStep, PStride, RATE>
+where
+    PStart: Permutation,
+    PStep: Permutation<State = PStart::State>,
+    PStride: Permutation<State = PStart::State>,
+{
+    /// Maximum number of bytes absorbed by a single duplexing call: the rate
+    /// minus one byte for the padding.
+    pub const MAX_INPUT: usize = RATE - 1;
+
+    /// Initialise the state with the concatenation of `parts`, padded to the
+    /// whole state with the multi-rate padding, and apply `PStart`.
+    pub(super) fn start_parts(parts: &[&[u8]]) -> Self {
+        assert!(RATE >= 2, "the rate must fit input and padding");
+        assert!(
+            RATE < PStart::State::SIZE,
+            "the rate must be smaller than the state"
+        );
+        let mut state = PStart::State::default();
+        let mut len = 0;
+        for part in parts {
+            assert!(
+                len + part.len() < PStart::State::SIZE,
+                "start input must fit the state with padding"
+            );
+            state.copy_bytes_at(len, part);
+            len += part.len();
+        }
+        state.xor_bytes_at(len, &[0x01]);
+        state.xor_bytes_at(PStart::State::SIZE - 1, &[PAD_LAST]);
+        PStart::default().apply(&mut state);
+        Self {
+            state,
+            permutations: core::marker::PhantomData,
+        }
+    }
+
+    /// Initialise the state with `input`, typically a key and a nonce, padded
+    /// to the whole state with the multi-rate padding, and apply `PStart`.
+    ///
+    /// # Panics
+    /// Panics when `input` doesn't fit the state with padding, or when `RATE`
+    /// is smaller than two or not smaller than the state size.
+    pub fn start(input: &[u8]) -> Self {
+        Self::start_parts(&[input])
+    }
+
+    /// Pad the absorbed input block of `len` bytes with the delimited suffix
+    /// `suffix`, and apply `PStride` when `stride` or `PStep` otherwise.
+    pub(super) fn pad_permute(&mut self, len: usize, suffix: u8, stride: bool) {
+        pad(&mut self.state, len, suffix, RATE);
+        if stride {
+            PStride::default().apply(&mut self.state);
+        } else {
+            PStep::default().apply(&mut self.state);
+        }
+    }
+
+    /// The duplexing call, applying `PStride` when `stride`.
+    fn call(&mut self, input: &[u8], suffix: u8, output: &mut [u8], stride: bool) {
+        assert!(input.len() <= Self::MAX_INPUT, "input exceeds the rate");
+        assert!(output.len() <= RATE, "output exceeds the rate");
+        self.state.xor_bytes_at(0, input);
+        self.pad_permute(input.len(), suffix, stride);
+        self.state
+            .reader()
+            .write_to_slice(output)
+            .expect("output fits the state");
+    }
+
+    /// Absorb `input` with the domain separation suffix bits of `suffix`,
+    /// apply `PStep` and squeeze `output.len()` bytes into `output`.
+    ///
+    /// The suffix is a delimited suffix as for
+    /// [`Duplex::duplex_with_suffix`](crate::Duplex::duplex_with_suffix).
+    ///
+    /// # Panics
+    /// Panics when `suffix` is zero or at least `0x80`, when
+    /// `input.len() > Self::MAX_INPUT` or when `output.len() > RATE`.
+    pub fn step(&mut self, input: &[u8], suffix: u8, output: &mut [u8]) {
+        self.call(input, suffix, output, false);
+    }
+
+    /// Like [`Self::step`], but applying `PStride`.
+    ///
+    /// # Panics
+    /// See [`Self::step`].
+    pub fn stride(&mut self, input: &[u8], suffix: u8, output: &mut [u8]) {
+        self.call(input, suffix, output, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MonkeyDuplex;
+    use crypto_permutation::{Permutation, PermutationState, Reader};
+    use permutation_keccak::{KeccakP1600, KeccakState1600};
+
+    type Monkey = MonkeyDuplex<KeccakP1600<12>, KeccakP1600<1>, KeccakP1600<6>, 168>;
+
+    /// The start input is padded to the whole state, and a stride absorbs
+    /// into the rate.
+    #[test]
+    fn start_and_stride() {
+        let mut output = [0; 32];
+        Monkey::start(b"key and nonce").stride(b"input", 0x02, &mut output);
+
+        let mut state = KeccakState1600::default();
+        state.copy_bytes_at(0, b"key and nonce");
+        state.xor_bytes_at(13, &[0x01]);
+        state.xor_bytes_at(199, &[0x80]);
+        KeccakP1600::<12>.apply(&mut state);
+        state.xor_bytes_at(0, b"input");
+        state.xor_bytes_at(5, &[0x02]);
+        state.xor_bytes_at(167, &[0x80]);
+        KeccakP1600::<6>.apply(&mut state);
+        let mut expected = [0; 32];
+        state.reader().write_to_slice(&mut expected).unwrap();
+        assert_eq!(output, expected);
+    }
+
+    /// Step and stride apply different permutations.
+    #[test]
+    fn step_stride() {
+        let monkey = Monkey::start(b"key and nonce");
+        let mut stepped = [0; 32];
+        let mut strided = [0; 32];
+        monkey.clone().step(b"input", 0x01, &mut stepped);
+        monkey.clone().stride(b"input", 0x01, &mut strided);
+        assert_ne!(stepped, strided);
+
+        let mut other = [0; 32];
+        Monkey::start(b"key and nonce!").stride(b"input", 0x01, &mut other);
+        assert_ne!(other, strided);
+    }
+}
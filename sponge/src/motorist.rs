@@ -0,0 +1,450 @@
+//! The Motorist construction of Keyak.
+
+use super::wrap::{decrypt_block, encrypt_block};
+use super::TagMismatchError;
+use crypto_permutation::{Permutation, PermutationState, Reader};
+use subtle::ConstantTimeEq;
+
+/// Capacity `c` of the pistons in bytes.
+const CAPACITY: usize = 32;
+/// Length in bytes of the tags of [`Motorist`].
+pub const MOTORIST_TAG_LEN: usize = 16;
+/// Length in bytes of the tag of every piston that is injected into all of
+/// them to make a knot.
+const KNOT_TAG_LEN: usize = 32;
+/// Zero bytes padding the packed key.
+const ZEROS: [u8; 64] = [0; 64];
+
+/// Phase of the engine, determining the next operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Phase {
+    /// The permutation has just been applied.
+    Fresh,
+    /// Data has been encrypted or decrypted since the last permutation.
+    Crypted,
+    /// The input of a message is complete.
+    EndOfMessage,
+}
+
+/// The Motorist session authenticated encryption construction over the
+/// permutation `P`, with `PISTONS` parallel states and lanes of `LANE` bytes.
+///
+/// Motorist is the mode of Keyak. Its engine drives `PISTONS` pistons, each a
+/// full-state duplex over `P` with a capacity of 32 bytes. The plaintext is
+/// encrypted in blocks of the squeezing rate of every piston in turn, and the
+/// metadata is absorbed into the rest of the state of the pistons alongside
+/// the plaintext, so all pistons can apply their permutations in parallel.
+/// After every message with more than one piston, the tags of all pistons
+/// are injected into all of them, tying them together in a knot. As with
+/// [`SpongeWrap`](crate::SpongeWrap), every message gives a
+/// [`MOTORIST_TAG_LEN`] byte tag authenticating the whole session so far.
+///
+/// Keyak uses Keccak-p with 12 rounds: Keccak-p\[800\] with lanes of 4 bytes
+/// and a single piston for River Keyak, and Keccak-p\[1600\] with lanes of 8
+/// bytes and 1, 2, 4 or 8 pistons for Lake, Sea, Ocean and Lunar Keyak. With
+/// the `keyak` feature, the `keyak` module names these instances.
+///
+/// # Crypto
+/// A nonce must never be used for two sessions under the same key.
+///
+/// ```
+/// use permutation_keccak::KeccakP1600;
+/// use sponge::Motorist;
+///
+/// // Sea Keyak.
+/// type SeaKeyak = Motorist<KeccakP1600<12>, 2, 8>;
+///
+/// let key = [0x42; 16];
+/// let mut sender = SeaKeyak::keyak(&key, b"nonce");
+/// let mut message = *b"hello world";
+/// let tag = sender.wrap(b"metadata", &mut message);
+///
+/// let mut receiver = SeaKeyak::keyak(&key, b"nonce");
+/// receiver.unwrap(b"metadata", &mut message, &tag).unwrap();
+/// assert_eq!(&message, b"hello world");
+/// ```
+#[derive(Clone)]
+pub struct Motorist<P: Permutation, const PISTONS: usize, const LANE: usize> {
+    pistons: [P::State; PISTONS],
+    phase: Phase,
+}
+
+impl<P: Permutation, const PISTONS: usize, const LANE: usize> core::fmt::Debug
+    for Motorist<P, PISTONS, LANE>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Motorist")
+            .field("pistons", &PISTONS)
+            .field("lane", &LANE)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<P: Permutation, const PISTONS: usize, const LANE: usize> Motorist<P, PISTONS, LANE> {
+    /// Squeezing rate `Rs` of the pistons in bytes: the number of plaintext
+    /// bytes encrypted per piston between permutations.
+    const SQUEEZE_RATE: usize = LANE * ((P::State::SIZE - CAPACITY) / LANE);
+    /// Absorbing rate `Ra` of the pistons in bytes, which is followed by the
+    /// four bytes that frame the input.
+    const ABSORB_RATE: usize = LANE * ((P::State::SIZE - 4) / LANE);
+    /// Offset of the byte that marks the end of a message.
+    const END_OF_MESSAGE: usize = Self::ABSORB_RATE;
+    /// Offset of the byte holding the end of the encrypted data.
+    const CRYPT_END: usize = Self::ABSORB_RATE + 1;
+    /// Offset of the byte holding the start of the injected data.
+    const INJECT_START: usize = Self::ABSORB_RATE + 2;
+    /// Offset of the byte holding the end of the injected data.
+    const INJECT_END: usize = Self::ABSORB_RATE + 3;
+
+    /// Start a session with the secret and unique value `suv`.
+    ///
+    /// # Panics
+    /// Panics when `PISTONS` is zero or more than 255, or when `LANE` is zero
+    /// or too large for the state to fit the capacity and a tag.
+    pub fn new(suv: &[u8]) -> Self {
+        Self::start(&[suv])
+    }
+
+    /// Start a Keyak session keyed by `key` with `nonce`.
+    ///
+    /// The secret and unique value is the key packed as the length of the
+    /// packed key in a byte, the key, a `0x01` byte and zero bytes, up to a
+    /// whole number of lanes fitting the key and at least 265 bits, followed
+    /// by the nonce.
+    ///
+    /// # Panics
+    /// Panics when `key` doesn't fit the packed key, or as [`Self::new`].
+    pub fn keyak(key: &[u8], nonce: &[u8]) -> Self {
+        let packed_len = LANE * ((CAPACITY * 8 + 9 + LANE * 8 - 1) / (LANE * 8));
+        assert!(key.len() + 2 <= packed_len, "key fits the packed key");
+        let packed_len_byte = u8::try_from(packed_len).expect("packed key fits a byte");
+        let padding = packed_len - key.len() - 2;
+        Self::start(&[&[packed_len_byte], key, &[0x01], &ZEROS[..padding], nonce])
+    }
+
+    /// Start a session with the concatenation of `suv` as secret and unique
+    /// value.
+    fn start(suv: &[&[u8]]) -> Self {
+        assert!(PISTONS > 0, "there must be a piston");
+        assert!(PISTONS < 256, "the number of pistons must fit a byte");
+        assert!(LANE > 0, "lanes must not be empty");
+        assert!(
+            P::State::SIZE >= CAPACITY + KNOT_TAG_LEN,
+            "the state must fit the capacity and a tag"
+        );
+        let mut motorist = Self {
+            pistons: core::array::from_fn(|_| Default::default()),
+            phase: Phase::Fresh,
+        };
+        motorist.inject_collective(suv, true);
+        motorist.spark(true, [0; PISTONS]);
+        motorist
+    }
+
+    /// Apply the permutation to all pistons, marking the end of a message
+    /// with tags of `tag_lens` bytes when `end_of_message`.
+    fn spark(&mut self, end_of_message: bool, tag_lens: [usize; PISTONS]) {
+        for (state, tag_len) in self.pistons.iter_mut().zip(tag_lens) {
+            if end_of_message {
+                let marker = if tag_len == 0 { 0xff } else { tag_len as u8 };
+                state.xor_bytes_at(Self::END_OF_MESSAGE, &[marker]);
+            }
+            P::default().apply(state);
+        }
+        self.phase = Phase::Fresh;
+    }
+
+    /// Inject the bytes `start..start + len` of the concatenation of `parts`
+    /// into `state`, from its start.
+    fn inject_range<'a>(
+        state: &mut P::State,
+        parts: impl Iterator<Item = &'a [u8]>,
+        mut start: usize,
+        len: usize,
+    ) {
+        let mut written = 0;
+        for part in parts {
+            if start >= part.len() {
+                start -= part.len();
+                continue;
+            }
+            let n = (len - written).min(part.len() - start);
+            state.xor_bytes_at(written, &part[start..start + n]);
+            written += n;
+            start = 0;
+        }
+    }
+
+    /// Inject the concatenation of `parts` into every piston, followed by the
+    /// number of pistons and the index of the piston when `diversify`.
+    fn inject_collective(&mut self, parts: &[&[u8]], diversify: bool) {
+        let len = parts.iter().map(|part| part.len()).sum::<usize>() + 2 * usize::from(diversify);
+        let mut start = 0;
+        loop {
+            let block_len = (len - start).min(Self::ABSORB_RATE);
+            for (i, state) in self.pistons.iter_mut().enumerate() {
+                let diversifier = [PISTONS as u8, i as u8];
+                let diversifier: &[u8] = if diversify { &diversifier } else { &[] };
+                let stream = parts.iter().copied().chain(core::iter::once(diversifier));
+                Self::inject_range(state, stream, start, block_len);
+                state.xor_bytes_at(Self::INJECT_END, &[block_len as u8]);
+            }
+            start += block_len;
+            if start == len {
+                break;
+            }
+            self.spark(false, [0; PISTONS]);
+        }
+        self.phase = Phase::EndOfMessage;
+    }
+
+    /// Inject the first bytes of `metadata` into the pistons, in the part of
+    /// the state that is not used by the encryption when data was encrypted
+    /// since the last permutation. Returns the number of bytes injected.
+    fn inject(&mut self, metadata: &[u8]) -> usize {
+        let crypted = self.phase == Phase::Crypted;
+        let offset = if crypted { Self::SQUEEZE_RATE } else { 0 };
+        let mut injected = 0;
+        for state in &mut self.pistons {
+            let len = (metadata.len() - injected).min(Self::ABSORB_RATE - offset);
+            state.xor_bytes_at(offset, &metadata[injected..injected + len]);
+            state.xor_bytes_at(Self::INJECT_START, &[offset as u8]);
+            state.xor_bytes_at(Self::INJECT_END, &[(offset + len) as u8]);
+            injected += len;
+        }
+        if crypted || injected < metadata.len() {
+            self.spark(false, [0; PISTONS]);
+        } else {
+            self.phase = Phase::EndOfMessage;
+        }
+        injected
+    }
+
+    /// Encrypt or decrypt the first bytes of `data` in place, a block for
+    /// every piston. Returns the number of bytes processed.
+    fn crypt(&mut self, data: &mut [u8], decrypt: bool) -> usize {
+        let mut processed = 0;
+        for state in &mut self.pistons {
+            let len = (data.len() - processed).min(Self::SQUEEZE_RATE);
+            let block = &mut data[processed..processed + len];
+            if decrypt {
+                decrypt_block(state, block);
+            } else {
+                encrypt_block(state, block);
+            }
+            state.xor_bytes_at(Self::CRYPT_END, &[len as u8]);
+            processed += len;
+        }
+        self.phase = Phase::Crypted;
+        processed
+    }
+
+    /// Tie the pistons together: inject the tags of all pistons into every
+    /// piston.
+    fn make_knot(&mut self) {
+        self.spark(true, [KNOT_TAG_LEN; PISTONS]);
+        let mut tags = [[0; KNOT_TAG_LEN]; PISTONS];
+        for (state, tag) in self.pistons.iter().zip(&mut tags) {
+            state
+                .reader()
+                .write_to_slice(tag)
+                .expect("tag fits the state");
+        }
+        let tags: [&[u8]; PISTONS] = core::array::from_fn(|i| &tags[i][..]);
+        self.inject_collective(&tags, false);
+    }
+
+    /// Encrypt or decrypt `data` in place after injecting `metadata`, and
+    /// return the tag.
+    fn crypt_message(
+        &mut self,
+        metadata: &[u8],
+        mut data: &mut [u8],
+        decrypt: bool,
+    ) -> [u8; MOTORIST_TAG_LEN] {
+        let mut metadata = metadata;
+        if data.is_empty() && metadata.is_empty() {
+            self.inject(&[]);
+        }
+        while !data.is_empty() {
+            let processed = self.crypt(data, decrypt);
+            data = &mut data[processed..];
+            let injected = self.inject(metadata);
+            metadata = &metadata[injected..];
+        }
+        while !metadata.is_empty() {
+            let injected = self.inject(metadata);
+            metadata = &metadata[injected..];
+        }
+        if PISTONS > 1 {
+            self.make_knot();
+        }
+        let mut tag_lens = [0; PISTONS];
+        tag_lens[0] = MOTORIST_TAG_LEN;
+        self.spark(true, tag_lens);
+        let mut tag = [0; MOTORIST_TAG_LEN];
+        self.pistons[0]
+            .reader()
+            .write_to_slice(&mut tag)
+            .expect("tag fits the state");
+        tag
+    }
+
+    /// Encrypt `data` in place and authenticate it together with `metadata`.
+    /// Either may be empty.
+    ///
+    /// Returns the tag authenticating the session so far.
+    pub fn wrap(&mut self, metadata: &[u8], data: &mut [u8]) -> [u8; MOTORIST_TAG_LEN] {
+        self.crypt_message(metadata, data, false)
+    }
+
+    /// Decrypt `data` in place and verify `tag` for the plaintext and
+    /// `metadata`.
+    ///
+    /// The tag is compared in constant time. When it doesn't match, `data` is
+    /// encrypted again, so the unverified plaintext is not released, and the
+    /// session is left unchanged.
+    ///
+    /// # Errors
+    /// Errors when the tag doesn't match, leaving the session and `data`
+    /// unchanged.
+    pub fn unwrap(
+        &mut self,
+        metadata: &[u8],
+        data: &mut [u8],
+        tag: &[u8; MOTORIST_TAG_LEN],
+    ) -> Result<(), TagMismatchError> {
+        let mut session = self.clone();
+        if !bool::from(session.crypt_message(metadata, data, true).ct_eq(tag)) {
+            self.clone().crypt_message(metadata, data, false);
+            return Err(TagMismatchError);
+        }
+        *self = session;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Motorist;
+    use crate::TagMismatchError;
+    use permutation_keccak::{KeccakP1600, KeccakP800};
+
+    type RiverKeyak = Motorist<KeccakP800<12>, 1, 4>;
+    type LakeKeyak = Motorist<KeccakP1600<12>, 1, 8>;
+    type OceanKeyak = Motorist<KeccakP1600<12>, 4, 8>;
+
+    const KEY: [u8; 16] = [0x6d; 16];
+
+    /// Messages with empty and long metadata and plaintext, around the
+    /// squeezing and absorbing rates of one and several pistons.
+    const MESSAGES: [(&[u8], &[u8]); 7] = [
+        (b"nonce", b"first message"),
+        (b"", b""),
+        (&[7; 24], &[3; 68]),
+        (&[7; 400], &[3; 1]),
+        (&[7; 1], &[3; 700]),
+        (&[7; 193], &[3; 169]),
+        (b"", b"last message"),
+    ];
+
+    /// Wrap and unwrap `MESSAGES` in a single session, checking that every
+    /// ciphertext differs from its plaintext.
+    fn roundtrip<const PISTONS: usize, const LANE: usize, P>()
+    where
+        P: crypto_permutation::Permutation,
+    {
+        let mut sender = Motorist::<P, PISTONS, LANE>::keyak(&KEY, b"nonce");
+        let mut receiver = Motorist::<P, PISTONS, LANE>::keyak(&KEY, b"nonce");
+        let mut tags = Vec::new();
+        for (metadata, plaintext) in MESSAGES {
+            let mut data = plaintext.to_vec();
+            let tag = sender.wrap(metadata, &mut data);
+            if !plaintext.is_empty() {
+                assert_ne!(data, plaintext);
+            }
+            receiver.unwrap(metadata, &mut data, &tag).unwrap();
+            assert_eq!(data, plaintext);
+            tags.push(tag);
+        }
+        for (i, tag) in tags.iter().enumerate() {
+            assert!(!tags[i + 1..].contains(tag));
+        }
+    }
+
+    #[test]
+    fn roundtrips() {
+        roundtrip::<1, 4, KeccakP800<12>>();
+        roundtrip::<1, 8, KeccakP1600<12>>();
+        roundtrip::<2, 8, KeccakP1600<12>>();
+        roundtrip::<4, 8, KeccakP1600<12>>();
+    }
+
+    #[test]
+    fn forgery_rejected() {
+        let mut sender = OceanKeyak::keyak(&KEY, b"nonce");
+        let mut data = *b"attack at dawn";
+        let tag = sender.wrap(b"metadata", &mut data);
+        let ciphertext = data;
+
+        let mut receiver = OceanKeyak::keyak(&KEY, b"nonce");
+        data[0] ^= 1;
+        let result = receiver.unwrap(b"metadata", &mut data, &tag);
+        assert_eq!(result, Err(TagMismatchError));
+        data[0] ^= 1;
+        assert_eq!(data, ciphertext);
+
+        let mut forged_tag = tag;
+        forged_tag[15] ^= 1;
+        let result = receiver.unwrap(b"metadata", &mut data, &forged_tag);
+        assert_eq!(result, Err(TagMismatchError));
+        let result = receiver.unwrap(b"metadatb", &mut data, &tag);
+        assert_eq!(result, Err(TagMismatchError));
+        assert_eq!(data, ciphertext);
+
+        // The failures left the session unchanged.
+        receiver.unwrap(b"metadata", &mut data, &tag).unwrap();
+        assert_eq!(&data, b"attack at dawn");
+    }
+
+    /// The key, the nonce and the earlier messages in the session change the
+    /// ciphertext and the tag, as does moving bytes between the metadata and
+    /// the plaintext.
+    #[test]
+    fn separation() {
+        let wrap = |mut session: LakeKeyak, metadata: &[u8], plaintext: &[u8]| {
+            let mut data = plaintext.to_vec();
+            let tag = session.wrap(metadata, &mut data);
+            (data, tag)
+        };
+        let expected = wrap(LakeKeyak::keyak(&KEY, b"nonce"), b"ad", b"message");
+        assert_ne!(
+            wrap(LakeKeyak::keyak(&[0x6e; 16], b"nonce"), b"ad", b"message"),
+            expected
+        );
+        assert_ne!(
+            wrap(LakeKeyak::keyak(&KEY, b"noncf"), b"ad", b"message"),
+            expected
+        );
+        assert_ne!(wrap(LakeKeyak::new(b"nonce"), b"ad", b"message"), expected);
+        let mut session = LakeKeyak::keyak(&KEY, b"nonce");
+        session.wrap(b"", &mut []);
+        assert_ne!(wrap(session, b"ad", b"message"), expected);
+        let (data, tag) = wrap(LakeKeyak::keyak(&KEY, b"nonce"), b"a", b"dmessage");
+        assert_ne!(tag, expected.1);
+        assert_ne!(data[1..], expected.0);
+
+        let river = |key: &[u8]| {
+            let mut data = [0; 32];
+            RiverKeyak::keyak(key, b"nonce").wrap(b"", &mut data);
+            data
+        };
+        assert_ne!(river(&KEY), river(&KEY[..15]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn key_too_long() {
+        RiverKeyak::keyak(&[0; 35], b"nonce");
+    }
+}
@@ -0,0 +1,229 @@
+//! SHA-3 and SHAKE instances of the sponge over Keccak-f\[1600\], implementing
+//! the [`digest`] crate traits.
+//!
+//! ```
+//! use digest::{Digest, ExtendableOutput, Update, XofReader};
+//! use sponge::sha3::{Sha3_256, Shake128};
+//!
+//! let digest = Sha3_256::digest(b"abc");
+//! assert_eq!(digest[..4], [0x3a, 0x98, 0x5d, 0xa7]);
+//!
+//! let mut shake = Shake128::default();
+//! shake.update(b"hello world");
+//! let mut output = [0; 100];
+//! shake.finalize_xof().read(&mut output);
+//! ```
+
+use super::{Sponge, SpongeReader};
+use crypto_permutation::{Permutation, Reader, Writer};
+use digest::consts::{U32, U64};
+use digest::{
+    ExtendableOutput, ExtendableOutputReset, FixedOutput, FixedOutputReset, HashMarker, Output,
+    OutputSizeUser, Reset, Update, XofReader,
+};
+use permutation_keccak::KeccakF1600;
+
+/// Domain separation suffix of the SHA-3 hash functions: the bits `01`.
+const SHA3_SUFFIX: u8 = 0x06;
+/// Domain separation suffix of the SHAKE extendable output functions: the
+/// bits `1111`.
+const SHAKE_SUFFIX: u8 = 0x1f;
+
+impl<P: Permutation, const RATE: usize> XofReader for SpongeReader<P, RATE> {
+    fn read(&mut self, buffer: &mut [u8]) {
+        self.write_to_slice(buffer)
+            .expect("sponge generates any output length");
+    }
+}
+
+macro_rules! sha3 {
+    ($name:ident, $rate:literal, $output_size:ty, $doc_name:literal) => {
+        #[doc = concat!("The ", $doc_name, " hash function from FIPS 202.")]
+        #[derive(Clone)]
+        pub struct $name {
+            sponge: Sponge<KeccakF1600, $rate>,
+        }
+
+        impl core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.debug_struct(stringify!($name)).finish_non_exhaustive()
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self {
+                    sponge: Sponge::with_suffix(SHA3_SUFFIX),
+                }
+            }
+        }
+
+        impl HashMarker for $name {}
+
+        impl Update for $name {
+            fn update(&mut self, data: &[u8]) {
+                self.sponge
+                    .write_bytes(data)
+                    .expect("sponge accepts any input length");
+            }
+        }
+
+        impl OutputSizeUser for $name {
+            type OutputSize = $output_size;
+        }
+
+        impl FixedOutput for $name {
+            fn finalize_into(self, out: &mut Output<Self>) {
+                self.sponge.finish().read(out);
+            }
+        }
+
+        impl Reset for $name {
+            fn reset(&mut self) {
+                *self = Self::default();
+            }
+        }
+
+        impl FixedOutputReset for $name {
+            fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+                core::mem::take(self).finalize_into(out);
+            }
+        }
+    };
+}
+
+macro_rules! shake {
+    ($name:ident, $rate:literal, $doc_name:literal) => {
+        #[doc = concat!("The ", $doc_name, " extendable output function from FIPS 202.")]
+        #[derive(Clone)]
+        pub struct $name {
+            sponge: Sponge<KeccakF1600, $rate>,
+        }
+
+        impl core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.debug_struct(stringify!($name)).finish_non_exhaustive()
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self {
+                    sponge: Sponge::with_suffix(SHAKE_SUFFIX),
+                }
+            }
+        }
+
+        impl HashMarker for $name {}
+
+        impl Update for $name {
+            fn update(&mut self, data: &[u8]) {
+                self.sponge
+                    .write_bytes(data)
+                    .expect("sponge accepts any input length");
+            }
+        }
+
+        impl ExtendableOutput for $name {
+            type Reader = SpongeReader<KeccakF1600, $rate>;
+
+            fn finalize_xof(self) -> Self::Reader {
+                self.sponge.finish()
+            }
+        }
+
+        impl Reset for $name {
+            fn reset(&mut self) {
+                *self = Self::default();
+            }
+        }
+
+        impl ExtendableOutputReset for $name {
+            fn finalize_xof_reset(&mut self) -> Self::Reader {
+                core::mem::take(self).finalize_xof()
+            }
+        }
+    };
+}
+
+sha3!(Sha3_256, 136, U32, "SHA3-256");
+sha3!(Sha3_512, 72, U64, "SHA3-512");
+shake!(Shake128, 168, "SHAKE128");
+shake!(Shake256, 136, "SHAKE256");
+
+#[cfg(test)]
+mod tests {
+    use super::{Sha3_256, Sha3_512, Shake128, Shake256};
+    use digest::{Digest, ExtendableOutput, ExtendableOutputReset, Update, XofReader};
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    fn shake<X: ExtendableOutput + Default>(message: &[u8], len: usize) -> String {
+        let mut output = vec![0; len];
+        X::default().chain(message).finalize_xof().read(&mut output);
+        hex(&output)
+    }
+
+    /// Test vectors from the NIST examples with algorithm intermediate
+    /// values.
+    #[test]
+    fn nist() {
+        assert_eq!(
+            hex(&Sha3_256::digest(b"")),
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"
+        );
+        assert_eq!(
+            hex(&Sha3_512::digest(b"")),
+            "a69f73cca23a9ac5c8b567dc185a756e97c982164fe25859e0d1dcc1475c80a6\
+             15b2123af1f5f94c11e3e9402c3ac558f500199d95b6d3e301758586281dcd26"
+        );
+        assert_eq!(
+            hex(&Sha3_512::digest(b"abc")),
+            "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e\
+             10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0"
+        );
+        assert_eq!(
+            shake::<Shake128>(b"", 32),
+            "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26"
+        );
+        assert_eq!(
+            shake::<Shake256>(b"", 64),
+            "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f\
+             d75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be"
+        );
+    }
+
+    /// A million times the byte `a`.
+    #[test]
+    fn million_a() {
+        let mut hasher = Sha3_256::new();
+        for _ in 0..1000 {
+            Digest::update(&mut hasher, [b'a'; 1000]);
+        }
+        assert_eq!(
+            hex(&hasher.finalize()),
+            "5c8875ae474a3634ba4fd55ec85bffd661f32aca75c6d699d0cdcb6c115891c1"
+        );
+    }
+
+    /// After a reset the hasher starts over.
+    #[test]
+    fn reset() {
+        let mut hasher = Sha3_256::new();
+        Digest::update(&mut hasher, b"first message");
+        hasher.finalize_reset();
+        Digest::update(&mut hasher, b"abc");
+        assert_eq!(hasher.finalize(), Sha3_256::digest(b"abc"));
+
+        let mut xof = Shake256::default();
+        xof.update(b"first message");
+        let mut first = [0; 32];
+        xof.finalize_xof_reset().read(&mut first);
+        let mut second = [0; 32];
+        xof.finalize_xof().read(&mut second);
+        assert_eq!(hex(&second), shake::<Shake256>(b"", 32));
+        assert_ne!(first, second);
+    }
+}
@@ -0,0 +1,497 @@
+//! The functions derived from SHA-3 in [NIST SP 800-185]: cSHAKE, KMAC and
+//! TupleHash.
+//!
+//! ```
+//! use crypto_permutation::Writer;
+//! use sponge::sp800_185::Kmac256;
+//!
+//! let mut kmac = Kmac256::new(b"secret key", b"my application");
+//! kmac.write_bytes(b"hello world").unwrap();
+//! let mut tag = [0; 32];
+//! kmac.clone().tag(&mut tag);
+//! kmac.verify(&tag).unwrap();
+//! ```
+//!
+//! [NIST SP 800-185]: https://doi.org/10.6028/NIST.SP.800-185
+
+use super::{Sponge, SpongeReader, TagMismatchError};
+use crypto_permutation::{Reader, WriteTooLargeError, Writer};
+use permutation_keccak::KeccakF1600;
+use subtle::ConstantTimeEq;
+
+/// Domain separation suffix of cSHAKE: the bits `00`.
+const CSHAKE_SUFFIX: u8 = 0x04;
+/// Domain separation suffix of SHAKE: the bits `1111`.
+const SHAKE_SUFFIX: u8 = 0x1f;
+/// Function name of KMAC.
+const KMAC: &[u8] = b"KMAC";
+/// Function name of TupleHash.
+const TUPLE_HASH: &[u8] = b"TupleHash";
+
+/// `left_encode` or `right_encode` of an integer: its big endian bytes
+/// without leading zeros, preceded or followed by their number.
+struct IntEncoding {
+    bytes: [u8; 17],
+    len: usize,
+}
+
+impl IntEncoding {
+    /// The big endian bytes of `x` without leading zeros, at least one, at
+    /// `offset`.
+    fn new(x: u128, offset: usize) -> (Self, usize) {
+        let n = (16 - x.leading_zeros() as usize / 8).max(1);
+        let mut bytes = [0; 17];
+        bytes[offset..offset + n].copy_from_slice(&x.to_be_bytes()[16 - n..]);
+        (Self { bytes, len: n + 1 }, n)
+    }
+
+    fn left(x: u128) -> Self {
+        let (mut encoding, n) = Self::new(x, 1);
+        encoding.bytes[0] = n as u8;
+        encoding
+    }
+
+    fn right(x: u128) -> Self {
+        let (mut encoding, n) = Self::new(x, 0);
+        encoding.bytes[n] = n as u8;
+        encoding
+    }
+
+    /// The encoding of the length of `len` bytes in bits.
+    fn bit_len(len: usize, right: bool) -> Self {
+        let bits = len as u128 * 8;
+        if right {
+            Self::right(bits)
+        } else {
+            Self::left(bits)
+        }
+    }
+}
+
+impl AsRef<[u8]> for IntEncoding {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+/// Absorb `bytepad(encode_string(S_0) || ... || encode_string(S_n), RATE)`
+/// into `sponge`, with `S_i` the `strings`.
+fn absorb_bytepad<const RATE: usize>(sponge: &mut Sponge<KeccakF1600, RATE>, strings: &[&[u8]]) {
+    let mut len = 0;
+    let mut write = |data: &[u8]| {
+        sponge
+            .write_bytes(data)
+            .expect("sponge accepts any input length");
+        len += data.len();
+    };
+    write(IntEncoding::left(RATE as u128).as_ref());
+    for string in strings {
+        write(IntEncoding::bit_len(string.len(), false).as_ref());
+        write(string);
+    }
+    sponge
+        .skip((RATE - len % RATE) % RATE)
+        .expect("sponge accepts any input length");
+}
+
+/// The cSHAKE customizable extendable output function with a rate of `RATE`
+/// bytes, in its absorbing phase.
+///
+/// Data written to it through the [`Writer`] trait is absorbed, and
+/// [`Writer::finish`] returns a [`SpongeReader`] squeezing the output. With
+/// an empty function name and customization string it equals SHAKE.
+#[derive(Clone)]
+pub struct CShake<const RATE: usize> {
+    sponge: Sponge<KeccakF1600, RATE>,
+}
+
+/// cSHAKE128, with a security strength of 128 bits.
+pub type CShake128 = CShake<168>;
+/// cSHAKE256, with a security strength of 256 bits.
+pub type CShake256 = CShake<136>;
+
+impl<const RATE: usize> core::fmt::Debug for CShake<RATE> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CShake")
+            .field("rate", &RATE)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<const RATE: usize> CShake<RATE> {
+    /// Create cSHAKE with the function name `function_name` and the
+    /// customization string `customization`.
+    ///
+    /// The function name is reserved for functions defined by NIST; use the
+    /// customization string for domain separation.
+    ///
+    /// # Panics
+    /// Panics when `RATE` is zero or not smaller than the state size.
+    pub fn new(function_name: &[u8], customization: &[u8]) -> Self {
+        if function_name.is_empty() && customization.is_empty() {
+            return Self {
+                sponge: Sponge::with_suffix(SHAKE_SUFFIX),
+            };
+        }
+        let mut sponge = Sponge::with_suffix(CSHAKE_SUFFIX);
+        absorb_bytepad(&mut sponge, &[function_name, customization]);
+        Self { sponge }
+    }
+
+    /// Absorb `data`.
+    fn absorb(&mut self, data: &[u8]) {
+        self.sponge
+            .write_bytes(data)
+            .expect("sponge accepts any input length");
+    }
+}
+
+impl<const RATE: usize> Writer for CShake<RATE> {
+    type Return = SpongeReader<KeccakF1600, RATE>;
+
+    /// Returns `usize::MAX`: cSHAKE accepts input of any length.
+    fn capacity(&self) -> usize {
+        self.sponge.capacity()
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), WriteTooLargeError> {
+        self.sponge.skip(len)
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), WriteTooLargeError> {
+        self.sponge.write_bytes(data)
+    }
+
+    fn finish(self) -> Self::Return {
+        self.sponge.finish()
+    }
+}
+
+/// The KMAC message authentication code with a rate of `RATE` bytes.
+///
+/// The message is written to it through the [`Writer`] trait. [`Self::tag`]
+/// gives a tag of any length, which depends on that length, and
+/// [`Writer::finish`] returns the output stream of KMACXOF, the variant with
+/// arbitrary length output.
+#[derive(Clone)]
+pub struct Kmac<const RATE: usize> {
+    cshake: CShake<RATE>,
+}
+
+/// KMAC128, with a security strength of 128 bits.
+pub type Kmac128 = Kmac<168>;
+/// KMAC256, with a security strength of 256 bits.
+pub type Kmac256 = Kmac<136>;
+
+impl<const RATE: usize> core::fmt::Debug for Kmac<RATE> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Kmac")
+            .field("rate", &RATE)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<const RATE: usize> Kmac<RATE> {
+    /// Create KMAC with the key `key` and the customization string
+    /// `customization`.
+    ///
+    /// # Panics
+    /// Panics when `RATE` is zero or not smaller than the state size.
+    pub fn new(key: &[u8], customization: &[u8]) -> Self {
+        let mut cshake = CShake::new(KMAC, customization);
+        absorb_bytepad(&mut cshake.sponge, &[key]);
+        Self { cshake }
+    }
+
+    /// Write the tag of the message to `out`, with the length of `out`.
+    pub fn tag(mut self, out: &mut [u8]) {
+        self.cshake
+            .absorb(IntEncoding::bit_len(out.len(), true).as_ref());
+        self.cshake
+            .finish()
+            .write_to_slice(out)
+            .expect("sponge generates any output length");
+    }
+
+    /// Verify that `tag` is the tag of the message, with the length of `tag`.
+    ///
+    /// The tag is compared in constant time.
+    ///
+    /// # Errors
+    /// Errors when the tag doesn't match.
+    pub fn verify(mut self, tag: &[u8]) -> Result<(), TagMismatchError> {
+        self.cshake
+            .absorb(IntEncoding::bit_len(tag.len(), true).as_ref());
+        let mut reader = self.cshake.finish();
+        let mut matches = subtle::Choice::from(1);
+        let mut block = [0; RATE];
+        for chunk in tag.chunks(RATE) {
+            let expected = &mut block[..chunk.len()];
+            reader
+                .write_to_slice(expected)
+                .expect("sponge generates any output length");
+            matches &= expected.ct_eq(chunk);
+        }
+        if bool::from(matches) {
+            Ok(())
+        } else {
+            Err(TagMismatchError)
+        }
+    }
+}
+
+impl<const RATE: usize> Writer for Kmac<RATE> {
+    type Return = SpongeReader<KeccakF1600, RATE>;
+
+    /// Returns `usize::MAX`: KMAC accepts messages of any length.
+    fn capacity(&self) -> usize {
+        self.cshake.capacity()
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), WriteTooLargeError> {
+        self.cshake.skip(len)
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), WriteTooLargeError> {
+        self.cshake.write_bytes(data)
+    }
+
+    /// Finish the message and return the output stream of KMACXOF.
+    fn finish(mut self) -> Self::Return {
+        self.cshake.absorb(IntEncoding::right(0).as_ref());
+        self.cshake.finish()
+    }
+}
+
+/// The TupleHash hash function of tuples of byte strings with a rate of
+/// `RATE` bytes.
+///
+/// Unlike the hash of their concatenation, the hash of a tuple changes when
+/// bytes move from one element to another.
+///
+/// ```
+/// use sponge::sp800_185::TupleHash128;
+///
+/// let mut tuple_hash = TupleHash128::new(b"");
+/// tuple_hash.push(b"abc");
+/// tuple_hash.push(b"d");
+/// let mut digest = [0; 32];
+/// tuple_hash.hash(&mut digest);
+/// ```
+#[derive(Clone)]
+pub struct TupleHash<const RATE: usize> {
+    cshake: CShake<RATE>,
+}
+
+/// TupleHash128, with a security strength of 128 bits.
+pub type TupleHash128 = TupleHash<168>;
+/// TupleHash256, with a security strength of 256 bits.
+pub type TupleHash256 = TupleHash<136>;
+
+impl<const RATE: usize> core::fmt::Debug for TupleHash<RATE> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TupleHash")
+            .field("rate", &RATE)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<const RATE: usize> TupleHash<RATE> {
+    /// Create TupleHash of the empty tuple with the customization string
+    /// `customization`.
+    ///
+    /// # Panics
+    /// Panics when `RATE` is zero or not smaller than the state size.
+    pub fn new(customization: &[u8]) -> Self {
+        Self {
+            cshake: CShake::new(TUPLE_HASH, customization),
+        }
+    }
+
+    /// Append `element` to the tuple.
+    pub fn push(&mut self, element: &[u8]) {
+        self.cshake
+            .absorb(IntEncoding::bit_len(element.len(), false).as_ref());
+        self.cshake.absorb(element);
+    }
+
+    /// Write the hash of the tuple to `out`, with the length of `out`.
+    pub fn hash(mut self, out: &mut [u8]) {
+        self.cshake
+            .absorb(IntEncoding::bit_len(out.len(), true).as_ref());
+        self.cshake
+            .finish()
+            .write_to_slice(out)
+            .expect("sponge generates any output length");
+    }
+
+    /// Return the output stream of TupleHashXOF, the variant with arbitrary
+    /// length output, of the tuple.
+    pub fn finish_xof(mut self) -> SpongeReader<KeccakF1600, RATE> {
+        self.cshake.absorb(IntEncoding::right(0).as_ref());
+        self.cshake.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CShake, CShake128, IntEncoding, Kmac128, Kmac256, TupleHash128, TupleHash256};
+    use crate::TagMismatchError;
+    use crypto_permutation::{Reader, Writer};
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    fn output<W: Writer>(writer: W, len: usize) -> String
+    where
+        W::Return: Reader,
+    {
+        let mut output = vec![0; len];
+        writer.finish().write_to_slice(&mut output).unwrap();
+        hex(&output)
+    }
+
+    fn with_message<W: Writer>(mut writer: W, message: &[u8]) -> W {
+        writer.write_bytes(message).unwrap();
+        writer
+    }
+
+    fn range(len: u8) -> Vec<u8> {
+        (0..len).collect()
+    }
+
+    const KMAC_KEY: [u8; 32] = {
+        let mut key = [0; 32];
+        let mut i = 0;
+        while i < 32 {
+            key[i] = 0x40 + i as u8;
+            i += 1;
+        }
+        key
+    };
+
+    #[test]
+    fn int_encoding() {
+        assert_eq!(IntEncoding::left(0).as_ref(), [0x01, 0x00]);
+        assert_eq!(IntEncoding::right(0).as_ref(), [0x00, 0x01]);
+        assert_eq!(IntEncoding::left(168).as_ref(), [0x01, 0xa8]);
+        assert_eq!(IntEncoding::left(256).as_ref(), [0x02, 0x01, 0x00]);
+        assert_eq!(IntEncoding::right(256).as_ref(), [0x01, 0x00, 0x02]);
+    }
+
+    /// Test vectors from the NIST cSHAKE samples.
+    #[test]
+    fn cshake() {
+        assert_eq!(
+            output(
+                with_message(CShake128::new(b"", b"Email Signature"), &range(4)),
+                32
+            ),
+            "c1c36925b6409a04f1b504fcbca9d82b4017277cb5ed2b2065fc1d3814d5aaf5"
+        );
+        assert_eq!(
+            output(
+                with_message(CShake128::new(b"", b"Email Signature"), &range(200)),
+                32
+            ),
+            "c5221d50e4f822d96a2e8881a961420f294b7b24fe3d2094baed2c6524cc166b"
+        );
+        assert_eq!(
+            output(
+                with_message(CShake::<136>::new(b"", b"Email Signature"), &range(4)),
+                64
+            ),
+            "d008828e2b80ac9d2218ffee1d070c48b8e4c87bff32c9699d5b6896eee0edd1\
+             64020e2be0560858d9c00c037e34a96937c561a74c412bb4c746469527281c8c"
+        );
+        // Without function name and customization string, cSHAKE is SHAKE.
+        assert_eq!(
+            output(CShake128::new(b"", b""), 32),
+            "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26"
+        );
+    }
+
+    /// Test vectors from the NIST KMAC samples.
+    #[test]
+    fn kmac() {
+        let tag = |kmac: Kmac128| {
+            let mut tag = [0; 32];
+            kmac.tag(&mut tag);
+            hex(&tag)
+        };
+        assert_eq!(
+            tag(with_message(Kmac128::new(&KMAC_KEY, b""), &range(4))),
+            "e5780b0d3ea6f7d3a429c5706aa43a00fadbd7d49628839e3187243f456ee14e"
+        );
+        assert_eq!(
+            tag(with_message(
+                Kmac128::new(&KMAC_KEY, b"My Tagged Application"),
+                &range(4)
+            )),
+            "3b1fba963cd8b0b59e8c1a6d71888b7143651af8ba0a7070c0979e2811324aa5"
+        );
+        assert_eq!(
+            output(with_message(Kmac128::new(&KMAC_KEY, b""), &range(4)), 32),
+            "cd83740bbd92ccc8cf032b1481a0f4460e7ca9dd12b08a0c4031178bacd6ec35"
+        );
+    }
+
+    #[test]
+    fn kmac_verify() {
+        let kmac = with_message(Kmac256::new(&KMAC_KEY, b"app"), b"message");
+        for len in [16, 64, 200] {
+            let mut tag = vec![0; len];
+            kmac.clone().tag(&mut tag);
+            assert_eq!(kmac.clone().verify(&tag), Ok(()));
+            assert_eq!(kmac.clone().verify(&tag[..len - 1]), Err(TagMismatchError));
+            tag[len - 1] ^= 1;
+            assert_eq!(kmac.clone().verify(&tag), Err(TagMismatchError));
+        }
+    }
+
+    /// Test vectors from the NIST TupleHash samples.
+    #[test]
+    fn tuple_hash() {
+        let hash = |customization: &[u8]| {
+            let mut tuple_hash = TupleHash128::new(customization);
+            tuple_hash.push(&range(3));
+            tuple_hash.push(&range(22)[0x10..]);
+            let mut digest = [0; 32];
+            tuple_hash.hash(&mut digest);
+            hex(&digest)
+        };
+        assert_eq!(
+            hash(b""),
+            "c5d8786c1afb9b82111ab34b65b2c0048fa64e6d48e263264ce1707d3ffc8ed1"
+        );
+        assert_eq!(
+            hash(b"My Tuple App"),
+            "75cdb20ff4db1154e841d758e24160c54bae86eb8c13e7f5f40eb35588e96dfb"
+        );
+    }
+
+    /// Moving bytes between elements changes the hash, and the output of
+    /// TupleHashXOF doesn't depend on its length.
+    #[test]
+    fn tuple_elements() {
+        let xof = |elements: &[&[u8]]| {
+            let mut tuple_hash = TupleHash256::new(b"");
+            for element in elements {
+                tuple_hash.push(element);
+            }
+            let mut output = [0; 64];
+            tuple_hash.finish_xof().write_to_slice(&mut output).unwrap();
+            output
+        };
+        assert_ne!(xof(&[b"abc", b"d"]), xof(&[b"ab", b"cd"]));
+        assert_ne!(xof(&[b"abcd"]), xof(&[b"abcd", b""]));
+
+        let mut tuple_hash = TupleHash256::new(b"");
+        tuple_hash.push(b"abc");
+        tuple_hash.push(b"d");
+        let mut short = [0; 32];
+        tuple_hash.finish_xof().write_to_slice(&mut short).unwrap();
+        assert_eq!(short, xof(&[b"abc", b"d"])[..32]);
+    }
+}
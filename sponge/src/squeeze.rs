@@ -0,0 +1,115 @@
+//! Squeezing phase of the sponge construction.
+
+use crypto_permutation::io::{check_write_size, CryptoReader, Reader, WriteTooLargeError, Writer};
+use crypto_permutation::{Permutation, StateView};
+
+/// The sponge construction over the permutation `P` with a rate of `RATE`
+/// bytes, in its squeezing phase.
+///
+/// Returned by [`Writer::finish`] on a [`Sponge`](crate::Sponge). It
+/// generates an output stream of arbitrary length through the [`Reader`]
+/// trait.
+#[derive(Clone)]
+pub struct SpongeReader<P: Permutation, const RATE: usize> {
+    state: P::State,
+    /// Number of bytes squeezed from the current block.
+    pos: usize,
+}
+
+impl<P: Permutation, const RATE: usize> core::fmt::Debug for SpongeReader<P, RATE> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SpongeReader")
+            .field("rate", &RATE)
+            .field("pos", &self.pos)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<P: Permutation, const RATE: usize> SpongeReader<P, RATE> {
+    /// Start squeezing from `state`, to which the permutation has just been
+    /// applied.
+    pub(super) fn new(state: P::State) -> Self {
+        Self { state, pos: 0 }
+    }
+
+    /// Apply the permutation when the current block is used up.
+    fn next_block_if_empty(&mut self) {
+        if self.pos == RATE {
+            P::default().apply(&mut self.state);
+            self.pos = 0;
+        }
+    }
+}
+
+impl<P: Permutation, const RATE: usize> Reader for SpongeReader<P, RATE> {
+    /// Returns `usize::MAX`: the sponge generates output of any length.
+    fn capacity(&self) -> usize {
+        usize::MAX
+    }
+
+    fn skip(&mut self, mut n: usize) -> Result<(), WriteTooLargeError> {
+        check_write_size(n, self.capacity())?;
+        while n > 0 {
+            self.next_block_if_empty();
+            let len = n.min(RATE - self.pos);
+            self.pos += len;
+            n -= len;
+        }
+        Ok(())
+    }
+
+    fn write_to<W: Writer>(
+        &mut self,
+        writer: &mut W,
+        mut n: usize,
+    ) -> Result<(), WriteTooLargeError> {
+        check_write_size(n, writer.capacity())?;
+        while n > 0 {
+            self.next_block_if_empty();
+            let len = n.min(RATE - self.pos);
+            let outer = StateView::new(&mut self.state, RATE);
+            let mut reader = outer.reader();
+            reader.skip(self.pos)?;
+            reader.write_to(writer, len)?;
+            self.pos += len;
+            n -= len;
+        }
+        Ok(())
+    }
+}
+
+impl<P: Permutation, const RATE: usize> CryptoReader for SpongeReader<P, RATE> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::Sponge;
+    use crypto_permutation::{Reader, Writer};
+    use permutation_keccak::KeccakF1600;
+
+    fn reader() -> crate::SpongeReader<KeccakF1600, 136> {
+        let mut sponge = Sponge::new();
+        sponge.write_bytes(b"hello world").unwrap();
+        sponge.finish()
+    }
+
+    /// Squeezing in parts or skipping gives the same output stream as
+    /// squeezing at once, also around block boundaries.
+    #[test]
+    fn split_output() {
+        let mut expected = [0; 500];
+        reader().write_to_slice(&mut expected).unwrap();
+        for split in [1, 135, 136, 137, 272, 499] {
+            let mut output = [0; 500];
+            let mut reader = reader();
+            reader.write_to_slice(&mut output[..split]).unwrap();
+            reader.write_to_slice(&mut output[split..]).unwrap();
+            assert_eq!(output, expected);
+
+            let mut reader = self::reader();
+            reader.skip(split).unwrap();
+            let mut rest = [0; 500];
+            reader.write_to_slice(&mut rest[split..]).unwrap();
+            assert_eq!(rest[split..], expected[split..]);
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! TurboSHAKE: the sponge over the 12 round Keccak-p\[1600, 12\].
+//!
+//! ```
+//! use crypto_permutation::{Reader, Writer};
+//! use sponge::turboshake::TurboShake128;
+//!
+//! let mut xof = TurboShake128::new(0x1f);
+//! xof.write_bytes(b"hello world").unwrap();
+//! let mut output = [0; 100];
+//! xof.finish().write_to_slice(&mut output).unwrap();
+//! ```
+
+use super::{Sponge, SpongeReader};
+use crypto_permutation::{WriteTooLargeError, Writer};
+use permutation_keccak::KeccakP1600;
+
+/// The TurboSHAKE extendable output function with a rate of `RATE` bytes, in
+/// its absorbing phase.
+///
+/// Data written to it through the [`Writer`] trait is absorbed, and
+/// [`Writer::finish`] returns a [`SpongeReader`] squeezing the output.
+/// TurboSHAKE halves the number of rounds of SHAKE, and has a domain
+/// separation byte `D` in `0x01..=0x7f` in place of its fixed suffix.
+#[derive(Clone)]
+pub struct TurboShake<const RATE: usize> {
+    sponge: Sponge<KeccakP1600<12>, RATE>,
+}
+
+/// TurboSHAKE128, with a security strength of 128 bits.
+pub type TurboShake128 = TurboShake<168>;
+/// TurboSHAKE256, with a security strength of 256 bits.
+pub type TurboShake256 = TurboShake<136>;
+
+impl<const RATE: usize> core::fmt::Debug for TurboShake<RATE> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TurboShake")
+            .field("rate", &RATE)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<const RATE: usize> TurboShake<RATE> {
+    /// Create TurboSHAKE with the domain separation byte `domain`.
+    ///
+    /// # Panics
+    /// Panics when `domain` is not in `0x01..=0x7f`, or when `RATE` is zero or
+    /// not smaller than the state size.
+    pub fn new(domain: u8) -> Self {
+        assert!(
+            (0x01..=0x7f).contains(&domain),
+            "the domain separation byte must be in 0x01..=0x7f"
+        );
+        Self {
+            sponge: Sponge::with_suffix(domain),
+        }
+    }
+}
+
+impl<const RATE: usize> Writer for TurboShake<RATE> {
+    type Return = SpongeReader<KeccakP1600<12>, RATE>;
+
+    /// Returns `usize::MAX`: TurboSHAKE accepts input of any length.
+    fn capacity(&self) -> usize {
+        self.sponge.capacity()
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), WriteTooLargeError> {
+        self.sponge.skip(len)
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), WriteTooLargeError> {
+        self.sponge.write_bytes(data)
+    }
+
+    fn finish(self) -> Self::Return {
+        self.sponge.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TurboShake, TurboShake128, TurboShake256};
+    use crypto_permutation::{Reader, Writer};
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    /// The last `len` bytes of the first `skip + len` bytes of output.
+    fn turboshake<const RATE: usize>(
+        message: &[u8],
+        domain: u8,
+        skip: usize,
+        len: usize,
+    ) -> String {
+        let mut xof = TurboShake::<RATE>::new(domain);
+        xof.write_bytes(message).unwrap();
+        let mut reader = xof.finish();
+        reader.skip(skip).unwrap();
+        let mut output = vec![0; len];
+        reader.write_to_slice(&mut output).unwrap();
+        hex(&output)
+    }
+
+    /// `ptn(n)`: the bytes `0x00, ..., 0xfa` repeated, truncated to `n` bytes.
+    fn ptn(n: usize) -> Vec<u8> {
+        (0..n).map(|i| (i % 251) as u8).collect()
+    }
+
+    /// Test vectors from RFC 9861.
+    #[test]
+    fn rfc9861() {
+        assert_eq!(
+            turboshake::<168>(b"", 0x1f, 0, 32),
+            "1e415f1c5983aff2169217277d17bb538cd945a397ddec541f1ce41af2c1b74c"
+        );
+        assert_eq!(
+            turboshake::<168>(b"", 0x1f, 10000, 32),
+            "a3b9b0385900ce761f22aed548e754da10a5242d62e8c658e3f3a923a7555607"
+        );
+        assert_eq!(
+            turboshake::<136>(b"", 0x1f, 0, 64),
+            "367a329dafea871c7802ec67f905ae13c57695dc2c6663c61035f59a18f8e7db\
+             11edc0e12e91ea60eb6b32df06dd7f002fbafabb6e13ec1cc20d995547600db0"
+        );
+    }
+
+    /// KangarooTwelve of a short message without customization string is
+    /// TurboSHAKE128 of the message followed by a zero byte, with domain
+    /// separation byte `0x07`.
+    #[test]
+    fn short_kangaroo_twelve() {
+        let mut message = ptn(17);
+        message.push(0);
+        assert_eq!(
+            turboshake::<168>(&message, 0x07, 0, 32),
+            "6bf75fa2239198db4772e36478f8e19b0f371205f6a9a93a273f51df37122888"
+        );
+    }
+
+    #[test]
+    fn domain_separation() {
+        assert_ne!(
+            turboshake::<168>(b"message", 0x01, 0, 32),
+            turboshake::<168>(b"message", 0x02, 0, 32)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn domain_out_of_range() {
+        TurboShake256::new(0x80);
+    }
+
+    #[test]
+    #[should_panic]
+    fn domain_zero() {
+        TurboShake128::new(0);
+    }
+}
@@ -0,0 +1,512 @@
+//! SpongeWrap and MonkeyWrap authenticated encryption.
+
+use super::{Duplex, MonkeyDuplex, TagMismatchError};
+use crypto_permutation::{Permutation, PermutationState, Reader};
+use subtle::ConstantTimeEq;
+
+/// A duplex object with the operations needed by the wrapping modes.
+///
+/// The wrapping modes work on the state directly: after a duplexing call the
+/// outer part of the state is the output, so xoring a plaintext block into
+/// the state both absorbs it and gives the ciphertext block.
+trait FramedDuplex: Clone {
+    type State: PermutationState;
+
+    /// Number of bytes of a block: the rate minus one byte for the frame bits
+    /// and padding.
+    const BLOCK_LEN: usize;
+
+    fn state(&mut self) -> &mut Self::State;
+
+    /// Pad the absorbed block of `len` bytes with the frame bits `suffix`, a
+    /// delimited suffix, and apply the permutation, the stride permutation
+    /// when `stride`.
+    fn pad_permute(&mut self, len: usize, suffix: u8, stride: bool);
+}
+
+impl<P: Permutation, const RATE: usize> FramedDuplex for Duplex<P, RATE> {
+    type State = P::State;
+
+    const BLOCK_LEN: usize = Self::MAX_INPUT;
+
+    fn state(&mut self) -> &mut Self::State {
+        &mut self.state
+    }
+
+    fn pad_permute(&mut self, len: usize, suffix: u8, _stride: bool) {
+        super::duplex::pad(&mut self.state, len, suffix, RATE);
+        P::default().apply(&mut self.state);
+    }
+}
+
+impl<PStart, PStep, PStride, const RATE: usize> FramedDuplex
+    for MonkeyDuplex<PStart, PStep, PStride, RATE>
+where
+    PStart: Permutation,
+    PStep: Permutation<State = PStart::State>,
+    PStride: Permutation<State = PStart::State>,
+{
+    type State = PStart::State;
+
+    const BLOCK_LEN: usize = Self::MAX_INPUT;
+
+    fn state(&mut self) -> &mut Self::State {
+        &mut self.state
+    }
+
+    fn pad_permute(&mut self, len: usize, suffix: u8, stride: bool) {
+        MonkeyDuplex::pad_permute(self, len, suffix, stride);
+    }
+}
+
+/// The frame bits of the wrapping modes, as delimited suffixes.
+struct Frames {
+    /// Following every metadata block but the last one.
+    metadata: u8,
+    /// Following the last metadata block.
+    last_metadata: u8,
+    /// Following every plaintext block but the last one.
+    body: u8,
+    /// Following the last plaintext block.
+    last_body: u8,
+    /// Absorbed before every tag block but the first one.
+    tag: u8,
+    /// Whether the last plaintext block is followed by the stride
+    /// permutation.
+    stride: bool,
+}
+
+/// Absorb `data` in blocks, followed by the frame bits `frame`, or
+/// `last_frame` for the last, possibly empty, block.
+fn absorb<D: FramedDuplex>(duplex: &mut D, data: &[u8], frame: u8, last_frame: u8) {
+    let mut blocks = data.chunks(D::BLOCK_LEN);
+    let last = blocks.next_back().unwrap_or(&[]);
+    for block in blocks {
+        duplex.state().xor_bytes_at(0, block);
+        duplex.pad_permute(block.len(), frame, false);
+    }
+    duplex.state().xor_bytes_at(0, last);
+    duplex.pad_permute(last.len(), last_frame, false);
+}
+
+/// Encrypt `block` in place with the output in the state, absorbing the
+/// plaintext.
+pub(super) fn encrypt_block<S: PermutationState>(state: &mut S, block: &mut [u8]) {
+    state.xor_bytes_at(0, block);
+    state
+        .reader()
+        .write_to_slice(block)
+        .expect("block fits the state");
+}
+
+/// Decrypt `block` in place with the output in the state, absorbing the
+/// plaintext.
+pub(super) fn decrypt_block<S: PermutationState>(state: &mut S, block: &mut [u8]) {
+    const CHUNK: usize = 64;
+    let mut keystream = [0; CHUNK];
+    for (i, chunk) in block.chunks_mut(CHUNK).enumerate() {
+        let keystream = &mut keystream[..chunk.len()];
+        {
+            let mut reader = state.reader();
+            reader.skip(i * CHUNK).expect("block fits the state");
+            reader
+                .write_to_slice(keystream)
+                .expect("block fits the state");
+        }
+        for (byte, key) in chunk.iter_mut().zip(keystream.iter()) {
+            *byte ^= key;
+        }
+        state.xor_bytes_at(i * CHUNK, chunk);
+    }
+}
+
+/// Encrypt or decrypt `data` in place after absorbing `metadata`, and write
+/// the tag to `tag`.
+fn crypt<D: FramedDuplex>(
+    duplex: &mut D,
+    frames: &Frames,
+    metadata: &[u8],
+    data: &mut [u8],
+    decrypt: bool,
+    tag: &mut [u8],
+) {
+    absorb(duplex, metadata, frames.metadata, frames.last_metadata);
+    let mut blocks = data.chunks_mut(D::BLOCK_LEN).peekable();
+    if blocks.peek().is_none() {
+        duplex.pad_permute(0, frames.last_body, frames.stride);
+    }
+    while let Some(block) = blocks.next() {
+        if decrypt {
+            decrypt_block(duplex.state(), block);
+        } else {
+            encrypt_block(duplex.state(), block);
+        }
+        if blocks.peek().is_some() {
+            duplex.pad_permute(block.len(), frames.body, false);
+        } else {
+            duplex.pad_permute(block.len(), frames.last_body, frames.stride);
+        }
+    }
+    for (i, block) in tag.chunks_mut(D::BLOCK_LEN).enumerate() {
+        if i > 0 {
+            duplex.pad_permute(0, frames.tag, false);
+        }
+        duplex
+            .state()
+            .reader()
+            .write_to_slice(block)
+            .expect("block fits the state");
+    }
+}
+
+/// Decrypt `data` in place and verify `tag`. On a tag mismatch `data` is
+/// encrypted again and `duplex` is left unchanged.
+fn uncrypt<D: FramedDuplex, const TAG_LEN: usize>(
+    duplex: &mut D,
+    frames: &Frames,
+    metadata: &[u8],
+    data: &mut [u8],
+    tag: &[u8; TAG_LEN],
+) -> Result<(), TagMismatchError> {
+    let mut session = duplex.clone();
+    let mut expected = [0; TAG_LEN];
+    crypt(&mut session, frames, metadata, data, true, &mut expected);
+    if !bool::from(expected.ct_eq(tag)) {
+        crypt(&mut duplex.clone(), frames, metadata, data, false, &mut []);
+        return Err(TagMismatchError);
+    }
+    *duplex = session;
+    Ok(())
+}
+
+/// Frame bits of SpongeWrap, which are single bits.
+const SPONGE_WRAP: Frames = Frames {
+    metadata: 0x02,
+    last_metadata: 0x03,
+    body: 0x03,
+    last_body: 0x02,
+    tag: 0x02,
+    stride: false,
+};
+
+/// SpongeWrap session authenticated encryption over the [`Duplex`] with
+/// permutation `P` and a rate of `RATE` bytes, with `TAG_LEN` byte tags.
+///
+/// A session is started with a key, after which a sequence of messages,
+/// each consisting of metadata (associated data) and plaintext, is wrapped.
+/// Every message gives a tag authenticating the whole session so far, so these
+/// are intermediate tags of the session. The metadata and plaintext are
+/// absorbed in blocks of [`Duplex::MAX_INPUT`] bytes, each followed by a frame
+/// bit, encoded together with the padding in a single delimited suffix byte.
+/// Hence this mode is not interoperable with the bit oriented specification.
+///
+/// # Crypto
+/// The metadata of the first message must contain a nonce, which must never be
+/// used for two sessions under the same key.
+///
+/// ```
+/// use permutation_keccak::KeccakF1600;
+/// use sponge::SpongeWrap;
+///
+/// let key = [0x42; 32];
+/// let mut sender = SpongeWrap::<KeccakF1600, 168>::new(&key);
+/// let mut message = *b"hello world";
+/// let tag = sender.wrap(b"nonce", &mut message);
+///
+/// let mut receiver = SpongeWrap::<KeccakF1600, 168>::new(&key);
+/// receiver.unwrap(b"nonce", &mut message, &tag).unwrap();
+/// assert_eq!(&message, b"hello world");
+/// ```
+#[derive(Clone)]
+pub struct SpongeWrap<P: Permutation, const RATE: usize, const TAG_LEN: usize = 16> {
+    duplex: Duplex<P, RATE>,
+}
+
+impl<P: Permutation, const RATE: usize, const TAG_LEN: usize> core::fmt::Debug
+    for SpongeWrap<P, RATE, TAG_LEN>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SpongeWrap")
+            .field("rate", &RATE)
+            .field("tag_len", &TAG_LEN)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<P: Permutation, const RATE: usize, const TAG_LEN: usize> SpongeWrap<P, RATE, TAG_LEN> {
+    /// Start a session keyed by `key`.
+    ///
+    /// # Panics
+    /// Panics when `RATE` is smaller than two or not smaller than the state
+    /// size.
+    pub fn new(key: &[u8]) -> Self {
+        let mut duplex = Duplex::new();
+        absorb(&mut duplex, key, 0x03, 0x02);
+        Self { duplex }
+    }
+
+    /// Encrypt `data` in place and authenticate it together with `metadata`.
+    /// Either may be empty.
+    ///
+    /// Returns the tag authenticating the session so far.
+    pub fn wrap(&mut self, metadata: &[u8], data: &mut [u8]) -> [u8; TAG_LEN] {
+        let mut tag = [0; TAG_LEN];
+        crypt(
+            &mut self.duplex,
+            &SPONGE_WRAP,
+            metadata,
+            data,
+            false,
+            &mut tag,
+        );
+        tag
+    }
+
+    /// Decrypt `data` in place and verify `tag` for the plaintext and
+    /// `metadata`.
+    ///
+    /// The tag is compared in constant time. Only when it matches is the
+    /// session advanced to the next message; otherwise `data` is encrypted
+    /// again, so the unverified plaintext is not released.
+    ///
+    /// # Errors
+    /// Errors when the tag doesn't match, leaving the session and `data`
+    /// unchanged.
+    pub fn unwrap(
+        &mut self,
+        metadata: &[u8],
+        data: &mut [u8],
+        tag: &[u8; TAG_LEN],
+    ) -> Result<(), TagMismatchError> {
+        uncrypt(&mut self.duplex, &SPONGE_WRAP, metadata, data, tag)
+    }
+}
+
+/// Frame bits of MonkeyWrap, which are pairs of bits.
+const MONKEY_WRAP: Frames = Frames {
+    metadata: 0x04,
+    last_metadata: 0x06,
+    body: 0x07,
+    last_body: 0x05,
+    tag: 0x01,
+    stride: true,
+};
+
+/// MonkeyWrap session authenticated encryption, as used by Ketje, over the
+/// [`MonkeyDuplex`] with permutations `PStart`, `PStep` and `PStride` and a
+/// rate of `RATE` bytes, with `TAG_LEN` byte tags.
+///
+/// Like [`SpongeWrap`], but the session is started from a key and a nonce with
+/// the strong permutation `PStart`. The blocks of a message are processed with
+/// the fast `PStep`, and only the last plaintext block is followed by the
+/// stronger `PStride`, before the tag is squeezed. The two frame bits of every
+/// block are encoded together with the padding in a single delimited suffix
+/// byte, so this mode is not interoperable with the bit oriented Ketje
+/// specification.
+///
+/// # Crypto
+/// A nonce must never be used for two sessions under the same key.
+///
+/// ```
+/// use permutation_keccak::KeccakP800;
+/// use sponge::MonkeyWrap;
+///
+/// // Parameters like Ketje Sr.
+/// type Wrap = MonkeyWrap<KeccakP800<12>, KeccakP800<1>, KeccakP800<6>, 5>;
+///
+/// let key = [0x42; 16];
+/// let mut sender = Wrap::new(&key, b"nonce");
+/// let mut message = *b"hello world";
+/// let tag = sender.wrap(b"metadata", &mut message);
+///
+/// let mut receiver = Wrap::new(&key, b"nonce");
+/// receiver.unwrap(b"metadata", &mut message, &tag).unwrap();
+/// assert_eq!(&message, b"hello world");
+/// ```
+#[derive(Clone)]
+pub struct MonkeyWrap<PStart, PStep, PStride, const RATE: usize, const TAG_LEN: usize = 16>
+where
+    PStart: Permutation,
+{
+    duplex: MonkeyDuplex<PStart, PStep, PStride, RATE>,
+}
+
+impl<PStart, PStep, PStride, const RATE: usize, const TAG_LEN: usize> core::fmt::Debug
+    for MonkeyWrap<PStart, PStep, PStride, RATE, TAG_LEN>
+where
+    PStart: Permutation,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("MonkeyWrap")
+            .field("rate", &RATE)
+            .field("tag_len", &TAG_LEN)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<PStart, PStep, PStride, const RATE: usize, const TAG_LEN: usize>
+    MonkeyWrap<PStart, PStep, PStride, RATE, TAG_LEN>
+where
+    PStart: Permutation,
+    PStep: Permutation<State = PStart::State>,
+    PStride: Permutation<State = PStart::State>,
+{
+    /// Start a session keyed by `key` with `nonce`.
+    ///
+    /// The duplex is started with the key packed as its length plus two in a
+    /// byte, the key and a `0x01` byte, followed by the nonce.
+    ///
+    /// # Panics
+    /// Panics when the packed key and the nonce don't fit the state with
+    /// padding, or when `RATE` is smaller than two or not smaller than the
+    /// state size.
+    pub fn new(key: &[u8], nonce: &[u8]) -> Self {
+        let packed_len = u8::try_from(key.len() + 2).expect("key fits the state");
+        Self {
+            duplex: MonkeyDuplex::start_parts(&[&[packed_len], key, &[0x01], nonce]),
+        }
+    }
+
+    /// Encrypt `data` in place and authenticate it together with `metadata`.
+    /// Either may be empty.
+    ///
+    /// Returns the tag authenticating the session so far.
+    pub fn wrap(&mut self, metadata: &[u8], data: &mut [u8]) -> [u8; TAG_LEN] {
+        let mut tag = [0; TAG_LEN];
+        crypt(
+            &mut self.duplex,
+            &MONKEY_WRAP,
+            metadata,
+            data,
+            false,
+            &mut tag,
+        );
+        tag
+    }
+
+    /// Decrypt `data` in place and verify `tag` for the plaintext and
+    /// `metadata`.
+    ///
+    /// See [`SpongeWrap::unwrap`].
+    ///
+    /// # Errors
+    /// Errors when the tag doesn't match, leaving the session and `data`
+    /// unchanged.
+    pub fn unwrap(
+        &mut self,
+        metadata: &[u8],
+        data: &mut [u8],
+        tag: &[u8; TAG_LEN],
+    ) -> Result<(), TagMismatchError> {
+        uncrypt(&mut self.duplex, &MONKEY_WRAP, metadata, data, tag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MonkeyWrap, SpongeWrap};
+    use permutation_keccak::{KeccakF1600, KeccakP800};
+
+    type TestSpongeWrap = SpongeWrap<KeccakF1600, 168>;
+    type TestMonkeyWrap = MonkeyWrap<KeccakP800<12>, KeccakP800<1>, KeccakP800<6>, 5>;
+
+    const KEY: [u8; 16] = [0x3c; 16];
+
+    /// Messages with empty and multi-block metadata and plaintext, around
+    /// block boundaries for both modes.
+    const MESSAGES: [(&[u8], &[u8]); 6] = [
+        (b"nonce", b"first message"),
+        (b"", b""),
+        (&[7; 167], &[3; 167]),
+        (&[7; 168], &[3; 168]),
+        (b"", &[5; 500]),
+        (b"only metadata", b""),
+    ];
+
+    macro_rules! session_tests {
+        ($name:ident, $new:expr) => {
+            mod $name {
+                use super::*;
+
+                #[test]
+                fn roundtrip() {
+                    let mut sender = $new;
+                    let mut receiver = $new;
+                    for (metadata, plaintext) in MESSAGES {
+                        let mut data = plaintext.to_vec();
+                        let tag = sender.wrap(metadata, &mut data);
+                        if !plaintext.is_empty() {
+                            assert_ne!(data, plaintext);
+                        }
+                        receiver.unwrap(metadata, &mut data, &tag).unwrap();
+                        assert_eq!(data, plaintext);
+                    }
+                }
+
+                /// Identical messages encrypt differently within a session.
+                #[test]
+                fn history_dependence() {
+                    let mut sender = $new;
+                    let mut first = *b"same plaintext";
+                    let mut second = first;
+                    let first_tag = sender.wrap(b"", &mut first);
+                    let second_tag = sender.wrap(b"", &mut second);
+                    assert_ne!(first, second);
+                    assert_ne!(first_tag, second_tag);
+                }
+
+                #[test]
+                fn forgery_rejected() {
+                    let mut sender = $new;
+                    let mut receiver = $new;
+                    let mut data = [0x11; 300];
+                    let tag = sender.wrap(b"metadata", &mut data);
+                    let ciphertext = data;
+
+                    data[299] ^= 1;
+                    assert!(receiver.unwrap(b"metadata", &mut data, &tag).is_err());
+                    data[299] ^= 1;
+                    assert_eq!(data, ciphertext);
+                    let mut forged_tag = tag;
+                    forged_tag[0] ^= 1;
+                    assert!(receiver
+                        .unwrap(b"metadata", &mut data, &forged_tag)
+                        .is_err());
+                    assert!(receiver.unwrap(b"metadatb", &mut data, &tag).is_err());
+                    assert_eq!(data, ciphertext);
+
+                    // A rejected message leaves the receiving session unchanged.
+                    receiver.unwrap(b"metadata", &mut data, &tag).unwrap();
+                    assert_eq!(data, [0x11; 300]);
+                }
+            }
+        };
+    }
+
+    session_tests!(sponge_wrap, TestSpongeWrap::new(&KEY));
+    session_tests!(monkey_wrap, TestMonkeyWrap::new(&KEY, b"nonce"));
+
+    /// Long tags extend short ones, squeezing several blocks.
+    #[test]
+    fn long_tag() {
+        let data = *b"message";
+        let long = SpongeWrap::<KeccakF1600, 168, 400>::new(&KEY).wrap(b"", &mut data.clone());
+        let short = TestSpongeWrap::new(&KEY).wrap(b"", &mut data.clone());
+        assert_eq!(long[..16], short);
+
+        let long =
+            MonkeyWrap::<KeccakP800<12>, KeccakP800<1>, KeccakP800<6>, 5, 40>::new(&KEY, b"nonce")
+                .wrap(b"", &mut data.clone());
+        let short = TestMonkeyWrap::new(&KEY, b"nonce").wrap(b"", &mut data.clone());
+        assert_eq!(long[..16], short);
+    }
+
+    #[test]
+    fn nonce_separation() {
+        let mut first = *b"message";
+        let mut second = first;
+        TestMonkeyWrap::new(&KEY, b"nonce").wrap(b"", &mut first);
+        TestMonkeyWrap::new(&KEY, b"nonce!").wrap(b"", &mut second);
+        assert_ne!(first, second);
+    }
+}